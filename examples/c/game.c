@@ -0,0 +1,75 @@
+/*
+ * Plays through the C interface and checks every answer, exiting with 1 at the first wrong one.
+ *
+ *   cargo rustc --lib --release --features ffi --crate-type cdylib
+ *   cc -Iinclude examples/c/game.c -Ltarget/release -lConnectFour -o target/cgame
+ *   LD_LIBRARY_PATH=target/release target/cgame
+ *
+ * cargo test --test c_example does the same.
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include "connectfour.h"
+
+static int failures = 0;
+
+static void Check(const char *what, int got, int want) {
+  if (got != want) {
+    printf("FAIL %s: got %d, wanted %d\n", what, got, want);
+    failures++;
+  }
+}
+
+int main(void) {
+  CfGame *game = cf_game_new();
+  Check("width", cf_width(game), 7);
+  Check("height", cf_height(game), 6);
+  Check("first player", cf_current_player(game), 1);
+
+  /* Player 1 stacks column 0 while player 2 answers in column 1 */
+  int columns[] = {0, 1, 0, 1, 0, 1};
+  for (int i = 0; i < 6; i++) {
+    Check("move", cf_make_move(game, columns[i]), CF_OK);
+  }
+  Check("bottom left cell", cf_get_cell(game, 5, 0), 1);
+  Check("cell next to it", cf_get_cell(game, 5, 1), 2);
+  Check("empty cell", cf_get_cell(game, 0, 0), 0);
+  Check("row off the board", cf_get_cell(game, 6, 0), CF_ERR_INVALID_ROW);
+  Check("column off the board", cf_get_cell(game, 0, -1), CF_ERR_INVALID_COLUMN);
+  Check("column too far", cf_make_move(game, 7), CF_ERR_INVALID_COLUMN);
+  Check("not finished", cf_is_finished(game), 0);
+  Check("no winner yet", cf_winner(game), 0);
+
+  Check("winning move", cf_make_move(game, 0), CF_OK);
+  Check("finished", cf_is_finished(game), 1);
+  Check("winner", cf_winner(game), 1);
+  Check("move after the end", cf_make_move(game, 3), CF_ERR_GAME_FINISHED);
+  cf_game_free(game);
+
+  /* A full column */
+  game = cf_game_new_sized(4, 4, 4);
+  int fill[] = {0, 0, 0, 0};
+  for (int i = 0; i < 4; i++) {
+    Check("filling", cf_make_move(game, fill[i]), CF_OK);
+  }
+  Check("full column", cf_make_move(game, 0), CF_ERR_COLUMN_FULL);
+  cf_game_free(game);
+
+  /* Bad sizes and null pointers */
+  if (cf_game_new_sized(2, 2, 4) != NULL) {
+    printf("FAIL tiny board: got a game\n");
+    failures++;
+  }
+  Check("null move", cf_make_move(NULL, 0), CF_ERR_NULL_GAME);
+  Check("null cell", cf_get_cell(NULL, 0, 0), CF_ERR_NULL_GAME);
+  Check("null player", cf_current_player(NULL), CF_ERR_NULL_GAME);
+  Check("null winner", cf_winner(NULL), CF_ERR_NULL_GAME);
+  Check("null finished", cf_is_finished(NULL), CF_ERR_NULL_GAME);
+  cf_game_free(NULL);
+
+  if (failures) {
+    return EXIT_FAILURE;
+  }
+  printf("all checks passed\n");
+  return EXIT_SUCCESS;
+}
@@ -0,0 +1,64 @@
+// Wraps the exports of the `wasm` feature (src/wasm.rs) in a Game class, so pages can play
+// without knowing about pointers or error codes.
+
+export async function Load(url) {
+  const { instance } = await WebAssembly.instantiateStreaming(fetch(url), {});
+  const wasm = instance.exports;
+
+  // Reads the message for an error code out of the module's memory
+  function Message(code) {
+    const length = wasm.error_message(code);
+    const bytes = new Uint8Array(wasm.memory.buffer, wasm.error_message_ptr(), length);
+    return new TextDecoder().decode(bytes);
+  }
+
+  class Game {
+    // A game on a width x height board, needing `connect` in a row to win
+    constructor(width = 7, height = 6, connect = 4) {
+      this.handle = wasm.game_new(width, height, connect);
+      if (this.handle === 0) {
+        throw new Error(`can't play connect ${connect} on a ${width}x${height} board`);
+      }
+    }
+
+    // Frees the game; it can't be used afterwards
+    free() {
+      wasm.game_free(this.handle);
+      this.handle = 0;
+    }
+
+    get width() { return wasm.game_width(this.handle); }
+    get height() { return wasm.game_height(this.handle); }
+    get currentPlayer() { return wasm.game_current_player(this.handle); }
+    get winner() { return wasm.game_winner(this.handle); }
+    get finished() { return wasm.game_is_finished(this.handle) !== 0; }
+
+    // Every cell row by row from the top, as the number of the player on it or 0
+    get board() {
+      const cells = new Uint8Array(wasm.memory.buffer, wasm.game_board(this.handle), this.width * this.height);
+      return Array.from(cells);
+    }
+
+    // Columns a piece can still be dropped into
+    get legalMoves() {
+      const columns = [];
+      for (let column = 0; column < this.width; column++) {
+        if (wasm.game_is_legal(this.handle, column)) columns.push(column);
+      }
+      return columns;
+    }
+
+    // Drops the current player's piece into the column (0 is the leftmost). Gives
+    // { ok: true, row, column, player, result } with result "continue", "win" or "draw",
+    // or { ok: false, code, error } when the move isn't allowed.
+    makeMove(column) {
+      const player = this.currentPlayer;
+      const code = wasm.game_make_move(this.handle, column);
+      if (code !== 0) return { ok: false, code, error: Message(code) };
+      const result = ["continue", "win", "draw"][wasm.game_result(this.handle)];
+      return { ok: true, row: wasm.game_last_row(this.handle), column, player, result };
+    }
+  }
+
+  return { Game };
+}
@@ -0,0 +1,81 @@
+/*
+ * C interface to the Connect Four game logic, matching src/ffi.rs. Build the library with
+ *   cargo rustc --lib --release --features ffi --crate-type cdylib
+ * and link against target/release/libConnectFour.so (ConnectFour.dll on Windows).
+ *
+ * Keep this file in step with src/ffi.rs when either changes.
+ */
+#ifndef CONNECTFOUR_H
+#define CONNECTFOUR_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes; everything below zero is an error */
+#define CF_OK                   0
+#define CF_ERR_GAME_FINISHED   -1  /* The game has already ended */
+#define CF_ERR_INVALID_COLUMN  -2  /* The column number is invalid */
+#define CF_ERR_COLUMN_FULL     -3  /* The selected column is full */
+#define CF_ERR_POPOUT_DISABLED -4  /* Popping pieces isn't part of this game's rules */
+#define CF_ERR_CANNOT_POP      -5  /* The bottom piece of the column isn't the current player's */
+#define CF_ERR_GRAVITY_ON      -6  /* Pieces can only be placed on any cell without gravity */
+#define CF_ERR_GRAVITY_OFF     -7  /* Pieces can't be dropped in games without gravity */
+#define CF_ERR_INVALID_ROW     -8  /* The row number is invalid */
+#define CF_ERR_CELL_OCCUPIED   -9  /* The selected cell already holds a piece */
+#define CF_ERR_BOMBS_DISABLED  -10 /* Bombs aren't part of this game's rules */
+#define CF_ERR_NO_BOMBS_LEFT   -11 /* The current player has already used their bombs */
+#define CF_ERR_NULL_GAME       -12 /* The game pointer was null */
+#define CF_ERR_NOTHING_TO_UNDO -13 /* No move has been played yet */
+#define CF_ERR_OUT_OF_TIME     -14 /* The game was lost on a clock that has run out */
+
+/* A game; only ever handled through a pointer */
+typedef struct CfGame CfGame;
+
+/* Starts a standard 7x6 game */
+CfGame *cf_game_new(void);
+
+/* Starts a game on a width x height board needing `connect` in a row to win, or gives NULL
+   when the rules don't make a valid game */
+CfGame *cf_game_new_sized(int width, int height, int connect);
+
+/* Frees a game; NULL is ignored */
+void cf_game_free(CfGame *game);
+
+/* Drops the current player's piece into the column (0 is the leftmost); gives CF_OK or an
+   error code */
+int cf_make_move(CfGame *game, int column);
+
+/* Player on a cell (row 0 is the top row): 0 for empty, 1 to 3 for a player, or an error code
+   when the cell is off the board */
+int cf_get_cell(const CfGame *game, int row, int column);
+
+int cf_width(const CfGame *game);
+int cf_height(const CfGame *game);
+
+/* Number of the player to move */
+int cf_current_player(const CfGame *game);
+
+/* Number of the winner, or 0 while nobody has won (including after a draw) */
+int cf_winner(const CfGame *game);
+
+/* 1 once the game is over, 0 while it goes on */
+int cf_is_finished(const CfGame *game);
+
+/* 1 if a piece can be dropped into the column now, 0 if not */
+int cf_is_legal(const CfGame *game, int column);
+
+/* Takes back the last move; gives CF_OK, CF_ERR_NOTHING_TO_UNDO or CF_ERR_OUT_OF_TIME */
+int cf_undo(CfGame *game);
+
+/* Writes the message for a status code into the buffer, cut short to fit and ended by a nul;
+   gives the message's full length, like snprintf */
+int cf_error_message(int code, char *buffer, size_t size);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
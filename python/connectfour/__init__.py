@@ -0,0 +1,156 @@
+"""Connect Four for Python, playing by the same rules as the terminal game.
+
+The rules live in the Rust library, reached through its C interface (src/ffi.rs), so build it
+once with the `python` feature first:
+
+    cargo rustc --lib --release --features python --crate-type cdylib
+
+The library is looked for in target/release, or wherever CONNECTFOUR_LIB points.
+
+    >>> from connectfour import Game
+    >>> game = Game()
+    >>> game.make_move(3)
+    >>> game.current_player
+    2
+"""
+
+import ctypes
+import os
+import sys
+from pathlib import Path
+
+__all__ = [
+    "Game", "MoveError", "GameFinished", "InvalidColumn", "ColumnFull", "NothingToUndo",
+]
+
+# Status codes from include/connectfour.h
+_OK = 0
+_NULL_GAME = -12
+_NOTHING_TO_UNDO = -13
+_OUT_OF_TIME = -14
+
+
+class MoveError(Exception):
+    """A move or undo the rules don't allow; `code` is its status code from the C interface."""
+
+    def __init__(self, message, code):
+        super().__init__(message)
+        self.code = code
+
+
+class GameFinished(MoveError):
+    """The game has already ended."""
+
+
+class InvalidColumn(MoveError, IndexError):
+    """The column isn't on the board."""
+
+
+class ColumnFull(MoveError):
+    """The column has no room left."""
+
+
+class NothingToUndo(MoveError):
+    """No move has been played yet."""
+
+
+_ERRORS = {-1: GameFinished, -2: InvalidColumn, -3: ColumnFull, _NOTHING_TO_UNDO: NothingToUndo}
+
+
+def _LibraryName():
+    if sys.platform == "win32":
+        return "ConnectFour.dll"
+    if sys.platform == "darwin":
+        return "libConnectFour.dylib"
+    return "libConnectFour.so"
+
+
+def _Load():
+    path = os.environ.get("CONNECTFOUR_LIB")
+    if path is None:
+        path = Path(__file__).resolve().parents[2] / "target" / "release" / _LibraryName()
+    lib = ctypes.CDLL(str(path))
+    game = ctypes.c_void_p
+    lib.cf_game_new_sized.argtypes = [ctypes.c_int, ctypes.c_int, ctypes.c_int]
+    lib.cf_game_new_sized.restype = game
+    lib.cf_game_free.argtypes = [game]
+    lib.cf_game_free.restype = None
+    lib.cf_make_move.argtypes = [game, ctypes.c_int]
+    lib.cf_get_cell.argtypes = [game, ctypes.c_int, ctypes.c_int]
+    lib.cf_is_legal.argtypes = [game, ctypes.c_int]
+    lib.cf_error_message.argtypes = [ctypes.c_int, ctypes.c_char_p, ctypes.c_size_t]
+    for name in ["cf_width", "cf_height", "cf_current_player", "cf_winner", "cf_is_finished", "cf_undo"]:
+        getattr(lib, name).argtypes = [game]
+    return lib
+
+
+_lib = _Load()
+
+
+def _Check(code):
+    if code >= _OK:
+        return code
+    size = _lib.cf_error_message(code, None, 0) + 1
+    buffer = ctypes.create_string_buffer(size)
+    _lib.cf_error_message(code, buffer, size)
+    raise _ERRORS.get(code, MoveError)(buffer.value.decode(), code)
+
+
+class Game:
+    """A game of Connect Four; players are numbered 1 and 2, columns from 0 at the left."""
+
+    def __init__(self, width=7, height=6, connect=4):
+        self._game = _lib.cf_game_new_sized(width, height, connect)
+        if not self._game:
+            raise ValueError(f"can't play connect {connect} on a {width}x{height} board")
+
+    def __del__(self):
+        if getattr(self, "_game", None):
+            _lib.cf_game_free(self._game)
+            self._game = None
+
+    @property
+    def width(self):
+        return _lib.cf_width(self._game)
+
+    @property
+    def height(self):
+        return _lib.cf_height(self._game)
+
+    def make_move(self, column):
+        """Drops the current player's piece into the column, raising MoveError if it can't."""
+        _Check(_lib.cf_make_move(self._game, column))
+
+    def undo(self):
+        """Takes back the last move, raising NothingToUndo before the first."""
+        _Check(_lib.cf_undo(self._game))
+
+    @property
+    def legal_moves(self):
+        """Columns a piece can be dropped into now."""
+        return [column for column in range(self.width) if _lib.cf_is_legal(self._game, column)]
+
+    @property
+    def board(self):
+        """Rows from the top, each a list of cells holding the player on it or 0."""
+        return [
+            [_Check(_lib.cf_get_cell(self._game, row, column)) for column in range(self.width)]
+            for row in range(self.height)
+        ]
+
+    @property
+    def current_player(self):
+        return _lib.cf_current_player(self._game)
+
+    @property
+    def winner(self):
+        """The winning player, or None while nobody has won (including after a draw)."""
+        return _lib.cf_winner(self._game) or None
+
+    @property
+    def finished(self):
+        return bool(_lib.cf_is_finished(self._game))
+
+    def __repr__(self):
+        rows = "\n".join(" ".join(".XO"[cell] for cell in row) for row in self.board)
+        return f"<Game {self.width}x{self.height}, player {self.current_player} to move>\n{rows}"
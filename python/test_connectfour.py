@@ -0,0 +1,63 @@
+"""Plays the Python bindings through a win and a draw. Run with pytest, or on its own:
+
+    cargo rustc --lib --release --features python --crate-type cdylib
+    python3 python/test_connectfour.py
+"""
+
+from connectfour import ColumnFull, Game, GameFinished, InvalidColumn, MoveError, NothingToUndo
+
+
+def test_win():
+    game = Game()
+    for column in [0, 1, 0, 1, 0, 1]:
+        game.make_move(column)
+    assert game.board[5][:2] == [1, 2]
+    assert game.winner is None and not game.finished
+    game.make_move(0)
+    assert game.winner == 1 and game.finished
+    assert game.legal_moves == []
+    try:
+        game.make_move(3)
+    except GameFinished as err:
+        assert err.code == -1
+    else:
+        raise AssertionError("moved after the game was won")
+
+
+def test_draw():
+    # On a 4x4 board with four in a row needed, this order fills every cell with no line
+    game = Game(4, 4, 4)
+    for column in [3, 2, 1, 0, 2, 0, 0, 0, 3, 3, 1, 2, 3, 1, 2, 1]:
+        game.make_move(column)
+    assert game.finished and game.winner is None
+    assert all(cell for row in game.board for cell in row)
+
+
+def test_errors_and_undo():
+    game = Game(4, 4, 4)
+    try:
+        game.undo()
+    except NothingToUndo:
+        pass
+    else:
+        raise AssertionError("undid a move before any was played")
+    for _ in range(4):
+        game.make_move(0)
+    assert game.legal_moves == [1, 2, 3]
+    for column, error in [(0, ColumnFull), (9, InvalidColumn), (-1, InvalidColumn)]:
+        try:
+            game.make_move(column)
+        except error as err:
+            assert isinstance(err, MoveError) and str(err)
+        else:
+            raise AssertionError(f"column {column} was accepted")
+    game.undo()
+    assert game.legal_moves == [0, 1, 2, 3]
+    assert game.current_player == 2
+
+
+if __name__ == "__main__":
+    for name, test in list(globals().items()):
+        if name.startswith("test_"):
+            test()
+            print("ok", name)
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use crate::bot::{Bot, WIN_SCORE};
+use crate::{Game, Move, MoveResult, Player, MAX_BOARD_SIZE};
+
+// Default drop in heuristic score that counts as a blunder
+pub const DEFAULT_THRESHOLD: i32 = 20;
+
+// Scores at least this far from zero are forced results rather than heuristic guesses
+const FORCED: i32 = WIN_SCORE - (MAX_BOARD_SIZE * MAX_BOARD_SIZE) as i32;
+
+// Struct describing how one move of a finished game held up
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveAnalysis {
+  pub MoveNumber: usize, // 1-based index of the move in the game
+  pub Player: Player,    // Who played it
+  pub Column: usize,     // Column that was played
+  pub Score: i32,        // Engine score of the played move, for the player who made it
+  pub BestColumn: usize, // Column the engine preferred
+  pub BestScore: i32,    // Engine score of the preferred column
+  pub IsBlunder: bool,   // Whether the move lost too much
+}
+
+// Enum naming what the engine's column would have done that a blunder didn't
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Better {
+  KeptTheWin,     // The game was won, and the blunder let the win go
+  AvoidedTheLoss, // The game wasn't lost before the blunder
+  WasBetter,      // It scored well above the move played
+}
+
+// Enum sorting a score into a forced result or a heuristic guess
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScoreKind {
+  Win,
+  Loss,
+  Heuristic(i32),
+}
+
+impl MoveAnalysis {
+  // What the engine's column would have done, if this move was a blunder
+  pub fn Better(&self) -> Option<Better> {
+    if !self.IsBlunder {
+      return None;
+    }
+    Some(if self.BestScore >= FORCED {
+      Better::KeptTheWin
+    } else if self.BestScore > -FORCED && self.Score <= -FORCED {
+      Better::AvoidedTheLoss
+    } else {
+      Better::WasBetter
+    })
+  }
+
+  // One-line verdict such as "Move 9 (column 2) was a blunder; column 4 kept the win"
+  pub fn Comment(&self) -> Option<String> {
+    let better = match self.Better()? {
+      Better::KeptTheWin => "kept the win",
+      Better::AvoidedTheLoss => "avoided the loss",
+      Better::WasBetter => "was better",
+    };
+    Some(format!(
+      "Move {} (column {}) was a blunder; column {} {}",
+      self.MoveNumber,
+      self.Column + 1,
+      self.BestColumn + 1,
+      better
+    ))
+  }
+}
+
+// Whether a score is a forced win or loss, or only the heuristic's guess
+pub fn Classify(score: i32) -> ScoreKind {
+  if score >= FORCED {
+    ScoreKind::Win
+  } else if score <= -FORCED {
+    ScoreKind::Loss
+  } else {
+    ScoreKind::Heuristic(score)
+  }
+}
+
+// Describes a score in words: "win", "loss" or the heuristic number
+pub fn DescribeScore(score: i32) -> String {
+  match Classify(score) {
+    ScoreKind::Win => "win".to_string(),
+    ScoreKind::Loss => "loss".to_string(),
+    ScoreKind::Heuristic(score) => format!("{:+}", score),
+  }
+}
+
+// Replays the moves of a game from an empty board of the same size and scores every move
+// with the search engine. Each position gets at most `budget` of search time, so long games
+// stay responsive.
+pub fn Analyze(original: &Game, budget: Duration, threshold: i32) -> Vec<MoveAnalysis> {
+  let bot = Bot::new(12).WithTimeLimit(budget);
+  let mut game = original.clone();
+  game.Reset();
+  let mut report = Vec::new();
+
+  for (index, &played) in original.History().iter().enumerate() {
+    let Some(best) = bot.Search(&game) else {
+      break; // Game already over, nothing left to analyze
+    };
+    let player = game.CurrentPlayer();
+    let Ok(outcome) = game.Play(played) else {
+      break; // The move list doesn't fit the rules
+    };
+    let score = match outcome.Result {
+      MoveResult::Win(_) => WIN_SCORE,
+      MoveResult::Draw => 0,
+      MoveResult::Continue => bot.Search(&game).map_or(0, |reply| -reply.Score),
+    };
+
+    // Throwing away a forced result is always a blunder; otherwise compare the numbers
+    let LostWin = best.Score >= FORCED && score < FORCED;
+    let AllowedLoss = best.Score > -FORCED && score <= -FORCED;
+    let IsBlunder = played != Move::Drop(best.BestMove)
+        && (LostWin || AllowedLoss || (score > -FORCED && best.Score - score > threshold));
+
+    report.push(MoveAnalysis {
+      MoveNumber: index + 1,
+      Player: player,
+      Column: played.Column(),
+      Score: score,
+      BestColumn: best.BestMove,
+      BestScore: best.Score,
+      IsBlunder,
+    });
+  }
+  report
+}
@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+
+use crate::json::{self, Value};
+use crate::save::{self, Field, LoadError};
+use crate::transcript::CivilDate;
+use crate::{Game, Player};
+
+// Version written into the games archive, bumped when the layout of an entry changes. Entries
+// written by an older version are read on, with what they lack worked out from the game kept
+// in each of them, so the archive never has to be converted.
+pub const ARCHIVE_VERSION: u64 = 1;
+
+// Struct holding one finished game of the archive, with what's wanted to list and find it
+#[derive(Clone)]
+pub struct Entry {
+  pub Id: u64,                     // Number the game is shown and looked up by, from 1
+  pub FinishedAt: u64,             // Seconds since 1970-01-01 UTC when the game ended
+  pub Players: Vec<String>,        // Names in turn order
+  pub Winner: Player,              // Player::None for a draw
+  pub Moves: usize,                // Moves played
+  pub TimeControl: Option<String>, // The clock the game was played with, if any
+  pub Settings: String,            // Board size and rules, e.g. "7x6, connect 4, PopOut"
+  pub MoveString: Option<String>,  // Moves as columns, e.g. "44435", for games that can be written so
+  pub Game: Game,                  // The whole game, to replay
+}
+
+impl Entry {
+  // When the game ended, e.g. "2026-10-15 18:04" in UTC
+  pub fn Date(&self) -> String {
+    let (year, month, day) = CivilDate(self.FinishedAt / 86_400);
+    let minutes = self.FinishedAt % 86_400 / 60;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, minutes / 60, minutes % 60)
+  }
+
+  // How the game ended, e.g. "Alice won" or "draw"
+  pub fn Result(&self) -> String {
+    match self.Winner {
+      Player::None => "draw".to_string(),
+      winner => format!("{} won", self.Players.get(winner as usize - 1).map_or("?", String::as_str)),
+    }
+  }
+
+  // Everything known about the game, for `games show`
+  pub fn Details(&self) -> String {
+    let mut text = format!("Game {}: {}\n", self.Id, self.Players.join(" vs "));
+    text += &format!("Played: {} UTC\n", self.Date());
+    text += &format!("Result: {}\n", self.Result());
+    text += &format!("Moves: {}\n", self.Moves);
+    text += &format!("Board: {}\n", self.Settings);
+    text += &format!("Clock: {}\n", self.TimeControl.as_deref().unwrap_or("none"));
+    if let Some(moves) = &self.MoveString {
+      text += &format!("Move string: {}\n", moves);
+    }
+    text
+  }
+}
+
+// Struct holding every finished game recorded, oldest first
+#[derive(Clone, Default)]
+pub struct Archive {
+  pub Games: Vec<Entry>,
+}
+
+impl Archive {
+  // Adds a finished game played by `players`, in turn order, that ended at `finished` seconds
+  // since 1970. Returns the number it's kept under; unfinished games aren't kept.
+  pub fn Add(&mut self, game: &Game, players: &[String], finished: u64) -> Option<u64> {
+    if !game.IsFinished() {
+      return None;
+    }
+    let id = self.Games.last().map_or(1, |last| last.Id + 1);
+    self.Games.push(Entry {
+      Id: id,
+      FinishedAt: finished,
+      Players: players[..game.Players().min(players.len())].to_vec(),
+      Winner: game.Winner(),
+      Moves: game.History().len(),
+      TimeControl: game.Clock().map(|clock| clock.Control().to_string()),
+      Settings: Settings(game),
+      MoveString: game.ToMoveString(),
+      Game: game.clone(),
+    });
+    Some(id)
+  }
+
+  // The game kept under the number, if there is one
+  pub fn Find(&self, id: u64) -> Option<&Entry> {
+    self.Games.iter().find(|entry| entry.Id == id)
+  }
+
+  // A table of the games for `games list`, newest first
+  pub fn List(&self) -> String {
+    if self.Games.is_empty() {
+      return "No games recorded yet\n".to_string();
+    }
+    let players: Vec<String> = self.Games.iter().map(|entry| entry.Players.join(" vs ")).collect();
+    let width = players.iter().map(|names| names.chars().count()).max().unwrap_or(0).max(7);
+    let mut text = format!("{:>5}  {:<16}  {:<width$}  {:<16}  {:>5}\n", "#", "Played (UTC)", "Players", "Result", "Moves");
+    for (entry, names) in self.Games.iter().zip(&players).rev() {
+      text += &format!(
+        "{:>5}  {:<16}  {:<width$}  {:<16}  {:>5}\n",
+        entry.Id,
+        entry.Date(),
+        names,
+        entry.Result(),
+        entry.Moves
+      );
+    }
+    text
+  }
+}
+
+// The board size and rules of a game, e.g. "7x6, connect 4, PopOut, cylinder"
+pub fn Settings(game: &Game) -> String {
+  let mut settings = vec![format!("{}x{}", game.Width(), game.Height()), format!("connect {}", game.WinLength())];
+  if game.Players() != 2 {
+    settings.push(format!("{} players", game.Players()));
+  }
+  let variants = [
+    (game.PopOut(), "PopOut"),
+    (!game.Gravity(), "no gravity"),
+    (game.Wrap(), "cylinder"),
+    (game.HasBombs(), "bombs"),
+    (game.Handicap().is_some(), "handicap"),
+  ];
+  settings.extend(variants.iter().filter(|(on, _)| *on).map(|&(_, name)| name.to_string()));
+  settings.join(", ")
+}
+
+// Where the archive is kept unless `--db` says otherwise, next to the statistics
+pub fn ArchivePath() -> Option<PathBuf> {
+  save::DataDir().map(|folder| folder.join("games.json"))
+}
+
+// Writes the archive as a JSON document, each game with its save file inside
+pub fn ToJson(archive: &Archive) -> String {
+  let optional = |text: &Option<String>| text.as_deref().map_or("null".to_string(), json::Quote);
+  let games: Vec<String> = archive
+      .Games
+      .iter()
+      .map(|entry| {
+        let players: Vec<String> = entry.Players.iter().map(|name| json::Quote(name)).collect();
+        let mut text = String::from("    {\n");
+        text += &format!("      \"id\": {},\n", entry.Id);
+        text += &format!("      \"finished_at\": {},\n", entry.FinishedAt);
+        text += &format!("      \"players\": [{}],\n", players.join(", "));
+        text += &format!("      \"winner\": {},\n", entry.Winner as u8);
+        text += &format!("      \"moves\": {},\n", entry.Moves);
+        text += &format!("      \"time_control\": {},\n", optional(&entry.TimeControl));
+        text += &format!("      \"settings\": {},\n", json::Quote(&entry.Settings));
+        text += &format!("      \"move_string\": {},\n", optional(&entry.MoveString));
+        text += &format!("      \"game\": {}", save::ToJson(&entry.Game).trim_end().replace('\n', "\n      "));
+        text += "\n    }";
+        text
+      })
+      .collect();
+  let mut text = String::from("{\n");
+  text += &format!("  \"version\": {},\n", ARCHIVE_VERSION);
+  text += &format!("  \"games\": [\n{}\n  ]\n", games.join(",\n"));
+  text += "}\n";
+  text
+}
+
+// Reads an archive written by ToJson, by this version or an older one
+pub fn FromJson(text: &str) -> Result<Archive, LoadError> {
+  let document = json::Parse(text).map_err(LoadError::Syntax)?;
+  let version = Field(&document, "version", Value::AsU64)?;
+  if version > ARCHIVE_VERSION {
+    return Err(LoadError::UnsupportedVersion(version));
+  }
+  let mut archive = Archive::default();
+  for entry in Field(&document, "games", Value::AsArray)? {
+    let game = save::FromValue(Field(entry, "game", Some)?)?;
+    let mut players = Vec::new();
+    for name in Field(entry, "players", Value::AsArray)? {
+      players.push(name.AsStr().ok_or(LoadError::MissingField("players"))?.to_string());
+    }
+    let text = |name| entry.Get(name).and_then(Value::AsStr).map(str::to_string);
+    archive.Games.push(Entry {
+      Id: Field(entry, "id", Value::AsU64)?,
+      FinishedAt: Field(entry, "finished_at", Value::AsU64)?,
+      Players: players,
+      // What's kept about the game alongside it is worked out again if it's missing
+      Winner: game.Winner(),
+      Moves: entry.Get("moves").and_then(Value::AsUsize).unwrap_or(game.History().len()),
+      TimeControl: text("time_control").or_else(|| game.Clock().map(|clock| clock.Control().to_string())),
+      Settings: text("settings").unwrap_or_else(|| Settings(&game)),
+      MoveString: text("move_string").or_else(|| game.ToMoveString()),
+      Game: game,
+    });
+  }
+  Ok(archive)
+}
+
+// Reads the archive file; a file that doesn't exist yet holds no games
+pub fn LoadFile(path: impl AsRef<Path>) -> Result<Archive, LoadError> {
+  match std::fs::read_to_string(path) {
+    Ok(text) => FromJson(&text),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Archive::default()),
+    Err(err) => Err(LoadError::Io(err)),
+  }
+}
+
+// Writes the archive file whole or not at all, the same way as the statistics
+pub fn SaveFile(archive: &Archive, path: impl AsRef<Path>) -> std::io::Result<()> {
+  let path = path.as_ref();
+  if let Some(folder) = path.parent() {
+    std::fs::create_dir_all(folder)?;
+  }
+  let temporary = path.with_extension(format!("json.{}.tmp", std::process::id()));
+  std::fs::write(&temporary, ToJson(archive))?;
+  std::fs::rename(&temporary, path).inspect_err(|_| {
+    let _ = std::fs::remove_file(&temporary);
+  })
+}
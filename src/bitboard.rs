@@ -0,0 +1,205 @@
+use crate::{Board, MoveError, Player, BOARD_HEIGHT, BOARD_WIDTH};
+
+// Bits per column: BOARD_HEIGHT cells plus an empty sentinel bit on top.
+// The sentinel keeps shifted lines from wrapping from one column into the next.
+pub(crate) const H1: usize = BOARD_HEIGHT + 1;
+
+const fn BottomMask() -> u64 {
+  let mut mask = 0;
+  let mut column = 0;
+  while column < BOARD_WIDTH {
+    mask |= 1 << (column * H1);
+    column += 1;
+  }
+  mask
+}
+
+// Bottom cell of every column
+pub(crate) const BOTTOM: u64 = BottomMask();
+
+// Every playable cell, without the sentinel bits
+pub(crate) const BOARD_MASK: u64 = BOTTOM * ((1 << BOARD_HEIGHT) - 1);
+
+// Every cell of a column
+pub(crate) fn ColumnMask(column: usize) -> u64 {
+  ((1 << BOARD_HEIGHT) - 1) << (column * H1)
+}
+
+// Highest cell of a column
+pub(crate) fn TopCell(column: usize) -> u64 {
+  1 << (BOARD_HEIGHT - 1 + column * H1)
+}
+
+// Lowest cell of a column
+pub(crate) fn BottomCell(column: usize) -> u64 {
+  1 << (column * H1)
+}
+
+// Bit for a cell, with rows numbered like the array board (0 is the top row)
+fn Cell(row: usize, column: usize) -> u64 {
+  1 << (column * H1 + BOARD_HEIGHT - 1 - row)
+}
+
+// Returns true if the stones contain four in a row in any direction
+pub(crate) fn HasFour(stones: u64) -> bool {
+  // Vertical, horizontal and both diagonals
+  [1, H1, H1 - 1, H1 + 1].into_iter().any(|shift| {
+    let pairs = stones & (stones >> shift);
+    pairs & (pairs >> (2 * shift)) != 0
+  })
+}
+
+// Struct representing the standard 7x6 board as one bit mask per player
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BitBoard {
+  Stones: [u64; 2],                // Cells taken by Player One and Player Two
+  Heights: [usize; BOARD_WIDTH],   // Number of pieces in each column
+}
+
+impl BitBoard {
+  // Creates an empty board
+  pub fn new() -> BitBoard {
+    BitBoard::default()
+  }
+
+  // Converts a standard-size board; floating pieces are kept where they are
+  pub fn FromBoard(board: &Board) -> BitBoard {
+    let mut bits = BitBoard::new();
+    for (row, cells) in board.Rows().enumerate() {
+      for (column, &cell) in cells.iter().enumerate() {
+        let player = Player::FromInt(cell);
+        if player != Player::None {
+          bits.Stones[player as usize - 1] |= Cell(row, column);
+          bits.Heights[column] = bits.Heights[column].max(BOARD_HEIGHT - row);
+        }
+      }
+    }
+    bits
+  }
+
+  // Converts back to the array form used for display
+  pub fn ToBoard(&self) -> Board {
+    let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+    for row in 0..BOARD_HEIGHT {
+      for column in 0..BOARD_WIDTH {
+        board[row][column] = self.At(row, column) as u8;
+      }
+    }
+    board
+  }
+
+  // The player occupying a cell (row 0 is the top row)
+  pub fn At(&self, row: usize, column: usize) -> Player {
+    let bit = Cell(row, column);
+    if self.Stones[0] & bit != 0 {
+      Player::One
+    } else if self.Stones[1] & bit != 0 {
+      Player::Two
+    } else {
+      Player::None
+    }
+  }
+
+  // Cells taken by one player
+  pub fn Stones(&self, player: Player) -> u64 {
+    match player {
+      Player::None => 0,
+      _ => self.Stones[player as usize - 1],
+    }
+  }
+
+  // Cells taken by either player
+  pub fn Occupied(&self) -> u64 {
+    self.Stones[0] | self.Stones[1]
+  }
+
+  // Number of pieces in a column
+  pub fn Height(&self, column: usize) -> usize {
+    self.Heights[column]
+  }
+
+  // Total number of pieces on the board
+  pub fn Count(&self) -> usize {
+    self.Heights.iter().sum()
+  }
+
+  // Returns true if the column exists and has at least one empty cell
+  pub fn CanPlay(&self, column: usize) -> bool {
+    column < BOARD_WIDTH && self.Heights[column] < BOARD_HEIGHT
+  }
+
+  // Drops a piece for `player` and returns the row it landed in (0 is the top row)
+  pub fn MakeMove(&mut self, column: usize, player: Player) -> Result<usize, MoveError> {
+    if column >= BOARD_WIDTH {
+      return Err(MoveError::InvalidColumn { Given: column, Max: BOARD_WIDTH - 1 });
+    }
+    if !self.CanPlay(column) {
+      return Err(MoveError::ColumnFull { Column: column });
+    }
+    let row = BOARD_HEIGHT - 1 - self.Heights[column];
+    if player != Player::None {
+      self.Stones[player as usize - 1] |= Cell(row, column);
+    }
+    self.Heights[column] += 1;
+    Ok(row)
+  }
+
+  // Removes the top piece of a column and returns its owner
+  pub fn Undo(&mut self, column: usize) -> Option<Player> {
+    if column >= BOARD_WIDTH || self.Heights[column] == 0 {
+      return None;
+    }
+    self.Heights[column] -= 1;
+    let row = BOARD_HEIGHT - 1 - self.Heights[column];
+    let player = self.At(row, column);
+    self.Stones.iter_mut().for_each(|stones| *stones &= !Cell(row, column));
+    Some(player)
+  }
+
+  // Returns true if the player has four in a row anywhere on the board
+  pub fn HasWon(&self, player: Player) -> bool {
+    HasFour(self.Stones(player))
+  }
+
+  // The player with four in a row, if any
+  pub fn Winner(&self) -> Player {
+    [Player::One, Player::Two]
+        .into_iter()
+        .find(|&player| self.HasWon(player))
+        .unwrap_or(Player::None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rng::Rng;
+  use crate::{Game, MoveResult};
+
+  // Plays random legal games, checking the bitboard's winner against the array board's scan
+  // after every move, and that the bitboard kept move by move matches the board
+  #[test]
+  fn WinnerAgreesWithScanForWinner() {
+    let mut rng = Rng::new(16);
+    for _ in 0..500 {
+      let mut game = Game::default();
+      let mut bits = BitBoard::new();
+      while !game.IsFinished() {
+        let column = rng.Pick(&game.LegalMoves());
+        let mover = game.CurrentPlayer();
+        let outcome = game.MakeMove(column).unwrap();
+        assert_eq!(bits.MakeMove(column, mover), Ok(outcome.Row));
+        assert_eq!(bits, BitBoard::FromBoard(game.Board()));
+        assert_eq!(bits.ToBoard(), *game.Board());
+        assert_eq!(bits.Winner(), game.ScanForWinner(), "{:?}", game.History());
+        let won = matches!(outcome.Result, MoveResult::Win(_));
+        assert_eq!(bits.Winner() != Player::None, won, "{:?}", game.History());
+      }
+      // Taking every move back empties the board again
+      for &played in game.History().iter().rev() {
+        bits.Undo(played.Column()).unwrap();
+      }
+      assert_eq!(bits, BitBoard::new());
+    }
+  }
+}
@@ -0,0 +1,70 @@
+use std::ops::{Index, IndexMut};
+
+// Struct representing the grid of cells, stored row by row (row 0 is the top row).
+// Indexing with a row number gives that row as a slice, so cells read as board[row][column].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Board {
+  Width: usize,    // Number of columns
+  Height: usize,   // Number of rows
+  Cells: Vec<u8>,  // Player number in each cell, 0 for empty
+}
+
+impl Board {
+  // Creates an empty board of the given size
+  pub fn new(width: usize, height: usize) -> Board {
+    Board { Width: width, Height: height, Cells: vec![0; width * height] }
+  }
+
+  pub fn Width(&self) -> usize {
+    self.Width
+  }
+
+  pub fn Height(&self) -> usize {
+    self.Height
+  }
+
+  // Iterates over the rows from top to bottom
+  pub fn Rows(&self) -> std::slice::Chunks<'_, u8> {
+    self.Cells.chunks(self.Width)
+  }
+
+  // Every cell, row by row from the top, as a player number (0 for empty)
+  pub fn Cells(&self) -> &[u8] {
+    &self.Cells
+  }
+
+  // Empties every cell
+  pub fn Clear(&mut self) {
+    self.Cells.iter_mut().for_each(|cell| *cell = 0);
+  }
+}
+
+impl Index<usize> for Board {
+  type Output = [u8];
+
+  fn index(&self, row: usize) -> &[u8] {
+    &self.Cells[row * self.Width..(row + 1) * self.Width]
+  }
+}
+
+impl IndexMut<usize> for Board {
+  fn index_mut(&mut self, row: usize) -> &mut [u8] {
+    &mut self.Cells[row * self.Width..(row + 1) * self.Width]
+  }
+}
+
+impl<'a> IntoIterator for &'a Board {
+  type Item = &'a [u8];
+  type IntoIter = std::slice::Chunks<'a, u8>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.Rows()
+  }
+}
+
+// The board in plain text, as in transcripts: a row of letters per line, column numbers below
+impl std::fmt::Display for Board {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", crate::transcript::PlainBoard(self))
+  }
+}
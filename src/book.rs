@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::{Game, BOARD_WIDTH};
+
+// Built-in opening lines: the moves played so far (columns 1-7) and the recommended reply.
+// Perfect play opens in the center, and the center is also the best answer to any first move.
+const EMBEDDED: &[(&str, usize)] = &[
+  ("", 4),
+  ("1", 4),
+  ("2", 4),
+  ("3", 4),
+  ("4", 4),
+  ("5", 4),
+  ("6", 4),
+  ("7", 4),
+  ("44", 4),
+  ("4433", 4),
+  ("4434", 5),
+  ("4443", 4),
+  ("4445", 4),
+  ("4454", 3),
+  ("4455", 4),
+  ("4444", 4),
+  ("444444", 3),
+];
+
+// Enum representing problems reading a book file
+#[derive(Debug)]
+pub enum BookError {
+  Io(std::io::Error),              // The file couldn't be read
+  BadLine { Line: usize, Text: String }, // A line isn't "<moves> <column>"
+}
+
+impl std::fmt::Display for BookError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BookError::Io(err) => write!(f, "Could not read book: {}", err),
+      BookError::BadLine { Line, Text } => write!(f, "Bad book entry on line {}: {}", Line, Text),
+    }
+  }
+}
+
+// Struct representing an opening book mapping move sequences to recommended columns
+#[derive(Clone, Debug, Default)]
+pub struct Book {
+  Entries: HashMap<String, usize>, // Move sequence (columns 1-9) -> column to play (0-based)
+}
+
+impl Book {
+  // The small book compiled into the program
+  pub fn Embedded() -> Book {
+    let mut book = Book::default();
+    for &(moves, column) in EMBEDDED {
+      book.Entries.insert(moves.to_string(), column - 1);
+    }
+    book
+  }
+
+  // Loads a book file with one "<moves> <column>" entry per line, e.g. "4453 3".
+  // Blank lines and lines starting with '#' are ignored; "-" stands for the empty board.
+  pub fn Load(path: &str) -> Result<Book, BookError> {
+    let text = fs::read_to_string(path).map_err(BookError::Io)?;
+    let mut book = Book::default();
+    for (index, line) in text.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let bad = || BookError::BadLine { Line: index + 1, Text: line.to_string() };
+      let mut parts = line.split_whitespace();
+      let (Some(moves), Some(column), None) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(bad());
+      };
+      let moves = if moves == "-" { "" } else { moves };
+      let column: usize = column.parse().map_err(|_| bad())?;
+      if !moves.chars().all(|c| c.is_ascii_digit()) || !(1..=BOARD_WIDTH).contains(&column) {
+        return Err(bad());
+      }
+      book.Entries.insert(moves.to_string(), column - 1);
+    }
+    Ok(book)
+  }
+
+  // Number of positions in the book
+  pub fn Len(&self) -> usize {
+    self.Entries.len()
+  }
+
+  // Recommended column for the game's position, if the book knows it.
+  // Book lines are for classic Connect 4 from the empty board, so other sizes, win lengths
+  // and set-up positions never match.
+  pub fn Lookup(&self, game: &Game) -> Option<usize> {
+    if !game.IsStandard() || game.IsSetUp() {
+      return None;
+    }
+    let key: String = game.History().iter().map(|played| (played.Column() + 1).to_string()).collect();
+    self.Entries
+        .get(&key)
+        .copied()
+        .filter(|&column| game.IsColumnPlayable(column))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Board, Player};
+
+  // A book loaded from the given file contents
+  fn Loaded(name: &str, text: &str) -> Result<Book, BookError> {
+    let path = std::env::temp_dir().join(format!("ConnectFour-book-test-{}-{}.txt", name, std::process::id()));
+    fs::write(&path, text).unwrap();
+    let book = Book::Load(path.to_str().unwrap());
+    let _ = fs::remove_file(&path);
+    book
+  }
+
+  #[test]
+  fn EmbeddedLines() {
+    let book = Book::Embedded();
+    assert_eq!(book.Len(), EMBEDDED.len());
+    assert_eq!(book.Lookup(&Game::default()), Some(3));
+    assert_eq!(book.Lookup(&Game::FromMoveString("4454").unwrap()), Some(2));
+    assert_eq!(book.Lookup(&Game::FromMoveString("4456").unwrap()), None);
+  }
+
+  #[test]
+  fn OnlyClassicGamesFromTheEmptyBoardMatch() {
+    let book = Book::Embedded();
+    assert_eq!(book.Lookup(&Game::new(9, 7).unwrap()), None);
+    assert_eq!(book.Lookup(&Game::default().WithPopOut(true)), None);
+    let mut board = Board::new(7, 6);
+    board[5][3] = 1;
+    board[4][3] = 2;
+    assert_eq!(book.Lookup(&Game::FromPosition(board, Player::One).unwrap()), None);
+  }
+
+  #[test]
+  fn LoadReadsEntries() {
+    let book = Loaded("good", "# my lines\n- 3\n\n  4 5  \n444444 4\n").unwrap();
+    assert_eq!(book.Len(), 3);
+    assert_eq!(book.Lookup(&Game::default()), Some(2));
+    assert_eq!(book.Lookup(&Game::FromMoveString("4").unwrap()), Some(4));
+    // A full column is never recommended
+    assert_eq!(book.Lookup(&Game::FromMoveString("444444").unwrap()), None);
+  }
+
+  #[test]
+  fn LoadRejectsBadLines() {
+    for (text, line) in [("4 5\n45\n", 2), ("4 8\n", 1), ("4 0\n", 1), ("4x 5\n", 1), ("4 5 6\n", 1)] {
+      assert!(matches!(Loaded("bad", text), Err(BookError::BadLine { Line, .. }) if Line == line), "{:?}", text);
+    }
+    assert!(matches!(Book::Load("/nonexistent/book.txt"), Err(BookError::Io(_))));
+  }
+}
@@ -0,0 +1,578 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::book::Book;
+use crate::engine::Engine;
+use crate::log;
+use crate::rng::Rng;
+use crate::transposition::{Bound, TranspositionTable};
+use crate::{Game, MoveOutcome, MoveResult, Player};
+
+// Score awarded for a won position, large enough to dominate any heuristic value
+pub const WIN_SCORE: i32 = 1_000_000;
+
+// Struct summarizing a finished search
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchResult {
+  pub BestMove: usize, // Column the engine recommends
+  pub Score: i32,      // Evaluation for the side to move (WIN_SCORE or more is a forced win)
+  pub Depth: usize,    // Deepest fully completed iteration
+  pub Nodes: u64,      // Positions visited across all iterations
+}
+
+// Struct representing a computer opponent that picks moves with an alpha-beta search
+pub struct Bot {
+  MaxDepth: usize,              // Deepest iteration to search
+  TimeLimit: Option<Duration>, // Optional wall-clock budget per move
+  Blunders: Option<(f64, Rng)>, // Chance of skipping the search and playing a careless move
+  Book: Option<Book>,           // Opening book consulted before searching
+  Tables: RefCell<Vec<TranspositionTable>>, // One transposition table per search thread
+  Stop: Option<Arc<AtomicBool>>, // Set from another thread to end the search early
+}
+
+impl Default for Bot {
+  fn default() -> Self {
+    Self::new(8)
+  }
+}
+
+impl Bot {
+  // Creates a bot that searches up to the given number of plies
+  pub fn new(depth: usize) -> Bot {
+    Bot {
+      MaxDepth: depth.max(1),
+      TimeLimit: None,
+      Blunders: None,
+      Book: None,
+      Tables: RefCell::new(vec![TranspositionTable::default()]),
+      Stop: None,
+    }
+  }
+
+  // Replaces the transposition tables; a table with no entries turns them off
+  pub fn WithTable(mut self, table: TranspositionTable) -> Bot {
+    let threads = self.Threads();
+    self.Tables = RefCell::new(vec![table; threads]);
+    self
+  }
+
+  // Searches the root moves on several threads, each with its own copy of the table.
+  // Every root move then gets a full-window search, so the chosen move and score don't
+  // depend on the number of threads, only the node count does.
+  pub fn WithThreads(mut self, threads: usize) -> Bot {
+    let table = self.Tables.get_mut()[0].clone();
+    self.Tables = RefCell::new(vec![table; threads.max(1)]);
+    self
+  }
+
+  pub fn Threads(&self) -> usize {
+    self.Tables.borrow().len()
+  }
+
+  // Stops deepening once the time budget is spent, keeping the best move found so far
+  pub fn WithTimeLimit(mut self, limit: Duration) -> Bot {
+    self.TimeLimit = Some(limit);
+    self
+  }
+
+  // Stops deepening as soon as the flag is set, keeping the best move found so far, so a
+  // search running on another thread can be cut short
+  pub fn WithStop(mut self, stop: Arc<AtomicBool>) -> Bot {
+    self.Stop = Some(stop);
+    self
+  }
+
+  // Makes the bot occasionally play a careless move instead of searching.
+  // Careless moves still take immediate wins and block immediate losses,
+  // but ignore anything deeper.
+  pub fn WithBlunders(mut self, probability: f64, seed: u64) -> Bot {
+    self.Blunders = Some((probability.clamp(0.0, 1.0), Rng::new(seed)));
+    self
+  }
+
+  // Plays straight from the opening book whenever it knows the position
+  pub fn WithBook(mut self, book: Book) -> Bot {
+    self.Book = Some(book);
+    self
+  }
+
+  pub fn MaxDepth(&self) -> usize {
+    self.MaxDepth
+  }
+
+  // Picks a move that only looks one reply ahead
+  fn CarelessMove(game: &Game, rng: &mut Rng) -> Option<usize> {
+    let me = game.CurrentPlayer();
+    let moves = game.LegalMoves();
+    if let Some(&column) = moves.iter().find(|&&column| game.WouldWin(column, me)) {
+      return Some(column);
+    }
+    if let Some(&column) = moves.iter().find(|&&column| game.WouldWin(column, me.Other())) {
+      return Some(column);
+    }
+
+    // Avoid handing the opponent a win on the very next move
+    let safe: Vec<usize> = moves
+        .iter()
+        .copied()
+        .filter(|&column| {
+          let mut scratch = game.clone();
+          scratch.MakeMove(column).is_ok()
+              && !scratch.LegalMoves().iter().any(|&reply| scratch.WouldWin(reply, me.Other()))
+        })
+        .collect();
+    match (safe.is_empty(), moves.is_empty()) {
+      (_, true) => None,
+      (true, false) => Some(rng.Pick(&moves)),
+      (false, false) => Some(rng.Pick(&safe)),
+    }
+  }
+
+  // Runs an iterative deepening search, returning None if the game has no legal moves
+  pub fn Search(&self, game: &Game) -> Option<SearchResult> {
+    self.SearchReporting(game, &mut |_, _| ())
+  }
+
+  // Runs the search like Search, calling `report` after each completed iteration with its
+  // result and the principal variation: the moves both sides are expected to play, best first
+  pub fn SearchReporting(&self, game: &Game, report: &mut dyn FnMut(&SearchResult, &[usize])) -> Option<SearchResult> {
+    let mut moves = OrderedMoves(game);
+    if moves.is_empty() {
+      return None;
+    }
+
+    let started = Instant::now();
+    let mut tables = self.Tables.borrow_mut();
+    tables.iter_mut().for_each(TranspositionTable::NewSearch);
+    let deadline = self.TimeLimit.map(|limit| Instant::now() + limit);
+    let mut nodes = 0;
+    let mut best = SearchResult { BestMove: moves[0], Score: 0, Depth: 0, Nodes: 0 };
+
+    for depth in 1..=self.MaxDepth {
+      let stop = self.Stop.as_deref();
+      let (result, visited) = SearchRoot(&mut tables, game, &moves, depth, deadline, stop);
+      nodes += visited;
+      let Some((column, score)) = result else {
+        break; // Out of time or stopped; keep the last completed iteration
+      };
+      best = SearchResult { BestMove: column, Score: score, Depth: depth, Nodes: nodes };
+      report(&best, &PrincipalVariation(&tables[0], game, column, depth));
+
+      // Search the previous best move first next time round
+      moves.retain(|&other| other != column);
+      moves.insert(0, column);
+
+      if score.abs() >= WIN_SCORE - (game.Width() * game.Height()) as i32 {
+        break; // A forced result was found, deeper searches can't change it
+      }
+    }
+    best.Nodes = nodes;
+    log::Debug(format_args!(
+      "Minimax searched to depth {} of {} in {:?}: column {}, score {}, {} nodes",
+      best.Depth,
+      self.MaxDepth,
+      started.elapsed(),
+      best.BestMove + 1,
+      best.Score,
+      nodes
+    ));
+    Some(best)
+  }
+}
+
+impl Engine for Bot {
+  fn ChooseMove(&mut self, game: &Game) -> Option<usize> {
+    if let Some((probability, rng)) = &mut self.Blunders {
+      if rng.NextF64() < *probability {
+        let careless = Self::CarelessMove(game, rng);
+        log::Debug(format_args!("Minimax skips the search and plays carelessly: column {:?}", careless.map(|column| column + 1)));
+        return careless;
+      }
+    }
+    if let Some(column) = self.Book.as_ref().and_then(|book| book.Lookup(game)) {
+      log::Debug(format_args!("Minimax plays column {} from the opening book", column + 1));
+      return Some(column);
+    }
+    self.Search(game).map(|result| result.BestMove)
+  }
+
+  fn Name(&self) -> String {
+    format!("Minimax (depth {})", self.MaxDepth)
+  }
+
+  fn LimitTime(&mut self, limit: Duration) {
+    self.TimeLimit = Some(limit);
+  }
+}
+
+// Searches every root move to the given depth, on one thread per table.
+// Returns the best move unless time ran out, and the number of positions visited.
+fn SearchRoot(
+  tables: &mut [TranspositionTable],
+  game: &Game,
+  moves: &[usize],
+  depth: usize,
+  deadline: Option<Instant>,
+  stop: Option<&AtomicBool>,
+) -> (Option<(usize, i32)>, u64) {
+  if let [table] = tables {
+    let mut search = Searcher { Nodes: 0, Deadline: deadline, Stop: stop, Aborted: false, Table: table };
+    let mut scratch = game.clone(); // Searched positions are played on a copy
+    let result = search.Root(&mut scratch, moves, depth);
+    return (result, search.Nodes);
+  }
+
+  // Threads take the next unsearched root move until none are left
+  let next = AtomicUsize::new(0);
+  let scores = Mutex::new(vec![None; moves.len()]);
+  let mut nodes = 0;
+  let mut aborted = false;
+  thread::scope(|scope| {
+    let workers: Vec<_> = tables
+        .iter_mut()
+        .map(|table| {
+          let (next, scores) = (&next, &scores);
+          scope.spawn(move || {
+            let mut search = Searcher { Nodes: 0, Deadline: deadline, Stop: stop, Aborted: false, Table: table };
+            let mut scratch = game.clone();
+            loop {
+              let index = next.fetch_add(1, Ordering::Relaxed);
+              if index >= moves.len() {
+                break;
+              }
+              let score = search.ScoreMove(&mut scratch, moves[index], depth, -i32::MAX, i32::MAX);
+              if search.Aborted {
+                break;
+              }
+              scores.lock().unwrap()[index] = Some(score);
+            }
+            (search.Nodes, search.Aborted)
+          })
+        })
+        .collect();
+    for worker in workers {
+      let (visited, stopped) = worker.join().expect("search thread panicked");
+      nodes += visited;
+      aborted |= stopped;
+    }
+  });
+  if aborted {
+    return (None, nodes);
+  }
+
+  // Ties go to the earlier, better ordered column, as in the single-threaded search
+  let mut best: Option<(usize, i32)> = None;
+  for (&column, score) in moves.iter().zip(scores.into_inner().unwrap()) {
+    let score = score.expect("every root move was searched");
+    if best.is_none_or(|(_, BestScore)| score > BestScore) {
+      best = Some((column, score));
+    }
+  }
+  (best, nodes)
+}
+
+// Mutable state for a single search
+struct Searcher<'a> {
+  Nodes: u64,                       // Positions visited so far
+  Deadline: Option<Instant>,       // When to give up
+  Stop: Option<&'a AtomicBool>,     // Set to give up before the deadline
+  Aborted: bool,                    // Set once the deadline passes or the search is stopped
+  Table: &'a mut TranspositionTable, // Results of positions searched before
+}
+
+impl Searcher<'_> {
+  // Searches every root move to the given depth, returning the best one unless aborted
+  fn Root(&mut self, game: &mut Game, moves: &[usize], depth: usize) -> Option<(usize, i32)> {
+    let mut best: Option<(usize, i32)> = None;
+    let mut alpha = -i32::MAX;
+    for &column in moves {
+      // Only a move scoring above the best so far needs its exact score
+      let score = self.ScoreMove(game, column, depth, alpha, i32::MAX);
+      if self.Aborted {
+        return None;
+      }
+      // Ties go to the earlier, better ordered column
+      if best.is_none_or(|(_, BestScore)| score > BestScore) {
+        best = Some((column, score));
+        alpha = alpha.max(score);
+      }
+    }
+    best
+  }
+
+  // Plays a move and scores it for the player making it
+  fn ScoreMove(&mut self, game: &mut Game, column: usize, depth: usize, alpha: i32, beta: i32) -> i32 {
+    let outcome = game.MakeMove(column).expect("legal move");
+    let score = match outcome.Result {
+      // Faster wins score better
+      MoveResult::Win(_) => WIN_SCORE + depth as i32,
+      MoveResult::Draw => 0,
+      MoveResult::Continue => -self.Negamax(game, depth - 1, -beta, -alpha),
+    };
+    game.UndoMove().expect("move was just made");
+    score
+  }
+
+  // Scores the position for the side to move
+  fn Negamax(&mut self, game: &mut Game, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+    self.Nodes += 1;
+    if self.Nodes.is_multiple_of(1024)
+        && (self.Deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            || self.Stop.is_some_and(|stop| stop.load(Ordering::Relaxed)))
+    {
+      self.Aborted = true;
+    }
+    if self.Aborted {
+      return 0; // Result is thrown away
+    }
+    if depth == 0 {
+      return Evaluate(game, game.CurrentPlayer());
+    }
+
+    // Only results searched to exactly this depth are reused, so the table never changes
+    // the outcome of a search; results from other depths still suggest the move to try first
+    let mut moves = OrderedMoves(game);
+    let OriginalAlpha = alpha;
+    if let Some(entry) = self.Table.Probe(game.Hash()) {
+      if entry.Depth as usize == depth {
+        match entry.Bound {
+          Bound::Exact => return entry.Score,
+          Bound::Lower if entry.Score >= beta => return entry.Score,
+          Bound::Upper if entry.Score <= alpha => return entry.Score,
+          _ => {}
+        }
+      }
+      if let Some(index) = moves.iter().position(|&column| column == entry.BestMove as usize) {
+        let column = moves.remove(index);
+        moves.insert(0, column);
+      }
+    }
+
+    let mut best = (-i32::MAX, moves[0]);
+    for column in moves {
+      let score = self.ScoreMove(game, column, depth, alpha, beta);
+      if score > best.0 {
+        best = (score, column);
+      }
+      alpha = alpha.max(score);
+      if alpha >= beta {
+        break; // The opponent will never allow this line
+      }
+    }
+    if !self.Aborted {
+      let bound = if best.0 <= OriginalAlpha {
+        Bound::Upper
+      } else if best.0 >= beta {
+        Bound::Lower
+      } else {
+        Bound::Exact
+      };
+      self.Table.Store(game.Hash(), depth as u8, best.0, bound, best.1 as u8);
+    }
+    best.0
+  }
+}
+
+// The moves expected from the position, starting with `first`, at most `depth` of them: each
+// next one is the best move the table holds for the position reached, while it has one that
+// can be played
+fn PrincipalVariation(table: &TranspositionTable, game: &Game, first: usize, depth: usize) -> Vec<usize> {
+  let mut line = vec![first];
+  let mut scratch = game.clone();
+  while line.len() < depth {
+    let column = line[line.len() - 1];
+    if !matches!(scratch.MakeMove(column), Ok(MoveOutcome { Result: MoveResult::Continue, .. })) {
+      break;
+    }
+    match table.Probe(scratch.Hash()) {
+      Some(entry) if scratch.LegalMoves().contains(&(entry.BestMove as usize)) => line.push(entry.BestMove as usize),
+      _ => break,
+    }
+  }
+  line
+}
+
+// Legal moves ordered from the center outwards, where the strongest moves usually are
+fn OrderedMoves(game: &Game) -> Vec<usize> {
+  let mut moves = game.LegalMoves();
+  moves.sort_by_key(|&column| (column as isize - (game.Width() / 2) as isize).abs());
+  moves
+}
+
+// Static evaluation: center control plus lines one or two pieces short of a win, counted in
+// every window as long as the win length
+fn Evaluate(game: &Game, me: Player) -> i32 {
+  let board = game.Board();
+  let mine = me as u8;
+  let mut score = 0;
+
+  // Pieces in the center column take part in the most lines
+  let (width, height) = (board.Width() as isize, board.Height() as isize);
+  let center = board.Width() / 2;
+  let length = game.WinLength() as isize;
+  for row in board {
+    if row[center] == mine {
+      score += 3;
+    } else if row[center] != 0 {
+      score -= 3;
+    }
+  }
+
+  let directions = [(0, 1), (1, 0), (1, 1), (1, -1)];
+  for row in 0..height {
+    for column in 0..width {
+      for &(RowStep, ColStep) in &directions {
+        let EndRow = row + RowStep * (length - 1);
+        let EndCol = column + ColStep * (length - 1);
+        if EndRow >= height || EndCol < 0 || EndCol >= width {
+          continue; // Window would run off the board
+        }
+
+        let (mut own, mut theirs, mut empty) = (0, 0, 0);
+        for step in 0..length {
+          match board[(row + RowStep * step) as usize][(column + ColStep * step) as usize] {
+            0 => empty += 1,
+            cell if cell == mine => own += 1,
+            _ => theirs += 1,
+          }
+        }
+        score += ScoreWindow(own, theirs, empty);
+      }
+    }
+  }
+  score
+}
+
+// Values a single window by how many empty cells it still needs
+fn ScoreWindow(own: i32, theirs: i32, empty: i32) -> i32 {
+  match (own, theirs, empty) {
+    (_, 0, 1) => 5,
+    (_, 0, 2) => 2,
+    (0, _, 1) => -4,
+    (0, _, 2) => -1,
+    _ => 0,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A position reached by random legal moves that leaves the game going
+  fn RandomPosition(rng: &mut Rng) -> Game {
+    let mut game = Game::default();
+    let moves = 4 + rng.Below(12);
+    while game.History().len() < moves {
+      let column = rng.Pick(&game.LegalMoves());
+      let mut next = game.clone();
+      if next.MakeMove(column).is_ok_and(|outcome| outcome.Result == MoveResult::Continue) {
+        game = next;
+      }
+    }
+    game
+  }
+
+  #[test]
+  fn ThreadCountDoesNotChangeTheResult() {
+    let mut rng = Rng::new(7);
+    for _ in 0..24 {
+      let game = RandomPosition(&mut rng);
+      let one = Bot::new(6).WithThreads(1).Search(&game).unwrap();
+      let four = Bot::new(6).WithThreads(4).Search(&game).unwrap();
+      assert_eq!((one.BestMove, one.Score, one.Depth), (four.BestMove, four.Score, four.Depth), "{:?}", game.History());
+    }
+  }
+
+  #[test]
+  fn TableDoesNotChangeTheResult() {
+    let mut rng = Rng::new(18);
+    for _ in 0..24 {
+      let game = RandomPosition(&mut rng);
+      let with = Bot::new(6).Search(&game).unwrap();
+      let without = Bot::new(6).WithTable(TranspositionTable::WithEntries(0)).Search(&game).unwrap();
+      assert_eq!((with.BestMove, with.Score, with.Depth), (without.BestMove, without.Score, without.Depth), "{:?}", game.History());
+    }
+  }
+
+  // A bot kept between moves reuses what it stored in the table, which mustn't change its choice
+  #[test]
+  fn TableKeptBetweenSearchesDoesNotChangeTheResult() {
+    let mut rng = Rng::new(19);
+    let kept = Bot::new(6);
+    for _ in 0..24 {
+      let game = RandomPosition(&mut rng);
+      let again = kept.Search(&game).unwrap();
+      let fresh = Bot::new(6).WithTable(TranspositionTable::WithEntries(0)).Search(&game).unwrap();
+      assert_eq!((again.BestMove, again.Score), (fresh.BestMove, fresh.Score), "{:?}", game.History());
+    }
+  }
+
+  // Searching a position again finds the results stored the first time
+  #[test]
+  fn TableHitsAreReused() {
+    let game = Game::FromMoveString("4453").unwrap();
+    let bot = Bot::new(8);
+    let first = bot.Search(&game).unwrap();
+    let second = bot.Search(&game).unwrap();
+    assert_eq!((first.BestMove, first.Score), (second.BestMove, second.Score));
+    assert!(second.Nodes < first.Nodes, "{} then {}", first.Nodes, second.Nodes);
+  }
+
+  #[test]
+  fn TakesAnImmediateWin() {
+    // Red has three in the bottom row and wins in column 4
+    let result = Bot::new(4).Search(&Game::FromMoveString("112233").unwrap()).unwrap();
+    assert_eq!(result.BestMove, 3);
+    assert!(result.Score >= WIN_SCORE);
+  }
+
+  #[test]
+  fn BlocksAnImmediateWin() {
+    // Yellow has to fill column 4 before Red does
+    let result = Bot::new(4).Search(&Game::FromMoveString("11223").unwrap()).unwrap();
+    assert_eq!(result.BestMove, 3);
+  }
+
+  #[test]
+  fn FindsAForcedWinInThree() {
+    // Only column 5 wins, with Red's third move from here
+    let result = Bot::new(6).Search(&Game::FromMoveString("233513336627").unwrap()).unwrap();
+    assert_eq!(result.BestMove, 4);
+    assert!(result.Score >= WIN_SCORE);
+  }
+
+  #[test]
+  fn HeadsOffAForcedWinInThree() {
+    // Yellow in columns 1, 3, 4 or 7 lets Red force a win within three moves
+    let result = Bot::new(8).Search(&Game::FromMoveString("23351333662").unwrap()).unwrap();
+    assert!([1, 4, 5].contains(&result.BestMove), "{:?}", result);
+    assert!(result.Score > -WIN_SCORE);
+  }
+
+  #[test]
+  fn PlaysTheBookMoveInABookPosition() {
+    // A book that passes up Red's win in column 4, so its move can't come from the search
+    let path = std::env::temp_dir().join(format!("ConnectFour-bot-book-test-{}.txt", std::process::id()));
+    std::fs::write(&path, "112233 7\n").unwrap();
+    let book = Book::Load(path.to_str().unwrap());
+    let _ = std::fs::remove_file(&path);
+    let mut bot = Bot::new(4).WithBook(book.unwrap());
+    assert_eq!(bot.ChooseMove(&Game::FromMoveString("112233").unwrap()), Some(6));
+
+    let mut bot = Bot::new(4).WithBook(Book::Embedded());
+    assert_eq!(bot.ChooseMove(&Game::FromMoveString("4454").unwrap()), Some(2));
+  }
+
+  #[test]
+  fn SearchesOffTheBook() {
+    let mut bot = Bot::new(4).WithBook(Book::Embedded());
+    for moves in ["112233", "11223"] {
+      let game = Game::FromMoveString(moves).unwrap();
+      assert_eq!(Book::Embedded().Lookup(&game), None);
+      assert_eq!(bot.ChooseMove(&game), Some(3), "{}", moves);
+    }
+  }
+}
@@ -0,0 +1,235 @@
+// The command line: every option the game understands, checked before anything starts so a
+// typo or a clash is reported rather than quietly ignored, and the `--help` that lists them.
+// The options themselves are read where they're used, with ArgValue and friends.
+
+// Commands that take the place of a game, by name, what follows them and what they do
+const COMMANDS: [(&str, &str, &str); 14] = [
+  ("replay", "<file>", "step through a saved game"),
+  ("solve", "[moves]", "solve a position exactly, e.g. solve 4453"),
+  ("bench", "[moves]", "count positions and time the search from a position"),
+  ("perft", "[moves]", "the same as bench"),
+  ("host", "", "wait for someone to join a game over the network, on --port"),
+  ("join", "[address]", "play a game hosted on another computer, e.g. join 192.168.1.5:4444, or pick one nearby"),
+  ("serve", "", "host games for browsers and other programs over WebSocket on --ws, or HTTP on --http"),
+  ("engine", "", "let another program run the search with text commands, such as go depth 10"),
+  ("simulate", "", "play computer games unseen and sum them up, e.g. simulate --p1 ai:mcts --p2 ai:minimax:6"),
+  ("tournament", "[file]", "play computer players against each other and show a crosstable"),
+  ("stats", "", "show the results of the games played so far"),
+  ("ratings", "", "show the players' ratings"),
+  ("games", "list | show <id> | replay <id>", "look through the finished games kept in the archive"),
+  ("config", "init", "write a config file with every setting explained, to start from"),
+];
+
+// Options by name, what follows them and what they do. "N" stands for a whole number and "X"
+// for any number; options without anything after them are switches.
+const OPTIONS: [(&str, &str, &str); 83] = [
+  ("--players", "N", "1 to play the computer, 2 for two people, 3 for a three-player game"),
+  ("--p1", "<name>", "name of the first player, or ai:<level> for the computer, e.g. ai:hard"),
+  ("--p2", "<name>", "name of the second player, or ai:<level>; both ai: to watch a game"),
+  ("--p3", "<name>", "name of the third player"),
+  ("--difficulty", "<level>", "computer opponent: easy, medium, hard or perfect, which is exact from move 10"),
+  ("--engine", "<name>", "computer opponent by engine: random, mcts or minimax"),
+  ("--depth", "N", "search depth of the minimax engine and of bench"),
+  ("--iters", "N", "iterations per move of the mcts engine"),
+  ("--blunders", "X", "chance of a medium computer playing a random move"),
+  ("--threads", "N", "threads the search uses"),
+  ("--tt-mb", "N", "megabytes for the search's transposition table"),
+  ("--book", "<file>", "opening book to play from"),
+  ("--no-book", "", "play without an opening book"),
+  ("--seed", "N", "seed for everything random, to play a game again (shown when left out)"),
+  ("--width", "N", "columns on the board (default 7)"),
+  ("--height", "N", "rows on the board (default 6)"),
+  ("--connect", "N", "pieces in a row that win (default 4)"),
+  ("--popout", "", "PopOut rules: pop your own piece from the bottom"),
+  ("--no-gravity", "", "place pieces on any empty cell"),
+  ("--cylinder", "", "lines wrap around the side edges"),
+  ("--bombs", "", "each player gets one bomb"),
+  ("--handicap", "N", "start with N pieces already placed"),
+  ("--handicap-player", "N", "player who gets the handicap pieces (default 1)"),
+  ("--handicap-seed", "N", "seed for where the handicap pieces go"),
+  ("--clock", "<time>", "chess clock such as 180, 60+2 or 60d3"),
+  ("--move-time", "N", "seconds each player has for a move"),
+  ("--load", "<file>", "play on from a saved game"),
+  ("--moves", "<moves>", "start from a position, e.g. 44435 or \"1. d d 2. c\""),
+  ("--setup", "", "build the starting position by hand"),
+  ("--hints", "N", "hints each player may ask for (default 3)"),
+  ("--solver-hints", "", "hints from the exact solver once it's fast enough"),
+  ("--takebacks", "N", "takebacks each player may ask for (default 3)"),
+  ("--competitive", "", "no hints or teaching warnings"),
+  ("--teach", "", "warn about threats before each move"),
+  ("--eval", "", "show the evaluation bar"),
+  ("--threats", "", "mark cells that win for either side"),
+  ("--analysis-ms", "N", "milliseconds per move for the analysis"),
+  ("--theme", "<name>", "how the board looks"),
+  ("--colorblind", "", "the same as --theme colorblind"),
+  ("--piece1", "<piece>", "pieces of the first player, e.g. square:red"),
+  ("--piece2", "<piece>", "pieces of the second player"),
+  ("--piece3", "<piece>", "pieces of the third player"),
+  ("--empty", "<piece>", "how empty cells look"),
+  ("--no-color", "", "no colors"),
+  ("--ascii", "", "draw with plain ASCII"),
+  ("--unicode", "", "draw with emoji even if the terminal seems not to cope"),
+  ("--letters", "", "label the columns a, b, c..."),
+  ("--big", "", "big board for across the room"),
+  ("--compact", "", "small board for narrow terminals"),
+  ("--preview", "", "show where a dropped piece lands and wait for Enter before dropping it"),
+  ("--no-animation", "", "no falling pieces or victory animation"),
+  ("--no-clear", "", "draw each board below the last instead of clearing the screen"),
+  ("--drop-delay", "N", "milliseconds per row of a falling piece"),
+  ("--delay", "N", "milliseconds between moves of a replay or a computer game"),
+  ("--games", "N", "games simulate plays (default 100), or a tournament per pairing and color (2)"),
+  ("--jobs", "N", "threads the simulate command plays its games on (default 1)"),
+  ("--entrants", "<list>", "players in a tournament, e.g. mcts,minimax:6,easy"),
+  ("--move-ms", "N", "milliseconds each player in a tournament has per move"),
+  ("--results", "<file>", "file a tournament keeps its results in, to carry on from"),
+  ("--csv", "<file>", "write a row for each game simulate or a tournament plays to this file"),
+  ("--accessible", "", "describe the game in sentences for screen readers"),
+  ("--plain", "", "draw the board in plain letters, one below the other, with no colors or screen control"),
+  ("--tui", "", "full-screen mode with a cursor over the columns"),
+  ("--mouse", "", "click a column to drop there in the full-screen mode"),
+  ("--raw-input", "", "moves and commands take a single key press"),
+  ("--script", "<file>", "play the moves and answers in the file, a line for each prompt, instead of reading the keyboard"),
+  ("--json", "", "write each change to the game as a line of JSON instead of drawing it"),
+  ("--port", "N", "port a network game is hosted on (default 4444)"),
+  ("--watch", "", "with join, watch the game instead of playing in it"),
+  ("--resume", "<token>", "with join, come back to a game left, with the token shown when it started"),
+  ("--grace", "N", "seconds to wait for a player who lost their connection before they forfeit (default 60)"),
+  ("--ws", "<address>", "address the serve command listens on, e.g. 0.0.0.0:9001"),
+  ("--http", "<address>", "address the serve command answers HTTP requests on, e.g. 127.0.0.1:8080"),
+  ("--lang", "<code>", "language of the messages, e.g. en or de"),
+  ("--no-stats", "", "leave the game out of the statistics"),
+  ("--stats", "", "the same as the stats command"),
+  ("--k-factor", "N", "how far one game moves the ratings"),
+  ("--db", "<file>", "keep the archive of finished games in this file instead of the data folder"),
+  ("--config", "<file>", "read the defaults from this file instead of the usual config file"),
+  ("-v", "", "log games and moves to stderr"),
+  ("-vv", "", "also log what the computer searched"),
+  ("-vvv", "", "also log every line and key read"),
+  ("--log-file", "<file>", "add the log to this file instead of stderr"),
+];
+
+// Options that can't be given together, and why
+const CONFLICTS: [(&str, &str, &str); 29] = [
+  ("--load", "--width", "a saved game brings its own board"),
+  ("--load", "--height", "a saved game brings its own board"),
+  ("--load", "--connect", "a saved game brings its own rules"),
+  ("--load", "--popout", "a saved game brings its own rules"),
+  ("--load", "--no-gravity", "a saved game brings its own rules"),
+  ("--load", "--cylinder", "a saved game brings its own rules"),
+  ("--load", "--bombs", "a saved game brings its own rules"),
+  ("--load", "--clock", "a saved game brings its own clock"),
+  ("--load", "--handicap", "a saved game brings its own position"),
+  ("--load", "--moves", "a saved game brings its own position"),
+  ("--load", "--setup", "a saved game brings its own position"),
+  ("--moves", "--setup", "the position comes from one or the other"),
+  ("--engine", "--difficulty", "both choose the computer opponent"),
+  ("--big", "--compact", "the board can only be one size"),
+  ("--tui", "--accessible", "the full-screen mode can't be read out"),
+  ("--tui", "--plain", "the full-screen mode draws over itself"),
+  ("--plain", "--accessible", "the sentences take the place of the board"),
+  ("--tui", "--no-clear", "the full-screen mode draws over itself"),
+  ("--json", "--tui", "the events take the place of the screen"),
+  ("--json", "--accessible", "the events take the place of the sentences"),
+  ("--json", "--plain", "the events take the place of the board"),
+  ("--script", "--tui", "the full-screen mode reads keys itself"),
+  ("--script", "--raw-input", "the script gives whole lines"),
+  ("--script", "--json", "the events are read from stdin"),
+  ("--ws", "--http", "the server speaks one or the other"),
+  ("--watch", "--resume", "only a player comes back to a game"),
+  ("-v", "-vv", "give one level of logging"),
+  ("-v", "-vvv", "give one level of logging"),
+  ("-vv", "-vvv", "give one level of logging"),
+];
+
+// Returns true if the command line asks for the help
+pub fn HelpWanted(args: &[String]) -> bool {
+  args.iter().skip(1).any(|arg| arg == "--help" || arg == "-h")
+}
+
+// Returns true if the command line asks for the version
+pub fn VersionWanted(args: &[String]) -> bool {
+  args.iter().skip(1).any(|arg| arg == "--version" || arg == "-V")
+}
+
+// The program's name and version, for `--version`
+pub fn Version() -> String {
+  format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
+// The help: how to start the program, its commands and every option
+pub fn Help() -> String {
+  let mut help = format!("{}\n\nUsage: ConnectFour [command] [options]\n\nCommands:\n", Version());
+  for (name, argument, description) in COMMANDS {
+    help += &format!("  {:<22} {}\n", format!("{} {}", name, argument).trim_end(), description);
+  }
+  help += "\nOptions:\n";
+  for (name, value, description) in OPTIONS {
+    help += &format!("  {:<22} {}\n", format!("{} {}", name, value).trim_end(), description);
+  }
+  help += &format!("  {:<22} {}\n", "--help, -h", "show this help");
+  help += &format!("  {:<22} {}\n", "--version, -V", "show the version");
+  help += "\nWithout options the game asks who is playing; everything else has a default.";
+  help
+}
+
+// Checks the command line: a command, if any, first, then only known options, each with the
+// value it needs, and none that clash. Returns what's wrong otherwise.
+pub fn Check(args: &[String]) -> Result<(), String> {
+  let mut rest = args.iter().skip(1).peekable();
+  if let Some((_, argument, _)) = rest.peek().and_then(|first| COMMANDS.iter().find(|(name, _, _)| name == first)) {
+    rest.next();
+    // The command's file or moves, unless they're left out; `games show 3` takes two words
+    let words = argument.split(" | ").map(|form| form.split_whitespace().count()).max().unwrap_or(0);
+    for _ in 0..words {
+      if rest.peek().is_some_and(|next| !next.starts_with("--")) {
+        rest.next();
+      }
+    }
+  }
+  let mut given: Vec<&str> = Vec::new();
+  while let Some(arg) = rest.next() {
+    let Some((name, value, _)) = OPTIONS.iter().find(|(name, _, _)| name == arg) else {
+      return Err(match arg.starts_with('-') {
+        true => format!("Unknown option '{}'; see --help for the options", arg),
+        false => format!("Didn't expect '{}' here; see --help for how to start the game", arg),
+      });
+    };
+    if given.contains(name) {
+      return Err(format!("{} is given more than once", name));
+    }
+    given.push(name);
+    if value.is_empty() {
+      continue;
+    }
+    let Some(text) = rest.next().filter(|text| !text.starts_with("--")) else {
+      return Err(format!("{} needs a value after it: {} {}", name, name, value));
+    };
+    CheckValue(name, text)?;
+  }
+  for (first, second, reason) in CONFLICTS {
+    if given.contains(&first) && given.contains(&second) {
+      return Err(format!("{} can't be used with {}: {}", first, second, reason));
+    }
+  }
+  Ok(())
+}
+
+// What follows the option: "" for a switch, or None if there's no such option
+pub fn Takes(name: &str) -> Option<&'static str> {
+  OPTIONS.iter().find(|(known, _, _)| *known == name).map(|&(_, value, _)| value)
+}
+
+// Checks a value given for an option is the kind it takes
+pub fn CheckValue(name: &str, text: &str) -> Result<(), String> {
+  match Takes(name) {
+    Some("N") if text.parse::<u64>().is_err() => Err(format!("{} needs a whole number, not '{}'", name, text)),
+    Some("X") if text.parse::<f64>().is_err() => Err(format!("{} needs a number, not '{}'", name, text)),
+    _ if name == "--players" && !["1", "2", "3"].contains(&text) => Err(format!("--players is 1, 2 or 3, not '{}'", text)),
+    _ => Ok(()),
+  }
+}
+
+// Returns true if the two options can't be given together
+pub fn Conflicting(first: &str, second: &str) -> bool {
+  CONFLICTS.iter().any(|&(one, other, _)| (one, other) == (first, second) || (one, other) == (second, first))
+}
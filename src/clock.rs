@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use crate::{ConfigError, Player, MAX_PLAYERS};
+
+// Struct describing how a timed game is played: the time each player starts with, time added
+// after each of their moves (Fischer increment), and time at the start of each turn before
+// their clock starts running (simple delay)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+  pub Budget: Duration,
+  pub Increment: Duration,
+  pub Delay: Duration,
+}
+
+impl TimeControl {
+  // A plain budget with no increment or delay
+  pub fn new(budget: Duration) -> TimeControl {
+    TimeControl { Budget: budget, Increment: Duration::ZERO, Delay: Duration::ZERO }
+  }
+}
+
+impl std::fmt::Display for TimeControl {
+  // E.g. "01:00 + 2s increment" or "01:00, 3s delay"
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", Format(self.Budget))?;
+    if !self.Increment.is_zero() {
+      write!(f, " + {}s increment", self.Increment.as_secs())?;
+    }
+    if !self.Delay.is_zero() {
+      write!(f, ", {}s delay", self.Delay.as_secs())?;
+    }
+    Ok(())
+  }
+}
+
+// Parses a time control written in whole seconds: "180" for three minutes each, "60+2" to
+// add two seconds after every move, or "60d3" to wait three seconds each turn before the
+// clock starts running
+pub fn ParseTimeControl(text: &str) -> Result<TimeControl, ConfigError> {
+  let invalid = || ConfigError::InvalidTimeControl(text.to_string());
+  let seconds = |number: &str| number.trim().parse::<u64>().map(Duration::from_secs).map_err(|_| invalid());
+  let text = text.trim();
+  let control = if let Some((budget, increment)) = text.split_once('+') {
+    TimeControl { Increment: seconds(increment)?, ..TimeControl::new(seconds(budget)?) }
+  } else if let Some((budget, delay)) = text.split_once(['d', 'D']) {
+    TimeControl { Delay: seconds(delay)?, ..TimeControl::new(seconds(budget)?) }
+  } else {
+    TimeControl::new(seconds(text)?)
+  };
+  match control.Budget.is_zero() {
+    true => Err(invalid()),
+    false => Ok(control),
+  }
+}
+
+// Struct representing chess-clock style timing: each player has a budget of time for the
+// whole game that only runs down on their own turns
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clock {
+  Control: TimeControl,               // How the game is timed
+  Remaining: [Duration; MAX_PLAYERS], // Time each player has left
+  DelayUsed: Duration,                // How much of this turn's delay has gone by
+}
+
+impl Clock {
+  pub fn new(control: TimeControl) -> Clock {
+    Clock { Control: control, Remaining: [control.Budget; MAX_PLAYERS], DelayUsed: Duration::ZERO }
+  }
+
+  pub fn Control(&self) -> TimeControl {
+    self.Control
+  }
+
+  // Time the player has left; none for Player::None
+  pub fn Remaining(&self, player: Player) -> Duration {
+    match player {
+      Player::None => Duration::ZERO,
+      player => self.Remaining[player as usize - 1],
+    }
+  }
+
+  // Time the player on turn can think before their clock runs out, counting what's left of
+  // the delay
+  pub fn TimeToFlag(&self, player: Player) -> Duration {
+    self.Remaining(player).saturating_add(self.Control.Delay.saturating_sub(self.DelayUsed))
+  }
+
+  // Takes time off the player's clock, once this turn's delay has been used up. Returns true
+  // if that ran it out.
+  pub fn Spend(&mut self, player: Player, elapsed: Duration) -> bool {
+    if player == Player::None {
+      return false;
+    }
+    let delay = self.Control.Delay.saturating_sub(self.DelayUsed).min(elapsed);
+    self.DelayUsed += delay;
+    let remaining = &mut self.Remaining[player as usize - 1];
+    *remaining = remaining.saturating_sub(elapsed - delay);
+    remaining.is_zero()
+  }
+
+  // Adds the increment for a move the player has just made, and starts the next turn
+  pub(crate) fn Moved(&mut self, player: Player) {
+    if player != Player::None {
+      let remaining = &mut self.Remaining[player as usize - 1];
+      *remaining = remaining.saturating_add(self.Control.Increment);
+    }
+    self.NewTurn();
+  }
+
+  // Gives the player now on turn a fresh delay
+  pub(crate) fn NewTurn(&mut self) {
+    self.DelayUsed = Duration::ZERO;
+  }
+
+  // Puts every player back to the full budget for a new game
+  pub fn Reset(&mut self) {
+    self.Remaining = [self.Control.Budget; MAX_PLAYERS];
+    self.NewTurn();
+  }
+
+  // Restores the time a player had left, e.g. from a saved game
+  pub(crate) fn SetRemaining(&mut self, player: Player, remaining: Duration) {
+    if player != Player::None {
+      self.Remaining[player as usize - 1] = remaining;
+    }
+  }
+}
+
+// Formats a time as mm:ss, rounding partial seconds up so 00:00 means the time has run out
+pub fn Format(time: Duration) -> String {
+  let seconds = time.as_millis().div_ceil(1000);
+  format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn Seconds(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+  }
+
+  #[test]
+  fn ParsesEachKindOfControl() {
+    assert_eq!(ParseTimeControl("10").unwrap(), TimeControl::new(Seconds(10)));
+    assert_eq!(
+      ParseTimeControl("5+3").unwrap(),
+      TimeControl { Budget: Seconds(5), Increment: Seconds(3), Delay: Duration::ZERO }
+    );
+    assert_eq!(
+      ParseTimeControl(" 60 d 2 ").unwrap(),
+      TimeControl { Budget: Seconds(60), Increment: Duration::ZERO, Delay: Seconds(2) }
+    );
+    assert_eq!(ParseTimeControl("60D2").unwrap().Delay, Seconds(2));
+  }
+
+  #[test]
+  fn RejectsAnEmptyBudget() {
+    // An increment alone would lose on time before the first move
+    for text in ["0", "0+5", "0d5"] {
+      assert!(matches!(ParseTimeControl(text), Err(ConfigError::InvalidTimeControl(t)) if t == text), "{}", text);
+    }
+  }
+
+  #[test]
+  fn RejectsMalformedControls() {
+    for text in ["", "abc", "5+", "+3", "5+3+1", "5m", "-5", "1.5", "5d", "99999999999999999999", "5+99999999999999999999"] {
+      assert!(matches!(ParseTimeControl(text), Err(ConfigError::InvalidTimeControl(_))), "{}", text);
+    }
+  }
+
+  #[test]
+  fn HugeBudgetsDoNotOverflow() {
+    let budget = ParseTimeControl("18446744073709551615").unwrap().Budget;
+    let mut clock = Clock::new(TimeControl { Budget: budget, Increment: Seconds(5), Delay: Seconds(5) });
+    assert_eq!(clock.TimeToFlag(Player::One), Duration::MAX);
+    clock.Spend(Player::One, Seconds(1));
+    clock.Moved(Player::One);
+    assert_eq!(clock.Remaining(Player::One), Duration::MAX);
+  }
+}
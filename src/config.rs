@@ -0,0 +1,306 @@
+// The config file: defaults for the command line options, so players who always play the same
+// way don't have to type them. It's a small part of TOML, settings named after the options:
+//
+//   theme = "colorblind"
+//   width = 8
+//   animation = false     # the same as --no-animation
+//
+//   [names]
+//   p1 = "Ann"
+//
+//   [keys]
+//   undo = "z"
+//
+// Options on the command line win over the file, and the file over the built-in defaults.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::cli;
+
+// Options that only make sense for one run, so they're never read from the file
+const COMMAND_LINE_ONLY: [&str; 5] = ["--load", "--moves", "--setup", "--stats", "--config"];
+
+// Actions in the full-screen mode that can be bound to another key, and their usual keys
+const KEYS: [(&str, char); 7] =
+  [("pop", 'p'), ("bomb", 'b'), ("undo", 'u'), ("redo", 'r'), ("threats", 't'), ("new", 'n'), ("quit", 'q')];
+
+// The file `config init` writes, all commented out so it changes nothing until it's edited
+const TEMPLATE: &str = r#"# ConnectFour settings. Each one is a command line option without its dashes, and options
+# typed on the command line win over what's here. Remove the # in front of a line to use it.
+
+# How the board looks: classic, retro, minimal and more
+# theme = "classic"
+
+# 1 to play the computer or 2 for two people, and how well the computer plays: easy, medium,
+# hard or perfect, which opens from the book and a timed search and plays exactly from move 10
+# players = 1
+# difficulty = "medium"
+
+# The board and how many in a row win
+# width = 7
+# height = 6
+# connect = 4
+
+# Rules
+# popout = true
+# bombs = true
+# clock = "180+2"
+
+# Switch off the falling pieces and victory animation, or show where a piece lands before it drops
+# animation = false
+# preview = true
+
+# Language of the messages, e.g. en or de
+# lang = "en"
+
+# Names, so they aren't asked for
+[names]
+# p1 = "Ann"
+# p2 = "Bob"
+
+# Keys in the full-screen mode, --tui
+[keys]
+# undo = "u"
+# redo = "r"
+# threats = "t"
+# pop = "p"
+# bomb = "b"
+# new = "n"
+# quit = "q"
+"#;
+
+// Struct holding the settings read from the file
+#[derive(Default)]
+struct Settings {
+  Options: Vec<(String, String)>, // Options with their values, an empty one for switches
+  Keys: Vec<(String, char)>,      // Keys bound to actions
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// Where the config file is: $XDG_CONFIG_HOME or ~/.config on Linux, ~/Library/Application
+// Support on macOS and %APPDATA% on Windows. None if the home directory can't be found.
+pub fn ConfigPath() -> Option<PathBuf> {
+  let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+  let base = if cfg!(windows) {
+    var("APPDATA")?
+  } else if cfg!(target_os = "macos") {
+    var("HOME")?.join("Library").join("Application Support")
+  } else {
+    var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))?
+  };
+  Some(base.join("connectfour").join("config.toml"))
+}
+
+// Reads the file from `--config`, or the usual one if there is one. Returns the warnings about
+// settings that were left out, or the first line that couldn't be read.
+pub fn Load(path: Option<String>) -> Result<Vec<String>, String> {
+  let (path, required) = match path {
+    Some(path) => (PathBuf::from(path), true),
+    None => match ConfigPath() {
+      Some(path) => (path, false),
+      None => return Ok(Vec::new()),
+    },
+  };
+  let text = match std::fs::read_to_string(&path) {
+    Ok(text) => text,
+    Err(_) if !required => return Ok(Vec::new()),
+    Err(err) => return Err(format!("couldn't read {}: {}", path.display(), err)),
+  };
+  let name = path.display().to_string();
+  let mut settings = Settings::default();
+  let mut warnings = Vec::new();
+  let mut unknown = Vec::new();
+  for line in Parse(&text, &name)? {
+    let at = |problem: String| format!("{} line {}: {}", name, line.Number, problem);
+    let (section, key, value) = (line.Section.as_str(), line.Key.as_str(), &line.Value);
+    if !["", "names", "keys"].contains(&section) {
+      if !unknown.contains(&line.Section) {
+        warnings.push(at(format!("unknown section [{}], ignored", section)));
+        unknown.push(line.Section.clone());
+      }
+      continue;
+    }
+    match section {
+      "" if COMMAND_LINE_ONLY.contains(&format!("--{}", key).as_str()) => {
+        warnings.push(at(format!("{} is only read from the command line, ignored", key)))
+      }
+      "" => match OptionFor(key, value) {
+        Some(Ok(option)) => settings.Options.extend(option),
+        Some(Err(problem)) => return Err(at(problem)),
+        None => warnings.push(at(format!("unknown setting '{}', ignored", key))),
+      },
+      "names" if ["p1", "p2", "p3"].contains(&key) => settings.Options.push((format!("--{}", key), value.Text())),
+      "keys" if KEYS.iter().any(|(action, _)| *action == key) => {
+        let text = value.Text();
+        let mut letters = text.chars();
+        match (letters.next(), letters.next()) {
+          (Some(letter), None) => settings.Keys.push((key.to_string(), letter)),
+          _ => return Err(at(format!("a key is a single character, not '{}'", text))),
+        }
+      }
+      _ => warnings.push(at(format!("unknown setting '{}' in [{}], ignored", key, section))),
+    }
+  }
+  let _ = SETTINGS.set(settings);
+  Ok(warnings)
+}
+
+// Struct holding one `key = value` line of a file in the config file's format
+pub struct Line {
+  pub Number: usize,    // Line number, from 1
+  pub Section: String,  // Section the line is in, empty before the first one
+  pub Key: String,      // Name of the setting
+  pub Value: Setting,   // Its value
+}
+
+// Reads the settings of a file in the config file's format, `name` being how errors refer
+// to it. Returns the first line that can't be read otherwise.
+pub fn Parse(text: &str, name: &str) -> Result<Vec<Line>, String> {
+  let mut lines = Vec::new();
+  let mut section = String::new();
+  for (index, line) in text.lines().enumerate() {
+    let at = |problem: String| format!("{} line {}: {}", name, index + 1, problem);
+    let line = WithoutComment(line).trim();
+    if line.is_empty() {
+      continue;
+    }
+    if let Some(rest) = line.strip_prefix('[') {
+      let Some(title) = rest.strip_suffix(']') else {
+        return Err(at(format!("expected a section such as [names], found '{}'", line)));
+      };
+      section = title.trim().to_string();
+      continue;
+    }
+    let Some((key, value)) = line.split_once('=') else {
+      return Err(at(format!("expected a setting such as theme = \"classic\", found '{}'", line)));
+    };
+    // A key in quotes can hold spaces
+    let key = key.trim();
+    let key = match key.starts_with('"') {
+      true => ParseValue(key).map_err(at)?.Text(),
+      false => key.to_string(),
+    };
+    let value = ParseValue(value.trim()).map_err(at)?;
+    lines.push(Line { Number: index + 1, Section: section.clone(), Key: key, Value: value });
+  }
+  Ok(lines)
+}
+
+// Writes the commented template to the file from `--config`, or the usual one, unless a file
+// is already there. Returns where it was written.
+pub fn Init(path: Option<String>) -> Result<PathBuf, String> {
+  let path = path.map(PathBuf::from).or_else(ConfigPath).ok_or("there's no home directory to put it in")?;
+  if path.exists() {
+    return Err(format!("{} already exists; delete it first to start again", path.display()));
+  }
+  if let Some(folder) = path.parent() {
+    std::fs::create_dir_all(folder).map_err(|err| format!("couldn't create {}: {}", folder.display(), err))?;
+  }
+  std::fs::write(&path, TEMPLATE).map_err(|err| format!("couldn't write {}: {}", path.display(), err))?;
+  Ok(path)
+}
+
+// The option's value from the file, unless the command line gives an option it can't be used
+// with, e.g. a width from the file alongside `--load`
+pub fn Value(name: &str) -> Option<String> {
+  let settings = SETTINGS.get()?;
+  let given: Vec<String> = std::env::args().filter(|arg| arg.starts_with("--")).collect();
+  if given.iter().any(|arg| cli::Conflicting(arg, name)) {
+    return None;
+  }
+  settings.Options.iter().find(|(option, _)| option == name).map(|(_, value)| value.clone())
+}
+
+// Returns true if the file turns the switch on
+pub fn Switch(name: &str) -> bool {
+  Value(name).is_some()
+}
+
+// The key bound to an action in the full-screen mode
+pub fn Key(action: &str) -> char {
+  let bound = SETTINGS.get().and_then(|settings| settings.Keys.iter().find(|(name, _)| name == action));
+  match bound {
+    Some(&(_, letter)) => letter,
+    None => KEYS.iter().find(|(name, _)| *name == action).map_or('?', |&(_, letter)| letter),
+  }
+}
+
+// Enum representing the values a setting can have
+pub enum Setting {
+  Text(String),
+  Number(String),
+  Bool(bool),
+}
+
+impl Setting {
+  // The value as it would be typed on the command line
+  pub fn Text(&self) -> String {
+    match self {
+      Setting::Text(text) | Setting::Number(text) => text.clone(),
+      Setting::Bool(on) => on.to_string(),
+    }
+  }
+}
+
+// Reads a value: a string in double quotes, a number, true or false
+fn ParseValue(text: &str) -> Result<Setting, String> {
+  if let Some(rest) = text.strip_prefix('"') {
+    let mut value = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+      match c {
+        '"' if chars.as_str().trim().is_empty() => return Ok(Setting::Text(value)),
+        '"' => return Err(format!("unexpected '{}' after the closing quote", chars.as_str().trim())),
+        '\\' => match chars.next() {
+          Some('n') => value.push('\n'),
+          Some('t') => value.push('\t'),
+          Some(escaped @ ('"' | '\\')) => value.push(escaped),
+          _ => return Err("unknown escape in a string; use \\\" or \\\\".to_string()),
+        },
+        c => value.push(c),
+      }
+    }
+    return Err("the string has no closing quote".to_string());
+  }
+  match text {
+    "true" => Ok(Setting::Bool(true)),
+    "false" => Ok(Setting::Bool(false)),
+    number if number.parse::<f64>().is_ok() => Ok(Setting::Number(number.to_string())),
+    _ => Err(format!("expected a string in quotes, a number, true or false, found '{}'", text)),
+  }
+}
+
+// The option a top-level setting stands for and its value: `theme = "x"` is `--theme x`,
+// `popout = true` is `--popout`, and `animation = false` is `--no-animation`. Ok(None) for a
+// setting that leaves things as they are, such as `popout = false`, and None if there's no
+// such option.
+fn OptionFor(key: &str, value: &Setting) -> Option<Result<Option<(String, String)>, String>> {
+  let option = format!("--{}", key);
+  let negated = format!("--no-{}", key);
+  Some(match (cli::Takes(&option), value) {
+    (Some(""), Setting::Bool(true)) => Ok(Some((option, String::new()))),
+    (Some(""), Setting::Bool(false)) => Ok(None),
+    (Some(""), _) => Err(format!("{} is true or false", key)),
+    (Some(_), Setting::Bool(_)) => Err(format!("{} needs a value, not true or false", key)),
+    (Some(_), value) => cli::CheckValue(&option, &value.Text()).map(|()| Some((option, value.Text()))),
+    (None, Setting::Bool(false)) if cli::Takes(&negated) == Some("") => Ok(Some((negated, String::new()))),
+    (None, Setting::Bool(true)) if cli::Takes(&negated) == Some("") => Ok(None),
+    (None, _) if cli::Takes(&negated) == Some("") => Err(format!("{} is true or false", key)),
+    (None, _) => return None,
+  })
+}
+
+// The line without a comment, leaving any # inside a string alone
+fn WithoutComment(line: &str) -> &str {
+  let mut quoted = false;
+  for (index, c) in line.char_indices() {
+    match c {
+      '"' => quoted = !quoted,
+      '#' if !quoted => return &line[..index],
+      _ => {}
+    }
+  }
+  line
+}
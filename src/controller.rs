@@ -0,0 +1,89 @@
+// Who chooses each player's moves: someone at the keyboard, an engine, or the other end of a
+// network game. The play loop asks the controller of the player on turn for a move and tells
+// the others about it once it's on the board, so every kind of game runs the same way.
+
+use std::time::Instant;
+
+use ConnectFour::engine::Engine;
+use ConnectFour::{Game, Move, MAX_PLAYERS};
+
+use crate::input::{self, Command, InputError};
+use crate::net::{self, Remote};
+use crate::{interrupt, SaidLine, Thinking};
+
+// Enum representing what a player chose to do on their turn
+pub enum PlayerAction {
+  Play(Move),       // Make this move
+  Command(Command), // Anything else typed at the prompt, for the play loop to carry out
+  Invalid(String),  // Something typed that isn't a move or command, and why
+  TimedOut,         // The time to move ran out first
+  Lost(String),     // The player can't be reached anymore, and why
+  Quit,             // The player left, with Ctrl+C or at the end of the input
+}
+
+// Trait for whatever chooses a player's moves
+pub trait PlayerController {
+  // Waits for the player's choice on their turn
+  fn ChooseMove(&mut self, game: &Game) -> PlayerAction;
+
+  // Told about every move another player made, once it's on the board
+  fn Observe(&mut self, _game: &Game, _played: Move) {}
+}
+
+// Struct reading a player's moves and commands from the input source in use
+pub struct HumanController<'a> {
+  pub Letters: bool,               // Whether columns are typed as letters
+  pub Deadline: Option<Instant>,   // When the time to move runs out, if it does
+  pub Tick: &'a mut dyn FnMut(),   // Called every so often while waiting, to keep a clock running
+}
+
+impl PlayerController for HumanController<'_> {
+  fn ChooseMove(&mut self, game: &Game) -> PlayerAction {
+    match input::NextCommand(game, self.Letters, self.Deadline, self.Tick) {
+      Ok(Command::Play(played)) => PlayerAction::Play(played),
+      Ok(command) => PlayerAction::Command(command),
+      Err(InputError::Invalid(err)) => PlayerAction::Invalid(err),
+      Err(InputError::TimedOut) => PlayerAction::TimedOut,
+      Err(InputError::Interrupted | InputError::Ended) => PlayerAction::Quit,
+    }
+  }
+}
+
+// Struct letting an engine choose a player's moves, with a spinner while it thinks
+pub struct BotController<'a> {
+  pub Engine: &'a mut dyn Engine,
+  pub Name: &'a str, // Shown while it thinks
+}
+
+impl PlayerController for BotController<'_> {
+  fn ChooseMove(&mut self, game: &Game) -> PlayerAction {
+    let column = Thinking(self.Name, || self.Engine.ChooseMove(game)).expect("unfinished game has a legal move");
+    PlayerAction::Play(Move::Drop(column))
+  }
+}
+
+// Struct waiting for the move of the player at the other end of a network game, who is sent
+// every move made here in return
+pub struct RemoteController<'a> {
+  pub Remote: &'a mut Remote,
+  pub Names: &'a [String; MAX_PLAYERS], // For chat that comes in while waiting
+}
+
+impl PlayerController for RemoteController<'_> {
+  // The move is checked by playing it on a copy of the game, so only a legal one is handed on
+  fn ChooseMove(&mut self, game: &Game) -> PlayerAction {
+    let mut copy = game.clone();
+    let heard = &mut |said: &net::Said| println!("{}", SaidLine(self.Names, said));
+    let received = self.Remote.SendMoves(game).and_then(|()| self.Remote.ReceiveMove(&mut copy, heard));
+    match received {
+      Ok(_) => PlayerAction::Play(*copy.History().last().expect("a move was received")),
+      Err(_) if interrupt::Interrupted() => PlayerAction::Quit,
+      Err(err) => PlayerAction::Lost(err),
+    }
+  }
+
+  // A move that can't be sent now goes with the next one, or is found missing while waiting
+  fn Observe(&mut self, game: &Game, _played: Move) {
+    let _ = self.Remote.SendMoves(game);
+  }
+}
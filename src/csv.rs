@@ -0,0 +1,129 @@
+// Results of computer games as CSV, one row per game, for `simulate` and `tournament` with
+// `--csv <file>`. Each row is written out as soon as its game ends, so the games played so
+// far are kept even if the run is cut short.
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use ConnectFour::{Game, Player};
+
+// Column names, the first row of the file
+const COLUMNS: [&str; 9] = ["game", "seed", "first", "second", "result", "moves", "duration_ms", "move_string", "problem"];
+
+// Struct representing the CSV file being written; it can be shared between threads
+pub struct Csv {
+  File: Mutex<File>,
+  Path: String,
+}
+
+impl Csv {
+  // Creates the file, replacing one that's there, and writes the column names
+  pub fn Create(path: &str) -> Result<Csv, String> {
+    let file = File::create(path).map_err(|err| format!("couldn't create {}: {}", path, err))?;
+    let csv = Csv { File: Mutex::new(file), Path: path.to_string() };
+    csv.Write(&COLUMNS.map(str::to_string))?;
+    Ok(csv)
+  }
+
+  // Writes the row for a game: its number, the seed the run was started with, the players
+  // moving first and second, how it ended and how long it took. A game that went wrong has
+  // no result, only the problem.
+  pub fn Game(&self, number: usize, seed: u64, players: [&str; 2], played: &Result<Game, String>, took: Duration) -> Result<(), String> {
+    let (result, moves, MoveString, problem) = match played {
+      Ok(game) => {
+        let result = match game.Winner() {
+          Player::One => "1-0",
+          Player::None => "1/2-1/2",
+          _ => "0-1",
+        };
+        (result, game.History().len().to_string(), game.ToMoveString().unwrap_or_default(), String::new())
+      }
+      Err(problem) => ("", String::new(), String::new(), problem.clone()),
+    };
+    self.Write(&[
+      number.to_string(),
+      seed.to_string(),
+      players[0].to_string(),
+      players[1].to_string(),
+      result.to_string(),
+      moves,
+      took.as_millis().to_string(),
+      MoveString,
+      problem,
+    ])
+  }
+
+  // Writes a row and flushes it to the disk
+  fn Write(&self, fields: &[String]) -> Result<(), String> {
+    let row: Vec<String> = fields.iter().map(|field| Quoted(field)).collect();
+    let mut file = self.File.lock().expect("csv file lock");
+    writeln!(file, "{}", row.join(","))
+        .and_then(|()| file.flush())
+        .map_err(|err| format!("couldn't write {}: {}", self.Path, err))
+  }
+}
+
+// The field as CSV has it: in double quotes, with its own doubled, if it holds a comma, a
+// quote or a line break
+fn Quoted(field: &str) -> String {
+  match field.contains([',', '"', '\n', '\r']) {
+    true => format!("\"{}\"", field.replace('"', "\"\"")),
+    false => field.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Splits CSV text into rows of fields, undoing Quoted
+  fn Parse(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let (mut quoted, mut chars) = (false, text.chars().peekable());
+    while let Some(c) = chars.next() {
+      match (quoted, c) {
+        (true, '"') if chars.peek() == Some(&'"') => {
+          field.push('"');
+          chars.next();
+        }
+        (_, '"') => quoted = !quoted,
+        (false, ',') => row.push(std::mem::take(&mut field)),
+        (false, '\n') => {
+          row.push(std::mem::take(&mut field));
+          rows.push(std::mem::take(&mut row));
+        }
+        _ => field.push(c),
+      }
+    }
+    rows
+  }
+
+  #[test]
+  fn QuotesOnlyWhenNeeded() {
+    assert_eq!(Quoted("MCTS (500 playouts)"), "MCTS (500 playouts)");
+    assert_eq!(Quoted("Bot, depth 8"), "\"Bot, depth 8\"");
+    assert_eq!(Quoted("the \"perfect\" one"), "\"the \"\"perfect\"\" one\"");
+    assert_eq!(Quoted("two\nlines"), "\"two\nlines\"");
+  }
+
+  #[test]
+  fn RowsReadBack() {
+    let path = std::env::temp_dir().join(format!("ConnectFour-csv-test-{}.csv", std::process::id()));
+    let csv = Csv::Create(path.to_str().unwrap()).unwrap();
+    let won = Ok(Game::FromMoveString("1212121").unwrap());
+    csv.Game(1, 42, ["Bot, depth 8", "the \"perfect\" one"], &won, Duration::from_millis(1500)).unwrap();
+    csv.Game(2, 42, ["Random", "Random"], &Err("engine quit, said \"bye\"".to_string()), Duration::ZERO).unwrap();
+    drop(csv);
+    let written = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    let rows = Parse(&written.unwrap());
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0], COLUMNS);
+    assert_eq!(rows[1], ["1", "42", "Bot, depth 8", "the \"perfect\" one", "1-0", "7", "1500", "1212121", ""]);
+    assert_eq!(rows[2], ["2", "42", "Random", "Random", "", "", "0", "", "engine quit, said \"bye\""]);
+  }
+}
@@ -0,0 +1,69 @@
+use crate::book::Book;
+use crate::bot::Bot;
+use crate::engine::Engine;
+use crate::random::RandomBot;
+use crate::solver::Solver;
+
+// Enum representing the named strength levels of the computer opponent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+  Easy,    // Random legal moves, but takes immediate wins
+  Medium,  // Shallow search that sometimes overlooks threats
+  Hard,    // Deep alpha-beta search
+  Perfect, // Opening book, a deeper timed search until SOLVE_FROM_MOVE, then the exact solver
+}
+
+// Chance that a Medium bot plays a careless move
+pub const MEDIUM_BLUNDER_PROBABILITY: f64 = 0.25;
+
+impl Difficulty {
+  pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Perfect];
+
+  // Parses a difficulty name such as "hard" or its first letter
+  pub fn FromName(name: &str) -> Option<Difficulty> {
+    match name.trim().to_lowercase().as_str() {
+      "e" | "easy" => Some(Difficulty::Easy),
+      "m" | "medium" => Some(Difficulty::Medium),
+      "h" | "hard" => Some(Difficulty::Hard),
+      "p" | "perfect" => Some(Difficulty::Perfect),
+      _ => None,
+    }
+  }
+
+  pub fn Name(self) -> &'static str {
+    match self {
+      Difficulty::Easy => "Easy",
+      Difficulty::Medium => "Medium",
+      Difficulty::Hard => "Hard",
+      Difficulty::Perfect => "Perfect",
+    }
+  }
+
+  // Fixed Elo rating players are measured against when they play this level
+  pub fn Rating(self) -> u64 {
+    match self {
+      Difficulty::Easy => 800,
+      Difficulty::Medium => 1200,
+      Difficulty::Hard => 1600,
+      Difficulty::Perfect => 2200,
+    }
+  }
+
+  // Builds the engine configuration for this level
+  pub fn MakeEngine(self, seed: u64) -> Box<dyn Engine> {
+    self.MakeEngineWith(MEDIUM_BLUNDER_PROBABILITY, Some(Book::Embedded()), seed)
+  }
+
+  // Same as MakeEngine, with a custom blunder probability for Medium and an optional
+  // opening book for Hard and Perfect
+  pub fn MakeEngineWith(self, blunders: f64, book: Option<Book>, seed: u64) -> Box<dyn Engine> {
+    match (self, book) {
+      (Difficulty::Easy, _) => Box::new(RandomBot::new(seed)),
+      (Difficulty::Medium, _) => Box::new(Bot::new(4).WithBlunders(blunders, seed)),
+      (Difficulty::Hard, Some(book)) => Box::new(Bot::new(8).WithBook(book)),
+      (Difficulty::Hard, None) => Box::new(Bot::new(8)),
+      (Difficulty::Perfect, Some(book)) => Box::new(Solver::new().WithBook(book)),
+      (Difficulty::Perfect, None) => Box::new(Solver::new()),
+    }
+  }
+}
@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use crate::Game;
+
+// Trait implemented by every computer opponent
+pub trait Engine {
+  // Picks a column for the player whose turn it is, or None if no move is possible
+  fn ChooseMove(&mut self, game: &Game) -> Option<usize>;
+
+  // Short description shown to the players
+  fn Name(&self) -> String;
+
+  // Caps the time spent on each move. Engines that don't search, or search exactly, ignore it.
+  fn LimitTime(&mut self, _limit: Duration) {}
+}
+
+// Returns true if the engines can play the game: they search two-player games where pieces fall
+pub fn Supports(game: &Game) -> bool {
+  game.Players() == 2 && game.Gravity()
+}
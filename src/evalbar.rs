@@ -0,0 +1,96 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use ConnectFour::bot::{Bot, WIN_SCORE};
+use ConnectFour::{Game, Move, Player};
+
+use crate::{terminal, theme};
+
+// Time the background search may spend on each position
+const BUDGET: Duration = Duration::from_millis(300);
+
+// Width of the bar in characters
+const BAR_WIDTH: usize = 20;
+
+// A finished evaluation and the move list of the position it belongs to
+type Evaluated = Option<(Vec<Move>, String)>;
+
+// Struct representing the live evaluation indicator shown under the board
+pub struct EvalBar {
+  Current: Arc<Mutex<Vec<Move>>>,                  // Move list of the position on screen
+  Latest: Arc<Mutex<Evaluated>>,                    // Last finished evaluation
+  Pending: Mutex<Option<Vec<Move>>>,               // Position currently being searched
+}
+
+impl EvalBar {
+  pub fn new() -> EvalBar {
+    EvalBar { Current: Arc::default(), Latest: Arc::default(), Pending: Mutex::new(None) }
+  }
+
+  // Returns the text to show for the game right now, starting a background search if needed.
+  // `row` is the 1-based screen line the text is drawn on, so the search can fill it in later.
+  pub fn Text(&self, game: &Game, row: usize) -> String {
+    let moves = game.History().to_vec();
+    *self.Current.lock().unwrap() = moves.clone();
+
+    if let Some((key, text)) = &*self.Latest.lock().unwrap() {
+      if *key == moves {
+        return text.clone();
+      }
+    }
+    // Without ANSI codes, or below boards that are kept, the bar can't be repainted in place,
+    // so wait for it instead
+    if !theme::Current().Color() || crate::NoClear() {
+      let text = Describe(game);
+      *self.Latest.lock().unwrap() = Some((moves, text.clone()));
+      return text;
+    }
+    let mut pending = self.Pending.lock().unwrap();
+    if pending.as_ref() != Some(&moves) {
+      *pending = Some(moves.clone());
+      let (current, latest, game) = (self.Current.clone(), self.Latest.clone(), game.clone());
+      thread::spawn(move || {
+        let text = Describe(&game);
+        *latest.lock().unwrap() = Some((moves.clone(), text.clone()));
+
+        // Repaint just the bar line if the board on screen is still this position
+        if *current.lock().unwrap() == moves {
+          print!("{}", terminal::PaintLine(row, &text));
+          let _ = std::io::stdout().flush();
+        }
+      });
+    }
+    "…".to_string()
+  }
+}
+
+// Searches the position and renders the verdict for Red
+fn Describe(game: &Game) -> String {
+  if game.IsFinished() {
+    return String::new();
+  }
+  let Some(result) = Bot::new(20).WithTimeLimit(BUDGET).Search(game) else {
+    return String::new();
+  };
+  let ForRed = if game.CurrentPlayer() == Player::One { result.Score } else { -result.Score };
+
+  // Forced results: the win was found `Depth - (Score - WIN_SCORE) + 1` plies from now
+  if result.Score.abs() >= WIN_SCORE - (game.Width() * game.Height()) as i32 {
+    let plies = result.Depth as i32 - (result.Score.abs() - WIN_SCORE) + 1;
+    let winner = theme::Current().Name(if ForRed > 0 { Player::One } else { Player::Two });
+    return format!("{} wins in {}", winner, plies.max(1));
+  }
+
+  // Squash the heuristic score into a winning chance for Red
+  let chance = 1.0 / (1.0 + (-(ForRed as f64) / 20.0).exp());
+  let filled = (chance * BAR_WIDTH as f64).round() as usize;
+  format!(
+    "[{}{}] {} {:.0}%",
+    "#".repeat(filled),
+    "-".repeat(BAR_WIDTH - filled),
+    theme::Current().Name(Player::One),
+    chance * 100.0
+  )
+}
@@ -0,0 +1,241 @@
+// Machine-readable mode, `--json`, for programs that put their own face on the game: every
+// change to it is written to stdout as one JSON object per line, and nothing else is, not even
+// a board. Messages meant for people go to stderr. Rows and columns in the events are 0-based,
+// rows counted from the top, as in save files:
+//
+//   {"event":"start","width":7,"height":6,"connect":4,"players":["Ann","Computer (Hard)"],"computer":[false,true],"seed":42}
+//   {"event":"prompt","player":1,"move_number":1,"legal":[0,1,2,3,4,5,6]}
+//   {"event":"move","player":1,"kind":"drop","column":3,"row":5,"move_number":1}
+//   {"event":"invalid","input":"9","reason":"Column 9 doesn't exist"}
+//   {"event":"undo","player":1,"move_number":0}
+//   {"event":"quit","player":1}
+//   {"event":"win","player":1,"line":[[5,0],[5,1],[5,2],[5,3]],"on_time":false}
+//   {"event":"draw"}
+//
+// A move is read from stdin as a column number the way it's typed at the usual prompt, from
+// 1, or as a JSON command: {"command":"move","column":3}, with "kind":"pop", "bomb" or
+// "place" and a "row" for the other moves, {"command":"undo"} or {"command":"quit"}.
+
+use std::io::Write;
+use std::time::Instant;
+
+use ConnectFour::engine::{self, Engine};
+use ConnectFour::json::{self, Value};
+use ConnectFour::{log, save, Game, Move, MoveOutcome, Player};
+
+use crate::input::{self, Command};
+use crate::interrupt::{self, Wait};
+use crate::{locale, ArgValue, Message};
+
+// Struct describing one player of the game: their name, and the engine choosing their moves
+// if the computer plays them
+struct Seat {
+  Name: String,
+  Engine: Option<Box<dyn Engine>>,
+}
+
+// Plays a game from the command line, writing events until it's over, the input ends or
+// the player on turn quits
+pub fn Run() {
+  let started = StartingGame().and_then(|game| Ok((Seats(&game)?, game)));
+  let (mut seats, mut game) = match started {
+    Ok(started) => started,
+    Err(err) => {
+      eprintln!("{}", locale::Say(Message::Error, &[("error", &err)]));
+      return;
+    }
+  };
+  let names: Vec<String> = seats.iter().map(|seat| json::Quote(&seat.Name)).collect();
+  let computer: Vec<String> = seats.iter().map(|seat| seat.Engine.is_some().to_string()).collect();
+  Event("start", &[
+    ("width", game.Width().to_string()),
+    ("height", game.Height().to_string()),
+    ("connect", game.WinLength().to_string()),
+    ("players", format!("[{}]", names.join(","))),
+    ("computer", format!("[{}]", computer.join(","))),
+    ("seed", crate::Seed().to_string()),
+  ]);
+  log::Info(format_args!("JSON game started: {}", seats.iter().map(|seat| seat.Name.as_str()).collect::<Vec<_>>().join(" vs ")));
+
+  while !game.IsFinished() {
+    let player = game.CurrentPlayer();
+    let turn = Instant::now();
+    if let Some(engine) = seats[player as usize - 1].Engine.as_mut() {
+      let column = engine.ChooseMove(&game).expect("unfinished game has a legal move");
+      if !game.SpendTime(turn.elapsed()) {
+        let outcome = game.MakeMove(column).expect("engine picks a legal column");
+        Moved(&game, &outcome);
+      }
+      continue;
+    }
+
+    let legal: Vec<String> = game.LegalMoves().iter().map(usize::to_string).collect();
+    Event("prompt", &[
+      ("player", (player as u8).to_string()),
+      ("move_number", (game.History().len() + 1).to_string()),
+      ("legal", format!("[{}]", legal.join(","))),
+    ]);
+    // The clock runs while the player on turn is at the prompt, as in a game on screen
+    let flag_fall = game.Clock().map(|clock| turn + clock.TimeToFlag(player));
+    let line = match interrupt::ReadLineBy(flag_fall, &mut || ()) {
+      Wait::Line(line) if line.is_empty() && interrupt::InputEnded() => return,
+      Wait::Line(line) => line,
+      Wait::Interrupted => return,
+      Wait::TimedOut => String::new(),
+    };
+    if game.SpendTime(turn.elapsed()) {
+      continue;
+    }
+    match ReadCommand(&game, &line) {
+      Ok(Command::Play(played)) => match game.Play(played) {
+        Ok(outcome) => Moved(&game, &outcome),
+        Err(err) => Invalid(&line, &err.to_string()),
+      },
+      Ok(Command::Undo) => Undo(&mut game, &seats),
+      Ok(Command::Quit) => {
+        Event("quit", &[("player", (player as u8).to_string())]);
+        return;
+      }
+      Ok(_) => Invalid(&line, "only moves, undo and quit can be used with --json"),
+      Err(reason) => Invalid(&line, &reason),
+    }
+  }
+
+  match game.Winner() {
+    Player::None => Event("draw", &[]),
+    winner => {
+      let line: Vec<String> = game.WinningLine().iter().map(|(row, column)| format!("[{},{}]", row, column)).collect();
+      Event("win", &[
+        ("player", (winner as u8).to_string()),
+        ("line", format!("[{}]", line.join(","))),
+        ("on_time", game.Forfeited().is_some().to_string()),
+      ]);
+    }
+  }
+  log::Info(format_args!("JSON game over: {:?} after {} moves", game.Winner(), game.History().len()));
+}
+
+// The game to play: the one from `--load`, or a new one from the options, after `--moves`
+fn StartingGame() -> Result<Game, String> {
+  if let Some(path) = ArgValue("--load") {
+    return save::LoadFile(&path).map_err(|err| err.to_string());
+  }
+  let mut game = crate::GameFromArgs().map_err(|err| err.to_string())?;
+  if let Some(moves) = ArgValue("--moves") {
+    game.PlayMoves(&moves).map_err(|err| err.to_string())?;
+  }
+  Ok(game)
+}
+
+// Who plays: `--p1 Ann`, `--p2 ai:hard` and so on, nobody being asked. `--players 1` has the
+// computer play Player Two at `--engine` or `--difficulty` (medium by default).
+fn Seats(game: &Game) -> Result<Vec<Seat>, String> {
+  let mut seats = Vec::new();
+  for index in 0..game.Players() {
+    let player = Player::FromInt(index as u8 + 1);
+    let given = ArgValue(["--p1", "--p2", "--p3"][index]);
+    let computer = match given.as_deref().and_then(|name| name.strip_prefix("ai:")) {
+      Some(spec) => Some(spec.to_string()),
+      None if index == 1 && given.is_none() && ArgValue("--players").as_deref() == Some("1") => {
+        Some(ArgValue("--engine").or_else(|| ArgValue("--difficulty")).unwrap_or("medium".to_string()))
+      }
+      None => None,
+    };
+    let seat = match computer {
+      Some(spec) => {
+        if !engine::Supports(game) {
+          return Err(locale::Text(Message::AiUnsupported).to_string());
+        }
+        let (engine, name) = crate::AiPlayer(&spec, crate::NextSeed())?;
+        Seat { Name: name, Engine: Some(engine) }
+      }
+      None => Seat { Name: given.unwrap_or_else(|| crate::ColorName(player).to_string()), Engine: None },
+    };
+    seats.push(seat);
+  }
+  Ok(seats)
+}
+
+// Reads a line of input: a JSON command if it starts with '{', or else what the usual prompt
+// takes
+fn ReadCommand(game: &Game, line: &str) -> Result<Command, String> {
+  if !line.trim_start().starts_with('{') {
+    return input::ParseCommand(game, line, false);
+  }
+  let command = json::Parse(line).map_err(|err| err.to_string())?;
+  match command.Get("command").and_then(Value::AsStr) {
+    Some("move") => MoveFrom(&command).map(Command::Play),
+    Some("undo") => Ok(Command::Undo),
+    Some("quit") => Ok(Command::Quit),
+    Some(other) => Err(format!("unknown command \"{}\"", other)),
+    None => Err("the object needs a \"command\"".to_string()),
+  }
+}
+
+// The move in a JSON move command: a "column", with a "kind" and a "row" for moves other
+// than drops
+pub fn MoveFrom(command: &Value) -> Result<Move, String> {
+  let number = |name: &str| command.Get(name).and_then(Value::AsUsize).ok_or(format!("the command needs a \"{}\"", name));
+  let column = number("column")?;
+  Ok(match command.Get("kind").and_then(Value::AsStr).unwrap_or("drop") {
+    "drop" => Move::Drop(column),
+    "pop" => Move::Pop(column),
+    "bomb" => Move::Bomb(column),
+    "place" => Move::Place(number("row")?, column),
+    kind => return Err(format!("unknown kind of move \"{}\"", kind)),
+  })
+}
+
+// Takes back the last move, and the computer's moves before it, so a person is on turn again
+fn Undo(game: &mut Game, seats: &[Seat]) {
+  loop {
+    if let Err(err) = game.UndoMove() {
+      Invalid("undo", &err.to_string());
+      return;
+    }
+    let player = game.CurrentPlayer();
+    Event("undo", &[("player", (player as u8).to_string()), ("move_number", game.History().len().to_string())]);
+    if seats[player as usize - 1].Engine.is_none() || game.History().is_empty() {
+      return;
+    }
+  }
+}
+
+// Writes the event for a move just made
+fn Moved(game: &Game, outcome: &MoveOutcome) {
+  let kind = match game.History().last() {
+    Some(Move::Pop(_)) => "pop",
+    Some(Move::Bomb(_)) => "bomb",
+    Some(Move::Place(..)) => "place",
+    _ => "drop",
+  };
+  Event("move", &[
+    ("player", (outcome.Player as u8).to_string()),
+    ("kind", json::Quote(kind)),
+    ("column", outcome.Column.to_string()),
+    ("row", outcome.Row.to_string()),
+    ("move_number", game.History().len().to_string()),
+  ]);
+}
+
+// Writes the event for input that isn't a move that can be played, and why
+fn Invalid(input: &str, reason: &str) {
+  Event("invalid", &[("input", json::Quote(input.trim())), ("reason", json::Quote(reason))]);
+}
+
+// Writes one event
+fn Event(name: &str, fields: &[(&str, String)]) {
+  let mut stdout = std::io::stdout().lock();
+  let _ = writeln!(stdout, "{}", Line(name, fields));
+  let _ = stdout.flush();
+}
+
+// An event as a JSON object: its name, then the fields, whose values are already JSON
+pub fn Line(name: &str, fields: &[(&str, String)]) -> String {
+  let mut line = format!("{{\"event\":{}", json::Quote(name));
+  for (key, value) in fields {
+    line += &format!(",{}:{}", json::Quote(key), value);
+  }
+  line += "}";
+  line
+}
@@ -0,0 +1,249 @@
+// C interface to the game for the `ffi` feature, for embedding it in C and C++ programs.
+// The matching header is include/connectfour.h; build the library with:
+//   cargo rustc --lib --release --features ffi --crate-type cdylib
+//
+// A game is an opaque pointer from cf_game_new, freed with cf_game_free. Every function
+// checks its pointer, so passing null gives CF_ERR_NULL_GAME rather than a crash. Any other
+// pointer can't be checked, so the functions taking one are unsafe: it has to be a game from
+// cf_game_new or cf_game_new_sized that hasn't been freed, and not in use by another thread.
+
+use crate::{Game, MoveError, UndoError};
+use std::ffi::{c_char, c_int};
+
+// Status codes; everything below zero is an error, one for each MoveError
+pub const CF_OK: c_int = 0;
+pub const CF_ERR_GAME_FINISHED: c_int = -1;
+pub const CF_ERR_INVALID_COLUMN: c_int = -2;
+pub const CF_ERR_COLUMN_FULL: c_int = -3;
+pub const CF_ERR_POPOUT_DISABLED: c_int = -4;
+pub const CF_ERR_CANNOT_POP: c_int = -5;
+pub const CF_ERR_GRAVITY_ON: c_int = -6;
+pub const CF_ERR_GRAVITY_OFF: c_int = -7;
+pub const CF_ERR_INVALID_ROW: c_int = -8;
+pub const CF_ERR_CELL_OCCUPIED: c_int = -9;
+pub const CF_ERR_BOMBS_DISABLED: c_int = -10;
+pub const CF_ERR_NO_BOMBS_LEFT: c_int = -11;
+pub const CF_ERR_NULL_GAME: c_int = -12; // The game pointer was null
+pub const CF_ERR_NOTHING_TO_UNDO: c_int = -13; // No move has been played yet
+pub const CF_ERR_OUT_OF_TIME: c_int = -14; // The game was lost on a clock that has run out
+
+// Code for each way a move can fail
+pub fn ErrorCode(err: &MoveError) -> c_int {
+  match err {
+    MoveError::GameFinished => CF_ERR_GAME_FINISHED,
+    MoveError::InvalidColumn { .. } => CF_ERR_INVALID_COLUMN,
+    MoveError::ColumnFull { .. } => CF_ERR_COLUMN_FULL,
+    MoveError::PopOutDisabled => CF_ERR_POPOUT_DISABLED,
+    MoveError::CannotPop => CF_ERR_CANNOT_POP,
+    MoveError::GravityOn => CF_ERR_GRAVITY_ON,
+    MoveError::GravityOff => CF_ERR_GRAVITY_OFF,
+    MoveError::InvalidRow => CF_ERR_INVALID_ROW,
+    MoveError::CellOccupied => CF_ERR_CELL_OCCUPIED,
+    MoveError::BombsDisabled => CF_ERR_BOMBS_DISABLED,
+    MoveError::NoBombsLeft => CF_ERR_NO_BOMBS_LEFT,
+  }
+}
+
+// The error behind a code, if it is one. A code doesn't say which column was at fault, so
+// those errors come back with column 0.
+pub fn FromCode(code: c_int) -> Option<MoveError> {
+  [
+    MoveError::GameFinished,
+    MoveError::InvalidColumn { Given: 0, Max: 0 },
+    MoveError::ColumnFull { Column: 0 },
+    MoveError::PopOutDisabled,
+    MoveError::CannotPop,
+    MoveError::GravityOn,
+    MoveError::GravityOff,
+    MoveError::InvalidRow,
+    MoveError::CellOccupied,
+    MoveError::BombsDisabled,
+    MoveError::NoBombsLeft,
+  ]
+  .into_iter()
+  .find(|err| ErrorCode(err) == code)
+}
+
+// Message for any status code; the column errors are worded without the column, which the
+// code doesn't carry
+pub(crate) fn Message(code: c_int) -> String {
+  match code {
+    CF_OK => "No error".to_string(),
+    CF_ERR_INVALID_COLUMN => "Invalid column".to_string(),
+    CF_ERR_COLUMN_FULL => "Column is full".to_string(),
+    CF_ERR_NULL_GAME => "No game given".to_string(),
+    CF_ERR_NOTHING_TO_UNDO => UndoError::NoMoves.to_string(),
+    CF_ERR_OUT_OF_TIME => UndoError::OutOfTime.to_string(),
+    _ => FromCode(code).map_or_else(|| "Unknown error".to_string(), |err| err.to_string()),
+  }
+}
+
+/// Borrows the game behind a pointer from the host, if there is one
+///
+/// # Safety
+/// The pointer is null or from Give, not yet taken back, and the game isn't changed elsewhere
+/// while it's borrowed.
+pub(crate) unsafe fn GameAt<'a>(game: *const Game) -> Option<&'a Game> {
+  unsafe { game.as_ref() }
+}
+
+/// # Safety
+/// As for GameAt, and nothing else borrows the game meanwhile.
+pub(crate) unsafe fn GameAtMut<'a>(game: *mut Game) -> Option<&'a mut Game> {
+  unsafe { game.as_mut() }
+}
+
+// Hands a game to the host, as a pointer it gives back to free it
+pub(crate) fn Give(game: Game) -> *mut Game {
+  Box::into_raw(Box::new(game))
+}
+
+/// Takes back a game handed out by Give, so it can be freed
+///
+/// # Safety
+/// The pointer is null or from Give, and isn't used again afterwards.
+pub(crate) unsafe fn Take(game: *mut Game) -> Option<Box<Game>> {
+  (!game.is_null()).then(|| unsafe { Box::from_raw(game) })
+}
+
+// Starts a standard 7x6 game
+#[no_mangle]
+pub extern "C" fn cf_game_new() -> *mut Game {
+  Give(Game::default())
+}
+
+// Starts a game on a board of the given size, needing `connect` in a row to win; gives null
+// when the rules don't make a valid game
+#[no_mangle]
+pub extern "C" fn cf_game_new_sized(width: c_int, height: c_int, connect: c_int) -> *mut Game {
+  let (Ok(width), Ok(height), Ok(connect)) = (usize::try_from(width), usize::try_from(height), usize::try_from(connect)) else {
+    return std::ptr::null_mut();
+  };
+  match Game::new(width, height).and_then(|game| game.WithWinLength(connect)) {
+    Ok(game) => Give(game),
+    Err(_) => std::ptr::null_mut(),
+  }
+}
+
+/// Frees a game; the pointer mustn't be used again afterwards. Null is ignored.
+///
+/// # Safety
+/// The pointer is null or a game from cf_game_new or cf_game_new_sized not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn cf_game_free(game: *mut Game) {
+  drop(Take(game));
+}
+
+/// Drops the current player's piece into the column (0 is the leftmost); gives CF_OK or an
+/// error code
+///
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_make_move(game: *mut Game, column: c_int) -> c_int {
+  let Some(game) = GameAtMut(game) else { return CF_ERR_NULL_GAME };
+  let Ok(column) = usize::try_from(column) else { return CF_ERR_INVALID_COLUMN };
+  match game.MakeMove(column) {
+    Ok(_) => CF_OK,
+    Err(err) => ErrorCode(&err),
+  }
+}
+
+/// Player on a cell (row 0 is the top row): 0 for empty, 1 to 3 for a player; CF_ERR_INVALID_ROW
+/// or CF_ERR_INVALID_COLUMN when the cell is off the board
+///
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_get_cell(game: *const Game, row: c_int, column: c_int) -> c_int {
+  let Some(game) = GameAt(game) else { return CF_ERR_NULL_GAME };
+  let Some(row) = usize::try_from(row).ok().filter(|&row| row < game.Height()) else { return CF_ERR_INVALID_ROW };
+  let Some(column) = usize::try_from(column).ok().filter(|&column| column < game.Width()) else {
+    return CF_ERR_INVALID_COLUMN;
+  };
+  game.Board()[row][column] as c_int
+}
+
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_width(game: *const Game) -> c_int {
+  GameAt(game).map_or(CF_ERR_NULL_GAME, |game| game.Width() as c_int)
+}
+
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_height(game: *const Game) -> c_int {
+  GameAt(game).map_or(CF_ERR_NULL_GAME, |game| game.Height() as c_int)
+}
+
+/// Number of the player to move
+///
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_current_player(game: *const Game) -> c_int {
+  GameAt(game).map_or(CF_ERR_NULL_GAME, |game| game.CurrentPlayer() as c_int)
+}
+
+/// Number of the winner, or 0 while nobody has won (including after a draw)
+///
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_winner(game: *const Game) -> c_int {
+  GameAt(game).map_or(CF_ERR_NULL_GAME, |game| game.Winner() as c_int)
+}
+
+/// 1 once the game is over, 0 while it goes on
+///
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_is_finished(game: *const Game) -> c_int {
+  GameAt(game).map_or(CF_ERR_NULL_GAME, |game| game.IsFinished() as c_int)
+}
+
+/// 1 if a piece can be dropped into the column now, 0 if not; the legal moves are the columns
+/// where this holds
+///
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_is_legal(game: *const Game, column: c_int) -> c_int {
+  let Some(game) = GameAt(game) else { return CF_ERR_NULL_GAME };
+  let legal = usize::try_from(column).is_ok_and(|column| !game.IsFinished() && game.IsColumnPlayable(column));
+  legal as c_int
+}
+
+/// Takes back the last move; gives CF_OK, CF_ERR_NOTHING_TO_UNDO or CF_ERR_OUT_OF_TIME
+///
+/// # Safety
+/// The pointer is null or a live game from cf_game_new or cf_game_new_sized.
+#[no_mangle]
+pub unsafe extern "C" fn cf_undo(game: *mut Game) -> c_int {
+  let Some(game) = GameAtMut(game) else { return CF_ERR_NULL_GAME };
+  match game.UndoMove() {
+    Ok(()) => CF_OK,
+    Err(UndoError::NoMoves) => CF_ERR_NOTHING_TO_UNDO,
+    Err(UndoError::OutOfTime) => CF_ERR_OUT_OF_TIME,
+  }
+}
+
+/// Writes the message for a status code into the buffer, cut short to fit and always ended by a
+/// nul as long as there's room for one; gives the message's full length, like snprintf
+///
+/// # Safety
+/// The buffer is null or has room for `size` bytes, none of them in use elsewhere meanwhile.
+#[no_mangle]
+pub unsafe extern "C" fn cf_error_message(code: c_int, buffer: *mut c_char, size: usize) -> c_int {
+  let message = Message(code);
+  if !buffer.is_null() && size > 0 {
+    let length = message.len().min(size - 1);
+    let out = unsafe { std::slice::from_raw_parts_mut(buffer.cast::<u8>(), size) };
+    out[..length].copy_from_slice(&message.as_bytes()[..length]);
+    out[length] = 0;
+  }
+  message.len() as c_int
+}
@@ -0,0 +1,96 @@
+use crate::Move;
+
+// Struct recording the moves played in a game, with room to redo undone moves
+#[derive(Clone, Debug, Default)]
+pub struct MoveHistory {
+  Moves: Vec<Move>,  // Every move played, including undone ones still available to redo
+  Position: usize,   // Number of moves currently applied to the board
+}
+
+impl MoveHistory {
+  pub fn new() -> MoveHistory {
+    MoveHistory { Moves: Vec::new(), Position: 0 }
+  }
+
+  // Records a freshly played move, discarding anything that could have been redone
+  pub fn Push(&mut self, played: Move) {
+    self.Moves.truncate(self.Position);
+    self.Moves.push(played);
+    self.Position += 1;
+  }
+
+  // Steps back one move and returns the move that was undone
+  pub fn Undo(&mut self) -> Option<Move> {
+    if self.Position == 0 {
+      return None;
+    }
+    self.Position -= 1;
+    Some(self.Moves[self.Position])
+  }
+
+  // Returns the move that a redo would replay, without consuming it
+  pub fn PeekRedo(&self) -> Option<Move> {
+    self.Moves.get(self.Position).copied()
+  }
+
+  // Steps forward one move and returns the move that was redone
+  pub fn Redo(&mut self) -> Option<Move> {
+    let played = self.PeekRedo()?;
+    self.Position += 1;
+    Some(played)
+  }
+
+  // The moves currently on the board, in the order they were played
+  pub fn Played(&self) -> &[Move] {
+    &self.Moves[..self.Position]
+  }
+
+  // Forgets the undone moves, once they can't be replayed anymore
+  pub fn ClearRedo(&mut self) {
+    self.Moves.truncate(self.Position);
+  }
+
+  // Number of undone moves that can still be replayed
+  pub fn RedoCount(&self) -> usize {
+    self.Moves.len() - self.Position
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A history with the columns dropped in order
+  fn Dropped(columns: &[usize]) -> MoveHistory {
+    let mut history = MoveHistory::new();
+    columns.iter().for_each(|&column| history.Push(Move::Drop(column)));
+    history
+  }
+
+  #[test]
+  fn UndoRedoUndo() {
+    let mut history = Dropped(&[3, 2]);
+    assert_eq!(history.Undo(), Some(Move::Drop(2)));
+    assert_eq!(history.Played(), [Move::Drop(3)]);
+    assert_eq!(history.RedoCount(), 1);
+    assert_eq!(history.Redo(), Some(Move::Drop(2)));
+    assert_eq!(history.Played(), [Move::Drop(3), Move::Drop(2)]);
+    assert_eq!(history.RedoCount(), 0);
+    assert_eq!(history.Undo(), Some(Move::Drop(2)));
+    assert_eq!(history.Undo(), Some(Move::Drop(3)));
+    assert_eq!(history.Undo(), None);
+    assert_eq!(history.RedoCount(), 2);
+  }
+
+  #[test]
+  fn NewMoveDropsTheRedos() {
+    let mut history = Dropped(&[3, 2, 4]);
+    history.Undo();
+    history.Undo();
+    history.Push(Move::Drop(0));
+    assert_eq!(history.Played(), [Move::Drop(3), Move::Drop(0)]);
+    assert_eq!(history.RedoCount(), 0);
+    assert_eq!(history.PeekRedo(), None);
+    assert_eq!(history.Redo(), None);
+  }
+}
@@ -0,0 +1,275 @@
+// HTTP server mode, `serve --http 127.0.0.1:8080`, for scripts and chat bots that would rather
+// make a request than hold a connection open. Bodies are JSON both ways:
+//
+//   POST   /games             make a game, by the rules from the options or with "width",
+//                             "height" and "connect" from the body; 201 with its state
+//   GET    /games/{id}        the game's state
+//   POST   /games/{id}/moves  play {"column":3}, with "kind" and "row" as for --json, and
+//                             "move_number", the number the move would have, to make sure
+//                             nobody has moved since the state was read; 200 with the new
+//                             state, or 409 with what's wrong with the move
+//   DELETE /games/{id}        throw the game away; 204
+//
+// The state has the board, rows from the top, whose turn it is, the result, and under "game"
+// the whole game as a save file has it. Requests are answered on threads of their own; each
+// game has a lock of its own, so moves posted at once are played one after the other against
+// the game as it is then.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ConnectFour::json::{self, Value};
+use ConnectFour::{log, save, Game, MoveError, Player};
+
+use crate::events;
+use crate::interrupt;
+use crate::{locale, ArgValue, Message};
+
+// How often the server stops waiting for requests to see whether Ctrl-C was pressed
+const POLL: Duration = Duration::from_millis(100);
+
+// How long a request may take to arrive, so a client that stops sending doesn't hold a thread
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Longest request head and body read
+const MAX_HEAD: usize = 8 * 1024;
+const MAX_BODY: usize = 64 * 1024;
+
+// Every game, by id, each behind a lock of its own
+type Games = Arc<Mutex<HashMap<u64, Arc<Mutex<Game>>>>>;
+
+// Struct representing an answer: its status, and the JSON body if it has one
+struct Response {
+  Status: u16,
+  Body: Option<String>,
+  Location: Option<String>, // For a game just made
+}
+
+impl Response {
+  fn Json(status: u16, body: String) -> Response {
+    Response { Status: status, Body: Some(body), Location: None }
+  }
+
+  fn Error(status: u16, message: &str) -> Response {
+    Response::Json(status, format!("{{\"error\":{}}}", json::Quote(message)))
+  }
+}
+
+// Answers requests on `--http` until Ctrl-C
+pub fn Run() {
+  let start = match crate::GameFromArgs() {
+    Ok(game) => game,
+    Err(err) => return Failed(&err.to_string()),
+  };
+  let address = ArgValue("--http").unwrap_or_default();
+  let listener = match TcpListener::bind(&address).and_then(|listener| listener.set_nonblocking(true).map(|()| listener)) {
+    Ok(listener) => listener,
+    Err(err) => return Failed(&format!("can't listen on {}: {}", address, err)),
+  };
+  println!("{}", locale::Say(Message::ServingHttp, &[("address", &address)]));
+  let games: Games = Arc::default();
+  let ids = Arc::new(AtomicU64::new(0));
+  while !interrupt::Interrupted() {
+    match listener.accept() {
+      Ok((stream, _)) => {
+        let (games, ids, start) = (Arc::clone(&games), Arc::clone(&ids), start.clone());
+        std::thread::spawn(move || Answer(stream, &games, &ids, &start));
+      }
+      Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(POLL),
+      Err(err) => log::Info(format_args!("Connection not accepted: {}", err)),
+    }
+  }
+}
+
+fn Failed(err: &str) {
+  println!("{}{}{}", crate::Alert(), locale::Say(Message::Error, &[("error", &err)]), crate::Reset());
+}
+
+// Reads one request, answers it and closes the connection
+fn Answer(mut stream: TcpStream, games: &Games, ids: &AtomicU64, start: &Game) {
+  let _ = stream.set_nonblocking(false);
+  let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+  let response = match ReadRequest(&mut stream) {
+    Ok((method, path, body)) => {
+      let response = Route(&method, &path, &body, games, ids, start);
+      log::Info(format_args!("{} {} {}", method, path, response.Status));
+      response
+    }
+    Err(response) => response,
+  };
+  let reason = match response.Status {
+    200 => "OK",
+    201 => "Created",
+    204 => "No Content",
+    400 => "Bad Request",
+    404 => "Not Found",
+    405 => "Method Not Allowed",
+    409 => "Conflict",
+    413 => "Payload Too Large",
+    _ => "Internal Server Error",
+  };
+  let body = response.Body.unwrap_or_default();
+  let mut head = format!("HTTP/1.1 {} {}\r\nConnection: close\r\nContent-Length: {}\r\n", response.Status, reason, body.len());
+  if !body.is_empty() {
+    head += "Content-Type: application/json\r\n";
+  }
+  if let Some(location) = response.Location {
+    head += &format!("Location: {}\r\n", location);
+  }
+  let _ = stream.write_all(format!("{}\r\n{}", head, body).as_bytes());
+}
+
+// The method, path and body of the request on the connection
+fn ReadRequest(stream: &mut TcpStream) -> Result<(String, String, String), Response> {
+  let mut head = Vec::new();
+  let mut byte = [0u8; 1];
+  while !head.ends_with(b"\r\n\r\n") {
+    if head.len() >= MAX_HEAD {
+      return Err(Response::Error(413, "the request head is too long"));
+    }
+    match stream.read(&mut byte) {
+      Ok(1) => head.push(byte[0]),
+      _ => return Err(Response::Error(400, "the request ended early")),
+    }
+  }
+  let head = String::from_utf8_lossy(&head);
+  let mut words = head.lines().next().unwrap_or("").split_whitespace();
+  let (Some(method), Some(path)) = (words.next(), words.next()) else {
+    return Err(Response::Error(400, "the request line isn't METHOD PATH VERSION"));
+  };
+  let length = head
+      .lines()
+      .skip(1)
+      .filter_map(|line| line.split_once(':'))
+      .find(|(key, _)| key.trim().eq_ignore_ascii_case("Content-Length"))
+      .map(|(_, value)| value.trim().parse::<usize>().map_err(|_| Response::Error(400, "Content-Length isn't a number")))
+      .transpose()?
+      .unwrap_or(0);
+  if length > MAX_BODY {
+    return Err(Response::Error(413, "the body is too long"));
+  }
+  let mut body = vec![0u8; length];
+  stream.read_exact(&mut body).map_err(|_| Response::Error(400, "the body ended early"))?;
+  let body = String::from_utf8(body).map_err(|_| Response::Error(400, "the body isn't UTF-8"))?;
+  // The query, if any, isn't used
+  let path = path.split('?').next().unwrap_or(path).trim_end_matches('/');
+  Ok((method.to_string(), path.to_string(), body))
+}
+
+// What the request asks for, by method and path
+fn Route(method: &str, path: &str, body: &str, games: &Games, ids: &AtomicU64, start: &Game) -> Response {
+  let parts: Vec<&str> = path.split('/').skip(1).collect();
+  let answered = match (method, parts.as_slice()) {
+    ("POST", ["games"]) => Ok(Create(body, games, ids, start)),
+    (_, ["games"]) => Err(Response::Error(405, "only POST is allowed on /games")),
+    ("GET", ["games", id]) => Find(games, id).map(|(id, game)| Response::Json(200, State(id, &game.lock().unwrap()))),
+    ("DELETE", ["games", id]) => Find(games, id).map(|(id, _)| {
+      games.lock().unwrap().remove(&id);
+      log::Info(format_args!("Game {} deleted", id));
+      Response { Status: 204, Body: None, Location: None }
+    }),
+    (_, ["games", _]) => Err(Response::Error(405, "only GET and DELETE are allowed on a game")),
+    ("POST", ["games", id, "moves"]) => Find(games, id).map(|(id, game)| Play(id, &game, body)),
+    (_, ["games", _, "moves"]) => Err(Response::Error(405, "only POST is allowed on moves")),
+    _ => Err(Response::Error(404, "no such resource; games are under /games")),
+  };
+  answered.unwrap_or_else(|response| response)
+}
+
+// The game with the id in the path. The map is only locked long enough to find it, so
+// requests for different games don't wait on each other.
+fn Find(games: &Games, id: &str) -> Result<(u64, Arc<Mutex<Game>>), Response> {
+  let found = id.parse().ok().and_then(|id| Some((id, Arc::clone(games.lock().unwrap().get(&id)?))));
+  found.ok_or(Response::Error(404, "no such game"))
+}
+
+// Makes a game by the rules from the options, with the size and line length from the body
+fn Create(body: &str, games: &Games, ids: &AtomicU64, start: &Game) -> Response {
+  let request = match body.trim().is_empty() {
+    true => Value::Object(Vec::new()),
+    false => match json::Parse(body) {
+      Ok(request) => request,
+      Err(err) => return Response::Error(400, &err.to_string()),
+    },
+  };
+  let number = |name: &str, default: usize| request.Get(name).and_then(Value::AsUsize).unwrap_or(default);
+  let made = Game::new(number("width", start.Width()), number("height", start.Height()))
+      .and_then(|game| game.WithWinLength(number("connect", start.WinLength())))
+      .and_then(|game| game.WithPlayers(start.Players()));
+  let game = match made {
+    Ok(game) => game.WithPopOut(start.PopOut()).WithGravity(start.Gravity()).WithWrap(start.Wrap()).WithBombs(start.BombsPerPlayer()),
+    Err(err) => return Response::Error(400, &err.to_string()),
+  };
+  let id = ids.fetch_add(1, Ordering::Relaxed) + 1;
+  let state = State(id, &game);
+  games.lock().unwrap().insert(id, Arc::new(Mutex::new(game)));
+  log::Info(format_args!("Game {} created", id));
+  Response { Status: 201, Body: Some(state), Location: Some(format!("/games/{}", id)) }
+}
+
+// Plays the move in the body, if it can be played
+fn Play(id: u64, game: &Mutex<Game>, body: &str) -> Response {
+  let request = match json::Parse(body) {
+    Ok(request) => request,
+    Err(err) => return Response::Error(400, &err.to_string()),
+  };
+  let played = match events::MoveFrom(&request) {
+    Ok(played) => played,
+    Err(err) => return Response::Error(400, &err),
+  };
+  let mut game = game.lock().unwrap();
+  let due = game.History().len() + 1;
+  if let Some(number) = request.Get("move_number").and_then(Value::AsUsize).filter(|&number| number != due) {
+    let stale = format!("move {} was asked for, but the next move is move {}", number, due);
+    return Response::Json(409, format!("{{\"error\":{},\"code\":\"MoveNumber\"}}", json::Quote(&stale)));
+  }
+  match game.Play(played) {
+    Ok(_) => Response::Json(200, State(id, &game)),
+    Err(err) => Response::Json(409, format!("{{\"error\":{},\"code\":{}}}", json::Quote(&err.to_string()), json::Quote(&Code(&err)))),
+  }
+}
+
+// The name of a move error, e.g. "ColumnFull", for programs to go by
+fn Code(err: &MoveError) -> String {
+  let debug = format!("{:?}", err);
+  debug.split(' ').next().unwrap_or_default().to_string()
+}
+
+// The game's state as a JSON object
+fn State(id: u64, game: &Game) -> String {
+  let rows: Vec<String> = game
+      .Board()
+      .Rows()
+      .map(|row| format!("[{}]", row.iter().map(u8::to_string).collect::<Vec<_>>().join(",")))
+      .collect();
+  let legal: Vec<String> = match game.IsFinished() {
+    true => Vec::new(),
+    false => game.LegalMoves().iter().map(usize::to_string).collect(),
+  };
+  let result = match (game.IsFinished(), game.Winner()) {
+    (false, _) => "in_progress",
+    (true, Player::None) => "draw",
+    (true, _) => "won",
+  };
+  let line: Vec<String> = game.WinningLine().iter().map(|(row, column)| format!("[{},{}]", row, column)).collect();
+  let fields = [
+    ("id", id.to_string()),
+    ("width", game.Width().to_string()),
+    ("height", game.Height().to_string()),
+    ("connect", game.WinLength().to_string()),
+    ("board", format!("[{}]", rows.join(","))),
+    ("turn", (game.CurrentPlayer() as u8).to_string()),
+    ("move_number", game.History().len().to_string()),
+    ("legal", format!("[{}]", legal.join(","))),
+    ("result", json::Quote(result)),
+    ("winner", (game.Winner() as u8).to_string()),
+    ("line", format!("[{}]", line.join(","))),
+    ("game", save::ToJson(game).trim_end().to_string()),
+  ];
+  let fields: Vec<String> = fields.iter().map(|(key, value)| format!("{}:{}", json::Quote(key), value)).collect();
+  format!("{{{}}}", fields.join(","))
+}
@@ -0,0 +1,481 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ConnectFour::{Game, Move};
+
+use crate::interrupt::{self, Wait};
+use crate::locale::{self, Message};
+use crate::terminal;
+
+// Enum representing everything a player can type at a prompt
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+  Play(Move),    // A move: a drop, pop, bomb or placement
+  Undo,          // Take back the last move
+  Redo,          // Replay an undone move
+  Hint,          // Ask the engine for a move
+  Eval,          // Toggle the evaluation bar
+  History,       // List the moves so far
+  Board,         // Describe the board in words
+  Threats,       // Toggle the threat overlay
+  Teach,         // Toggle teaching mode for the player on turn
+  Draw,          // Offer the opponent a draw
+  Takeback,      // Ask the opponent to let you take back your last move
+  Save(String),  // Save the game to the named file
+  Load(String),  // Load a game from the named file
+  Theme(String), // Switch to the named theme, or list them if there's no name
+  Analysis,      // Analyze the finished game
+  Transcript,    // Write a transcript of the finished game
+  Yes,           // Answer yes, e.g. to play again
+  No,            // Answer no
+  Help,          // List the commands
+  Quit,          // Leave the program
+  Say(String),   // Say something to the other end of a network game
+  Chat,          // Show what's been said in the network game
+}
+
+// Commands by full name, shortcut letter and the message saying what they do, which is also
+// where the help comes from. Shortcuts aren't understood while columns are typed as letters, since they
+// would name columns. Save and load stand for the commands with a file name after them, theme
+// for the one with a theme's name and say for the one with something to say.
+pub static COMMANDS: [(&str, &str, Message, Command); 22] = [
+  ("undo", "u", Message::HelpUndo, Command::Undo),
+  ("redo", "r", Message::HelpRedo, Command::Redo),
+  ("hint", "h", Message::HelpHint, Command::Hint),
+  ("eval", "e", Message::HelpEval, Command::Eval),
+  ("history", "m", Message::HelpHistory, Command::History),
+  ("board", "", Message::HelpBoard, Command::Board),
+  ("threats", "t", Message::HelpThreats, Command::Threats),
+  ("teach", "", Message::HelpTeach, Command::Teach),
+  ("draw", "", Message::HelpDraw, Command::Draw),
+  ("takeback", "", Message::HelpTakeback, Command::Takeback),
+  ("save", "", Message::HelpSave, Command::Save(String::new())),
+  ("load", "", Message::HelpLoad, Command::Load(String::new())),
+  ("theme", "", Message::HelpTheme, Command::Theme(String::new())),
+  ("analysis", "a", Message::HelpAnalysis, Command::Analysis),
+  ("transcript", "s", Message::HelpTranscript, Command::Transcript),
+  ("yes", "y", Message::HelpYes, Command::Yes),
+  ("no", "n", Message::HelpNo, Command::No),
+  ("help", "?", Message::HelpHelp, Command::Help),
+  ("quit", "q", Message::HelpQuit, Command::Quit),
+  ("exit", "", Message::HelpQuit, Command::Quit),
+  ("say", "", Message::HelpSay, Command::Say(String::new())),
+  ("chat", "", Message::HelpChat, Command::Chat),
+];
+
+// Parses anything typed at a prompt, forgiving of spacing, case and a trailing full stop:
+// "4", " 4 ", "4.", "c4" and "col 4" all drop into column 4, and so does "d" when `letters`
+// says columns are being typed as letters. Without it single letters are only the commands'
+// shortcuts. Commands may start with a slash, as in chat programs: "/say good game".
+pub fn ParseCommand(game: &Game, input: &str, letters: bool) -> Result<Command, String> {
+  let trimmed = input.trim();
+  let trimmed = trimmed.strip_prefix('/').unwrap_or(trimmed);
+  if let Some(path) = FileName(trimmed, "save") {
+    return Ok(Command::Save(path));
+  }
+  if let Some(path) = FileName(trimmed, "load") {
+    return Ok(Command::Load(path));
+  }
+  if let Some(name) = FileName(trimmed, "theme") {
+    return Ok(Command::Theme(name));
+  }
+  if let Some(text) = FileName(trimmed, "say") {
+    return Ok(Command::Say(text));
+  }
+
+  let text = trimmed.to_lowercase();
+  let text = text.trim_end_matches('.').trim();
+  if text.is_empty() {
+    return Err(locale::Text(Message::TypeAColumn).to_string());
+  }
+  let found = COMMANDS.iter().find(|(name, short, _, _)| {
+    *name == text || (!short.is_empty() && *short == text && (!letters || *short == "?"))
+  });
+  match found {
+    Some((name, _, _, Command::Save(_) | Command::Load(_))) => {
+      return Err(locale::Say(Message::NeedFileName, &[("command", name)]))
+    }
+    Some((name, _, _, Command::Say(_))) => return Err(locale::Say(Message::NeedChatText, &[("command", name)])),
+    Some((_, _, _, command)) => return Ok(command.clone()),
+    None => {}
+  }
+  match ParseMove(game, text, letters) {
+    Ok(played) => Ok(Command::Play(played)),
+    Err(err) if LooksLikeMove(text) => Err(err),
+    Err(_) => Err(locale::Say(Message::NotUnderstood, &[("input", &trimmed)])),
+  }
+}
+
+// Parses a move: a column number, or letter when `letters` is set, optionally written "c4" or
+// "col 4"; "p4" or "pop 4" to pop column 4 in PopOut games; "b4" or "bomb 4" to drop a bomb in
+// bomb games; or "column,row" such as "4,3" in games without gravity (rows count from the
+// bottom)
+pub fn ParseMove(game: &Game, input: &str, letters: bool) -> Result<Move, String> {
+  let text = input.trim().to_lowercase();
+  let text = text.trim_end_matches('.').trim();
+  if !game.Gravity() {
+    // "4,3", "4 3" and "col 4, row 3" all name the same cell
+    let words: Vec<&str> = text
+        .split([',', ' '])
+        .filter(|word| !word.is_empty() && !["column", "col", "row"].contains(word))
+        .collect();
+    let [column, row] = words[..] else {
+      return Err(locale::Text(Message::CellFormat).to_string());
+    };
+    let column = ParseColumn(column, game.Width(), letters)?;
+    let row = match row.parse::<usize>() {
+      Ok(row) if (1..=game.Height()).contains(&row) => row,
+      _ => return Err(locale::Say(Message::NoSuchRow, &[("row", &row), ("height", &game.Height())])),
+    };
+    return Ok(Move::Place(game.Height() - row, column));
+  }
+  // "p", "b" and "c" on their own are columns rather than a prefix missing its column
+  if let Some(rest) = Prefixed(text, &["pop", "p"]).filter(|_| game.PopOut()) {
+    return Ok(Move::Pop(ParseColumn(rest, game.Width(), letters)?));
+  }
+  if let Some(rest) = Prefixed(text, &["bomb", "b"]).filter(|_| game.HasBombs()) {
+    return Ok(Move::Bomb(ParseColumn(rest, game.Width(), letters)?));
+  }
+  let column = Prefixed(text, &["column", "col", "c"]).unwrap_or(text);
+  Ok(Move::Drop(ParseColumn(column, game.Width(), letters)?))
+}
+
+// Parses a column typed as its number or, with `letters` on boards of up to 26 columns, its
+// letter
+pub fn ParseColumn(text: &str, width: usize, letters: bool) -> Result<usize, String> {
+  let text = text.trim();
+  let mut chars = text.chars();
+  if let (true, Some(letter @ 'a'..='z'), None) = (letters, chars.next(), chars.next()) {
+    let column = letter as usize - 'a' as usize;
+    return match column < width && width <= 26 {
+      true => Ok(column),
+      false => Err(locale::Say(Message::NoSuchColumnLetter, &[("column", &letter)])),
+    };
+  }
+  match text.parse::<usize>() {
+    Ok(number) if (1..=width).contains(&number) => Ok(number - 1),
+    _ => Err(locale::Say(Message::NoSuchColumn, &[("column", &text), ("width", &width)])),
+  }
+}
+
+// The rest of the text after the first of the prefixes it starts with, if that rest is
+// something; a bare prefix such as "c" is left alone to be read as a column
+fn Prefixed<'a>(text: &'a str, prefixes: &[&str]) -> Option<&'a str> {
+  prefixes
+      .iter()
+      .find_map(|prefix| text.strip_prefix(prefix))
+      .map(str::trim)
+      .filter(|rest| !rest.is_empty())
+}
+
+// The file name after a command word such as "save", keeping its case
+fn FileName(text: &str, command: &str) -> Option<String> {
+  let rest = text.get(..command.len()).filter(|word| word.eq_ignore_ascii_case(command)).map(|_| &text[command.len()..])?;
+  rest.starts_with(char::is_whitespace).then(|| rest.trim().to_string()).filter(|path| !path.is_empty())
+}
+
+// Returns true if the text is shaped like a move, so a parse error is worth reporting as is
+fn LooksLikeMove(text: &str) -> bool {
+  text.chars().any(|c| c.is_ascii_digit()) || text.chars().count() == 1
+}
+
+// Enum representing why an input source gave no move or command
+#[derive(Debug, PartialEq)]
+pub enum InputError {
+  Invalid(String), // Something came that isn't a move or command, and why
+  TimedOut,        // The deadline passed first
+  Interrupted,     // Ctrl+C was pressed
+  Ended,           // There's nothing more to come, such as at the end of a script
+}
+
+// Trait for where the players' moves and answers come from
+pub trait InputSource: Send {
+  // Waits for the next line until the deadline, if there is one; `tick` is called every so
+  // often while waiting
+  fn NextLine(&mut self, deadline: Option<Instant>, tick: &mut dyn FnMut()) -> Result<String, InputError>;
+
+  // Waits for the next move or command at the prompt. `letters` is whether columns are being
+  // typed as letters.
+  fn NextCommand(
+    &mut self,
+    game: &Game,
+    letters: bool,
+    deadline: Option<Instant>,
+    tick: &mut dyn FnMut(),
+  ) -> Result<Command, InputError> {
+    let line = self.NextLine(deadline, tick)?;
+    ParseCommand(game, &line, letters).map_err(InputError::Invalid)
+  }
+}
+
+// Where input comes from; the terminal until something else is chosen
+static SOURCE: Mutex<Option<Box<dyn InputSource>>> = Mutex::new(None);
+
+// Takes input from the source from now on
+pub fn Use(source: Box<dyn InputSource>) {
+  *SOURCE.lock().expect("input source lock") = Some(source);
+}
+
+// Runs `read` on the input source in use
+fn WithSource<T>(read: impl FnOnce(&mut dyn InputSource) -> T) -> T {
+  let mut source = SOURCE.lock().expect("input source lock");
+  read(source.get_or_insert_with(|| Box::new(Terminal)).as_mut())
+}
+
+// Waits for the next line from the input source in use
+pub fn NextLine(deadline: Option<Instant>, tick: &mut dyn FnMut()) -> Result<String, InputError> {
+  WithSource(|source| source.NextLine(deadline, tick))
+}
+
+// Waits for the next move or command from the input source in use
+pub fn NextCommand(game: &Game, letters: bool, deadline: Option<Instant>, tick: &mut dyn FnMut()) -> Result<Command, InputError> {
+  WithSource(|source| source.NextCommand(game, letters, deadline, tick))
+}
+
+// Struct reading what's typed at the terminal. With `--raw-input` each key pressed is a line
+// of its own, and ':' starts a longer command such as a save, typed out and entered as usual.
+// Once stdin ends, reading gives InputError::Ended.
+pub struct Terminal;
+
+impl InputSource for Terminal {
+  fn NextLine(&mut self, deadline: Option<Instant>, tick: &mut dyn FnMut()) -> Result<String, InputError> {
+    let waited = match terminal::ReadingKeys() {
+      true => ReadKeys(deadline, tick),
+      false => interrupt::ReadLineBy(deadline, tick),
+    };
+    match waited {
+      Wait::Line(line) if line.is_empty() && interrupt::InputEnded() => Err(InputError::Ended),
+      Wait::Line(line) => Ok(line),
+      Wait::Interrupted => Err(InputError::Interrupted),
+      Wait::TimedOut => Err(InputError::TimedOut),
+    }
+  }
+
+  // Keys that mean nothing at the prompt only ring the bell with `--raw-input`, so a slip
+  // doesn't redraw the board with an error
+  fn NextCommand(
+    &mut self,
+    game: &Game,
+    letters: bool,
+    deadline: Option<Instant>,
+    tick: &mut dyn FnMut(),
+  ) -> Result<Command, InputError> {
+    loop {
+      let line = self.NextLine(deadline, tick)?;
+      match ParseCommand(game, &line, letters) {
+        Err(_) if terminal::ReadingKeys() && !line.is_empty() => {
+          print!("{}", terminal::Bell());
+          let _ = std::io::stdout().flush();
+        }
+        parsed => return parsed.map_err(InputError::Invalid),
+      }
+    }
+  }
+}
+
+// Waits for a key, or a ':' command, until the deadline
+fn ReadKeys(deadline: Option<Instant>, tick: &mut dyn FnMut()) -> Wait {
+  // Without a deadline, wait as long as it takes
+  let until = || deadline.unwrap_or_else(|| Instant::now() + Duration::from_secs(3600));
+  loop {
+    let key = terminal::ReadKey(until().min(Instant::now() + interrupt::POLL_INTERVAL));
+    let line = match key {
+      Some(terminal::Key::Char(':')) => {
+        print!(":");
+        let _ = std::io::stdout().flush();
+        let mut line = String::new();
+        loop {
+          match terminal::ReadKey(until()) {
+            Some(terminal::Key::Enter) => break,
+            Some(terminal::Key::Escape) => {
+              line.clear();
+              break;
+            }
+            Some(terminal::Key::Backspace) if line.pop().is_some() => print!("\x08 \x08"),
+            Some(terminal::Key::Char(letter)) => {
+              line.push(letter);
+              print!("{}", letter);
+            }
+            Some(terminal::Key::Space) => {
+              line.push(' ');
+              print!(" ");
+            }
+            Some(_) => continue,
+            None => return KeyWait(deadline),
+          }
+          let _ = std::io::stdout().flush();
+        }
+        println!();
+        return Wait::Line(line + "\n");
+      }
+      Some(terminal::Key::Char(letter)) => letter.to_string(),
+      Some(terminal::Key::Enter) => String::new(),
+      Some(_) => continue,
+      None if !interrupt::Interrupted()
+        && !interrupt::InputEnded()
+        && deadline.is_none_or(|deadline| Instant::now() < deadline) =>
+      {
+        tick();
+        continue;
+      }
+      None => return KeyWait(deadline),
+    };
+    println!("{}", line);
+    return Wait::Line(line + "\n");
+  }
+}
+
+// Why waiting for a key ended without one
+fn KeyWait(deadline: Option<Instant>) -> Wait {
+  if interrupt::Interrupted() {
+    Wait::Interrupted
+  } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+    Wait::TimedOut
+  } else {
+    Wait::Line(String::new())
+  }
+}
+
+// Struct giving lines decided beforehand, one at each prompt as if they'd been typed, and
+// echoing them so the output reads like a game played at the keyboard. Drives games from a
+// `--script` file, or from a program embedding the game.
+pub struct Scripted {
+  Lines: VecDeque<String>, // Lines still to come
+}
+
+impl Scripted {
+  pub fn new<T: Into<String>>(lines: impl IntoIterator<Item = T>) -> Scripted {
+    Scripted { Lines: lines.into_iter().map(Into::into).collect() }
+  }
+
+  // Reads the lines of a file, leaving out blank lines and ones starting with '#'
+  pub fn FromFile(path: &str) -> Result<Scripted, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("couldn't read the script {}: {}", path, err))?;
+    let lines = text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+    Ok(Scripted::new(lines))
+  }
+}
+
+impl InputSource for Scripted {
+  fn NextLine(&mut self, _deadline: Option<Instant>, _tick: &mut dyn FnMut()) -> Result<String, InputError> {
+    if interrupt::Interrupted() {
+      return Err(InputError::Interrupted);
+    }
+    let line = self.Lines.pop_front().ok_or(InputError::Ended)?;
+    println!("{}", line);
+    Ok(line + "\n")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ShortcutLettersAreCommandsWithoutLetters() {
+    let game = Game::default();
+    assert_eq!(ParseCommand(&game, "a", false), Ok(Command::Analysis));
+    assert_eq!(ParseCommand(&game, "e", false), Ok(Command::Eval));
+    for letter in ["b", "c", "d", "f", "g"] {
+      assert!(ParseCommand(&game, letter, false).is_err(), "{}", letter);
+    }
+    assert_eq!(ParseCommand(&game, "c4", false), Ok(Command::Play(Move::Drop(3))));
+    assert_eq!(ParseCommand(&game, "4", false), Ok(Command::Play(Move::Drop(3))));
+  }
+
+  #[test]
+  fn LettersAreColumnsWithLetters() {
+    let game = Game::default();
+    for (letter, column) in ["a", "b", "c", "d", "e", "f", "g"].into_iter().zip(0..) {
+      assert_eq!(ParseCommand(&game, letter, true), Ok(Command::Play(Move::Drop(column))), "{}", letter);
+    }
+    assert!(ParseCommand(&game, "h", true).is_err());
+    assert_eq!(ParseCommand(&game, "?", true), Ok(Command::Help));
+    assert_eq!(ParseCommand(&game, "analysis", true), Ok(Command::Analysis));
+  }
+
+  #[test]
+  fn DropsAreWrittenManyWays() {
+    let game = Game::default();
+    for text in ["4", " 4 ", "4.", "c4", "C4", "c 4", "col 4", "COLUMN 4", "  Col 4.  \n"] {
+      assert_eq!(ParseCommand(&game, text, false), Ok(Command::Play(Move::Drop(3))), "{:?}", text);
+    }
+    for text in ["d", " D ", "col d", "cd"] {
+      assert_eq!(ParseCommand(&game, text, true), Ok(Command::Play(Move::Drop(3))), "{:?}", text);
+    }
+    // Numbers work with letters on too
+    assert_eq!(ParseCommand(&game, "4", true), Ok(Command::Play(Move::Drop(3))));
+  }
+
+  #[test]
+  fn ColumnsOffTheBoardAreRejected() {
+    let game = Game::default();
+    for text in ["0", "8", "99", "-1", "c8", "4x"] {
+      assert!(ParseMove(&game, text, false).is_err(), "{:?}", text);
+    }
+    assert_eq!(
+      ParseMove(&game, "8", false),
+      Err(locale::Say(Message::NoSuchColumn, &[("column", &"8"), ("width", &7)]))
+    );
+    assert_eq!(ParseMove(&game, "h", true), Err(locale::Say(Message::NoSuchColumnLetter, &[("column", &'h')])));
+    let wide = Game::new(9, 7).unwrap();
+    assert_eq!(ParseMove(&wide, "9", false), Ok(Move::Drop(8)));
+    assert_eq!(ParseMove(&wide, "i", true), Ok(Move::Drop(8)));
+  }
+
+  #[test]
+  fn PopsAndBombsNeedTheirGames() {
+    let popout = Game::default().WithPopOut(true);
+    for text in ["p4", "P4", "pop 4", "Pop 4."] {
+      assert_eq!(ParseMove(&popout, text, false), Ok(Move::Pop(3)), "{:?}", text);
+    }
+    assert_eq!(ParseMove(&popout, "pd", true), Ok(Move::Pop(3)));
+    let bombs = Game::default().WithBombs(1);
+    for text in ["b4", "B 4", "bomb 4"] {
+      assert_eq!(ParseMove(&bombs, text, false), Ok(Move::Bomb(3)), "{:?}", text);
+    }
+    // A prefix letter on its own is still a column
+    assert_eq!(ParseMove(&bombs, "b", true), Ok(Move::Drop(1)));
+    // In a plain game they aren't moves at all
+    let game = Game::default();
+    assert!(ParseMove(&game, "p4", false).is_err());
+    assert!(ParseMove(&game, "b4", false).is_err());
+    assert!(ParseMove(&popout, "b4", false).is_err());
+  }
+
+  #[test]
+  fn CellsWithoutGravity() {
+    let game = Game::default().WithGravity(false);
+    // Rows count from the bottom, so row 3 is the fourth from the top
+    for text in ["4,3", "4, 3", " 4 3 ", "col 4, row 3", "COLUMN 4 ROW 3."] {
+      assert_eq!(ParseMove(&game, text, false), Ok(Move::Place(3, 3)), "{:?}", text);
+    }
+    assert_eq!(ParseMove(&game, "d,3", true), Ok(Move::Place(3, 3)));
+    assert_eq!(ParseMove(&game, "1,1", false), Ok(Move::Place(5, 0)));
+    assert_eq!(ParseMove(&game, "4", false), Err(locale::Text(Message::CellFormat).to_string()));
+    assert_eq!(ParseMove(&game, "4,3,2", false), Err(locale::Text(Message::CellFormat).to_string()));
+    assert_eq!(
+      ParseMove(&game, "4,7", false),
+      Err(locale::Say(Message::NoSuchRow, &[("row", &"7"), ("height", &6)]))
+    );
+    assert!(ParseMove(&game, "8,1", false).is_err());
+    assert!(ParseMove(&game, "4,0", false).is_err());
+  }
+
+  #[test]
+  fn CommandsIgnoreCaseAndSpacing() {
+    let game = Game::default();
+    assert_eq!(ParseCommand(&game, "  UNDO  ", false), Ok(Command::Undo));
+    assert_eq!(ParseCommand(&game, "Redo.", true), Ok(Command::Redo));
+    assert_eq!(ParseCommand(&game, "/quit", false), Ok(Command::Quit));
+    assert_eq!(ParseCommand(&game, "U", false), Ok(Command::Undo));
+    // File names and chat keep their case
+    assert_eq!(ParseCommand(&game, "SAVE My Game.json ", false), Ok(Command::Save("My Game.json".to_string())));
+    assert_eq!(ParseCommand(&game, "/say Good Game", false), Ok(Command::Say("Good Game".to_string())));
+    assert_eq!(ParseCommand(&game, "save", false), Err(locale::Say(Message::NeedFileName, &[("command", &"save")])));
+    assert_eq!(ParseCommand(&game, "   ", false), Err(locale::Text(Message::TypeAColumn).to_string()));
+    assert_eq!(ParseCommand(&game, "dance", false), Err(locale::Say(Message::NotUnderstood, &[("input", &"dance")])));
+  }
+}
@@ -0,0 +1,284 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ConnectFour::log;
+
+// A second Ctrl+C this soon after the first quits at once, even if the game is busy
+const FORCE_QUIT_WINDOW_MS: u64 = 2000;
+
+// How often a waiting read looks for an interrupt, and gives the caller a chance to update
+// the screen
+pub const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false); // Ctrl+C pressed and not yet handled
+static LAST_INTERRUPT_MS: AtomicU64 = AtomicU64::new(0); // When it was pressed
+
+// What the user types, read on a background thread so waiting for input can be interrupted
+static INPUT: OnceLock<Mutex<Input>> = OnceLock::new();
+
+// Struct holding input as it arrives: whole lines in the usual line-by-line mode, or single
+// keys once the terminal has been switched to reading keys as they're pressed
+struct Input {
+  Chunks: Receiver<Vec<u8>>, // Bytes from the reader thread
+  Pending: Vec<u8>,          // Bytes received and not yet taken
+  Ended: bool,               // Whether the input has run out
+}
+
+impl Input {
+  // Waits up to `wait` for more input. Returns false if none came.
+  fn Receive(&mut self, wait: Duration) -> bool {
+    match self.Chunks.recv_timeout(wait) {
+      Ok(chunk) => {
+        self.Pending.extend(chunk);
+        true
+      }
+      Err(RecvTimeoutError::Timeout) => false,
+      Err(RecvTimeoutError::Disconnected) => {
+        self.Ended = true;
+        false
+      }
+    }
+  }
+
+  // The first whole line received, or what's left once the input has ended
+  fn TakeLine(&mut self) -> Option<String> {
+    let end = match self.Pending.iter().position(|&byte| byte == b'\n') {
+      Some(newline) => newline + 1,
+      None if self.Ended && !self.Pending.is_empty() => self.Pending.len(),
+      None => return None,
+    };
+    let line: Vec<u8> = self.Pending.drain(..end).collect();
+    let line = String::from_utf8_lossy(&line);
+    Some(line.trim_end_matches(['\r', '\n']).to_string() + "\n")
+  }
+}
+
+// Returns true once Ctrl+C has been pressed
+pub fn Interrupted() -> bool {
+  INTERRUPTED.load(Ordering::SeqCst)
+}
+
+// Enum representing how waiting for a line of input ended
+pub enum Wait {
+  Line(String), // A line was entered in time
+  Interrupted,  // Ctrl+C was pressed
+  TimedOut,     // The deadline passed first
+}
+
+// Returns true once there's no more input to read
+pub fn InputEnded() -> bool {
+  let input = Input().lock().expect("input reader lock");
+  input.Ended && input.Pending.is_empty()
+}
+
+// Waits for the next line of input until the deadline, if there is one. Once it passes,
+// anything typed so far is thrown away, including a line still being typed, so a move
+// finished after the deadline can't land on the next prompt. `tick` is called each time it
+// looks for input, so a clock on screen can keep counting down meanwhile.
+pub fn ReadLineBy(deadline: Option<Instant>, tick: &mut dyn FnMut()) -> Wait {
+  let mut input = Input().lock().expect("input reader lock");
+  loop {
+    if Interrupted() {
+      return Wait::Interrupted;
+    }
+    // A line that only turns up after the deadline is too late, however close it was
+    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+      platform::DiscardTyped();
+      while input.Receive(Duration::ZERO) {}
+      input.Pending.clear();
+      return Wait::TimedOut;
+    }
+    if let Some(line) = input.TakeLine() {
+      log::Trace(format_args!("Read the line {:?}", line));
+      return Wait::Line(line);
+    }
+    if input.Ended {
+      return Wait::Line(String::new());
+    }
+    if !input.Receive(POLL_INTERVAL) {
+      tick();
+    }
+  }
+}
+
+// The next byte typed, waiting until the deadline for it. None if it passes first, Ctrl+C is
+// pressed or the input ends. Used once the terminal reads keys as they're pressed.
+pub fn NextByte(deadline: Instant) -> Option<u8> {
+  let mut input = Input().lock().expect("input reader lock");
+  loop {
+    if !input.Pending.is_empty() {
+      return Some(input.Pending.remove(0));
+    }
+    let left = deadline.saturating_duration_since(Instant::now());
+    if Interrupted() || input.Ended || left.is_zero() {
+      return None;
+    }
+    input.Receive(left.min(POLL_INTERVAL));
+  }
+}
+
+// Waits for the given time, or less if a line is entered, Ctrl+C is pressed or the input
+// ends. Returns true if it was cut short. Unlike ReadLineBy, anything half typed is kept.
+pub fn Pause(time: Duration) -> bool {
+  let deadline = Instant::now() + time;
+  let mut input = Input().lock().expect("input reader lock");
+  while !Interrupted() {
+    if input.TakeLine().is_some() || input.Ended {
+      return true;
+    }
+    let left = deadline.saturating_duration_since(Instant::now());
+    if left.is_zero() {
+      return false;
+    }
+    input.Receive(left.min(POLL_INTERVAL));
+  }
+  true
+}
+
+// The input, read on a background thread started on first use
+fn Input() -> &'static Mutex<Input> {
+  INPUT.get_or_init(|| {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+      let mut buffer = [0u8; 256];
+      loop {
+        match std::io::stdin().lock().read(&mut buffer) {
+          Ok(0) | Err(_) => break,
+          Ok(read) => {
+            if sender.send(buffer[..read].to_vec()).is_err() {
+              break;
+            }
+          }
+        }
+      }
+    });
+    Mutex::new(Input { Chunks: receiver, Pending: Vec::new(), Ended: false })
+  })
+}
+
+// Catches Ctrl+C so the game can save and leave the terminal tidy instead of being killed
+pub fn Install() {
+  platform::Install();
+}
+
+fn NowMs() -> u64 {
+  SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|elapsed| elapsed.as_millis() as u64)
+      .unwrap_or(0)
+}
+
+// Called from the signal handler, so it only touches atomics unless it has to quit
+fn OnInterrupt() {
+  let now = NowMs();
+  let last = LAST_INTERRUPT_MS.swap(now, Ordering::SeqCst);
+  if INTERRUPTED.swap(true, Ordering::SeqCst) && now.saturating_sub(last) < FORCE_QUIT_WINDOW_MS {
+    platform::ForceQuit();
+  }
+}
+
+#[cfg(unix)]
+mod platform {
+  const SIGINT: i32 = 2;
+  const NEWLINE: &[u8] = b"\n";
+
+  // tcflush's selector for input received but not yet read
+  #[cfg(target_os = "linux")]
+  const TCIFLUSH: i32 = 0;
+  #[cfg(not(target_os = "linux"))]
+  const TCIFLUSH: i32 = 1;
+
+  extern "C" {
+    fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    fn write(fd: i32, buf: *const u8, count: usize) -> isize;
+    fn tcflush(fd: i32, queue: i32) -> i32;
+    fn _exit(status: i32) -> !;
+  }
+
+  extern "C" fn Handler(_: i32) {
+    super::OnInterrupt();
+  }
+
+  pub fn Install() {
+    unsafe {
+      signal(SIGINT, Handler);
+    }
+  }
+
+  // Only async-signal-safe calls here: reset the colors, if they're on, and leave
+  pub fn ForceQuit() {
+    crate::terminal::Restore();
+    unsafe {
+      if crate::theme::Colored() {
+        write(1, crate::terminal::RESET.as_ptr(), crate::terminal::RESET.len());
+      }
+      write(1, NEWLINE.as_ptr(), NEWLINE.len());
+      _exit(130);
+    }
+  }
+
+  // Drops whatever has been typed at the terminal but not yet entered. Does nothing when
+  // input comes from a pipe or a file.
+  pub fn DiscardTyped() {
+    unsafe {
+      tcflush(0, TCIFLUSH);
+    }
+  }
+}
+
+#[cfg(windows)]
+mod platform {
+  extern "system" {
+    fn SetConsoleCtrlHandler(handler: extern "system" fn(u32) -> i32, add: i32) -> i32;
+    fn GetStdHandle(which: u32) -> *mut u8;
+    fn FlushConsoleInputBuffer(console: *mut u8) -> i32;
+  }
+
+  const CTRL_C_EVENT: u32 = 0;
+  const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+
+  extern "system" fn Handler(event: u32) -> i32 {
+    if event != CTRL_C_EVENT {
+      return 0; // Let Windows deal with closing the console and logging off
+    }
+    super::OnInterrupt();
+    1
+  }
+
+  pub fn Install() {
+    unsafe {
+      SetConsoleCtrlHandler(Handler, 1);
+    }
+  }
+
+  // Windows runs the handler on its own thread, so exiting normally is fine
+  pub fn ForceQuit() {
+    crate::terminal::Restore();
+    if crate::theme::Colored() {
+      print!("{}", crate::terminal::RESET);
+    }
+    println!();
+    std::process::exit(130);
+  }
+
+  // Drops whatever has been typed in the console but not yet entered
+  pub fn DiscardTyped() {
+    unsafe {
+      FlushConsoleInputBuffer(GetStdHandle(STD_INPUT_HANDLE));
+    }
+  }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+  pub fn Install() {}
+
+  pub fn ForceQuit() {
+    std::process::exit(130);
+  }
+
+  pub fn DiscardTyped() {}
+}
@@ -0,0 +1,262 @@
+// Minimal JSON reader, enough for the game's own save files without pulling in a library.
+// Numbers are limited to whole non-negative values, which is all a saved game contains.
+
+// Enum representing a parsed JSON value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+  Null,
+  Bool(bool),
+  Number(u64),
+  String(String),
+  Array(Vec<Value>),
+  Object(Vec<(String, Value)>), // Keys in the order they appeared
+}
+
+// Struct describing malformed JSON and where it was found
+#[derive(Debug, PartialEq)]
+pub struct SyntaxError {
+  pub Line: usize,          // 1-based line of the offending character
+  pub Column: usize,        // 1-based column of the offending character
+  pub Message: &'static str,
+}
+
+impl std::fmt::Display for SyntaxError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} at line {}, column {}", self.Message, self.Line, self.Column)
+  }
+}
+
+impl Value {
+  // Looks up a key of an object; None for missing keys and for anything that isn't an object
+  pub fn Get(&self, key: &str) -> Option<&Value> {
+    match self {
+      Value::Object(fields) => fields.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  pub fn AsU64(&self) -> Option<u64> {
+    match self {
+      Value::Number(number) => Some(*number),
+      _ => None,
+    }
+  }
+
+  pub fn AsUsize(&self) -> Option<usize> {
+    self.AsU64().and_then(|number| usize::try_from(number).ok())
+  }
+
+  pub fn AsBool(&self) -> Option<bool> {
+    match self {
+      Value::Bool(flag) => Some(*flag),
+      _ => None,
+    }
+  }
+
+  pub fn AsStr(&self) -> Option<&str> {
+    match self {
+      Value::String(text) => Some(text),
+      _ => None,
+    }
+  }
+
+  pub fn AsArray(&self) -> Option<&[Value]> {
+    match self {
+      Value::Array(items) => Some(items),
+      _ => None,
+    }
+  }
+}
+
+// Parses a complete JSON document
+pub fn Parse(text: &str) -> Result<Value, SyntaxError> {
+  let mut parser = Parser { Text: text.as_bytes(), Position: 0 };
+  let value = parser.Value()?;
+  parser.SkipWhitespace();
+  if parser.Position < parser.Text.len() {
+    return Err(parser.Error("Unexpected text after the end of the document"));
+  }
+  Ok(value)
+}
+
+// Quotes a string for writing into JSON
+pub fn Quote(text: &str) -> String {
+  let mut quoted = String::with_capacity(text.len() + 2);
+  quoted.push('"');
+  for character in text.chars() {
+    match character {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+      c => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+// Recursive descent parser over the raw bytes of the document
+struct Parser<'a> {
+  Text: &'a [u8],
+  Position: usize, // Index of the next byte to read
+}
+
+impl Parser<'_> {
+  fn Error(&self, message: &'static str) -> SyntaxError {
+    let before = &self.Text[..self.Position.min(self.Text.len())];
+    let line = before.iter().filter(|&&byte| byte == b'\n').count() + 1;
+    let column = before.iter().rev().take_while(|&&byte| byte != b'\n').count() + 1;
+    SyntaxError { Line: line, Column: column, Message: message }
+  }
+
+  fn SkipWhitespace(&mut self) {
+    while matches!(self.Text.get(self.Position), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+      self.Position += 1;
+    }
+  }
+
+  fn Peek(&self) -> Option<u8> {
+    self.Text.get(self.Position).copied()
+  }
+
+  // Consumes the given byte or fails with the message
+  fn Expect(&mut self, byte: u8, message: &'static str) -> Result<(), SyntaxError> {
+    if self.Peek() != Some(byte) {
+      return Err(self.Error(message));
+    }
+    self.Position += 1;
+    Ok(())
+  }
+
+  fn Value(&mut self) -> Result<Value, SyntaxError> {
+    self.SkipWhitespace();
+    match self.Peek() {
+      Some(b'{') => self.Object(),
+      Some(b'[') => self.Array(),
+      Some(b'"') => Ok(Value::String(self.String()?)),
+      Some(b'0'..=b'9') => self.Number(),
+      Some(b'-') => Err(self.Error("Negative numbers aren't expected here")),
+      Some(b't') => self.Literal("true", Value::Bool(true)),
+      Some(b'f') => self.Literal("false", Value::Bool(false)),
+      Some(b'n') => self.Literal("null", Value::Null),
+      Some(_) => Err(self.Error("Expected a value")),
+      None => Err(self.Error("Unexpected end of the document")),
+    }
+  }
+
+  fn Literal(&mut self, word: &'static str, value: Value) -> Result<Value, SyntaxError> {
+    if !self.Text[self.Position..].starts_with(word.as_bytes()) {
+      return Err(self.Error("Expected a value"));
+    }
+    self.Position += word.len();
+    Ok(value)
+  }
+
+  fn Number(&mut self) -> Result<Value, SyntaxError> {
+    let mut number: u64 = 0;
+    while let Some(byte @ b'0'..=b'9') = self.Peek() {
+      number = number
+          .checked_mul(10)
+          .and_then(|number| number.checked_add((byte - b'0') as u64))
+          .ok_or_else(|| self.Error("Number is too large"))?;
+      self.Position += 1;
+    }
+    if matches!(self.Peek(), Some(b'.' | b'e' | b'E')) {
+      return Err(self.Error("Only whole numbers are expected here"));
+    }
+    Ok(Value::Number(number))
+  }
+
+  fn String(&mut self) -> Result<String, SyntaxError> {
+    self.Expect(b'"', "Expected a string")?;
+    let mut bytes = Vec::new();
+    loop {
+      match self.Peek() {
+        None => return Err(self.Error("Unterminated string")),
+        Some(b'"') => break,
+        Some(b'\\') => {
+          self.Position += 1;
+          let escaped = match self.Peek() {
+            Some(b'"') => b'"',
+            Some(b'\\') => b'\\',
+            Some(b'/') => b'/',
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b't') => b'\t',
+            Some(b'b') => 0x08,
+            Some(b'f') => 0x0c,
+            Some(b'u') => {
+              let code = self.Text
+                  .get(self.Position + 1..self.Position + 5)
+                  .and_then(|hex| std::str::from_utf8(hex).ok())
+                  .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                  .and_then(char::from_u32)
+                  .ok_or_else(|| self.Error("Invalid unicode escape"))?;
+              let mut buffer = [0; 4];
+              bytes.extend_from_slice(code.encode_utf8(&mut buffer).as_bytes());
+              self.Position += 5;
+              continue;
+            }
+            _ => return Err(self.Error("Invalid escape sequence")),
+          };
+          bytes.push(escaped);
+        }
+        Some(byte) => bytes.push(byte),
+      }
+      self.Position += 1;
+    }
+    self.Position += 1;
+    String::from_utf8(bytes).map_err(|_| self.Error("String is not valid UTF-8"))
+  }
+
+  fn Array(&mut self) -> Result<Value, SyntaxError> {
+    self.Expect(b'[', "Expected an array")?;
+    let mut items = Vec::new();
+    self.SkipWhitespace();
+    if self.Peek() == Some(b']') {
+      self.Position += 1;
+      return Ok(Value::Array(items));
+    }
+    loop {
+      items.push(self.Value()?);
+      self.SkipWhitespace();
+      match self.Peek() {
+        Some(b',') => self.Position += 1,
+        Some(b']') => {
+          self.Position += 1;
+          return Ok(Value::Array(items));
+        }
+        _ => return Err(self.Error("Expected ',' or ']'")),
+      }
+    }
+  }
+
+  fn Object(&mut self) -> Result<Value, SyntaxError> {
+    self.Expect(b'{', "Expected an object")?;
+    let mut fields = Vec::new();
+    self.SkipWhitespace();
+    if self.Peek() == Some(b'}') {
+      self.Position += 1;
+      return Ok(Value::Object(fields));
+    }
+    loop {
+      self.SkipWhitespace();
+      let key = self.String()?;
+      self.SkipWhitespace();
+      self.Expect(b':', "Expected ':' after a key")?;
+      fields.push((key, self.Value()?));
+      self.SkipWhitespace();
+      match self.Peek() {
+        Some(b',') => self.Position += 1,
+        Some(b'}') => {
+          self.Position += 1;
+          return Ok(Value::Object(fields));
+        }
+        _ => return Err(self.Error("Expected ',' or '}'")),
+      }
+    }
+  }
+}
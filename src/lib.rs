@@ -0,0 +1,1790 @@
+#![allow(non_snake_case)] // Disable the snake_case warning, PascalCase FTW!
+
+pub mod analysis;
+pub mod archive;
+pub mod bitboard;
+pub mod board;
+pub mod book;
+pub mod bot;
+pub mod clock;
+pub mod difficulty;
+pub mod engine;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod history;
+pub mod json;
+pub mod log;
+pub mod mcts;
+pub mod observer;
+pub mod perft;
+pub mod random;
+pub mod rating;
+pub mod rng;
+pub mod save;
+pub mod solver;
+pub mod stats;
+pub mod transcript;
+pub mod transposition;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod zobrist;
+
+pub use bitboard::BitBoard;
+pub use board::Board;
+pub use clock::{Clock, TimeControl};
+pub use history::MoveHistory;
+use rng::Rng;
+use std::time::Duration;
+
+// Constants defining the default (standard) board dimensions
+pub const BOARD_WIDTH: usize = 7;
+pub const BOARD_HEIGHT: usize = 6;
+
+// Limits on the board dimensions, in both directions
+pub const MIN_BOARD_SIZE: usize = 4;
+pub const MAX_BOARD_SIZE: usize = 32;
+
+// Number of players: the classic two, or up to three
+pub const MAX_PLAYERS: usize = 3;
+
+// Number of pieces in a row needed to win, by default and at the least
+pub const WIN_LENGTH: usize = 4;
+pub const MIN_WIN_LENGTH: usize = 3;
+
+// Enum representing the players and an empty cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum Player {
+  One = 1,   // Player One
+  Two = 2,   // Player Two
+  Three = 3, // Player Three, only in three-player games
+  None = 0,  // No player (empty cell)
+}
+
+impl Player {
+  // Converts an integer value to a Player enum
+  pub fn FromInt(value: u8) -> Player {
+    match value {
+      1 => Player::One,
+      2 => Player::Two,
+      3 => Player::Three,
+      _ => Player::None,
+    }
+  }
+
+  // Returns the player who moves after this one in a two-player game
+  pub fn Other(self) -> Player {
+    self.Next(2)
+  }
+
+  // Returns the player who moves after this one when `players` take turns
+  pub fn Next(self, players: usize) -> Player {
+    match self {
+      Player::None => Player::None,
+      _ => Player::FromInt(self as u8 % players as u8 + 1),
+    }
+  }
+
+  // Returns the player who moved before this one when `players` take turns
+  pub fn Previous(self, players: usize) -> Player {
+    match self {
+      Player::None => Player::None,
+      _ => Player::FromInt((self as u8 + players as u8 - 2) % players as u8 + 1),
+    }
+  }
+}
+
+// Enum representing possible errors when making a move
+#[derive(Clone, Debug, PartialEq)]
+pub enum MoveError {
+  GameFinished, // The game has already ended
+  InvalidColumn { Given: usize, Max: usize }, // The column isn't on the board; Max is the last one there is
+  ColumnFull { Column: usize },                // The selected column is full
+  PopOutDisabled, // Popping pieces isn't part of this game's rules
+  CannotPop,     // The bottom piece of the column isn't the current player's
+  GravityOn,     // Pieces can only be placed on any cell in games without gravity
+  GravityOff,    // Pieces can't be dropped in games without gravity
+  InvalidRow,    // The row number is invalid
+  CellOccupied,  // The selected cell already holds a piece
+  BombsDisabled, // Bombs aren't part of this game's rules
+  NoBombsLeft,   // The current player has already used their bombs
+}
+
+impl std::fmt::Display for MoveError {
+  // Provides a user-friendly description for each error
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      MoveError::GameFinished => write!(f, "Game is already finished"),
+      // Columns are counted from 1 here, as players type them
+      MoveError::InvalidColumn { Given, Max } => {
+        write!(f, "Column {} is out of range (1–{})", Given.saturating_add(1), Max + 1)
+      }
+      MoveError::ColumnFull { Column } => write!(f, "Column {} is full", Column + 1),
+      MoveError::PopOutDisabled => write!(f, "Popping pieces is only allowed in PopOut games"),
+      MoveError::CannotPop => write!(f, "You can only pop your own piece from the bottom row"),
+      MoveError::GravityOn => write!(f, "Pieces can only be placed freely in games without gravity"),
+      MoveError::GravityOff => write!(f, "Pick a cell as column,row; pieces don't fall in this game"),
+      MoveError::InvalidRow => write!(f, "Invalid row"),
+      MoveError::CellOccupied => write!(f, "Cell is already taken"),
+      MoveError::BombsDisabled => write!(f, "Bombs are only allowed in bomb games"),
+      MoveError::NoBombsLeft => write!(f, "You have already used your bomb this game"),
+    }
+  }
+}
+
+impl std::error::Error for MoveError {}
+
+// Enum representing problems with the settings of a new game
+#[derive(Debug)]
+pub enum ConfigError {
+  InvalidSize { Width: usize, Height: usize }, // The board is too small or too large
+  InvalidWinLength(usize),                     // No line of this length fits on the board
+  InvalidPlayers(usize),                       // Only two or three players are supported
+  InvalidHandicap(usize),                      // The handicap pieces don't fit on the board
+  InvalidTimeControl(String),                  // The time control isn't understood
+}
+
+impl std::fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ConfigError::InvalidSize { Width, Height } => write!(
+        f,
+        "Invalid board size {}x{}, each side must be between {} and {}",
+        Width, Height, MIN_BOARD_SIZE, MAX_BOARD_SIZE
+      ),
+      ConfigError::InvalidWinLength(length) => write!(
+        f,
+        "Invalid win length {}, it must be at least {} and fit on the board",
+        length, MIN_WIN_LENGTH
+      ),
+      ConfigError::InvalidPlayers(players) => {
+        write!(f, "Invalid number of players {}, it must be 2 or {}", players, MAX_PLAYERS)
+      }
+      ConfigError::InvalidHandicap(pieces) => {
+        write!(f, "Invalid handicap of {} pieces, they don't fit on the board", pieces)
+      }
+      ConfigError::InvalidTimeControl(text) => write!(
+        f,
+        "Invalid time control '{}', give seconds such as 180, 60+2 for an increment or 60d3 for a delay",
+        text
+      ),
+    }
+  }
+}
+
+// Enum representing problems with a position that was set up by hand
+#[derive(Debug)]
+pub enum PositionError {
+  Config(ConfigError),                                  // The board size doesn't fit the rules
+  WrongSize { Width: usize, Height: usize },            // The board isn't the size of the game
+  InvalidPiece { Row: usize, Column: usize, Value: u8 }, // A cell holds no known player (rows from the bottom)
+  FloatingPiece { Row: usize, Column: usize },          // A piece has an empty cell below it
+  InvalidPlayer,                                        // The player to move isn't in the game
+  PieceCounts { Counts: Vec<usize>, ToMove: Player },   // The counts don't fit alternating turns
+  SeveralWinners,                                       // More than one player has a line
+  WrongWinner(Player),                                  // The winner wasn't the last to move
+}
+
+impl std::fmt::Display for PositionError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PositionError::Config(err) => write!(f, "{}", err),
+      PositionError::WrongSize { Width, Height } => {
+        write!(f, "The board is {}x{}, which isn't the size of this game", Width, Height)
+      }
+      PositionError::InvalidPiece { Row, Column, Value } => write!(
+        f,
+        "The cell in column {}, row {} holds {}, which isn't a player",
+        Column + 1,
+        Row + 1,
+        Value
+      ),
+      PositionError::FloatingPiece { Row, Column } => write!(
+        f,
+        "The piece in column {}, row {} is floating above an empty cell",
+        Column + 1,
+        Row + 1
+      ),
+      PositionError::InvalidPlayer => write!(f, "The player to move isn't in this game"),
+      PositionError::PieceCounts { Counts, ToMove } => {
+        let counts: Vec<String> = Counts.iter().map(|count| count.to_string()).collect();
+        write!(
+          f,
+          "Piece counts of {} don't fit alternating turns with Player {} to move",
+          counts.join("/"),
+          *ToMove as u8
+        )
+      }
+      PositionError::SeveralWinners => write!(f, "More than one player already has a line"),
+      PositionError::WrongWinner(player) => {
+        write!(f, "Player {} has a line but wasn't the last to move", *player as u8)
+      }
+    }
+  }
+}
+
+// Enum representing possible errors when undoing a move
+#[derive(Debug)]
+pub enum UndoError {
+  NoMoves,   // Nothing has been played yet
+  OutOfTime, // The game was lost on a clock that has run out
+}
+
+impl std::fmt::Display for UndoError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      UndoError::NoMoves => write!(f, "No moves to undo"),
+      UndoError::OutOfTime => write!(f, "The clock has run out, the loss on time stands"),
+    }
+  }
+}
+
+// Enum representing possible errors when redoing a move
+#[derive(Debug)]
+pub enum RedoError {
+  NothingToRedo, // No undone moves are waiting to be replayed
+  GameFinished,  // The game has ended since the moves were undone
+}
+
+impl std::fmt::Display for RedoError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RedoError::NothingToRedo => write!(f, "No moves to redo"),
+      RedoError::GameFinished => write!(f, "Game is already finished"),
+    }
+  }
+}
+
+// Enum representing reasons a draw can't be offered or accepted
+#[derive(Debug, PartialEq)]
+pub enum DrawError {
+  GameFinished,   // The game has already ended
+  TwoPlayersOnly, // Draws are only agreed between two players
+  NotYourTurn,    // Draws are offered on your own turn
+  AlreadyOffered, // An offer is already waiting for an answer
+  TooSoon,        // The same player offered on their previous turn
+  NoOffer,        // There is no offer to answer
+}
+
+impl std::fmt::Display for DrawError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DrawError::GameFinished => write!(f, "Game is already finished"),
+      DrawError::TwoPlayersOnly => write!(f, "Draws can only be agreed in two-player games"),
+      DrawError::NotYourTurn => write!(f, "You can only offer a draw on your turn"),
+      DrawError::AlreadyOffered => write!(f, "A draw offer is already waiting for an answer"),
+      DrawError::TooSoon => write!(f, "You offered a draw last turn; wait a turn before offering again"),
+      DrawError::NoOffer => write!(f, "No draw has been offered"),
+    }
+  }
+}
+
+// Enum representing problems with a move sequence, either a digit string such as "44435" or
+// notation such as "1. d d 2. c e". Index is the 0-based position of the offending character
+// or move.
+#[derive(Debug)]
+pub enum ParseError {
+  NotAColumn { Index: usize, Character: char },      // The character isn't a column of the board
+  UnexpectedToken { Move: usize, Token: String },    // The word isn't a column letter or move number
+  IllegalMove { Index: usize, Error: MoveError },    // The column can't be played at that point
+}
+
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ParseError::NotAColumn { Index, Character } => {
+        write!(f, "'{}' at position {} is not a column", Character, Index + 1)
+      }
+      ParseError::UnexpectedToken { Move, Token } => write!(f, "Unexpected token '{}' at move {}", Token, Move),
+      ParseError::IllegalMove { Index, Error } => write!(f, "Move {} can't be played: {}", Index + 1, Error),
+    }
+  }
+}
+
+// Enum representing a single move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Move {
+  Drop(usize), // Drop a new piece into the column
+  Pop(usize),  // Remove the mover's own piece from the bottom of the column (PopOut only)
+  Place(usize, usize), // Put a piece on the cell at (row, column) (games without gravity only)
+  Bomb(usize), // Drop the mover's bomb into the column (bomb games only)
+}
+
+impl Move {
+  // Column the move was played in
+  pub fn Column(self) -> usize {
+    match self {
+      Move::Drop(column) | Move::Pop(column) | Move::Place(_, column) | Move::Bomb(column) => column,
+    }
+  }
+}
+
+// Enum describing how the game stands after a move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveResult {
+  Continue,    // The game goes on
+  Win(Player), // The move won the game for the given player
+  Draw,        // The move filled the board without a winner
+}
+
+// Struct describing what happened when a move was made
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveOutcome {
+  pub Row: usize,         // Row the piece landed in, or was popped from (0 is the top row)
+  pub Column: usize,      // Column the piece was dropped into or popped from
+  pub Player: Player,     // Player who made the move
+  pub Result: MoveResult, // State of the game after the move
+}
+
+// Struct describing pieces placed for one player before the first move, to level a game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Handicap {
+  pub Player: Player, // Player the pieces belong to, usually the weaker one
+  pub Pieces: usize,  // Number of pieces placed
+  pub Seed: u64,      // Seed for the random layout; the same seed gives the same layout
+}
+
+// Struct representing the state of the game
+#[derive(Clone)]
+pub struct Game {
+  CurrentMove: usize,   // Counter for the current move
+  CurrentPlayer: Player, // The player whose turn it is
+  Board: Board,         // The game board
+  Heights: Vec<usize>,  // Number of pieces in each column (its height, when pieces fall)
+  Hash: u64,            // Zobrist hash of the board
+  WinLength: usize,     // Pieces in a row needed to win
+  PopOut: bool,         // Whether players may pop their own pieces from the bottom row
+  Players: usize,       // Number of players taking turns
+  Gravity: bool,        // Whether pieces fall to the bottom; without it any empty cell can be taken
+  Wrap: bool,           // Whether lines wrap around the side edges, as on a cylinder
+  BombsPerPlayer: u8,   // Bombs each player starts the game with
+  BombsLeft: [u8; MAX_PLAYERS], // Bombs each player still has
+  Exploded: Vec<Board>, // The board before each bomb still on the history, for undoing it
+  Handicap: Option<Handicap>, // Pieces placed before the first move, if any
+  Start: Option<(Board, Player)>, // Position set up by hand to start from, and who moves first
+  Clock: Option<Clock>, // Each player's time for the whole game, if the game is timed
+  IsFinished: bool,     // Flag indicating if the game is finished
+  Winner: Player,       // The winner of the game (if any)
+  History: MoveHistory, // Moves played so far, plus any undone moves
+  LastPlaced: Option<(usize, usize)>, // Cell (row, column) of the piece the last move put down
+  DrawOffer: Option<Player>,  // Player whose draw offer awaits an answer
+  LastDrawOffer: Option<(Player, usize)>, // Who last offered a draw, and at which move
+  DrawAgreed: bool,           // Whether the game ended in a draw by agreement
+  Forfeited: Option<Player>,  // Player who lost by running out of time, if any
+}
+
+// The standard 7x6 game
+impl Default for Game {
+  fn default() -> Self {
+    Self::new(BOARD_WIDTH, BOARD_HEIGHT).expect("standard size is valid")
+  }
+}
+
+// The board in plain text with a line below saying how the game stands, with no colors or
+// screen control, for logs, tests and front-ends of other kinds
+impl std::fmt::Display for Game {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}{}", self.Board, transcript::Status(self))
+  }
+}
+
+impl Game {
+  // Creates a new game on a board of the given size
+  pub fn new(width: usize, height: usize) -> Result<Game, ConfigError> {
+    let valid = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+    if !valid.contains(&width) || !valid.contains(&height) {
+      return Err(ConfigError::InvalidSize { Width: width, Height: height });
+    }
+    Ok(Game {
+      CurrentMove: 0,
+      CurrentPlayer: Player::One,
+      Board: Board::new(width, height),
+      Heights: vec![0; width],
+      Hash: 0,
+      WinLength: WIN_LENGTH,
+      PopOut: false,
+      Players: 2,
+      Gravity: true,
+      Wrap: false,
+      BombsPerPlayer: 0,
+      BombsLeft: [0; MAX_PLAYERS],
+      Exploded: Vec::new(),
+      Handicap: None,
+      Start: None,
+      Clock: None,
+      IsFinished: false,
+      Winner: Player::None,
+      History: MoveHistory::new(),
+      LastPlaced: None,
+      DrawOffer: None,
+      LastDrawOffer: None,
+      DrawAgreed: false,
+      Forfeited: None,
+    })
+  }
+
+  // Changes the number of pieces in a row needed to win; the line must fit on the board
+  pub fn WithWinLength(mut self, length: usize) -> Result<Game, ConfigError> {
+    if length < MIN_WIN_LENGTH || length > self.Width().max(self.Height()) {
+      return Err(ConfigError::InvalidWinLength(length));
+    }
+    self.WinLength = length;
+    Ok(self)
+  }
+
+  // Sets the number of players taking turns, two or three
+  pub fn WithPlayers(mut self, players: usize) -> Result<Game, ConfigError> {
+    if !(2..=MAX_PLAYERS).contains(&players) {
+      return Err(ConfigError::InvalidPlayers(players));
+    }
+    self.Players = players;
+    Ok(self)
+  }
+
+  // Switches gravity off, letting pieces be placed on any empty cell with PlaceAt
+  pub fn WithGravity(mut self, enabled: bool) -> Game {
+    self.Gravity = enabled;
+    self
+  }
+
+  // Rolls the board into a cylinder: horizontal and diagonal lines wrap around from the last
+  // column to the first
+  pub fn WithWrap(mut self, enabled: bool) -> Game {
+    self.Wrap = enabled;
+    self
+  }
+
+  // Gives each player this many bombs per game; see BombMove
+  pub fn WithBombs(mut self, bombs: u8) -> Game {
+    self.BombsPerPlayer = bombs;
+    self.BombsLeft = [bombs; MAX_PLAYERS];
+    self
+  }
+
+  // Times the game with a chess clock for each player; see SpendTime
+  pub fn WithClock(mut self, control: TimeControl) -> Game {
+    self.Clock = Some(Clock::new(control));
+    self
+  }
+
+  // Starts every game with `pieces` random pieces for `player` already on the board. They are
+  // dropped into random columns, never completing a line, and can't be undone. Set this after
+  // the other rules, since the layout depends on them.
+  pub fn WithHandicap(mut self, player: Player, pieces: usize, seed: u64) -> Result<Game, ConfigError> {
+    if player == Player::None || player as usize > self.Players || pieces >= self.Width() * self.Height() / 2 {
+      return Err(ConfigError::InvalidHandicap(pieces));
+    }
+    self.Handicap = Some(Handicap { Player: player, Pieces: pieces, Seed: seed });
+    self.Start = None;
+    self.Reset();
+    if self.Heights.iter().sum::<usize>() < pieces {
+      return Err(ConfigError::InvalidHandicap(pieces)); // Ran out of safe columns
+    }
+    Ok(self)
+  }
+
+  // Turns on the PopOut rules, where a player may remove one of their own pieces from the
+  // bottom row instead of dropping a new one
+  pub fn WithPopOut(mut self, enabled: bool) -> Game {
+    self.PopOut = enabled;
+    self
+  }
+
+  // Clears the board for a new game, keeping the size and rules
+  pub fn Reset(&mut self) {
+    self.CurrentMove = 0;
+    self.CurrentPlayer = Player::One;
+    self.Board.Clear();
+    self.Heights.iter_mut().for_each(|height| *height = 0);
+    self.Hash = 0;
+    self.IsFinished = false;
+    self.Winner = Player::None;
+    self.History = MoveHistory::new();
+    self.LastPlaced = None;
+    self.DrawOffer = None;
+    self.LastDrawOffer = None;
+    self.DrawAgreed = false;
+    self.Forfeited = None;
+    if let Some(clock) = &mut self.Clock {
+      clock.Reset();
+    }
+    self.BombsLeft = [self.BombsPerPlayer; MAX_PLAYERS];
+    self.Exploded.clear();
+    if let Some(handicap) = self.Handicap {
+      self.PlaceHandicap(handicap);
+    }
+    if let Some((board, ToMove)) = self.Start.clone() {
+      // The position was checked when it was set up
+      let _ = self.LoadPosition(board, ToMove);
+    }
+  }
+
+  // Creates a standard-rules game starting from a position set up by hand; see SetPosition
+  pub fn FromPosition(board: Board, ToMove: Player) -> Result<Game, PositionError> {
+    let mut game = Game::new(board.Width(), board.Height()).map_err(PositionError::Config)?;
+    game.SetPosition(board, ToMove)?;
+    Ok(game)
+  }
+
+  // Replaces the board with a position set up by hand, which rematches also start from.
+  // The position must be reachable by alternating turns: every cell holds a player of this
+  // game, pieces rest on each other when they fall, the piece counts fit `ToMove` being on
+  // turn, and at most one player has a line, who must have moved last. A position with a
+  // line or a full board is a finished game. Any handicap is replaced.
+  pub fn SetPosition(&mut self, board: Board, ToMove: Player) -> Result<(), PositionError> {
+    if board.Width() != self.Width() || board.Height() != self.Height() {
+      return Err(PositionError::WrongSize { Width: board.Width(), Height: board.Height() });
+    }
+    if ToMove == Player::None || ToMove as usize > self.Players {
+      return Err(PositionError::InvalidPlayer);
+    }
+    let mut counts = vec![0; self.Players];
+    for (row, cells) in board.Rows().enumerate() {
+      for (column, &value) in cells.iter().enumerate() {
+        if value as usize > self.Players {
+          return Err(PositionError::InvalidPiece { Row: self.Height() - 1 - row, Column: column, Value: value });
+        }
+        if value != 0 {
+          counts[value as usize - 1] += 1;
+        }
+        if self.Gravity && value != 0 && row + 1 < self.Height() && board[row + 1][column] == 0 {
+          return Err(PositionError::FloatingPiece { Row: self.Height() - 1 - row, Column: column });
+        }
+      }
+    }
+
+    // Turns go round in order, so the first (total % players) players have one extra piece
+    let total: usize = counts.iter().sum();
+    let fits = (0..self.Players).all(|index| {
+      counts[index] == total / self.Players + (index < total % self.Players) as usize
+    });
+    if !fits || ToMove as usize != total % self.Players + 1 {
+      return Err(PositionError::PieceCounts { Counts: counts, ToMove });
+    }
+
+    // Set up a copy, so a position with the wrong winner leaves this game untouched
+    let mut game = self.clone();
+    game.Handicap = None;
+    game.Start = None;
+    game.Reset();
+    game.LoadPosition(board.clone(), ToMove)?;
+    game.Start = Some((board, ToMove));
+    *self = game;
+    Ok(())
+  }
+
+  // Creates a standard game from a string of the columns played, e.g. "44435" (1-based)
+  pub fn FromMoveString(moves: &str) -> Result<Game, ParseError> {
+    let mut game = Game::default();
+    game.PlayMoveString(moves)?;
+    Ok(game)
+  }
+
+  // Drops pieces into the columns listed in the string, one digit per move, stopping at the
+  // first character that isn't a column or can't be played
+  pub fn PlayMoveString(&mut self, moves: &str) -> Result<(), ParseError> {
+    for (index, character) in moves.chars().enumerate() {
+      let column = match character.to_digit(10) {
+        Some(digit) if digit >= 1 && digit as usize <= self.Width() => digit as usize - 1,
+        _ => return Err(ParseError::NotAColumn { Index: index, Character: character }),
+      };
+      self.MakeMove(column).map_err(|err| ParseError::IllegalMove { Index: index, Error: err })?;
+    }
+    Ok(())
+  }
+
+  // Writes the moves played as a string of 1-based columns, e.g. "44435". Only games of
+  // dropped pieces on boards of at most 9 columns from an empty start can be written this way.
+  pub fn ToMoveString(&self) -> Option<String> {
+    if self.Width() > 9 || self.Handicap.is_some() || self.Start.is_some() {
+      return None;
+    }
+    self.History()
+        .iter()
+        .map(|played| match played {
+          Move::Drop(column) => char::from_digit(*column as u32 + 1, 10),
+          _ => None,
+        })
+        .collect()
+  }
+
+  // Creates a standard game from move notation with column letters, e.g. "1. d d 2. c e"
+  pub fn FromNotation(notation: &str) -> Result<Game, ParseError> {
+    let mut game = Game::default();
+    game.PlayNotation(notation)?;
+    Ok(game)
+  }
+
+  // Drops pieces into the columns named by letters (a is the leftmost column). Move numbers
+  // such as "2." are skipped wherever they appear, and any whitespace separates moves.
+  pub fn PlayNotation(&mut self, notation: &str) -> Result<(), ParseError> {
+    let mut played = 0;
+    for word in notation.split_whitespace() {
+      // A move number may be written against the move, as in "1.d"
+      let token = match word.split_once('.') {
+        Some((number, rest)) if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) => rest,
+        _ => word,
+      };
+      if token.is_empty() {
+        continue;
+      }
+      let mut letters = token.chars();
+      let column = match (letters.next().map(|letter| letter.to_ascii_lowercase()), letters.next()) {
+        (Some(letter @ 'a'..='z'), None) if (letter as usize - 'a' as usize) < self.Width() => {
+          letter as usize - 'a' as usize
+        }
+        _ => return Err(ParseError::UnexpectedToken { Move: played + 1, Token: token.to_string() }),
+      };
+      self.MakeMove(column).map_err(|err| ParseError::IllegalMove { Index: played, Error: err })?;
+      played += 1;
+    }
+    Ok(())
+  }
+
+  // Plays a move sequence in either format, telling them apart by the letters: digit strings
+  // like "44435" or notation like "1. d d 2. c e"
+  pub fn PlayMoves(&mut self, moves: &str) -> Result<(), ParseError> {
+    if moves.chars().any(|character| character.is_ascii_alphabetic()) {
+      self.PlayNotation(moves)
+    } else {
+      self.PlayMoveString(moves)
+    }
+  }
+
+  // Writes the moves played as notation with column letters, numbered by pairs of turns,
+  // e.g. "1. d d 2. c e 3. c". Like ToMoveString, only games of dropped pieces from an empty
+  // start can be written, on boards of at most 26 columns.
+  pub fn ToNotation(&self) -> Option<String> {
+    if self.Width() > 26 || self.Handicap.is_some() || self.Start.is_some() {
+      return None;
+    }
+    let letters: Vec<char> = self
+        .History()
+        .iter()
+        .map(|played| match played {
+          Move::Drop(column) => Some((b'a' + *column as u8) as char),
+          _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let turns: Vec<String> = letters
+        .chunks(self.Players)
+        .enumerate()
+        .map(|(turn, moves)| {
+          let moves: Vec<String> = moves.iter().map(char::to_string).collect();
+          format!("{}. {}", turn + 1, moves.join(" "))
+        })
+        .collect();
+    Some(turns.join(" "))
+  }
+
+  // Puts a position on the board with an empty history and settles whether it is already won
+  fn LoadPosition(&mut self, board: Board, ToMove: Player) -> Result<(), PositionError> {
+    self.Board = board;
+    self.CurrentPlayer = ToMove;
+    self.Hash = 0;
+    for row in 0..self.Height() {
+      for column in 0..self.Width() {
+        let player = Player::FromInt(self.Board[row][column]);
+        self.Hash ^= zobrist::Key(row, column, self.Width(), player);
+      }
+    }
+    self.Heights = (0..self.Width())
+        .map(|column| (0..self.Height()).filter(|&row| self.Board[row][column] != 0).count())
+        .collect();
+    self.CurrentMove = self.Heights.iter().sum();
+    self.WinnerOfPosition()
+  }
+
+  // Finishes the game if the position already has a winner or a full board
+  fn WinnerOfPosition(&mut self) -> Result<(), PositionError> {
+    let winners: Vec<Player> = (1..=self.Players as u8)
+        .map(Player::FromInt)
+        .filter(|&player| {
+          (0..self.Height()).any(|row| {
+            (0..self.Width()).any(|column| {
+              self.Board[row][column] == player as u8 && self.CompletesLine(row, column, player)
+            })
+          })
+        })
+        .collect();
+    log::Debug(format_args!("Position checked for a winner, found {:?}", winners));
+    match winners[..] {
+      [] => {
+        self.IsFinished = self.IsBoardFull();
+        Ok(())
+      }
+      [winner] => {
+        // The winner moved last, and the turn doesn't pass on after a win
+        if winner != self.CurrentPlayer.Previous(self.Players) {
+          return Err(PositionError::WrongWinner(winner));
+        }
+        self.CurrentPlayer = winner;
+        self.Winner = winner;
+        self.IsFinished = true;
+        Ok(())
+      }
+      _ => Err(PositionError::SeveralWinners),
+    }
+  }
+
+  // Drops the handicap pieces into random columns that don't complete a line, stopping early
+  // if no such column is left
+  fn PlaceHandicap(&mut self, handicap: Handicap) {
+    let mut rng = Rng::new(handicap.Seed);
+    for _ in 0..handicap.Pieces {
+      let safe: Vec<usize> = (0..self.Width())
+          .filter(|&column| self.IsColumnPlayable(column))
+          .filter(|&column| {
+            let row = self.Height() - 1 - self.Heights[column];
+            !self.CompletesLine(row, column, handicap.Player)
+          })
+          .collect();
+      if safe.is_empty() {
+        return;
+      }
+      let column = rng.Pick(&safe);
+      let row = self.Height() - 1 - self.Heights[column];
+      self.Heights[column] += 1;
+      self.Board[row][column] = handicap.Player as u8;
+      self.Hash ^= zobrist::Key(row, column, self.Width(), handicap.Player);
+    }
+  }
+
+  // Read-only accessors for the game state
+  pub fn CurrentMove(&self) -> usize {
+    self.CurrentMove
+  }
+
+  pub fn CurrentPlayer(&self) -> Player {
+    self.CurrentPlayer
+  }
+
+  pub fn Board(&self) -> &Board {
+    &self.Board
+  }
+
+  pub fn Width(&self) -> usize {
+    self.Board.Width()
+  }
+
+  pub fn Height(&self) -> usize {
+    self.Board.Height()
+  }
+
+  pub fn WinLength(&self) -> usize {
+    self.WinLength
+  }
+
+  pub fn PopOut(&self) -> bool {
+    self.PopOut
+  }
+
+  pub fn Players(&self) -> usize {
+    self.Players
+  }
+
+  pub fn Gravity(&self) -> bool {
+    self.Gravity
+  }
+
+  pub fn Wrap(&self) -> bool {
+    self.Wrap
+  }
+
+  pub fn Handicap(&self) -> Option<Handicap> {
+    self.Handicap
+  }
+
+  // Returns true if the game started from a position set up by hand
+  pub fn IsSetUp(&self) -> bool {
+    self.Start.is_some()
+  }
+
+  // Position set up by hand that the game started from, and who was on turn there
+  pub fn Start(&self) -> Option<(&Board, Player)> {
+    self.Start.as_ref().map(|(board, ToMove)| (board, *ToMove))
+  }
+
+  // Bombs the player hasn't used yet
+  pub fn BombsLeft(&self, player: Player) -> u8 {
+    match player {
+      Player::None => 0,
+      _ => self.BombsLeft[player as usize - 1],
+    }
+  }
+
+  // Bombs each player starts the game with
+  pub fn BombsPerPlayer(&self) -> u8 {
+    self.BombsPerPlayer
+  }
+
+  // The players' clocks, if the game is timed
+  pub fn Clock(&self) -> Option<&Clock> {
+    self.Clock.as_ref()
+  }
+
+  // Takes the time the player on turn has spent thinking off their clock. If that runs it out
+  // they lose on time, and this returns true. Untimed games ignore it.
+  pub fn SpendTime(&mut self, elapsed: Duration) -> bool {
+    let player = self.CurrentPlayer;
+    let flagged = match &mut self.Clock {
+      Some(clock) if !self.IsFinished => clock.Spend(player, elapsed),
+      _ => false,
+    };
+    if flagged {
+      let _ = self.Forfeit();
+    }
+    flagged
+  }
+
+  // Restores the time a player had left on a timed game's clock
+  pub(crate) fn SetTimeLeft(&mut self, player: Player, remaining: Duration) {
+    if let Some(clock) = &mut self.Clock {
+      clock.SetRemaining(player, remaining);
+    }
+  }
+
+  // Returns true if bombs are part of this game's rules
+  pub fn HasBombs(&self) -> bool {
+    self.BombsPerPlayer > 0
+  }
+
+  // Returns true for the standard 7x6 board
+  pub fn IsStandardSize(&self) -> bool {
+    self.Width() == BOARD_WIDTH && self.Height() == BOARD_HEIGHT
+  }
+
+  // Returns true for classic Connect 4: the standard board, two players, four in a row,
+  // no variants and no handicap
+  pub fn IsStandard(&self) -> bool {
+    self.IsStandardSize()
+        && self.Players == 2
+        && self.WinLength == WIN_LENGTH
+        && self.Gravity
+        && !self.Wrap
+        && !self.PopOut
+        && !self.HasBombs()
+        && self.Handicap.is_none()
+  }
+
+  // The board as bit masks, only available for two players on the standard size the bit
+  // layout is built for
+  pub fn Bits(&self) -> Option<BitBoard> {
+    (self.IsStandardSize() && self.Players == 2).then(|| BitBoard::FromBoard(&self.Board))
+  }
+
+  // Zobrist hash of the board; equal for positions reached by different move orders
+  pub fn Hash(&self) -> u64 {
+    self.Hash
+  }
+
+  pub fn IsFinished(&self) -> bool {
+    self.IsFinished
+  }
+
+  pub fn Winner(&self) -> Player {
+    self.Winner
+  }
+
+  // Player whose draw offer is waiting for the opponent's answer
+  pub fn DrawOffer(&self) -> Option<Player> {
+    self.DrawOffer
+  }
+
+  // Returns true if the game ended with both players agreeing to a draw
+  pub fn IsDrawAgreed(&self) -> bool {
+    self.DrawAgreed
+  }
+
+  // Offers the opponent a draw on the player's own turn; the player still moves as usual and
+  // the opponent answers before their move. A player can't offer on two turns in a row.
+  pub fn OfferDraw(&mut self, player: Player) -> Result<(), DrawError> {
+    if self.IsFinished {
+      return Err(DrawError::GameFinished);
+    }
+    if self.Players != 2 {
+      return Err(DrawError::TwoPlayersOnly);
+    }
+    if player != self.CurrentPlayer {
+      return Err(DrawError::NotYourTurn);
+    }
+    if self.DrawOffer.is_some() {
+      return Err(DrawError::AlreadyOffered);
+    }
+    if let Some((last, at)) = self.LastDrawOffer {
+      // The player's previous turn was one round of turns ago
+      if last == player && self.CurrentMove < at + 2 * self.Players {
+        return Err(DrawError::TooSoon);
+      }
+    }
+    self.DrawOffer = Some(player);
+    self.LastDrawOffer = Some((player, self.CurrentMove));
+    Ok(())
+  }
+
+  // Accepts the waiting draw offer on the opponent's turn, ending the game without a winner
+  pub fn AcceptDraw(&mut self) -> Result<(), DrawError> {
+    if self.IsFinished {
+      return Err(DrawError::GameFinished);
+    }
+    match self.DrawOffer {
+      Some(offerer) if offerer != self.CurrentPlayer => {
+        self.DrawOffer = None;
+        self.AgreeDraw();
+        Ok(())
+      }
+      _ => Err(DrawError::NoOffer),
+    }
+  }
+
+  // Turns the waiting draw offer down; play carries on
+  pub fn DeclineDraw(&mut self) -> Result<(), DrawError> {
+    self.DrawOffer.take().map(|_| ()).ok_or(DrawError::NoOffer)
+  }
+
+  // Ends the game as a draw by agreement; moves undone before can't be replayed after it, as
+  // after any other ending
+  pub(crate) fn AgreeDraw(&mut self) {
+    self.History.ClearRedo();
+    self.IsFinished = true;
+    self.Winner = Player::None;
+    self.DrawAgreed = true;
+  }
+
+  // Player who forfeited, on time or by leaving, if the game ended that way
+  pub fn Forfeited(&self) -> Option<Player> {
+    self.Forfeited
+  }
+
+  // Ends the game because the player on turn ran out of time. The win goes to the player after
+  // them, which in a two-player game is the opponent.
+  pub fn Forfeit(&mut self) -> Result<(), MoveError> {
+    self.ForfeitBy(self.CurrentPlayer)
+  }
+
+  // Ends the game with the player forfeiting it, e.g. for leaving a network game and not
+  // coming back. The win goes to the player after them.
+  pub fn ForfeitBy(&mut self, player: Player) -> Result<(), MoveError> {
+    if self.IsFinished {
+      return Err(MoveError::GameFinished);
+    }
+    self.DrawOffer = None;
+    self.History.ClearRedo();
+    self.Forfeited = Some(player);
+    self.IsFinished = true;
+    self.Winner = player.Next(self.Players);
+    Ok(())
+  }
+
+  // The moves played so far, in order
+  pub fn History(&self) -> &[Move] {
+    self.History.Played()
+  }
+
+  // Cell (row, column) of the piece put down by the last move, if that move put one down.
+  // Pops, bombs and undos leave no such piece.
+  pub fn LastPlaced(&self) -> Option<(usize, usize)> {
+    self.LastPlaced
+  }
+
+  // Number of undone moves that can be redone
+  pub fn RedoCount(&self) -> usize {
+    self.History.RedoCount()
+  }
+
+  // Returns true if the column exists and has at least one empty cell
+  pub fn IsColumnPlayable(&self, column: usize) -> bool {
+    column < self.Width() && self.Heights[column] < self.Height()
+  }
+
+  // Lists the columns a piece can currently be dropped into
+  pub fn LegalMoves(&self) -> Vec<usize> {
+    if self.IsFinished {
+      return Vec::new(); // No moves once the game is over
+    }
+    (0..self.Width()).filter(|&column| self.IsColumnPlayable(column)).collect()
+  }
+
+  // Returns true if the current player may pop the bottom piece of the column
+  pub fn CanPop(&self, column: usize) -> bool {
+    self.PopOut
+        && self.Gravity
+        && !self.IsFinished
+        && column < self.Width()
+        && self.Board[self.Height() - 1][column] == self.CurrentPlayer as u8
+  }
+
+  // Returns true once every cell holds a piece
+  fn IsBoardFull(&self) -> bool {
+    self.Heights.iter().all(|&height| height == self.Height())
+  }
+
+  // Row a piece dropped into the column would land in, without dropping it; None if the
+  // column doesn't exist or is full, or pieces don't fall in this game
+  pub fn LandingRow(&self, column: usize) -> Option<usize> {
+    if !self.Gravity || column >= self.Width() || self.Heights[column] == self.Height() {
+      return None;
+    }
+    Some(self.Height() - 1 - self.Heights[column])
+  }
+
+  // Returns true if dropping a piece for `player` into the column would complete a winning line
+  pub fn WouldWin(&self, column: usize, player: Player) -> bool {
+    if self.IsFinished || !self.IsColumnPlayable(column) {
+      return false;
+    }
+    let row = self.Height() - 1 - self.Heights[column];
+    self.CompletesLine(row, column, player)
+  }
+
+  // Returns true if a piece of `player` at (row, column) is part of a winning line.
+  // Only the most recent piece can complete a new line, so this walks the eight rays from it.
+  // The cell itself isn't read, so this also works for a piece that hasn't been placed yet.
+  // On a cylinder the rays wrap around the side edges.
+  fn CompletesLine(&self, row: usize, column: usize, player: Player) -> bool {
+    let cell = player as u8;
+    let (width, height) = (self.Width() as isize, self.Height() as isize);
+    let wrap = |c: isize| if self.Wrap { c.rem_euclid(width) } else { c };
+    let directions = [(0, 1), (1, 0), (1, 1), (1, -1)]; // Each direction covers two opposite rays
+    directions.iter().any(|&(RowStep, ColStep)| {
+      let count = |RowStep: isize, ColStep: isize, limit: usize| {
+        let mut count = 0;
+        let mut r = row as isize + RowStep;
+        let mut c = wrap(column as isize + ColStep);
+        while count < limit
+            && r >= 0
+            && r < height
+            && c >= 0
+            && c < width
+            && self.Board[r as usize][c as usize] == cell
+        {
+          count += 1;
+          r += RowStep;
+          c = wrap(c + ColStep);
+        }
+        count
+      };
+      // A line running all the way round a cylinder must not count any cell twice
+      let limit = if self.Wrap && ColStep != 0 { self.Width() - 1 } else { usize::MAX };
+      let forward = count(RowStep, ColStep, limit);
+      1 + forward + count(-RowStep, -ColStep, limit - forward) >= self.WinLength
+    })
+  }
+
+  // Empty cells (row, column) where a piece of the player would complete a line right now
+  pub fn ThreatCells(&self, player: Player) -> Vec<(usize, usize)> {
+    if self.IsFinished {
+      return Vec::new();
+    }
+    let candidates: Vec<(usize, usize)> = if self.Gravity {
+      self.LegalMoves().into_iter().filter_map(|column| Some((self.LandingRow(column)?, column))).collect()
+    } else {
+      (0..self.Height())
+          .flat_map(|row| (0..self.Width()).map(move |column| (row, column)))
+          .filter(|&(row, column)| self.Board[row][column] == 0)
+          .collect()
+    };
+    candidates.into_iter().filter(|&(row, column)| self.CompletesLine(row, column, player)).collect()
+  }
+
+  // Cells (row, column) of every line of the winner once the game is won, for front-ends to
+  // pick out; a move that made two lines at once gives the cells of both. Empty otherwise.
+  pub fn WinningLine(&self) -> Vec<(usize, usize)> {
+    if self.Winner == Player::None {
+      return Vec::new();
+    }
+    let mut cells = Vec::new();
+    for row in 0..self.Height() {
+      for column in 0..self.Width() {
+        // A piece of the winner completes a line exactly when it already lies on one
+        if self.Board[row][column] == self.Winner as u8 && self.CompletesLine(row, column, self.Winner) {
+          cells.push((row, column));
+        }
+      }
+    }
+    cells
+  }
+
+  // Checks for a winner by scanning the whole board. Moves are checked incrementally, so this
+  // is only needed for positions that weren't built move by move, such as loaded ones.
+  pub fn ScanForWinner(&self) -> Player {
+    for row in 0..self.Height() {
+      for column in 0..self.Width() {
+        let owner = Player::FromInt(self.Board[row][column]);
+        if owner != Player::None && self.CompletesLine(row, column, owner) {
+          return owner; // Return the winning player
+        }
+      }
+    }
+    Player::None // No winner yet
+  }
+
+  // Processes a move by a player
+  pub fn MakeMove(&mut self, column: usize) -> Result<MoveOutcome, MoveError> {
+    let mover = self.CurrentPlayer;
+    let outcome = self.DropPiece(column).inspect_err(|err| {
+      log::Debug(format_args!("{:?} can't drop in column {}: {}", mover, column.saturating_add(1), err));
+    })?;
+    self.Record(mover, Move::Drop(column));
+    Ok(outcome)
+  }
+
+  // Removes the current player's piece from the bottom of a column (PopOut only)
+  pub fn PopMove(&mut self, column: usize) -> Result<MoveOutcome, MoveError> {
+    let mover = self.CurrentPlayer;
+    let outcome = self.PopPiece(column)?;
+    self.Record(mover, Move::Pop(column));
+    Ok(outcome)
+  }
+
+  // Puts a piece for the current player on any empty cell (games without gravity only)
+  pub fn PlaceAt(&mut self, row: usize, column: usize) -> Result<MoveOutcome, MoveError> {
+    let mover = self.CurrentPlayer;
+    let outcome = self.PlacePiece(row, column)?;
+    self.Record(mover, Move::Place(row, column));
+    Ok(outcome)
+  }
+
+  // Drops the current player's bomb into a column (bomb games only)
+  pub fn BombMove(&mut self, column: usize) -> Result<MoveOutcome, MoveError> {
+    let mover = self.CurrentPlayer;
+    let outcome = self.DropBomb(column)?;
+    self.Record(mover, Move::Bomb(column));
+    Ok(outcome)
+  }
+
+  // Adds a freshly played move to the history and the mover's increment to their clock
+  fn Record(&mut self, mover: Player, played: Move) {
+    self.History.Push(played);
+    if let Some(clock) = &mut self.Clock {
+      clock.Moved(mover);
+    }
+  }
+
+  // Plays any kind of move
+  pub fn Play(&mut self, played: Move) -> Result<MoveOutcome, MoveError> {
+    match played {
+      Move::Drop(column) => self.MakeMove(column),
+      Move::Pop(column) => self.PopMove(column),
+      Move::Place(row, column) => self.PlaceAt(row, column),
+      Move::Bomb(column) => self.BombMove(column),
+    }
+  }
+
+  // Drops a piece for the current player without touching the history
+  fn DropPiece(&mut self, column: usize) -> Result<MoveOutcome, MoveError> {
+    if self.IsFinished {
+      return Err(MoveError::GameFinished); // Game is over
+    }
+
+    if column >= self.Width() {
+      return Err(MoveError::InvalidColumn { Given: column, Max: self.Width() - 1 }); // Invalid column
+    }
+    if !self.Gravity {
+      return Err(MoveError::GravityOff); // Pieces have to be placed on a cell
+    }
+    if self.Heights[column] == self.Height() {
+      return Err(MoveError::ColumnFull { Column: column }); // No empty cell left
+    }
+
+    // The piece lands on top of the ones already in the column
+    let row = self.LandingRow(column).expect("checked above");
+    Ok(self.PutPiece(row, column))
+  }
+
+  // Places a piece for the current player on a chosen cell without touching the history
+  fn PlacePiece(&mut self, row: usize, column: usize) -> Result<MoveOutcome, MoveError> {
+    if self.IsFinished {
+      return Err(MoveError::GameFinished);
+    }
+    if self.Gravity {
+      return Err(MoveError::GravityOn);
+    }
+    if column >= self.Width() {
+      return Err(MoveError::InvalidColumn { Given: column, Max: self.Width() - 1 });
+    }
+    if row >= self.Height() {
+      return Err(MoveError::InvalidRow);
+    }
+    if self.Board[row][column] != 0 {
+      return Err(MoveError::CellOccupied);
+    }
+    Ok(self.PutPiece(row, column))
+  }
+
+  // Puts the current player's piece on an empty cell, then checks for a win or a draw
+  // and passes the turn on
+  fn PutPiece(&mut self, row: usize, column: usize) -> MoveOutcome {
+    let mover = self.CurrentPlayer;
+    self.Heights[column] += 1;
+    self.Board[row][column] = mover as u8; // Place the piece
+    self.Hash ^= zobrist::Key(row, column, self.Width(), mover);
+    self.CurrentMove += 1;
+    self.LastPlaced = Some((row, column));
+
+    // The first player needs WinLength pieces down, after every other player's WinLength - 1.
+    // Handicap pieces can complete a line sooner, so those games are always checked.
+    let fewest = self.Players * (self.WinLength - 1) + 1;
+    let possible = self.CurrentMove >= fewest || self.Handicap.is_some();
+    let result = if possible && self.CompletesLine(row, column, mover) {
+      self.Winner = mover;     // Set the winner
+      self.IsFinished = true; // Mark the game as finished
+      MoveResult::Win(mover)
+    } else if self.IsBoardFull() {
+      self.IsFinished = true; // The last empty cell was filled
+      MoveResult::Draw
+    } else {
+      // Switch to the next player
+      self.CurrentPlayer = mover.Next(self.Players);
+      MoveResult::Continue
+    };
+
+    MoveOutcome { Row: row, Column: column, Player: mover, Result: result }
+  }
+
+  // Pops a piece for the current player without touching the history
+  fn PopPiece(&mut self, column: usize) -> Result<MoveOutcome, MoveError> {
+    if self.IsFinished {
+      return Err(MoveError::GameFinished);
+    }
+    if !self.PopOut || !self.Gravity {
+      return Err(MoveError::PopOutDisabled);
+    }
+    if column >= self.Width() {
+      return Err(MoveError::InvalidColumn { Given: column, Max: self.Width() - 1 });
+    }
+    if !self.CanPop(column) {
+      return Err(MoveError::CannotPop);
+    }
+
+    // Everything above the popped piece slides down one cell
+    let mover = self.CurrentPlayer;
+    let mut cells = self.Column(column);
+    cells.pop();
+    cells.insert(0, 0);
+    self.SetColumn(column, &cells);
+    self.CurrentMove += 1;
+    self.LastPlaced = None;
+
+    let result = self.SettleAfterShift(mover, &[column]);
+    Ok(MoveOutcome { Row: self.Height() - 1, Column: column, Player: mover, Result: result })
+  }
+
+  // Drops the current player's bomb, which clears the 3x3 area around where it lands,
+  // itself included, after which the pieces above fall into the gaps
+  fn DropBomb(&mut self, column: usize) -> Result<MoveOutcome, MoveError> {
+    if self.IsFinished {
+      return Err(MoveError::GameFinished);
+    }
+    if self.BombsPerPlayer == 0 || !self.Gravity {
+      return Err(MoveError::BombsDisabled);
+    }
+    if column >= self.Width() {
+      return Err(MoveError::InvalidColumn { Given: column, Max: self.Width() - 1 });
+    }
+    if self.Heights[column] == self.Height() {
+      return Err(MoveError::ColumnFull { Column: column });
+    }
+    let mover = self.CurrentPlayer;
+    if self.BombsLeft[mover as usize - 1] == 0 {
+      return Err(MoveError::NoBombsLeft);
+    }
+
+    // On a cylinder the blast wraps around the side edges like the lines do
+    let row = self.Height() - 1 - self.Heights[column];
+    let width = self.Width() as isize;
+    let columns: Vec<usize> = (column as isize - 1..=column as isize + 1)
+        .filter_map(|c| match self.Wrap {
+          true => Some(c.rem_euclid(width) as usize),
+          false => (0..width).contains(&c).then_some(c as usize),
+        })
+        .collect();
+
+    self.Exploded.push(self.Board.clone());
+    for &c in &columns {
+      let mut cells = self.Column(c);
+      let blast = row.saturating_sub(1)..=(row + 1).min(self.Height() - 1);
+      cells[blast].iter_mut().for_each(|cell| *cell = 0);
+      // Pieces keep their order as they fall
+      let mut settled: Vec<u8> = cells.iter().copied().filter(|&cell| cell != 0).collect();
+      while settled.len() < self.Height() {
+        settled.insert(0, 0);
+      }
+      self.SetColumn(c, &settled);
+    }
+    self.BombsLeft[mover as usize - 1] -= 1;
+    self.CurrentMove += 1;
+    self.LastPlaced = None;
+
+    let result = self.SettleAfterShift(mover, &columns);
+    Ok(MoveOutcome { Row: row, Column: column, Player: mover, Result: result })
+  }
+
+  // Decides the game after pieces moved within the given columns. Every moved piece may now
+  // be part of a line, for any player; a line for an opponent wins for that opponent, even if
+  // the mover completed one too. Otherwise the turn passes on.
+  fn SettleAfterShift(&mut self, mover: Player, columns: &[usize]) -> MoveResult {
+    let (mut MoverWins, mut OpponentWin) = (false, None);
+    for &column in columns {
+      for row in self.Height() - self.Heights[column]..self.Height() {
+        let owner = Player::FromInt(self.Board[row][column]);
+        if self.CompletesLine(row, column, owner) {
+          if owner == mover {
+            MoverWins = true;
+          } else {
+            OpponentWin.get_or_insert(owner);
+          }
+        }
+      }
+    }
+    match OpponentWin.or(MoverWins.then_some(mover)) {
+      Some(winner) => {
+        self.Winner = winner;
+        self.IsFinished = true;
+        MoveResult::Win(winner)
+      }
+      None => {
+        self.CurrentPlayer = mover.Next(self.Players);
+        MoveResult::Continue
+      }
+    }
+  }
+
+  // The cells of a column, from top to bottom
+  fn Column(&self, column: usize) -> Vec<u8> {
+    (0..self.Height()).map(|row| self.Board[row][column]).collect()
+  }
+
+  // Replaces the cells of a column, listed from top to bottom, keeping the hash and the
+  // column height in step. Pieces must sit at the bottom with no gaps.
+  fn SetColumn(&mut self, column: usize, cells: &[u8]) {
+    let width = self.Width();
+    for (row, &cell) in cells.iter().enumerate() {
+      self.Hash ^= zobrist::Key(row, column, width, Player::FromInt(self.Board[row][column]));
+      self.Hash ^= zobrist::Key(row, column, width, Player::FromInt(cell));
+      self.Board[row][column] = cell;
+    }
+    self.Heights[column] = cells.iter().filter(|&&cell| cell != 0).count();
+  }
+
+  // Takes back the most recent move
+  pub fn UndoMove(&mut self) -> Result<(), UndoError> {
+    // An agreed draw or a forfeit can be taken back before any move has been played
+    if self.History.Played().is_empty() && !self.DrawAgreed && self.Forfeited.is_none() {
+      return Err(UndoError::NoMoves);
+    }
+    if let Some(clock) = &mut self.Clock {
+      clock.NewTurn();
+    }
+    // Taking back an agreed draw reopens the game where it stood
+    if self.DrawAgreed {
+      self.DrawAgreed = false;
+      self.IsFinished = false;
+      return Ok(());
+    }
+    // So does taking back a loss on time, unless the clock ran out, since time isn't refunded
+    if let Some(loser) = self.Forfeited {
+      if self.Clock.as_ref().is_some_and(|clock| clock.Remaining(loser).is_zero()) {
+        return Err(UndoError::OutOfTime);
+      }
+      self.Forfeited = None;
+      self.IsFinished = false;
+      self.Winner = Player::None;
+      return Ok(());
+    }
+    self.DrawOffer = None;
+    match self.History.Undo().ok_or(UndoError::NoMoves)? {
+      played @ (Move::Drop(_) | Move::Place(..)) => {
+        // A dropped piece is the top one of its column
+        let (row, column) = match played {
+          Move::Place(row, column) => (row, column),
+          _ => (self.Height() - self.Heights[played.Column()], played.Column()),
+        };
+        self.CurrentPlayer = Player::FromInt(self.Board[row][column]); // Their turn again
+        self.Heights[column] -= 1;
+        self.Board[row][column] = 0;
+        self.Hash ^= zobrist::Key(row, column, self.Width(), self.CurrentPlayer);
+      }
+      Move::Pop(column) => {
+        // The turn only passed on if the pop didn't end the game
+        if !self.IsFinished {
+          self.CurrentPlayer = self.CurrentPlayer.Previous(self.Players);
+        }
+        let mut cells = self.Column(column);
+        cells.remove(0);
+        cells.push(self.CurrentPlayer as u8); // Put the popped piece back
+        self.SetColumn(column, &cells);
+      }
+      Move::Bomb(_) => {
+        if !self.IsFinished {
+          self.CurrentPlayer = self.CurrentPlayer.Previous(self.Players);
+        }
+        let before = self.Exploded.pop().expect("every applied bomb has a snapshot");
+        for column in 0..self.Width() {
+          let cells: Vec<u8> = (0..self.Height()).map(|row| before[row][column]).collect();
+          self.SetColumn(column, &cells);
+        }
+        self.BombsLeft[self.CurrentPlayer as usize - 1] += 1;
+      }
+    }
+    self.CurrentMove -= 1;
+    self.LastPlaced = None;
+
+    // Any win or draw was caused by the undone move
+    self.IsFinished = false;
+    self.Winner = Player::None;
+    Ok(())
+  }
+
+  // Replays the most recently undone move; a draw offer still waiting lapses, as it does on
+  // an undo
+  pub fn RedoMove(&mut self) -> Result<MoveOutcome, RedoError> {
+    let played = self.History.PeekRedo().ok_or(RedoError::NothingToRedo)?;
+    if self.IsFinished {
+      return Err(RedoError::GameFinished);
+    }
+    self.DrawOffer = None;
+    let outcome = match played {
+      Move::Drop(column) => self.DropPiece(column),
+      Move::Pop(column) => self.PopPiece(column),
+      Move::Place(row, column) => self.PlacePiece(row, column),
+      Move::Bomb(column) => self.DropBomb(column),
+    }
+    .expect("undone move is legal again after undoing");
+    self.History.Redo();
+    // Replaying a move earns no increment
+    if let Some(clock) = &mut self.Clock {
+      clock.NewTurn();
+    }
+    Ok(outcome)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A standard game after the moves, as 1-based columns, and the outcome of the last one
+  fn Played(moves: &str) -> (Game, MoveOutcome) {
+    let (before, last) = moves.split_at(moves.len() - 1);
+    let mut game = Game::FromMoveString(before).unwrap();
+    let column = last.parse::<usize>().unwrap() - 1;
+    let outcome = game.MakeMove(column).unwrap();
+    (game, outcome)
+  }
+
+  // Checks the last move won the game for player one, along the cells given
+  fn AssertWon(moves: &str, line: &[(usize, usize)]) {
+    let (game, outcome) = Played(moves);
+    assert_eq!(outcome.Result, MoveResult::Win(Player::One));
+    assert!(game.IsFinished());
+    assert_eq!(game.Winner(), Player::One);
+    assert_eq!(game.ScanForWinner(), Player::One);
+    assert_eq!(game.WinningLine(), line);
+  }
+
+  #[test]
+  fn HorizontalWin() {
+    AssertWon("1122334", &[(5, 0), (5, 1), (5, 2), (5, 3)]);
+  }
+
+  #[test]
+  fn VerticalWin() {
+    AssertWon("1212121", &[(2, 0), (3, 0), (4, 0), (5, 0)]);
+  }
+
+  #[test]
+  fn RisingDiagonalWin() {
+    AssertWon("12234334454", &[(2, 3), (3, 2), (4, 1), (5, 0)]);
+  }
+
+  #[test]
+  fn FallingDiagonalWin() {
+    AssertWon("76654554434", &[(2, 3), (3, 4), (4, 5), (5, 6)]);
+  }
+
+  // The winning piece can land in the middle of a line as well as at its end
+  #[test]
+  fn HorizontalWinFillingAGap() {
+    AssertWon("1122443", &[(5, 0), (5, 1), (5, 2), (5, 3)]);
+  }
+
+  #[test]
+  fn VerticalWinFillingAGap() {
+    // Only a game without gravity can leave a hole in a column
+    let mut game = Game::default().WithGravity(false);
+    for (row, column) in [(5, 0), (5, 6), (4, 0), (4, 6), (2, 0), (2, 6)] {
+      game.PlaceAt(row, column).unwrap();
+    }
+    let outcome = game.PlaceAt(3, 0).unwrap();
+    assert_eq!(outcome.Result, MoveResult::Win(Player::One));
+    assert_eq!(game.WinningLine(), [(2, 0), (3, 0), (4, 0), (5, 0)]);
+  }
+
+  #[test]
+  fn RisingDiagonalWinFillingAGap() {
+    AssertWon("12333444462", &[(2, 3), (3, 2), (4, 1), (5, 0)]);
+  }
+
+  #[test]
+  fn FallingDiagonalWinFillingAGap() {
+    AssertWon("76555444426", &[(2, 3), (3, 4), (4, 5), (5, 6)]);
+  }
+
+  #[test]
+  fn WrappedWinAcrossTheSeam() {
+    let mut game = Game::default().WithWrap(true);
+    game.PlayMoveString("771122").unwrap();
+    let outcome = game.MakeMove(2).unwrap();
+    assert_eq!(outcome.Result, MoveResult::Win(Player::One));
+    assert_eq!(game.ScanForWinner(), Player::One);
+    assert_eq!(game.WinningLine(), [(5, 0), (5, 1), (5, 2), (5, 6)]);
+  }
+
+  #[test]
+  fn FlatBoardDoesNotWrap() {
+    let (game, outcome) = Played("7711223");
+    assert_eq!(outcome.Result, MoveResult::Continue);
+    assert!(!game.IsFinished());
+    assert_eq!(game.ScanForWinner(), Player::None);
+  }
+
+  // A standard board with the given pieces as (row, column, player), row 0 at the top
+  fn Position(pieces: &[(usize, usize, u8)]) -> Board {
+    let mut board = Board::new(7, 6);
+    for &(row, column, player) in pieces {
+      board[row][column] = player;
+    }
+    board
+  }
+
+  #[test]
+  fn PositionWithAFinishedLine() {
+    let board = Position(&[(5, 0, 1), (5, 1, 1), (5, 2, 1), (5, 3, 1), (4, 0, 2), (4, 1, 2), (4, 2, 2)]);
+    let game = Game::FromPosition(board, Player::Two).unwrap();
+    assert!(game.IsFinished());
+    assert_eq!(game.Winner(), Player::One);
+  }
+
+  #[test]
+  fn PositionWithWrongPieceCounts() {
+    let board = Position(&[(5, 0, 1), (5, 1, 1)]);
+    assert!(matches!(
+      Game::FromPosition(board, Player::One),
+      Err(PositionError::PieceCounts { Counts, ToMove: Player::One }) if Counts == [2, 0]
+    ));
+    // Even counts with the wrong player on turn are just as unreachable
+    let board = Position(&[(5, 0, 1), (5, 1, 2)]);
+    assert!(matches!(
+      Game::FromPosition(board, Player::Two),
+      Err(PositionError::PieceCounts { Counts, ToMove: Player::Two }) if Counts == [1, 1]
+    ));
+  }
+
+  #[test]
+  fn PositionWithAFloatingPiece() {
+    let board = Position(&[(5, 0, 1), (4, 3, 2)]);
+    assert!(matches!(
+      Game::FromPosition(board.clone(), Player::One),
+      Err(PositionError::FloatingPiece { Row: 1, Column: 3 })
+    ));
+    // Without gravity pieces can be anywhere
+    let mut game = Game::default().WithGravity(false);
+    assert!(game.SetPosition(board, Player::One).is_ok());
+  }
+
+  #[test]
+  fn PositionWithSeveralWinners() {
+    let mut pieces = Vec::new();
+    for column in 0..4 {
+      pieces.push((5, column, 1));
+      pieces.push((4, column, 2));
+    }
+    assert!(matches!(Game::FromPosition(Position(&pieces), Player::One), Err(PositionError::SeveralWinners)));
+  }
+
+  #[test]
+  fn PositionWithTheWrongWinner() {
+    // Red has the line, but Yellow moved last
+    let board = Position(&[(5, 0, 1), (5, 1, 1), (5, 2, 1), (5, 3, 1), (4, 0, 2), (4, 1, 2), (4, 2, 2), (5, 6, 2)]);
+    let mut game = Game::default();
+    game.PlayMoveString("44").unwrap();
+    assert!(matches!(game.SetPosition(board, Player::One), Err(PositionError::WrongWinner(Player::One))));
+    // A rejected position leaves the game as it was
+    assert_eq!(game.History().len(), 2);
+    assert!(!game.IsFinished());
+  }
+
+  #[test]
+  fn RedoReplaysAWinningMove() {
+    let (mut game, _) = Played("1122334");
+    game.UndoMove().unwrap();
+    assert!(!game.IsFinished());
+    assert_eq!(game.CurrentPlayer(), Player::One);
+    let outcome = game.RedoMove().unwrap();
+    assert_eq!(outcome.Result, MoveResult::Win(Player::One));
+    assert_eq!(game.Winner(), Player::One);
+    assert_eq!(game.WinningLine(), [(5, 0), (5, 1), (5, 2), (5, 3)]);
+    assert!(matches!(game.RedoMove(), Err(RedoError::NothingToRedo)));
+  }
+
+  #[test]
+  fn NewMoveEndsTheRedos() {
+    let mut game = Game::FromMoveString("4455").unwrap();
+    game.UndoMove().unwrap();
+    game.UndoMove().unwrap();
+    assert_eq!(game.RedoCount(), 2);
+    game.MakeMove(0).unwrap();
+    assert_eq!(game.RedoCount(), 0);
+    assert!(matches!(game.RedoMove(), Err(RedoError::NothingToRedo)));
+    assert_eq!(game.ToMoveString().unwrap(), "441");
+  }
+
+  #[test]
+  fn UndoWithNothingToUndoLeavesTheDelay() {
+    let control = clock::ParseTimeControl("60d3").unwrap();
+    let mut game = Game::default().WithClock(control);
+    game.SpendTime(Duration::from_secs(2));
+    assert!(matches!(game.UndoMove(), Err(UndoError::NoMoves)));
+    assert_eq!(game.Clock().unwrap().TimeToFlag(Player::One), Duration::from_secs(61));
+
+    // A forfeit before the first move can still be taken back
+    game.Forfeit().unwrap();
+    assert!(game.UndoMove().is_ok());
+    assert!(!game.IsFinished());
+  }
+
+  #[test]
+  fn FullBoardWithoutALineIsADraw() {
+    let (game, outcome) = Played("512414477327537234737551113144622532666665");
+    assert_eq!(outcome.Result, MoveResult::Draw);
+    assert!(game.IsFinished());
+    assert_eq!(game.Winner(), Player::None);
+    assert_eq!(game.ScanForWinner(), Player::None);
+    assert!(game.LegalMoves().is_empty());
+    assert!(game.Board().Cells().iter().all(|&cell| cell != 0));
+  }
+
+  #[test]
+  fn MoveIntoFullColumnIsRejected() {
+    let mut game = Game::FromMoveString("111111").unwrap();
+    assert_eq!(game.MakeMove(0), Err(MoveError::ColumnFull { Column: 0 }));
+    assert_eq!(game.History().len(), 6);
+    assert_eq!(game.CurrentPlayer(), Player::One);
+  }
+
+  #[test]
+  fn MoveOffTheBoardIsRejected() {
+    let mut game = Game::default();
+    assert_eq!(game.MakeMove(7), Err(MoveError::InvalidColumn { Given: 7, Max: 6 }));
+    assert_eq!(game.MakeMove(usize::MAX), Err(MoveError::InvalidColumn { Given: usize::MAX, Max: 6 }));
+    assert!(game.History().is_empty());
+  }
+
+  #[test]
+  fn MoveAfterTheGameIsRejected() {
+    let (mut game, _) = Played("1212121");
+    assert_eq!(game.MakeMove(2), Err(MoveError::GameFinished));
+    assert_eq!(game.History().len(), 7);
+  }
+
+  #[test]
+  fn MoveErrorMessages() {
+    let messages = [
+      (MoveError::GameFinished, "Game is already finished"),
+      (MoveError::InvalidColumn { Given: 7, Max: 6 }, "Column 8 is out of range (1–7)"),
+      (MoveError::InvalidColumn { Given: usize::MAX, Max: 8 }, &format!("Column {} is out of range (1–9)", usize::MAX)),
+      (MoveError::ColumnFull { Column: 2 }, "Column 3 is full"),
+      (MoveError::PopOutDisabled, "Popping pieces is only allowed in PopOut games"),
+      (MoveError::CannotPop, "You can only pop your own piece from the bottom row"),
+      (MoveError::GravityOn, "Pieces can only be placed freely in games without gravity"),
+      (MoveError::GravityOff, "Pick a cell as column,row; pieces don't fall in this game"),
+      (MoveError::InvalidRow, "Invalid row"),
+      (MoveError::CellOccupied, "Cell is already taken"),
+      (MoveError::BombsDisabled, "Bombs are only allowed in bomb games"),
+      (MoveError::NoBombsLeft, "You have already used your bomb this game"),
+    ];
+    for (err, message) in messages {
+      assert_eq!(err.to_string(), message);
+    }
+  }
+
+  #[test]
+  fn RejectedMoveNamesTheColumn() {
+    let mut game = Game::FromMoveString("333333").unwrap();
+    assert_eq!(game.MakeMove(2).unwrap_err().to_string(), "Column 3 is full");
+    assert_eq!(game.MakeMove(9).unwrap_err().to_string(), "Column 10 is out of range (1–7)");
+  }
+  // The moves of a game played to the end with random legal moves
+  fn RandomGame(rng: &mut Rng) -> Game {
+    let mut game = Game::default();
+    while !game.IsFinished() {
+      game.MakeMove(rng.Pick(&game.LegalMoves())).unwrap();
+    }
+    game
+  }
+
+  #[test]
+  fn MoveStringRoundTrip() {
+    let mut rng = Rng::new(31);
+    for _ in 0..50 {
+      let game = RandomGame(&mut rng);
+      let moves = game.ToMoveString().unwrap();
+      let parsed = Game::FromMoveString(&moves).unwrap();
+      assert_eq!(parsed.History(), game.History());
+      assert_eq!(parsed.Winner(), game.Winner());
+      assert_eq!(parsed.ToMoveString().unwrap(), moves);
+    }
+    assert_eq!(Game::default().ToMoveString().unwrap(), "");
+  }
+
+  #[test]
+  fn MoveStringErrors() {
+    assert!(matches!(Game::FromMoveString("4480"), Err(ParseError::NotAColumn { Index: 2, Character: '8' })));
+    assert!(matches!(Game::FromMoveString("44 4"), Err(ParseError::NotAColumn { Index: 2, Character: ' ' })));
+    assert!(matches!(
+      Game::FromMoveString("1111111"),
+      Err(ParseError::IllegalMove { Index: 6, Error: MoveError::ColumnFull { Column: 0 } })
+    ));
+    // A pop can't be written as a column
+    let mut popped = Game::default().WithPopOut(true);
+    popped.PlayMoveString("12").unwrap();
+    popped.PopMove(0).unwrap();
+    assert_eq!(popped.ToMoveString(), None);
+  }
+
+  #[test]
+  fn NotationRoundTrip() {
+    let mut rng = Rng::new(36);
+    for _ in 0..50 {
+      let game = RandomGame(&mut rng);
+      let notation = game.ToNotation().unwrap();
+      let parsed = Game::FromNotation(&notation).unwrap();
+      assert_eq!(parsed.History(), game.History());
+      assert_eq!(parsed.ToNotation().unwrap(), notation);
+      let mut detected = Game::default();
+      detected.PlayMoves(&notation).unwrap();
+      assert_eq!(detected.History(), game.History());
+    }
+    assert_eq!(Game::FromMoveString("44435").unwrap().ToNotation().unwrap(), "1. d d 2. d c 3. e");
+  }
+
+  #[test]
+  fn NotationRoundTripWithThreePlayers() {
+    let mut game = Game::new(9, 7).unwrap().WithPlayers(3).unwrap();
+    game.PlayMoveString("1234567891").unwrap();
+    let notation = game.ToNotation().unwrap();
+    assert_eq!(notation, "1. a b c 2. d e f 3. g h i 4. a");
+    let mut parsed = Game::new(9, 7).unwrap().WithPlayers(3).unwrap();
+    parsed.PlayNotation(&notation).unwrap();
+    assert_eq!(parsed.History(), game.History());
+  }
+
+  #[test]
+  fn NotationReadsLooseSpacing() {
+    let game = Game::FromNotation("1.d D 2.c   e\n3. c").unwrap();
+    assert_eq!(game.ToNotation().unwrap(), "1. d d 2. c e 3. c");
+    assert!(matches!(Game::FromNotation("1. d x"), Err(ParseError::UnexpectedToken { Move: 2, .. })));
+    assert!(matches!(Game::FromNotation("1. d dd"), Err(ParseError::UnexpectedToken { Move: 2, .. })));
+  }
+}
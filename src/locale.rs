@@ -0,0 +1,1154 @@
+// Messages shown to the players, in English and the languages they've been translated into.
+// Each message has an identifier, and text with named placeholders such as {name} that are
+// filled in when it's shown. A message missing from a translation is shown in English.
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use ConnectFour::{DrawError, MoveError, RedoError, UndoError};
+
+use crate::theme::{Color, Shape};
+
+// Enum representing the languages messages can be shown in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+  English,
+  German,
+}
+
+// Languages by code, as given to `--lang` or found at the start of LANG, and by their own name
+const LANGUAGES: [(Language, &str, &str); 2] = [(Language::English, "en", "English"), (Language::German, "de", "Deutsch")];
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+// Picks the language from `--lang`, or else from the locale set in LC_ALL, LC_MESSAGES or
+// LANG, e.g. "de_DE.UTF-8". A `--lang` that isn't known is an error; a locale that isn't
+// known just means English.
+pub fn Init(lang: Option<&str>) -> Result<(), String> {
+  let language = match lang {
+    Some(code) => FromCode(code).ok_or_else(|| {
+      let known: Vec<String> = LANGUAGES.iter().map(|(_, code, name)| format!("{} ({})", code, name)).collect();
+      format!("Unknown language '{}': choose {}", code, known.join(", "))
+    })?,
+    None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| FromCode(&value))
+        .unwrap_or(Language::English),
+  };
+  let _ = LANGUAGE.set(language);
+  Ok(())
+}
+
+// The language with this code, reading only the language part of a locale such as "de_AT"
+fn FromCode(code: &str) -> Option<Language> {
+  let code = code.split(['_', '.', '-', '@']).next().unwrap_or("").to_lowercase();
+  LANGUAGES.iter().find(|(_, known, _)| *known == code).map(|&(language, _, _)| language)
+}
+
+// The language messages are shown in
+pub fn Current() -> Language {
+  *LANGUAGE.get().unwrap_or(&Language::English)
+}
+
+// Enum representing each message, named for what it says
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Message {
+  // Players and pieces
+  PlayerOne,
+  PlayerTwo,
+  PlayerThree,
+  Nobody,
+  ComputerLevel,
+  Red,
+  Orange,
+  Yellow,
+  Green,
+  Blue,
+  Purple,
+  Brown,
+  Black,
+  White,
+  Circle,
+  Square,
+  Letter,
+  Dot,
+  ColorAndShape,
+  ColumnNumber,
+  And,
+  Or,
+
+  // Starting a game
+  HowManyPlayers,
+  ChooseDifficulty,
+  NamePrompt,
+  NameTaken,
+  ResumeGame,
+  InvalidInput,
+  YesNo,
+
+  // The board
+  Title,
+  MoveRule,
+  ComputerIs,
+  Ratings,
+  Handicap,
+  ThreatLegend,
+  ForfeitsOnTime,
+  DrawByAgreement,
+  ItsADraw,
+  Wins,
+  Error,
+  Themes,
+  BigTooSmall,
+
+  // Moves
+  Popped,
+  Bombed,
+  Placed,
+  Dropped,
+  BotChose,
+  IsThinking,
+  BotAcceptsDraw,
+  BotDeclinesDraw,
+  PreviewPrompt,
+
+  // Two computer players
+  ExhibitionKeys,
+  ExhibitionPaused,
+  ExhibitionOver,
+  UnknownAi,
+  BothAi,
+  AiUnsupported,
+
+  // Network games
+  Hosting,
+  TurnedAway,
+  Connecting,
+  Connected,
+  WaitingForMove,
+  ConnectionLost,
+  SaveBeforeLeaving,
+  NotOverNetwork,
+  Watching,
+  Spectators,
+  ChatLine,
+  NoChat,
+  ChatOnlyOverNetwork,
+  NeedChatText,
+  ResumeToken,
+  ResumeNeedsToken,
+  Reconnecting,
+  WaitingForReturn,
+  Reconnected,
+  ForfeitsByLeaving,
+  LookingForGames,
+  FoundGame,
+  ChooseGame,
+
+  // The move prompt
+  TurnWithBombs,
+  Turn,
+  TakebacksUsed,
+  RedoAvailable,
+  PopHint,
+  BombHint,
+  SaveHint,
+  SecondsToMove,
+  RawKeys,
+  RawInputNeedsTerminal,
+  EnterColumnLetters,
+  EnterColumnNumbers,
+  EnterCell,
+  TimeUp,
+  EvalUnavailable,
+  TeachingCompetitive,
+  TeachingOn,
+  TeachingOff,
+  CarefulThreat,
+  CarefulMove,
+  DrawOffered,
+  OnlyWhenOver,
+
+  // The full-screen mode
+  Thinking,
+  TimesUp,
+  MoveUndone,
+  FullColumn,
+  GameOverKeys,
+  RanOutOfTime,
+  ToMove,
+  MovesHeading,
+  KeyMove,
+  KeyDrop,
+  KeyClick,
+  KeyPop,
+  KeyBomb,
+  KeyUndo,
+  KeyRedo,
+  KeyThreats,
+  KeyNewGame,
+  KeyQuit,
+
+  // Commands
+  HintsOver,
+  HintsUnavailable,
+  HintsCompetitive,
+  NoHintsLeft,
+  Hint,
+  NoTakebacksLeft,
+  NothingToTakeBack,
+  TakebackRequest,
+  TakebackRefused,
+  TakebackGranted,
+  QuitQuestion,
+  SaveQuestion,
+  AcceptDraw,
+  DrawDeclined,
+  GameSaved,
+  CouldNotSave,
+  GameLoaded,
+  NoMovesYet,
+  MovesSoFar,
+  TranscriptName,
+  TranscriptSaved,
+  CouldNotWrite,
+  Bye,
+  SavedBye,
+
+  // The end of a game
+  Moves,
+  Notation,
+  Seed,
+  PlayAgain,
+
+  // Help
+  Commands,
+  HelpDropLetters,
+  HelpDropNumbers,
+  HelpPlace,
+  HelpPop,
+  HelpBomb,
+  HelpLeft,
+  HelpUndo,
+  HelpRedo,
+  HelpHint,
+  HelpEval,
+  HelpHistory,
+  HelpBoard,
+  HelpThreats,
+  HelpTeach,
+  HelpDraw,
+  HelpTakeback,
+  HelpSave,
+  HelpLoad,
+  HelpTheme,
+  HelpAnalysis,
+  HelpTranscript,
+  HelpYes,
+  HelpNo,
+  HelpHelp,
+  HelpQuit,
+  HelpSay,
+  HelpChat,
+
+  // Reading what's typed
+  TypeAColumn,
+  NeedFileName,
+  NotUnderstood,
+  NoSuchColumn,
+  NoSuchColumnLetter,
+  NoSuchRow,
+  CellFormat,
+
+  // The accessible mode
+  RowInWords,
+  EmptyCell,
+  CellInWords,
+  SaysPop,
+  SaysBomb,
+  SaysPlace,
+  SaysDrop,
+  SaysCanWin,
+  SaysThreatens,
+  SaysTurn,
+  SaysClock,
+  SaysBombs,
+  SaysTypeColumn,
+  SaysTypeCell,
+  SaysForfeit,
+  SaysDrawAgreed,
+  SaysDraw,
+  SaysWins,
+
+  // Setting up a position
+  SetupTitle,
+  SetupDrop,
+  SetupPlace,
+  SetupRemove,
+  SetupTurn,
+  SetupClear,
+  SetupPlay,
+  SetupChoosePlayer,
+  SetupNoCell,
+  SetupUnknown,
+
+  // Analysis and replays
+  AnalysisUnavailable,
+  Analyzing,
+  AnalysisHeading,
+  ScoreWin,
+  ScoreLoss,
+  Blunder,
+  KeptTheWin,
+  AvoidedTheLoss,
+  WasBetter,
+  NoBlunders,
+  ReplayStep,
+  ReplayKeys,
+  ReplayNoSuchMove,
+
+  // Tools run from the command line
+  Position,
+  EmptyPosition,
+  BenchDepth,
+  BenchSearch,
+  GamesPlayed,
+  TallyWins,
+  TallyDraws,
+  TallyLosses,
+  MovingFirst,
+  AverageGame,
+  AverageMoveTime,
+  GameProblem,
+  ToMoveWins,
+  ToMoveLoses,
+  DrawWithPerfectPlay,
+  BestColumns,
+  PositionsSearched,
+  SolverUnsupported,
+  CarryingOn,
+  TournamentStopped,
+  TournamentGame,
+  PairingProblem,
+  RoundRobin,
+  TimePerMove,
+  Entrant,
+  Score,
+  Rating,
+
+  // Serving games
+  ServingHttp,
+  ServingWebSocket,
+
+  // Files kept between games
+  Warning,
+  CouldNotRead,
+  BuiltInBook,
+  StatsStartingOver,
+  StatsNotUpdated,
+  NoHomeForStats,
+  AutosaveSkipped,
+  NoReplayFile,
+  ConfigWritten,
+  UnknownConfigAction,
+  ConfigReadFrom,
+  ArchiveUnreadable,
+  ArchiveNotAdded,
+  NoHomeForArchive,
+  NeedGameNumber,
+  NotAGameNumber,
+  NoSuchGame,
+  UnknownGamesCommand,
+  NoHomeForConfig,
+
+  // Errors from the rules
+  GameFinished,
+  InvalidColumn,
+  ColumnFull,
+  PopOutDisabled,
+  CannotPop,
+  GravityOn,
+  GravityOff,
+  InvalidRow,
+  CellOccupied,
+  BombsDisabled,
+  NoBombsLeft,
+  NoMovesToUndo,
+  LossOnTimeStands,
+  NoMovesToRedo,
+  DrawTwoPlayersOnly,
+  DrawNotYourTurn,
+  DrawAlreadyOffered,
+  DrawTooSoon,
+  NoDrawOffered,
+}
+
+// The message in English, which every message has
+fn English(message: Message) -> &'static str {
+  use Message::*;
+  match message {
+    PlayerOne => "Player One",
+    PlayerTwo => "Player Two",
+    PlayerThree => "Player Three",
+    Nobody => "Nobody",
+    ComputerLevel => "Computer ({level})",
+    Red => "Red",
+    Orange => "Orange",
+    Yellow => "Yellow",
+    Green => "Green",
+    Blue => "Blue",
+    Purple => "Purple",
+    Brown => "Brown",
+    Black => "Black",
+    White => "White",
+    Circle => "circle",
+    Square => "square",
+    Letter => "letter",
+    Dot => "dot",
+    ColorAndShape => "{color} {shape}",
+    ColumnNumber => "column {column}",
+    And => " and ",
+    Or => " or ",
+
+    HowManyPlayers => "1 player or 2 players? (1/2)",
+    ChooseDifficulty => "Choose a difficulty: (e)asy, (m)edium, (h)ard, (p)erfect",
+    NamePrompt => "Name for {color} (Enter for {name}):",
+    NameTaken => "{name} is taken, pick another name",
+    ResumeGame => "Resume previous game? (y/n)",
+    InvalidInput => "Invalid input",
+    YesNo => "{question} (y/n)",
+
+    Title => "Connect {length}{variants}  (Move: {move})",
+    MoveRule => "Move {move}",
+    ComputerIs => "Computer: {level}",
+    Ratings => "Ratings: {one} {rating1} vs {two} {rating2}, expected score {score1}-{score2}",
+    Handicap => "Handicap: {pieces} {color} piece(s), seed {seed}",
+    ThreatLegend => "{win} wins for {player} now   {danger} {next} would win there next",
+    ForfeitsOnTime => "{name} forfeits on time",
+    DrawByAgreement => "Draw by agreement",
+    ItsADraw => "It's a Draw!",
+    Wins => "{name} Wins!",
+    Error => "Error: {error}",
+    Themes => "Themes: {themes}",
+    BigTooSmall => "The terminal is too small for --big: it needs {columns} columns and {lines} lines, and has {width}x{height}",
+
+    Popped => "{name} popped a piece out of column {column}",
+    Bombed => "{name} bombed column {column}, row {row}",
+    Placed => "{name} placed a piece in column {column}, row {row}",
+    Dropped => "{name} dropped into column {column}, row {row}",
+    BotChose => "{name} chose column {column}",
+    IsThinking => "{name} is thinking {spinner}",
+    BotAcceptsDraw => "{name} accepts the draw",
+    BotDeclinesDraw => "{name} declines the draw",
+    PreviewPrompt => "Column {column}: press Enter to drop here, or type another column",
+
+    ExhibitionKeys => "p pause, q quit",
+    ExhibitionPaused => "Paused: p to carry on, q to quit",
+    ExhibitionOver => "Game over after {moves} moves",
+    UnknownAi => "There's no computer player '{name}'; use ai: with easy, medium, hard, perfect, random, mcts or minimax, e.g. ai:hard or ai:minimax:6",
+    BothAi => "For two computer players both --p1 and --p2 need ai:, e.g. --p1 ai:hard --p2 ai:easy",
+    AiUnsupported => "Computer players only play two-player games where the pieces fall",
+
+    Hosting => "Waiting for someone to join on port {port}...",
+    TurnedAway => "Turned away {address}: {reason}",
+    Connecting => "Connecting to {address}...",
+    Connected => "Connected to {name}; {first} moves first",
+    WaitingForMove => "Waiting for {name} to move...",
+    ConnectionLost => "Lost the connection to {name}: {error}",
+    SaveBeforeLeaving => "Type a file name to save the game, or press Enter to leave without saving:",
+    NotOverNetwork => "That can't be done in a network game",
+    Watching => "Watching {one} against {two}",
+    Spectators => "Spectators: {count}",
+    ChatLine => "{name}: {text}",
+    NoChat => "Nothing has been said yet",
+    ChatOnlyOverNetwork => "Chat is for network games",
+    NeedChatText => "Type what to say after '{command}', e.g. '{command} good game'",
+    ResumeToken => "If the connection drops for good, join again with --resume {token} to carry on",
+    ResumeNeedsToken => "--resume needs the token shown when the game started, e.g. --resume 3f2a9c0d41b7e856",
+    Reconnecting => "Connecting to {name} again ({seconds}s left)...",
+    WaitingForReturn => "Waiting for {name} to reconnect ({seconds}s)...",
+    Reconnected => "Back in the game with {name}",
+    ForfeitsByLeaving => "{name} forfeits by not coming back",
+    LookingForGames => "Looking for games on the local network...",
+    FoundGame => "{number}. {name}'s game, {game}, at {address}",
+    ChooseGame => "Type the number of the game to join:",
+
+    TurnWithBombs => "{name} ({color}, {bombs} bomb(s) left)",
+    Turn => "{name} ({color})",
+    TakebacksUsed => "Takebacks used: {used}",
+    RedoAvailable => "{count} move(s) available to redo with '{redo}'",
+    PopHint => "Type 'p' and a column number, e.g. 'p4', to pop your piece from the bottom",
+    BombHint => "Type 'b' and a column number, e.g. 'b4', to drop your bomb",
+    SaveHint => {
+      "Type 'save' or 'load' and a file name to keep the game for later or pick one up, '{history}' for the moves so far or '{threats}' to mark threats"
+    }
+    SecondsToMove => "{seconds}s left to move",
+    RawKeys => "Keys take effect at once; type ':' first for a longer command, e.g. ':save game.json'",
+    RawInputNeedsTerminal => "--raw-input needs to be run in a terminal, reading whole lines",
+    EnterColumnLetters => "Enter a column ({columns}), 'undo', 'hint' or 'eval' to toggle the evaluation: ",
+    EnterColumnNumbers => "Enter a column number ({columns}), 'u' to undo, 'h' for a hint or 'e' to toggle the evaluation: ",
+    EnterCell => "Enter a cell as column,row ({columns}, 1-{rows} from the bottom), e.g. 4,3, or 'undo': ",
+    TimeUp => "Time's up! Anything typed after the deadline was ignored",
+    EvalUnavailable => "The evaluation isn't available in this variant",
+    TeachingCompetitive => "Teaching mode is off in competitive games",
+    TeachingOn => "Teaching mode on for {color}",
+    TeachingOff => "Teaching mode off for {color}",
+    CarefulThreat => "Careful{dash}{color} threatens {cells}",
+    CarefulMove => "Careful{dash}playing column {columns} would let {color} win next",
+    DrawOffered => "{name} offers a draw; make your move and {other} will answer",
+    OnlyWhenOver => "That only works once the game is over",
+
+    Thinking => "Thinking...",
+    TimesUp => "Time's up!",
+    MoveUndone => "Move undone",
+    FullColumn => "Column {column} is full",
+    GameOverKeys => "The game is over: press 'n' for a new one or 'u' to undo",
+    RanOutOfTime => "{name} ran out of time",
+    ToMove => "{name} to move",
+    MovesHeading => "Moves",
+    KeyMove => "{arrows} move",
+    KeyDrop => "Enter drop",
+    KeyClick => "click drop",
+    KeyPop => "{key} pop",
+    KeyBomb => "{key} bomb",
+    KeyUndo => "{key} undo",
+    KeyRedo => "{key} redo",
+    KeyThreats => "{key} threats",
+    KeyNewGame => "{key} new game",
+    KeyQuit => "{key} quit",
+
+    HintsOver => "The game is over, there is nothing left to hint at",
+    HintsUnavailable => "Hints aren't available in this variant",
+    HintsCompetitive => "Hints are off in competitive games",
+    NoHintsLeft => "No hints left for this game",
+    Hint => "Hint: column {column} ({left} hint(s) left)",
+    NoTakebacksLeft => "No takebacks left for this game",
+    NothingToTakeBack => "You have no move to take back",
+    TakebackRequest => "{name} requests a takeback{dash}{other}, allow?",
+    TakebackRefused => "{name} refused the takeback, play on",
+    TakebackGranted => "Takeback granted ({used} of {limit} used by {name})",
+    QuitQuestion => "Quit the current game?",
+    SaveQuestion => "Save before quitting?",
+    AcceptDraw => "{name} offers a draw. Accept? (y/n)",
+    DrawDeclined => "Draw declined, play on",
+    GameSaved => "Game saved to {path}",
+    CouldNotSave => "couldn't save to {path}: {error}",
+    GameLoaded => "Game loaded from {path}",
+    NoMovesYet => "No moves have been played yet",
+    MovesSoFar => "Moves so far:",
+    TranscriptName => "Transcript file name (Enter for {default}):",
+    TranscriptSaved => "Transcript saved to {path}",
+    CouldNotWrite => "couldn't write {path}: {error}",
+    Bye => "Bye",
+    SavedBye => "Game saved to {path}, bye",
+
+    Moves => "Moves: {moves}",
+    Notation => "Notation: {notation}",
+    Seed => "Seed: {seed}; play the same game again with --seed {seed}",
+    PlayAgain => "Do you want to play again? (y/n, 'u' to undo the last move, 'a' for analysis or 's' to save a transcript)",
+
+    Commands => "Commands:",
+    HelpDropLetters => "drop into a column, by letter or number",
+    HelpDropNumbers => "drop into a column, by number",
+    HelpPlace => "place a piece in a cell, rows counted from the bottom",
+    HelpPop => "pop your piece from the bottom of a column",
+    HelpBomb => "drop your bomb into a column",
+    HelpLeft => "({left} left)",
+    HelpUndo => "take back the last move",
+    HelpRedo => "replay an undone move",
+    HelpHint => "suggest a move",
+    HelpEval => "toggle the evaluation bar",
+    HelpHistory => "list the moves so far",
+    HelpBoard => "describe the board in words, row by row",
+    HelpThreats => "mark cells that win for either side",
+    HelpTeach => "toggle threat warnings for you",
+    HelpDraw => "offer your opponent a draw",
+    HelpTakeback => "ask your opponent to take back your last move",
+    HelpSave => "save the game to a file",
+    HelpLoad => "load a game from a file",
+    HelpTheme => "change how the board looks, or list the themes",
+    HelpAnalysis => "look for the mistakes in the game",
+    HelpTranscript => "write a record of the game to a file",
+    HelpYes => "play again",
+    HelpNo => "leave",
+    HelpHelp => "list the commands",
+    HelpQuit => "leave, keeping the game if you like",
+    HelpSay => "say something to the other player in a network game",
+    HelpChat => "show what's been said this game",
+
+    TypeAColumn => "Type a column, or 'help' for the commands",
+    NeedFileName => "Type a file name after '{command}', e.g. '{command} game.json'",
+    NotUnderstood => "Didn't understand '{input}'; type 'help' for the commands",
+    NoSuchColumn => "There's no column '{column}'; columns are 1-{width}",
+    NoSuchColumnLetter => "There's no column '{column}'",
+    NoSuchRow => "There's no row '{row}'; rows are 1-{height}",
+    CellFormat => "Enter a cell as column,row, e.g. 4,3",
+
+    RowInWords => "Row {row}: {cells}",
+    EmptyCell => "empty",
+    CellInWords => "column {column}, row {row}",
+    SaysPop => "{color} pops a piece out of column {column}.",
+    SaysBomb => "{color} bombs column {column}, row {row}.",
+    SaysPlace => "{color} places a piece in column {column}, row {row}.",
+    SaysDrop => "{color} drops in column {column}, landing on row {row}.",
+    SaysCanWin => "{color} can win now at {cells}.",
+    SaysThreatens => "{color} threatens to win at {cells}.",
+    SaysTurn => "Move {move}. {name}, {color}, to move.",
+    SaysClock => " {time} left on your clock.",
+    SaysBombs => " {bombs} bomb(s) left.",
+    SaysTypeColumn => " Type a column from 1 to {width}, 'board' to hear the board, or 'help'.",
+    SaysTypeCell => " Type a cell as column,row, 'board' to hear the board, or 'help'.",
+    SaysForfeit => "{name} forfeits on time.",
+    SaysDrawAgreed => "The game is drawn by agreement.",
+    SaysDraw => "The game is a draw.",
+    SaysWins => "{name}, {color}, wins.",
+
+    SetupTitle => "Setup  (to move: {player})",
+    SetupDrop => "drop a piece into column 4",
+    SetupPlace => "put a piece on column 4, row 3 (counting from the bottom)",
+    SetupRemove => "remove the top piece of column 4, or the piece on a cell",
+    SetupTurn => "choose who moves first",
+    SetupClear => "empty the board",
+    SetupPlay => "check the position and start the game",
+    SetupChoosePlayer => "Choose a player by color, e.g. 'turn y'",
+    SetupNoCell => "No such cell, or nothing to put or remove there",
+    SetupUnknown => "Unknown command",
+
+    AnalysisUnavailable => "Analysis isn't available for this variant",
+    Analyzing => "Analyzing {moves} moves...",
+    AnalysisHeading => "Move  Player  Column  Eval    Best",
+    ScoreWin => "win",
+    ScoreLoss => "loss",
+    Blunder => "Move {move} (column {column}) was a blunder; column {best} {better}",
+    KeptTheWin => "kept the win",
+    AvoidedTheLoss => "avoided the loss",
+    WasBetter => "was better",
+    NoBlunders => "No blunders found",
+    ReplayStep => "Move {step} of {total}",
+    ReplayKeys => "'n' next, 'p' previous, a move number to jump, 'a' to autoplay, 'q' to quit",
+    ReplayNoSuchMove => "enter a move number from 0 to {total}",
+
+    Position => "Position: {moves}",
+    EmptyPosition => "(empty)",
+    BenchDepth => "Depth {depth}: {nodes} nodes in {seconds}s ({rate} nodes/s)",
+    BenchSearch => "Search with {threads} thread(s): column {column} ({score}) in {seconds}s, {nodes} nodes{speedup}",
+    GamesPlayed => "{games} games in {seconds}s, {one} against {two}",
+    TallyWins => "Wins",
+    TallyDraws => "Draws",
+    TallyLosses => "Losses",
+    MovingFirst => "Moving first:",
+    AverageGame => "Average game: {moves} moves",
+    AverageMoveTime => "Average time per move, {player}: {ms} ms",
+    GameProblem => "Game {number}: {problem}",
+    ToMoveWins => "{player} to move wins in {moves} move(s)",
+    ToMoveLoses => "{player} to move loses in {moves} move(s)",
+    DrawWithPerfectPlay => "Draw with perfect play",
+    BestColumns => "Best columns: {columns}",
+    PositionsSearched => "Positions searched: {nodes}",
+    SolverUnsupported => "the solver only handles Connect {length} on the standard {width}x{height} board",
+    CarryingOn => "Carrying on from {path}: {played} of {total} games played",
+    TournamentStopped => "Stopped after {played} of {total} games; run it again to carry on",
+    TournamentGame => "Game {number} of {total}: {one} - {two} {score}",
+    PairingProblem => "{one} against {two}: {problem}",
+    RoundRobin => "Round robin, {games} game(s) per pairing with each color",
+    TimePerMove => ", {ms} ms per move",
+    Entrant => "Entrant",
+    Score => "Score",
+    Rating => "Rating",
+
+    ServingHttp => "Serving games over HTTP on {address}; Ctrl-C to stop",
+    ServingWebSocket => "Serving games over WebSocket on {address}; Ctrl-C to stop",
+
+    Warning => "Warning: {warning}",
+    CouldNotRead => "couldn't read {path}: {error}",
+    BuiltInBook => "{error}, using the built-in book",
+    StatsStartingOver => "starting the statistics at {path} over: {error}",
+    StatsNotUpdated => "couldn't update the statistics at {path}: {error}",
+    NoHomeForStats => "no home directory to keep statistics in",
+    AutosaveSkipped => "skipping the autosave at {path}: {error}",
+    NoReplayFile => "give the saved game to replay, e.g. replay game.json",
+    ConfigWritten => "Config file written to {path}; remove the # in front of a setting to use it",
+    UnknownConfigAction => "unknown config action '{action}'; try config init",
+    ConfigReadFrom => "The config file is read from {path}; config init writes one to start from",
+    ArchiveUnreadable => "couldn't read the games archive at {path}: {error}",
+    ArchiveNotAdded => "couldn't add the game to {path}: {error}",
+    NoHomeForArchive => "no home directory to keep the games archive in",
+    NeedGameNumber => "give the number of the game, as shown by games list",
+    NotAGameNumber => "'{id}' isn't a game number",
+    NoSuchGame => "there's no game {number} in {path}",
+    UnknownGamesCommand => "unknown games command '{command}'; it's list, show or replay",
+    NoHomeForConfig => "there's no home directory for a config file",
+
+    GameFinished => "Game is already finished",
+    InvalidColumn => "Column {column} is out of range (1–{columns})",
+    ColumnFull => "Column {column} is full",
+    PopOutDisabled => "Popping pieces is only allowed in PopOut games",
+    CannotPop => "You can only pop your own piece from the bottom row",
+    GravityOn => "Pieces can only be placed freely in games without gravity",
+    GravityOff => "Pick a cell as column,row; pieces don't fall in this game",
+    InvalidRow => "Invalid row",
+    CellOccupied => "Cell is already taken",
+    BombsDisabled => "Bombs are only allowed in bomb games",
+    NoBombsLeft => "You have already used your bomb this game",
+    NoMovesToUndo => "No moves to undo",
+    LossOnTimeStands => "The clock has run out, the loss on time stands",
+    NoMovesToRedo => "No moves to redo",
+    DrawTwoPlayersOnly => "Draws can only be agreed in two-player games",
+    DrawNotYourTurn => "You can only offer a draw on your turn",
+    DrawAlreadyOffered => "A draw offer is already waiting for an answer",
+    DrawTooSoon => "You offered a draw last turn; wait a turn before offering again",
+    NoDrawOffered => "No draw has been offered",
+  }
+}
+
+// The message in German, if it's been translated. Commands and the letters typed for them
+// stay in English, as that's what the game understands.
+fn German(message: Message) -> Option<&'static str> {
+  use Message::*;
+  Some(match message {
+    PlayerOne => "Spieler Eins",
+    PlayerTwo => "Spieler Zwei",
+    PlayerThree => "Spieler Drei",
+    Nobody => "Niemand",
+    ComputerLevel => "Computer ({level})",
+    Red => "Rot",
+    Orange => "Orange",
+    Yellow => "Gelb",
+    Green => "Grün",
+    Blue => "Blau",
+    Purple => "Lila",
+    Brown => "Braun",
+    Black => "Schwarz",
+    White => "Weiß",
+    Circle => "Kreis",
+    Square => "Quadrat",
+    Letter => "Buchstabe",
+    Dot => "Punkt",
+    ColorAndShape => "{color} ({shape})",
+    ColumnNumber => "Spalte {column}",
+    And => " und ",
+    Or => " oder ",
+
+    HowManyPlayers => "1 Spieler oder 2 Spieler? (1/2)",
+    ChooseDifficulty => "Schwierigkeit wählen: (e)asy, (m)edium, (h)ard, (p)erfect",
+    NamePrompt => "Name für {color} (Enter für {name}):",
+    NameTaken => "{name} ist schon vergeben, bitte einen anderen Namen wählen",
+    ResumeGame => "Letztes Spiel fortsetzen? (y/n)",
+    InvalidInput => "Ungültige Eingabe",
+    YesNo => "{question} (y/n)",
+
+    Title => "{length} gewinnt{variants}  (Zug: {move})",
+    MoveRule => "Zug {move}",
+    ComputerIs => "Computer: {level}",
+    Ratings => "Wertungen: {one} {rating1} gegen {two} {rating2}, erwartetes Ergebnis {score1}-{score2}",
+    Handicap => "Vorgabe: {pieces} {color} Stein(e), Startwert {seed}",
+    ThreatLegend => "{win} gewinnt jetzt für {player}   {danger} dort gewänne {next} als Nächstes",
+    ForfeitsOnTime => "{name} verliert auf Zeit",
+    DrawByAgreement => "Remis nach Vereinbarung",
+    ItsADraw => "Unentschieden!",
+    Wins => "{name} gewinnt!",
+    Error => "Fehler: {error}",
+    Themes => "Designs: {themes}",
+    BigTooSmall => "Das Terminal ist zu klein für --big: es braucht {columns} Spalten und {lines} Zeilen und hat {width}x{height}",
+
+    Popped => "{name} hat einen Stein unten aus Spalte {column} gezogen",
+    Bombed => "{name} hat Spalte {column}, Reihe {row} gesprengt",
+    Placed => "{name} hat einen Stein auf Spalte {column}, Reihe {row} gesetzt",
+    Dropped => "{name} hat in Spalte {column} geworfen, Reihe {row}",
+    BotChose => "{name} wählt Spalte {column}",
+    IsThinking => "{name} denkt nach {spinner}",
+    BotAcceptsDraw => "{name} nimmt das Remis an",
+    BotDeclinesDraw => "{name} lehnt das Remis ab",
+    PreviewPrompt => "Spalte {column}: Enter drücken, um hier zu werfen, oder eine andere Spalte eingeben",
+
+    ExhibitionKeys => "p Pause, q Beenden",
+    ExhibitionPaused => "Pausiert: p zum Weiterspielen, q zum Beenden",
+    ExhibitionOver => "Partie nach {moves} Zügen vorbei",
+    UnknownAi => "Es gibt keinen Computerspieler '{name}'; ai: mit easy, medium, hard, perfect, random, mcts oder minimax verwenden, z. B. ai:hard oder ai:minimax:6",
+    BothAi => "Für zwei Computerspieler brauchen --p1 und --p2 beide ai:, z. B. --p1 ai:hard --p2 ai:easy",
+    AiUnsupported => "Computerspieler spielen nur Partien zu zweit, in denen die Steine fallen",
+
+    Hosting => "Warte auf Port {port} auf einen Mitspieler...",
+    TurnedAway => "{address} abgewiesen: {reason}",
+    Connecting => "Verbinde mit {address}...",
+    Connected => "Verbunden mit {name}; {first} beginnt",
+    WaitingForMove => "Warte auf den Zug von {name}...",
+    ConnectionLost => "Die Verbindung zu {name} ist abgebrochen: {error}",
+    SaveBeforeLeaving => "Einen Dateinamen eingeben, um die Partie zu speichern, oder Enter, um ohne Speichern zu gehen:",
+    NotOverNetwork => "Das geht in einer Netzwerkpartie nicht",
+    Watching => "Zuschauen: {one} gegen {two}",
+    Spectators => "Zuschauer: {count}",
+    ChatLine => "{name}: {text}",
+    NoChat => "Bisher wurde nichts gesagt",
+    ChatOnlyOverNetwork => "Chatten geht nur in Netzwerkpartien",
+    NeedChatText => "Nach '{command}' eingeben, was gesagt werden soll, z. B. '{command} gutes Spiel'",
+    ResumeToken => "Bricht die Verbindung ganz ab, mit --resume {token} wieder beitreten und weiterspielen",
+    ResumeNeedsToken => "--resume braucht das Kennwort, das beim Start der Partie gezeigt wurde, z. B. --resume 3f2a9c0d41b7e856",
+    Reconnecting => "Neue Verbindung zu {name} ({seconds} s übrig)...",
+    WaitingForReturn => "Warte, bis {name} wieder verbunden ist ({seconds} s)...",
+    Reconnected => "Wieder im Spiel mit {name}",
+    ForfeitsByLeaving => "{name} hat verloren: nicht rechtzeitig zurückgekommen",
+    LookingForGames => "Suche nach Partien im lokalen Netzwerk...",
+    FoundGame => "{number}. Partie von {name}, {game}, bei {address}",
+    ChooseGame => "Die Nummer der Partie eingeben, der beigetreten werden soll:",
+
+    TurnWithBombs => "{name} ({color}, noch {bombs} Bombe(n))",
+    Turn => "{name} ({color})",
+    TakebacksUsed => "Zurücknahmen genutzt: {used}",
+    RedoAvailable => "{count} Zug/Züge mit '{redo}' wiederherstellbar",
+    PopHint => "'p' und eine Spaltennummer eingeben, z. B. 'p4', um den eigenen Stein unten herauszuziehen",
+    BombHint => "'b' und eine Spaltennummer eingeben, z. B. 'b4', um die Bombe zu werfen",
+    SaveHint => {
+      "'save' oder 'load' und einen Dateinamen eingeben, um das Spiel zu speichern oder zu laden, '{history}' für die bisherigen Züge oder '{threats}' für Drohungen"
+    }
+    SecondsToMove => "Noch {seconds}s für den Zug",
+    RawKeys => "Tasten wirken sofort; für längere Befehle zuerst ':' eingeben, z. B. ':save spiel.json'",
+    RawInputNeedsTerminal => "--raw-input braucht ein Terminal; es werden ganze Zeilen gelesen",
+    EnterColumnLetters => "Spalte eingeben ({columns}), 'undo', 'hint' oder 'eval' für die Bewertung: ",
+    EnterColumnNumbers => "Spaltennummer eingeben ({columns}), 'u' zum Zurücknehmen, 'h' für einen Tipp oder 'e' für die Bewertung: ",
+    EnterCell => "Feld als Spalte,Reihe eingeben ({columns}, 1-{rows} von unten), z. B. 4,3, oder 'undo': ",
+    TimeUp => "Die Zeit ist um! Alles nach Ablauf Eingegebene wurde ignoriert",
+    EvalUnavailable => "Die Bewertung gibt es in dieser Variante nicht",
+    TeachingCompetitive => "Im Wettkampf gibt es keinen Lernmodus",
+    TeachingOn => "Lernmodus an für {color}",
+    TeachingOff => "Lernmodus aus für {color}",
+    CarefulThreat => "Vorsicht{dash}{color} droht mit {cells}",
+    CarefulMove => "Vorsicht{dash}Spalte {columns} ließe {color} als Nächstes gewinnen",
+    DrawOffered => "{name} bietet Remis an; zieh und {other} antwortet",
+    OnlyWhenOver => "Das geht erst, wenn das Spiel vorbei ist",
+
+    Thinking => "Denke nach...",
+    TimesUp => "Die Zeit ist um!",
+    MoveUndone => "Zug zurückgenommen",
+    FullColumn => "Spalte {column} ist voll",
+    GameOverKeys => "Das Spiel ist vorbei: 'n' für ein neues oder 'u' zum Zurücknehmen",
+    RanOutOfTime => "{name} hat die Zeit überschritten",
+    ToMove => "{name} ist am Zug",
+    MovesHeading => "Züge",
+    KeyMove => "{arrows} bewegen",
+    KeyDrop => "Enter werfen",
+    KeyClick => "Klick werfen",
+    KeyPop => "{key} ziehen",
+    KeyBomb => "{key} Bombe",
+    KeyUndo => "{key} zurück",
+    KeyRedo => "{key} wiederholen",
+    KeyThreats => "{key} Drohungen",
+    KeyNewGame => "{key} neues Spiel",
+    KeyQuit => "{key} beenden",
+
+    HintsOver => "Das Spiel ist vorbei, es gibt nichts mehr zu raten",
+    HintsUnavailable => "Tipps gibt es in dieser Variante nicht",
+    HintsCompetitive => "Im Wettkampf gibt es keine Tipps",
+    NoHintsLeft => "Keine Tipps mehr für dieses Spiel",
+    Hint => "Tipp: Spalte {column} (noch {left} Tipp(s))",
+    NoTakebacksLeft => "Keine Zurücknahmen mehr für dieses Spiel",
+    NothingToTakeBack => "Du hast keinen Zug zum Zurücknehmen",
+    TakebackRequest => "{name} möchte einen Zug zurücknehmen{dash}{other}, erlauben?",
+    TakebackRefused => "{name} lehnt die Zurücknahme ab, weiter geht's",
+    TakebackGranted => "Zurücknahme gewährt ({used} von {limit} von {name} genutzt)",
+    QuitQuestion => "Das laufende Spiel beenden?",
+    SaveQuestion => "Vor dem Beenden speichern?",
+    AcceptDraw => "{name} bietet Remis an. Annehmen? (y/n)",
+    DrawDeclined => "Remis abgelehnt, weiter geht's",
+    GameSaved => "Spiel gespeichert in {path}",
+    CouldNotSave => "konnte nicht in {path} speichern: {error}",
+    GameLoaded => "Spiel geladen aus {path}",
+    NoMovesYet => "Es wurden noch keine Züge gespielt",
+    MovesSoFar => "Bisherige Züge:",
+    TranscriptName => "Dateiname für das Protokoll (Enter für {default}):",
+    TranscriptSaved => "Protokoll gespeichert in {path}",
+    CouldNotWrite => "konnte {path} nicht schreiben: {error}",
+    Bye => "Tschüss",
+    SavedBye => "Spiel gespeichert in {path}, tschüss",
+
+    Moves => "Züge: {moves}",
+    Notation => "Notation: {notation}",
+    Seed => "Startwert: {seed}; mit --seed {seed} lässt sich dieselbe Partie wiederholen",
+    PlayAgain => "Noch eine Runde? (y/n, 'u' nimmt den letzten Zug zurück, 'a' für die Analyse oder 's' speichert ein Protokoll)",
+
+    Commands => "Befehle:",
+    HelpDropLetters => "in eine Spalte werfen, per Buchstabe oder Nummer",
+    HelpDropNumbers => "in eine Spalte werfen, per Nummer",
+    HelpPlace => "einen Stein auf ein Feld setzen, Reihen von unten gezählt",
+    HelpPop => "den eigenen Stein unten aus einer Spalte ziehen",
+    HelpBomb => "die Bombe in eine Spalte werfen",
+    HelpLeft => "(noch {left})",
+    HelpUndo => "den letzten Zug zurücknehmen",
+    HelpRedo => "einen zurückgenommenen Zug wiederholen",
+    HelpHint => "einen Zug vorschlagen",
+    HelpEval => "die Bewertungsleiste ein- oder ausblenden",
+    HelpHistory => "die bisherigen Züge auflisten",
+    HelpBoard => "das Brett Reihe für Reihe in Worten beschreiben",
+    HelpThreats => "Felder markieren, die für eine Seite gewinnen",
+    HelpTeach => "Warnungen vor Drohungen ein- oder ausschalten",
+    HelpDraw => "dem Gegner Remis anbieten",
+    HelpTakeback => "den Gegner bitten, den letzten eigenen Zug zurückzunehmen",
+    HelpSave => "das Spiel in eine Datei speichern",
+    HelpLoad => "ein Spiel aus einer Datei laden",
+    HelpTheme => "das Aussehen des Bretts ändern oder die Designs auflisten",
+    HelpAnalysis => "die Fehler im Spiel suchen",
+    HelpTranscript => "ein Protokoll des Spiels in eine Datei schreiben",
+    HelpYes => "noch einmal spielen",
+    HelpNo => "aufhören",
+    HelpHelp => "die Befehle auflisten",
+    HelpQuit => "aufhören, auf Wunsch mit gespeichertem Spiel",
+    HelpSay => "dem Gegner in einer Netzwerkpartie etwas sagen",
+    HelpChat => "anzeigen, was in dieser Partie gesagt wurde",
+
+    TypeAColumn => "Eine Spalte eingeben, oder 'help' für die Befehle",
+    NeedFileName => "Nach '{command}' einen Dateinamen eingeben, z. B. '{command} spiel.json'",
+    NotUnderstood => "'{input}' nicht verstanden; 'help' zeigt die Befehle",
+    NoSuchColumn => "Es gibt keine Spalte '{column}'; die Spalten sind 1-{width}",
+    NoSuchColumnLetter => "Es gibt keine Spalte '{column}'",
+    NoSuchRow => "Es gibt keine Reihe '{row}'; die Reihen sind 1-{height}",
+    CellFormat => "Ein Feld als Spalte,Reihe eingeben, z. B. 4,3",
+
+    RowInWords => "Reihe {row}: {cells}",
+    EmptyCell => "leer",
+    CellInWords => "Spalte {column}, Reihe {row}",
+    SaysPop => "{color} zieht einen Stein unten aus Spalte {column}.",
+    SaysBomb => "{color} sprengt Spalte {column}, Reihe {row}.",
+    SaysPlace => "{color} setzt einen Stein auf Spalte {column}, Reihe {row}.",
+    SaysDrop => "{color} wirft in Spalte {column} und landet in Reihe {row}.",
+    SaysCanWin => "{color} kann jetzt gewinnen auf {cells}.",
+    SaysThreatens => "{color} droht zu gewinnen auf {cells}.",
+    SaysTurn => "Zug {move}. {name}, {color}, ist am Zug.",
+    SaysClock => " Noch {time} auf deiner Uhr.",
+    SaysBombs => " Noch {bombs} Bombe(n).",
+    SaysTypeColumn => " Eine Spalte von 1 bis {width} eingeben, 'board' für das Brett oder 'help'.",
+    SaysTypeCell => " Ein Feld als Spalte,Reihe eingeben, 'board' für das Brett oder 'help'.",
+    SaysForfeit => "{name} verliert auf Zeit.",
+    SaysDrawAgreed => "Das Spiel endet mit vereinbartem Remis.",
+    SaysDraw => "Das Spiel endet unentschieden.",
+    SaysWins => "{name}, {color}, gewinnt.",
+
+    SetupTitle => "Aufstellung  (am Zug: {player})",
+    SetupDrop => "einen Stein in Spalte 4 werfen",
+    SetupPlace => "einen Stein auf Spalte 4, Reihe 3 setzen (von unten gezählt)",
+    SetupRemove => "den obersten Stein aus Spalte 4 nehmen, oder den Stein auf einem Feld",
+    SetupTurn => "wählen, wer anfängt",
+    SetupClear => "das Brett leeren",
+    SetupPlay => "die Stellung prüfen und das Spiel beginnen",
+    SetupChoosePlayer => "Einen Spieler nach Farbe wählen, z. B. 'turn y'",
+    SetupNoCell => "Dieses Feld gibt es nicht, oder dort ist nichts zu setzen oder zu nehmen",
+    SetupUnknown => "Unbekannter Befehl",
+
+    AnalysisUnavailable => "Für diese Variante gibt es keine Analyse",
+    Analyzing => "Analysiere {moves} Züge...",
+    AnalysisHeading => "Zug   Spieler Spalte  Wert    Bester",
+    ScoreWin => "Sieg",
+    ScoreLoss => "Verlust",
+    Blunder => "Zug {move} (Spalte {column}) war ein grober Fehler; Spalte {best} {better}",
+    KeptTheWin => "hätte den Sieg gehalten",
+    AvoidedTheLoss => "hätte die Niederlage vermieden",
+    WasBetter => "war besser",
+    NoBlunders => "Keine groben Fehler gefunden",
+    ReplayStep => "Zug {step} von {total}",
+    ReplayKeys => "'n' weiter, 'p' zurück, eine Zugnummer zum Springen, 'a' zum Abspielen, 'q' zum Beenden",
+    ReplayNoSuchMove => "eine Zugnummer von 0 bis {total} eingeben",
+
+    Position => "Stellung: {moves}",
+    EmptyPosition => "(leer)",
+    BenchDepth => "Tiefe {depth}: {nodes} Knoten in {seconds}s ({rate} Knoten/s)",
+    BenchSearch => "Suche mit {threads} Thread(s): Spalte {column} ({score}) in {seconds}s, {nodes} Knoten{speedup}",
+    GamesPlayed => "{games} Partien in {seconds}s, {one} gegen {two}",
+    TallyWins => "Siege",
+    TallyDraws => "Remis",
+    TallyLosses => "Niederlagen",
+    MovingFirst => "Mit dem ersten Zug:",
+    AverageGame => "Durchschnittliche Partie: {moves} Züge",
+    AverageMoveTime => "Durchschnittliche Zeit pro Zug, {player}: {ms} ms",
+    GameProblem => "Partie {number}: {problem}",
+    ToMoveWins => "{player} am Zug gewinnt in {moves} Zug/Zügen",
+    ToMoveLoses => "{player} am Zug verliert in {moves} Zug/Zügen",
+    DrawWithPerfectPlay => "Remis bei perfektem Spiel",
+    BestColumns => "Beste Spalten: {columns}",
+    PositionsSearched => "Durchsuchte Stellungen: {nodes}",
+    SolverUnsupported => "der Löser kann nur {length} gewinnt auf dem üblichen Brett mit {width}x{height} Feldern",
+    CarryingOn => "Weiter mit {path}: {played} von {total} Partien gespielt",
+    TournamentStopped => "Nach {played} von {total} Partien angehalten; zum Weitermachen erneut starten",
+    TournamentGame => "Partie {number} von {total}: {one} - {two} {score}",
+    PairingProblem => "{one} gegen {two}: {problem}",
+    RoundRobin => "Rundenturnier, {games} Partie(n) je Paarung mit jeder Farbe",
+    TimePerMove => ", {ms} ms pro Zug",
+    Entrant => "Teilnehmer",
+    Score => "Punkte",
+    Rating => "Wertung",
+
+    ServingHttp => "Spiele werden über HTTP auf {address} angeboten; Strg-C zum Beenden",
+    ServingWebSocket => "Spiele werden über WebSocket auf {address} angeboten; Strg-C zum Beenden",
+
+    Warning => "Warnung: {warning}",
+    CouldNotRead => "{path} konnte nicht gelesen werden: {error}",
+    BuiltInBook => "{error}, das eingebaute Eröffnungsbuch wird verwendet",
+    StatsStartingOver => "die Statistik in {path} wird neu begonnen: {error}",
+    StatsNotUpdated => "die Statistik in {path} konnte nicht aktualisiert werden: {error}",
+    NoHomeForStats => "kein Home-Verzeichnis für die Statistik",
+    AutosaveSkipped => "die automatische Sicherung in {path} wird übersprungen: {error}",
+    NoReplayFile => "die gespeicherte Partie zum Abspielen angeben, z. B. replay game.json",
+    ConfigWritten => "Konfigurationsdatei nach {path} geschrieben; das # vor einer Einstellung entfernen, um sie zu verwenden",
+    UnknownConfigAction => "unbekannte Konfigurationsaktion '{action}'; config init versuchen",
+    ConfigReadFrom => "Die Konfigurationsdatei wird aus {path} gelesen; config init schreibt eine als Ausgangspunkt",
+    ArchiveUnreadable => "das Partienarchiv in {path} konnte nicht gelesen werden: {error}",
+    ArchiveNotAdded => "die Partie konnte nicht zu {path} hinzugefügt werden: {error}",
+    NoHomeForArchive => "kein Home-Verzeichnis für das Partienarchiv",
+    NeedGameNumber => "die Nummer der Partie angeben, wie games list sie zeigt",
+    NotAGameNumber => "'{id}' ist keine Partienummer",
+    NoSuchGame => "in {path} gibt es keine Partie {number}",
+    UnknownGamesCommand => "unbekannter games-Befehl '{command}'; es gibt list, show und replay",
+    NoHomeForConfig => "es gibt kein Home-Verzeichnis für eine Konfigurationsdatei",
+
+    GameFinished => "Das Spiel ist schon vorbei",
+    InvalidColumn => "Spalte {column} liegt außerhalb des Bereichs (1–{columns})",
+    ColumnFull => "Spalte {column} ist voll",
+    PopOutDisabled => "Steine herausziehen geht nur in PopOut-Spielen",
+    CannotPop => "Du kannst nur deinen eigenen Stein aus der untersten Reihe ziehen",
+    GravityOn => "Steine frei setzen geht nur in Spielen ohne Schwerkraft",
+    GravityOff => "Ein Feld als Spalte,Reihe wählen; in diesem Spiel fallen die Steine nicht",
+    InvalidRow => "Ungültige Reihe",
+    CellOccupied => "Das Feld ist schon besetzt",
+    BombsDisabled => "Bomben gibt es nur in Bombenspielen",
+    NoBombsLeft => "Du hast deine Bombe in diesem Spiel schon benutzt",
+    NoMovesToUndo => "Keine Züge zum Zurücknehmen",
+    LossOnTimeStands => "Die Uhr ist abgelaufen, die Niederlage auf Zeit bleibt",
+    NoMovesToRedo => "Keine Züge zum Wiederherstellen",
+    DrawTwoPlayersOnly => "Remis gibt es nur in Spielen zu zweit",
+    DrawNotYourTurn => "Remis kann man nur am eigenen Zug anbieten",
+    DrawAlreadyOffered => "Ein Remisangebot wartet schon auf Antwort",
+    DrawTooSoon => "Du hast letzte Runde Remis angeboten; warte eine Runde",
+    NoDrawOffered => "Es wurde kein Remis angeboten",
+  })
+}
+
+// The message's text in the current language, falling back to English
+pub fn Text(message: Message) -> &'static str {
+  let translated = match Current() {
+    Language::English => None,
+    Language::German => German(message),
+  };
+  translated.unwrap_or_else(|| English(message))
+}
+
+// The message with its placeholders filled in, e.g. {name} from ("name", &name). A
+// placeholder without a value is left as it is rather than failing.
+pub fn Say(message: Message, values: &[(&str, &dyn Display)]) -> String {
+  let mut text = Text(message).to_string();
+  for (name, value) in values {
+    text = text.replace(&format!("{{{}}}", name), &value.to_string());
+  }
+  text
+}
+
+// Name of a piece color in the current language
+pub fn ColorWord(color: Color) -> &'static str {
+  Text(match color {
+    Color::Red => Message::Red,
+    Color::Orange => Message::Orange,
+    Color::Yellow => Message::Yellow,
+    Color::Green => Message::Green,
+    Color::Blue => Message::Blue,
+    Color::Purple => Message::Purple,
+    Color::Brown => Message::Brown,
+    Color::Black => Message::Black,
+    Color::White => Message::White,
+  })
+}
+
+// Name of a piece shape in the current language
+pub fn ShapeWord(shape: Shape) -> &'static str {
+  Text(match shape {
+    Shape::Circle => Message::Circle,
+    Shape::Square => Message::Square,
+    Shape::Letter => Message::Letter,
+    Shape::Dot => Message::Dot,
+  })
+}
+
+// Trait for errors that can describe themselves in the current language
+pub trait Localized {
+  fn Localized(&self) -> String;
+}
+
+impl Localized for MoveError {
+  fn Localized(&self) -> String {
+    Text(match self {
+      MoveError::InvalidColumn { Given, Max } => {
+        return Say(Message::InvalidColumn, &[("column", &Given.saturating_add(1)), ("columns", &(Max + 1))]);
+      }
+      MoveError::ColumnFull { Column } => return Say(Message::ColumnFull, &[("column", &(Column + 1))]),
+      MoveError::GameFinished => Message::GameFinished,
+      MoveError::PopOutDisabled => Message::PopOutDisabled,
+      MoveError::CannotPop => Message::CannotPop,
+      MoveError::GravityOn => Message::GravityOn,
+      MoveError::GravityOff => Message::GravityOff,
+      MoveError::InvalidRow => Message::InvalidRow,
+      MoveError::CellOccupied => Message::CellOccupied,
+      MoveError::BombsDisabled => Message::BombsDisabled,
+      MoveError::NoBombsLeft => Message::NoBombsLeft,
+    })
+    .to_string()
+  }
+}
+
+impl Localized for UndoError {
+  fn Localized(&self) -> String {
+    Text(match self {
+      UndoError::NoMoves => Message::NoMovesToUndo,
+      UndoError::OutOfTime => Message::LossOnTimeStands,
+    })
+    .to_string()
+  }
+}
+
+impl Localized for RedoError {
+  fn Localized(&self) -> String {
+    Text(match self {
+      RedoError::NothingToRedo => Message::NoMovesToRedo,
+      RedoError::GameFinished => Message::GameFinished,
+    })
+    .to_string()
+  }
+}
+
+impl Localized for DrawError {
+  fn Localized(&self) -> String {
+    Text(match self {
+      DrawError::GameFinished => Message::GameFinished,
+      DrawError::TwoPlayersOnly => Message::DrawTwoPlayersOnly,
+      DrawError::NotYourTurn => Message::DrawNotYourTurn,
+      DrawError::AlreadyOffered => Message::DrawAlreadyOffered,
+      DrawError::TooSoon => Message::DrawTooSoon,
+      DrawError::NoOffer => Message::NoDrawOffered,
+    })
+    .to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Without --lang or a German locale messages are in English, and say what Display says
+  #[test]
+  fn MoveErrorsInEnglishMatchTheirDisplay() {
+    let errors = [
+      MoveError::GameFinished,
+      MoveError::InvalidColumn { Given: 7, Max: 6 },
+      MoveError::InvalidColumn { Given: usize::MAX, Max: 8 },
+      MoveError::ColumnFull { Column: 2 },
+      MoveError::PopOutDisabled,
+      MoveError::CannotPop,
+      MoveError::GravityOn,
+      MoveError::GravityOff,
+      MoveError::InvalidRow,
+      MoveError::CellOccupied,
+      MoveError::BombsDisabled,
+      MoveError::NoBombsLeft,
+    ];
+    for err in errors {
+      assert_eq!(err.Localized(), err.to_string());
+    }
+  }
+}
@@ -0,0 +1,104 @@
+use std::fmt::Arguments;
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+// Enum representing how much is logged, each level including the ones before it
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum Level {
+  Off = 0,   // Nothing, the default
+  Info = 1,  // Games started and finished, moves made
+  Debug = 2, // Summaries of the engines' searches, rejected moves
+  Trace = 3, // Every line and key read
+}
+
+impl Level {
+  // Reads a level by name, as in CONNECTFOUR_LOG=debug
+  pub fn FromName(name: &str) -> Option<Level> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "off" => Some(Level::Off),
+      "info" => Some(Level::Info),
+      "debug" => Some(Level::Debug),
+      "trace" => Some(Level::Trace),
+      _ => None,
+    }
+  }
+
+  // Name written in front of each line
+  fn Name(self) -> &'static str {
+    match self {
+      Level::Off => "OFF",
+      Level::Info => "INFO",
+      Level::Debug => "DEBUG",
+      Level::Trace => "TRACE",
+    }
+  }
+}
+
+// Highest level written; checked before anything is formatted, so logging costs next to
+// nothing while it's off, even in the search
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Off as u8);
+
+// The file from `--log-file`; without one lines go to stderr, so they never mix with the
+// board drawn on stdout
+static FILE: Mutex<Option<File>> = Mutex::new(None);
+
+// When logging started, for the time in front of each line
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+// Starts logging up to the level, to the file if one is given. The file is added to, so the
+// logs of several runs can be kept together.
+pub fn Init(level: Level, path: Option<&str>) -> Result<(), String> {
+  if let Some(path) = path {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("couldn't open the log file {}: {}", path, err))?;
+    *FILE.lock().expect("log file lock") = Some(file);
+  }
+  STARTED.get_or_init(Instant::now);
+  LEVEL.store(level as u8, Ordering::Relaxed);
+  Ok(())
+}
+
+// Returns true if messages at the level are written
+pub fn Enabled(level: Level) -> bool {
+  level != Level::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+// Logs what happened to the game: a game started or finished, a move made
+pub fn Info(message: Arguments) {
+  Write(Level::Info, message);
+}
+
+// Logs details useful when something looks wrong, such as what an engine searched
+pub fn Debug(message: Arguments) {
+  Write(Level::Debug, message);
+}
+
+// Logs everything, down to each line and key read
+pub fn Trace(message: Arguments) {
+  Write(Level::Trace, message);
+}
+
+// Writes one line, with the seconds since logging started and its level in front
+fn Write(level: Level, message: Arguments) {
+  if !Enabled(level) {
+    return;
+  }
+  let elapsed = STARTED.get().map_or(0.0, |started| started.elapsed().as_secs_f64());
+  let line = format!("[{:>9.3} {:<5}] {}\n", elapsed, level.Name(), message);
+  // A log that can't be written is dropped rather than getting in the way of the game
+  match FILE.lock().expect("log file lock").as_mut() {
+    Some(file) => {
+      let _ = file.write_all(line.as_bytes());
+    }
+    None => {
+      let _ = std::io::stderr().write_all(line.as_bytes());
+    }
+  }
+}
@@ -1,239 +1,3208 @@
 #![allow(non_snake_case)] // Disable the snake_case warning, PascalCase FTW!
-use std::io;
+mod cli;
+mod config;
+mod controller;
+mod csv;
+mod evalbar;
+mod events;
+mod http;
+mod input;
+mod interrupt;
+mod locale;
+mod net;
+mod protocol;
+mod render;
+mod server;
+mod terminal;
+mod theme;
+mod tournament;
+mod tui;
+mod websocket;
 
-// Constants defining the board dimensions
-const BOARD_WIDTH: usize = 7;
-const BOARD_HEIGHT: usize = 6;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
-// ANSI color codes for styling terminal output
-const RESET: &str = "\x1b[0m";
-const RED: &str = "\x1b[31m";
-const YELLOW: &str = "\x1b[33m";
+use ConnectFour::analysis::{self, Better, ScoreKind};
+use ConnectFour::archive;
+use ConnectFour::book::Book;
+use ConnectFour::bot::Bot;
+use ConnectFour::clock;
+use ConnectFour::difficulty::Difficulty;
+use ConnectFour::engine::{self, Engine};
+use ConnectFour::log::{self, Level};
+use ConnectFour::mcts::Mcts;
+use ConnectFour::observer::{self, ObservedGame};
+use ConnectFour::perft;
+use ConnectFour::random::RandomBot;
+use ConnectFour::rating;
+use ConnectFour::rng::Rng;
+use ConnectFour::save;
+use ConnectFour::solver::{Outcome, Solver, DEFAULT_TABLE_MB, SOLVE_FROM_MOVE};
+use ConnectFour::stats;
+use ConnectFour::transcript;
+use ConnectFour::transposition::TranspositionTable;
+use controller::{BotController, HumanController, PlayerAction, PlayerController, RemoteController};
+use evalbar::EvalBar;
+use input::{Command, InputError};
+use interrupt::Wait;
+use render::{GameEvent, Renderer};
+use locale::{Localized, Message};
+use theme::{Mark, BLOCK_HEIGHT, BLOCK_WIDTH};
+use ConnectFour::{Board, ConfigError, Game, Move, MoveOutcome, MoveResult, Player, BOARD_HEIGHT, BOARD_WIDTH, MAX_PLAYERS, WIN_LENGTH};
 
-// Type alias for the game board
-type Board = [[u8; BOARD_WIDTH]; BOARD_HEIGHT];
+// Longest player name, so names fit in the board header
+const MAX_NAME_LENGTH: usize = 16;
 
-// Enum representing the players and an empty cell
+// Frames of the victory animation, alternating between the winning line picked out and not,
+// and how long each is shown
+const ANIMATION_FRAMES: usize = 8;
+const ANIMATION_FRAME_TIME: Duration = Duration::from_millis(200);
+
+// How often the spinner turns while the computer thinks
+const SPINNER_FRAME_TIME: Duration = Duration::from_millis(100);
+
+// Lines the big layout needs besides the board: headings, the column labels, rules, the
+// result and the prompt
+const BIG_EXTRA_LINES: usize = 14;
+
+// The seed for this run, and the generator every other seed is drawn from; see NextSeed
+static SEED: OnceLock<u64> = OnceLock::new();
+static RANDOM: OnceLock<Mutex<Rng>> = OnceLock::new();
+
+// Struct holding the game being played and who is playing it
+struct Session {
+  Game: ObservedGame,              // The game in progress, telling Events what happens in it
+  Events: Receiver<observer::Event>, // What the game's observer was told that hasn't been shown yet
+  Bot: Option<Box<dyn Engine>>,    // Computer opponent playing Player Two, if any
+  Difficulty: Option<Difficulty>, // Named level of the computer opponent, if one was chosen
+  HintLimit: u32,                  // Hints each player may ask for per game
+  HintsUsed: [u32; MAX_PLAYERS],   // Hints asked for so far by each player
+  Eval: Option<EvalBar>,           // Live evaluation shown under the board, when switched on
+  Threats: bool,                   // Whether winning cells for both sides are marked on the board
+  Competitive: bool,               // Serious game: no hints or teaching warnings
+  Teaching: [bool; MAX_PLAYERS],   // Players who get warned about threats before they move
+  Preview: bool,                   // Whether a dropped piece is shown where it lands before it's confirmed
+  Ghost: Option<(usize, usize)>,   // Cell of the piece being previewed
+  Letters: bool,                   // Whether columns are labelled a, b, c... instead of numbers
+  TakebackLimit: u32,              // Takebacks each player may ask for per game
+  TakebacksUsed: [u32; MAX_PLAYERS], // Takebacks granted so far to each player
+  MoveTime: Option<Duration>,      // Time each player has to enter a move, if moves are timed
+  MoveClock: Option<(usize, Instant)>, // Move number the move timer is running for, and when it runs out
+  StatsRecorded: bool,             // Whether the finished game has been counted in the statistics
+  Ratings: Option<(u64, u64)>,     // Both players' ratings as the game began, if they're rated
+  Names: [String; MAX_PLAYERS],    // What each player is called
+  Celebrated: bool,                // Whether the victory animation has been shown for this game
+  HideWinning: bool,               // Whether the winning line is drawn plainly, for the animation
+  FallingRow: Option<usize>,       // Row the last piece dropped is drawn in while it falls
+  Remote: Option<net::Remote>,     // The other end of a network game, if this is one
+  Renderer: Box<dyn Renderer>,     // How the game is shown, chosen when the session starts
+}
+
+impl Session {
+  fn new(game: Game, bot: Option<Box<dyn Engine>>, difficulty: Option<Difficulty>) -> Session {
+    let (told, events) = mpsc::channel();
+    let mut session = Session {
+      Bot: bot,
+      Difficulty: difficulty,
+      HintLimit: ArgValue("--hints").and_then(|value| value.parse().ok()).unwrap_or(3),
+      HintsUsed: [0; MAX_PLAYERS],
+      Eval: (engine::Supports(&game) && Flag("--eval")).then(EvalBar::new),
+      Threats: Flag("--threats"),
+      Competitive: Flag("--competitive"),
+      Teaching: [Flag("--teach"); MAX_PLAYERS],
+      Preview: Flag("--preview"),
+      Ghost: None,
+      Letters: Flag("--letters"),
+      TakebackLimit: ArgValue("--takebacks").and_then(|value| value.parse().ok()).unwrap_or(3),
+      TakebacksUsed: [0; MAX_PLAYERS],
+      MoveTime: ArgValue("--move-time")
+          .and_then(|value| value.parse().ok())
+          .filter(|&seconds| seconds > 0)
+          .map(Duration::from_secs),
+      MoveClock: None,
+      // A game that was already over when loaded was counted when it was played
+      StatsRecorded: game.IsFinished(),
+      Ratings: None,
+      Names: [Message::PlayerOne, Message::PlayerTwo, Message::PlayerThree].map(|name| locale::Text(name).to_string()),
+      Celebrated: game.IsFinished(),
+      HideWinning: false,
+      FallingRow: None,
+      Remote: None,
+      Renderer: render::Chosen(),
+      Game: ObservedGame::new(game),
+      Events: events,
+    };
+    session.Game.Subscribe(move |event| {
+      let _ = told.send(event.clone());
+    });
+    if let Some(bot) = &session.Bot {
+      session.Names[1] = match session.Difficulty {
+        Some(difficulty) => locale::Say(Message::ComputerLevel, &[("level", &difficulty.Name())]),
+        None => bot.Name(),
+      };
+    }
+    for (index, flag) in ["--p1", "--p2", "--p3"].iter().enumerate() {
+      if let Some(name) = ArgValue(flag).and_then(|name| CleanName(&name)) {
+        session.Names[index] = name;
+      }
+    }
+    session.Ratings = Ratings(&session);
+    session
+  }
+
+  // What the player is called
+  fn Name(&self, player: Player) -> &str {
+    match player {
+      Player::None => locale::Text(Message::Nobody),
+      player => &self.Names[player as usize - 1],
+    }
+  }
+
+  // Starts a fresh game with the same players and settings
+  fn Restart(&mut self) {
+    self.Game.Reset();
+    self.HintsUsed = [0; MAX_PLAYERS];
+    self.TakebacksUsed = [0; MAX_PLAYERS];
+    self.MoveClock = None;
+    self.StatsRecorded = false;
+    self.Celebrated = false;
+    self.Ratings = Ratings(self);
+    self.LogStart();
+  }
+
+  // Logs who is playing what, as a game starts
+  fn LogStart(&self) {
+    let game = &self.Game;
+    let names: Vec<&str> = (1..=game.Players()).map(|number| self.Name(Player::FromInt(number as u8))).collect();
+    log::Info(format_args!(
+      "Game started on a {}x{} board, {} in a row{}: {} from move {}",
+      game.Width(),
+      game.Height(),
+      game.WinLength(),
+      VariantNames(game),
+      names.join(" against "),
+      game.CurrentMove()
+    ));
+  }
+
+  // Logs how the game ended
+  fn LogResult(&self) {
+    let game = &self.Game;
+    match game.Winner() {
+      Player::None => log::Info(format_args!("Game finished in a draw after {} moves", game.CurrentMove())),
+      winner => log::Info(format_args!("Game finished: {} won after {} moves", self.Name(winner), game.CurrentMove())),
+    }
+  }
+}
+
+// Clears the screen, or without colors or with `--no-clear` just leaves a blank line. The
+// accessible mode leaves what's been said where it is.
+fn ClearScreen() {
+  if Accessible() {
+    return;
+  }
+  match theme::Current().Color() && !NoClear() {
+    true => print!("{}", theme::Current().ClearScreen()),
+    false => println!(),
+  }
+}
+
+// Returns true if `--no-clear` asks for every board to be drawn below the last, keeping the
+// scrollback to look back through or a log of the whole game when the output is saved
+fn NoClear() -> bool {
+  Flag("--no-clear")
+}
+
+// Enum representing how large the board is drawn
 #[derive(Clone, Copy, Debug, PartialEq)]
-#[repr(u8)]
-enum Player {
-  One = 1,  // Player One
-  Two = 2,  // Player Two
-  None = 0, // No player (empty cell)
+enum Layout {
+  Compact, // One character per cell with nothing between them, for narrow terminals
+  Wide,    // Two characters per cell with a space between them, the usual layout
+  Big,     // Blocks of BLOCK_WIDTH by BLOCK_HEIGHT with a gap around them, for projectors
+}
+
+impl Layout {
+  // Characters across each cell and between cells
+  fn CellWidth(self) -> usize {
+    match self {
+      Layout::Compact => 1,
+      Layout::Wide => 2,
+      Layout::Big => BLOCK_WIDTH,
+    }
+  }
+
+  fn Gap(self) -> &'static str {
+    match self {
+      Layout::Compact => "",
+      Layout::Wide | Layout::Big => " ",
+    }
+  }
+
+  // What goes either side of each line of cells: a cylinder's edge markers or the frame
+  fn Edges(self, wrap: bool) -> (&'static str, &'static str) {
+    match (self, wrap, theme::Current().Framed()) {
+      (Layout::Compact, true, _) => ("~", "~"),
+      (Layout::Compact, false, true) => ("|", "|"),
+      (Layout::Wide, false, true) => ("|", " |"),
+      (Layout::Big, false, true) => ("| ", " |"),
+      (_, true, _) => ("~ ", " ~"),
+      (_, false, false) => ("", ""),
+    }
+  }
+
+  // Characters the cells of a row take up across
+  fn CellsWidth(self, width: usize) -> usize {
+    width * self.CellWidth() + (width - 1) * self.Gap().len()
+  }
+
+  // Characters the board takes up across, edges included
+  fn BoardWidth(self, width: usize, wrap: bool) -> usize {
+    let (left, right) = self.Edges(wrap);
+    self.CellsWidth(width) + left.len() + right.len()
+  }
+
+  // Lines the board's cells take up, with a blank line between rows of blocks
+  fn BoardLines(self, height: usize) -> usize {
+    match self {
+      Layout::Big => height * (BLOCK_HEIGHT + 1) - 1,
+      Layout::Compact | Layout::Wide => height,
+    }
+  }
+}
+
+// The layout for a board of this size: big if `--big` asks and it fits the terminal, compact
+// if `--compact` asks or the usual layout wouldn't fit, and the usual one otherwise. Checked
+// on every redraw, so resizing the terminal switches between them.
+fn CurrentLayout(width: usize, height: usize, wrap: bool) -> Layout {
+  if Flag("--big") && BigFits(width, height, wrap).is_ok() {
+    Layout::Big
+  } else if Flag("--compact")
+      || terminal::Width().is_some_and(|columns| columns < Layout::Wide.BoardWidth(width, wrap))
+  {
+    Layout::Compact
+  } else {
+    Layout::Wide
+  }
+}
+
+// Checks the big layout fits the terminal, as far as its size is known, leaving room for the
+// headings and prompts around the board. The error says how much room it needs.
+fn BigFits(width: usize, height: usize, wrap: bool) -> Result<(), String> {
+  let columns = Layout::Big.BoardWidth(width, wrap);
+  let lines = Layout::Big.BoardLines(height) + BIG_EXTRA_LINES;
+  let narrow = terminal::Width().is_some_and(|have| have < columns);
+  let short = terminal::Height().is_some_and(|have| have < lines);
+  match narrow || short {
+    true => Err(locale::Say(
+      Message::BigTooSmall,
+      &[
+        ("columns", &columns),
+        ("lines", &lines),
+        ("width", &terminal::Width().map_or("?".to_string(), |have| have.to_string())),
+        ("height", &terminal::Height().map_or("?".to_string(), |have| have.to_string())),
+      ],
+    )),
+    false => Ok(()),
+  }
+}
+
+// The game's layout right now
+fn GameLayout(game: &Game) -> Layout {
+  CurrentLayout(game.Width(), game.Height(), game.Wrap())
+}
+
+// Horizontal rule as wide as the board, with the edge markers of a cylinder. Framed boards get
+// +----+ style corners, and big ones a double rule.
+fn Separator(game: &Game) -> String {
+  let layout = GameLayout(game);
+  let total = layout.BoardWidth(game.Width(), game.Wrap());
+  let dash = if layout == Layout::Big { "=" } else { "-" };
+  match theme::Current().Framed() {
+    true => format!("{}+{}+{}", Accent(), dash.repeat(total - 2), Reset()),
+    false => format!("{}{}{}", Accent(), dash.repeat(total), Reset()),
+  }
+}
+
+// The text centered across the big board, or as it is in the other layouts
+fn Centered(game: &Game, text: &str) -> String {
+  let layout = GameLayout(game);
+  match layout {
+    Layout::Big => {
+      let total = layout.BoardWidth(game.Width(), game.Wrap());
+      format!("{:^total$}", text).trim_end().to_string()
+    }
+    Layout::Compact | Layout::Wide => text.to_string(),
+  }
+}
+
+// Names of the rule variants in play, for the board header, e.g. " PopOut, cylinder"
+fn VariantNames(game: &Game) -> String {
+  let variants = [
+    (game.PopOut(), "PopOut"),
+    (!game.Gravity(), "no gravity"),
+    (game.Wrap(), "cylinder"),
+    (game.HasBombs(), "bombs"),
+  ];
+  let names: Vec<&str> = variants.iter().filter(|(on, _)| *on).map(|&(_, name)| name).collect();
+  if names.is_empty() {
+    String::new()
+  } else {
+    format!(" {}", names.join(", "))
+  }
+}
+
+// Cells drawn differently from their plain piece
+#[derive(Default)]
+struct Overlay {
+  Last: Option<(usize, usize)>, // The last piece played, in reverse video
+  Winning: Vec<(usize, usize)>, // Cells of the winning line, blinking
+  Wins: Vec<(usize, usize)>,    // Empty cells that win at once for the player on turn
+  Dangers: Vec<(usize, usize)>, // Empty cells where the next player would win
+  Ghost: Option<(usize, usize)>, // Where a previewed piece would land
+  // Where a dropped piece is drawn on its way down, and the cell it lands in, which is drawn
+  // empty until it gets there
+  Falling: Option<((usize, usize), (usize, usize))>,
+}
+
+// The cells of a board as lines of text, with edge markers when lines wrap around and the
+// overlay on top
+fn RenderRows(board: &Board, wrap: bool, overlay: &Overlay) -> String {
+  let mut text = String::new();
+  let theme = theme::Current();
+  let layout = CurrentLayout(board.Width(), board.Height(), wrap);
+  let (left, right) = layout.Edges(wrap);
+  let edge = |text: &str| match text.is_empty() {
+    true => String::new(),
+    false => format!("{}{}{}", Accent(), text, Reset()),
+  };
+  for (RowIndex, row) in board.Rows().enumerate() {
+    // Each cell as its lines, one line unless the board is big
+    let cells: Vec<Vec<String>> = row
+        .iter()
+        .enumerate()
+        .map(|(column, &piece)| {
+          let cell = (RowIndex, column);
+          let player = match overlay.Falling {
+            Some((_, landed)) if landed == cell => Player::None,
+            Some((falling, (row, column))) if falling == cell => Player::FromInt(board[row][column]),
+            _ => Player::FromInt(piece),
+          };
+          let mark = [(Mark::Ghost, overlay.Ghost == Some(cell)), (Mark::Win, overlay.Wins.contains(&cell)), (Mark::Danger, overlay.Dangers.contains(&cell))]
+              .into_iter()
+              .find_map(|(mark, shown)| shown.then_some(mark));
+          let highlight = if overlay.Winning.contains(&cell) {
+            Some(true)
+          } else if overlay.Last == Some(cell) {
+            Some(false)
+          } else {
+            None
+          };
+          match (layout, mark, highlight) {
+            (Layout::Big, Some(mark), _) => theme.Block(Player::None, Some(theme.CompactMark(mark))),
+            // Without colors the block is already filled with the letter
+            (Layout::Big, None, Some(blink)) => {
+              let (open, close) = if blink { ("*", "*") } else { ("<", ">") };
+              let letter = if theme.Color() { theme.Letter(player).to_string() } else { String::new() };
+              theme.Block(player, Some(&format!("{}{}{}", open, letter, close)))
+            }
+            (Layout::Big, None, None) => theme.Block(player, None),
+            (Layout::Compact, Some(mark), _) => vec![theme.CompactMark(mark).to_string()],
+            (Layout::Wide, Some(mark), _) => vec![theme.Mark(mark).to_string()],
+            (Layout::Compact, None, highlight) => vec![Highlighted(theme.CompactCell(player), highlight)],
+            (Layout::Wide, None, highlight) => vec![Highlighted(theme.Cell(player), highlight)],
+          }
+        })
+        .collect();
+    if RowIndex > 0 && layout == Layout::Big {
+      text += &format!("{}{}{}\n", edge(left), " ".repeat(layout.CellsWidth(board.Width())), edge(right));
+    }
+    for line in 0..cells[0].len() {
+      let line: Vec<&str> = cells.iter().map(|cell| cell[line].as_str()).collect();
+      text += &format!("{}{}{}\n", edge(left), line.join(layout.Gap()), edge(right));
+    }
+  }
+  text
+}
+
+// The cell highlighted as part of the winning line if `Some(true)`, as the last move if
+// `Some(false)`, or as it is
+fn Highlighted(cell: String, highlight: Option<bool>) -> String {
+  match highlight {
+    Some(blink) => theme::Current().Highlight(&cell, blink),
+    None => cell,
+  }
+}
+
+// Displays the game board and game state the way the session's renderer shows them
+fn DisplayBoard(session: &Session) {
+  session.Renderer.DrawBoard(session);
+}
+
+// Chat from the last couple of moves, which stays above the board for the one it was said to
+fn RecentChat(session: &Session) -> Vec<&net::Said> {
+  let moves = session.Game.History().len();
+  match &session.Remote {
+    Some(remote) => remote.Chat.iter().filter(|said| said.Move + 1 >= moves).collect(),
+    None => Vec::new(),
+  }
+}
+
+// The game board and game state as drawn on the screen, one line after another
+fn RenderBoard(session: &Session) -> String {
+  let game = &session.Game;
+  let mut text = String::new();
+  for said in RecentChat(session) {
+    text += &format!("{}\n", SaidLine(&session.Names, said));
+  }
+  if NoClear() {
+    // Where one board ends and the next begins when scrolling back
+    let rule = format!("=== {} ===", locale::Say(Message::MoveRule, &[("move", &game.CurrentMove())]));
+    text += &format!("{}{}{}\n", Accent(), rule, Reset());
+  }
+  text += &format!("{}\n", Separator(game));
+  match GameLayout(game) {
+    Layout::Compact => text += &format!("{}C{} #{}{}\n", Accent(), game.WinLength(), game.CurrentMove(), Reset()),
+    _ => {
+      let title = locale::Say(
+        Message::Title,
+        &[("length", &game.WinLength()), ("variants", &VariantNames(game)), ("move", &game.CurrentMove())],
+      );
+      text += &format!("{}{}{}\n", Accent(), Centered(game, &title), Reset());
+    }
+  }
+  if let Some(line) = ClockLine(session, Duration::ZERO) {
+    text += &format!("{}\n", line);
+  }
+  if let Some(difficulty) = session.Difficulty {
+    text += &format!("{}{}{}\n", Accent(), locale::Say(Message::ComputerIs, &[("level", &difficulty.Name())]), Reset());
+  }
+  let spectators = session.Remote.as_ref().map_or(0, net::Remote::Spectators);
+  if spectators > 0 {
+    text += &format!("{}{}{}\n", Accent(), locale::Say(Message::Spectators, &[("count", &spectators)]), Reset());
+  }
+  let ratings = session.Ratings.filter(|_| game.History().is_empty() && !game.IsFinished());
+  if let Some((one, two)) = ratings {
+    let expected = rating::ExpectedScore(one, two);
+    let ratings = locale::Say(
+      Message::Ratings,
+      &[
+        ("one", &Participant(session, Player::One).Name),
+        ("rating1", &one),
+        ("two", &Participant(session, Player::Two).Name),
+        ("rating2", &two),
+        ("score1", &format!("{:.2}", expected)),
+        ("score2", &format!("{:.2}", 1.0 - expected)),
+      ],
+    );
+    text += &format!("{}{}{}\n", Accent(), ratings, Reset());
+  }
+  if let Some(handicap) = game.Handicap() {
+    let handicap = locale::Say(
+      Message::Handicap,
+      &[("pieces", &handicap.Pieces), ("color", &ColorName(handicap.Player)), ("seed", &handicap.Seed)],
+    );
+    text += &format!("{}{}{}\n", Accent(), handicap, Reset());
+  }
+  text += &format!("{}\n", Separator(game));
+  let falling = session.FallingRow.zip(game.LastPlaced()).map(|(row, landed)| ((row, landed.1), landed));
+  let mut overlay = Overlay {
+    Last: if falling.is_some() { None } else { game.LastPlaced() },
+    Falling: falling,
+    Winning: if session.HideWinning { Vec::new() } else { game.WinningLine() },
+    Ghost: session.Ghost,
+    ..Overlay::default()
+  };
+  if session.Threats {
+    overlay.Wins = game.ThreatCells(game.CurrentPlayer());
+    overlay.Dangers = game.ThreatCells(game.CurrentPlayer().Next(game.Players()));
+  }
+  let layout = GameLayout(game);
+  text += &format!("{}\n", ColumnHeader(game, UseLetters(session)));
+  text += &RenderRows(game.Board(), game.Wrap(), &overlay);
+  if layout == Layout::Big {
+    // Labels below as well, where they can be read from the back of the room
+    text += &format!("{}\n", ColumnHeader(game, UseLetters(session)));
+  }
+  text += &format!("{}\n", Separator(game));
+  if let Some(eval) = &session.Eval {
+    // Separators, title, optional clock, difficulty and handicap lines, the column labels and
+    // the board come before it
+    let optional = [
+      game.Clock().is_some(),
+      session.Difficulty.is_some(),
+      spectators > 0,
+      ratings.is_some(),
+      game.Handicap().is_some(),
+      layout == Layout::Big,
+    ];
+    let row = 6 + optional.iter().filter(|&&shown| shown).count() + layout.BoardLines(game.Height());
+    text += &format!("{}\n", eval.Text(game, row));
+  }
+  if session.Threats && !game.IsFinished() {
+    let legend = locale::Say(
+      Message::ThreatLegend,
+      &[
+        ("win", &theme::Current().Mark(Mark::Win).trim_start()),
+        ("player", &ColorName(game.CurrentPlayer())),
+        ("danger", &theme::Current().Mark(Mark::Danger).trim_start()),
+        ("next", &ColorName(game.CurrentPlayer().Next(game.Players()))),
+      ],
+    );
+    text += &format!("{}\n", legend);
+  }
+  if game.IsFinished() {
+    if let Some(loser) = game.Forfeited() {
+      let forfeit = match session.Remote.as_ref().is_some_and(|remote| remote.Abandoned) {
+        true => locale::Say(Message::ForfeitsByLeaving, &[("name", &session.Name(loser))]),
+        false => locale::Say(Message::ForfeitsOnTime, &[("name", &session.Name(loser))]),
+      };
+      text += &format!("{}{}{}{}\n", Alert(), theme::Current().Mark(Mark::Timeout), forfeit, Reset());
+    }
+    match game.Winner() {
+      Player::None if game.IsDrawAgreed() => {
+        text += &format!("{}{}{}{}\n", Accent(), theme::Current().Mark(Mark::Agreement), locale::Text(Message::DrawByAgreement), Reset());
+      }
+      Player::None => text += &format!("{}{}{}\n", Accent(), locale::Text(Message::ItsADraw), Reset()),
+      winner => {
+        let banner = locale::Say(Message::Wins, &[("name", &session.Name(winner))]);
+        match GameLayout(game) {
+          Layout::Compact => text += &format!("{} {}{}{}\n", theme::Current().CompactCell(winner), Accent(), banner, Reset()),
+          Layout::Wide => text += &format!("{}{} {}{}\n", theme::Current().Cell(winner).trim_start(), Accent(), banner, Reset()),
+          Layout::Big => {
+            let banner = format!("***  {}  ***", banner.to_uppercase());
+            text += &format!("{}{}{}\n", Accent(), Centered(game, &banner), Reset());
+          }
+        }
+      }
+    }
+  }
+  text += &format!("{}\n", Separator(game));
+  text
+}
+
+// Line of the board header with the time control and each player's time left, `elapsed`
+// being how long the player on turn has been thinking. None for untimed games.
+fn ClockLine(session: &Session, elapsed: Duration) -> Option<String> {
+  let game = &session.Game;
+  let clock = game.Clock()?;
+  let times: Vec<String> = (1..=game.Players())
+      .map(|number| {
+        let player = Player::FromInt(number as u8);
+        let running = player == game.CurrentPlayer() && !game.IsFinished();
+        let left = match running {
+          true => clock.Remaining(player).saturating_sub(elapsed),
+          false => clock.Remaining(player),
+        };
+        let time = format!("{} {}", session.Name(player), clock::Format(left));
+        match (running, theme::Current().Color()) {
+          (true, true) => format!("{}{}{}{}", theme::Current().Reverse(), time, Reset(), Accent()),
+          (true, false) => format!("[{}]", time),
+          (false, _) => time,
+        }
+      })
+      .collect();
+  Some(format!("{}{}{}   {}{}", Accent(), theme::Current().Mark(Mark::Clock), clock.Control(), times.join("   "), Reset()))
+}
+
+// Keeps the clock in the board header counting down while the player on turn thinks, if the
+// board was drawn on a cleared screen where the line can be found again
+fn TickClock(session: &Session, started: Instant, shown: &mut String) {
+  if !theme::Current().Color() || !std::io::stdout().is_terminal() || NoClear() {
+    return;
+  }
+  // The clock comes after the rule and the title
+  let Some(line) = ClockLine(session, started.elapsed()) else { return };
+  if line != *shown {
+    print!("{}", terminal::PaintLine(3, &line));
+    let _ = std::io::stdout().flush();
+    *shown = line;
+  }
+}
+
+// Displays an error message, along with the current board state where the renderer redraws it
+fn DisplayError(session: &Session, error: String) {
+  session.Renderer.ShowError(session, &error);
+}
+
+// An error message as shown below the board
+fn RenderError(error: &str) -> String {
+  format!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset())
+}
+
+// Draws the board with the theme and pieces chosen on the command line, if they can be told
+// apart in this game. Returns false after reporting the problem if not.
+fn InitTheme(game: &Game) -> bool {
+  match ChosenTheme(game, None) {
+    Ok(chosen) => {
+      theme::Set(chosen);
+      true
+    }
+    Err(err) => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      false
+    }
+  }
+}
+
+// The named theme, or the one from `--theme` (`--colorblind` being short for
+// `--theme colorblind`), adjusted by the other display flags
+fn ChosenTheme(game: &Game, name: Option<&str>) -> Result<theme::Theme, String> {
+  let name = match name {
+    Some(name) => name.to_string(),
+    None if Flag("--colorblind") => "colorblind".to_string(),
+    None => ArgValue("--theme").unwrap_or_else(|| "classic".to_string()),
+  };
+  let base = theme::Theme::Named(&name)?;
+  // Terminals set to a non-UTF-8 locale, or without emoji in their font, get ASCII unless
+  // `--unicode` says they cope
+  let unicode = Flag("--unicode");
+  let ascii = base.Ascii()
+      || Flag("--ascii")
+      || ((theme::NonUtf8Locale() || !terminal::Emoji()) && !unicode);
+  let plain = Accessible() || Flag("--plain");
+  let base = base.WithAscii(ascii || plain).WithColor(theme::ColorWanted() && !plain);
+  theme::Theme::FromArgs(base, game.Players(), ArgValue)
+}
+
+// Switches to the named theme and redraws the board with it, or lists the themes if no name
+// is given
+fn SwitchTheme(session: &Session, name: &str) {
+  if name.is_empty() {
+    DisplayBoard(session);
+    println!("{}", locale::Say(Message::Themes, &[("themes", &theme::ThemeNames().join(", "))]));
+    return;
+  }
+  match ChosenTheme(&session.Game, Some(name)) {
+    Ok(chosen) => {
+      theme::Set(chosen);
+      DisplayBoard(session);
+    }
+    Err(err) => DisplayError(session, err),
+  }
+}
+
+// Color for headings and banners, from the theme
+fn Accent() -> &'static str {
+  theme::Current().Accent()
+}
+
+// Color for errors and warnings, from the theme
+fn Alert() -> &'static str {
+  theme::Current().Alert()
+}
+
+// Ends a color, unless colors are off
+fn Reset() -> &'static str {
+  theme::Current().Reset()
+}
+
+// When the player on turn runs out of time, starting their clock if this is a new turn
+fn Deadline(session: &mut Session) -> Option<Instant> {
+  let limit = session.MoveTime?;
+  let turn = session.Game.CurrentMove();
+  match session.MoveClock {
+    Some((running, deadline)) if running == turn => Some(deadline),
+    _ => {
+      let deadline = Instant::now() + limit;
+      session.MoveClock = Some((turn, deadline));
+      Some(deadline)
+    }
+  }
+}
+
+// Color of a player's pieces, used to name them
+fn ColorName(player: Player) -> String {
+  theme::Current().Name(player)
+}
+
+// Returns true if `--accessible` asks for output a screen reader can follow: plain sentences,
+// no colors, grid, rules, banners or animations, and a screen that's never cleared
+fn Accessible() -> bool {
+  Flag("--accessible")
+}
+
+// Describes the board in words, a row per line from the top, e.g. "Row 6: empty, red,
+// empty"
+fn DescribeBoard(game: &Game) {
+  for (index, row) in game.Board().Rows().enumerate() {
+    let cells: Vec<String> = row
+        .iter()
+        .map(|&piece| match Player::FromInt(piece) {
+          Player::None => locale::Text(Message::EmptyCell).to_string(),
+          player => ColorName(player).to_lowercase(),
+        })
+        .collect();
+    println!("{}", locale::Say(Message::RowInWords, &[("row", &(game.Height() - index)), ("cells", &cells.join(", "))]));
+  }
+}
+
+// Cells as "column 4, row 3" for saying aloud, rows counted from the bottom
+fn CellsInWords(game: &Game, cells: &[(usize, usize)]) -> String {
+  let words: Vec<String> = cells
+      .iter()
+      .map(|&(row, column)| locale::Say(Message::CellInWords, &[("column", &(column + 1)), ("row", &(game.Height() - row))]))
+      .collect();
+  words.join("; ")
+}
+
+// Announces the last move in a sentence, followed by the threats it leaves on the board
+fn AnnounceMove(game: &Game, outcome: &MoveOutcome) {
+  let name = ColorName(outcome.Player);
+  let row = game.Height() - outcome.Row;
+  match game.History().last() {
+    Some(Move::Pop(column)) => println!("{}", locale::Say(Message::SaysPop, &[("color", &name), ("column", &(column + 1))])),
+    Some(Move::Bomb(column)) => {
+      println!("{}", locale::Say(Message::SaysBomb, &[("color", &name), ("column", &(column + 1)), ("row", &row)]))
+    }
+    Some(Move::Place(..)) => {
+      println!("{}", locale::Say(Message::SaysPlace, &[("color", &name), ("column", &(outcome.Column + 1)), ("row", &row)]))
+    }
+    _ => println!("{}", locale::Say(Message::SaysDrop, &[("color", &name), ("column", &(outcome.Column + 1)), ("row", &row)])),
+  }
+  if game.IsFinished() {
+    return;
+  }
+  let next = game.CurrentPlayer();
+  let wins = game.ThreatCells(next);
+  if !wins.is_empty() {
+    println!("{}", locale::Say(Message::SaysCanWin, &[("color", &ColorName(next)), ("cells", &CellsInWords(game, &wins))]));
+  }
+  let after = next.Next(game.Players());
+  let threats = game.ThreatCells(after);
+  if !threats.is_empty() {
+    println!("{}", locale::Say(Message::SaysThreatens, &[("color", &ColorName(after)), ("cells", &CellsInWords(game, &threats))]));
+  }
+}
+
+// Tells the player on turn what's going on and what to type, in the accessible mode
+fn AccessiblePrompt(session: &Session) {
+  let game = &session.Game;
+  let player = game.CurrentPlayer();
+  let mut prompt = locale::Say(
+    Message::SaysTurn,
+    &[("move", &(game.CurrentMove() + 1)), ("name", &session.Name(player)), ("color", &ColorName(player))],
+  );
+  if let Some(clock) = game.Clock() {
+    prompt += &locale::Say(Message::SaysClock, &[("time", &clock::Format(clock.Remaining(player)))]);
+  }
+  if game.BombsLeft(player) > 0 {
+    prompt += &locale::Say(Message::SaysBombs, &[("bombs", &game.BombsLeft(player))]);
+  }
+  match game.Gravity() {
+    true => prompt += &locale::Say(Message::SaysTypeColumn, &[("width", &game.Width())]),
+    false => prompt += locale::Text(Message::SaysTypeCell),
+  }
+  println!("{}", prompt);
+}
+
+// The game's result in a sentence, for the accessible mode
+fn AnnounceResult(session: &Session) {
+  let game = &session.Game;
+  if let Some(loser) = game.Forfeited() {
+    println!("{}", locale::Say(Message::SaysForfeit, &[("name", &session.Name(loser))]));
+  }
+  match game.Winner() {
+    Player::None if game.IsDrawAgreed() => println!("{}", locale::Text(Message::SaysDrawAgreed)),
+    Player::None => println!("{}", locale::Text(Message::SaysDraw)),
+    winner => println!("{}", locale::Say(Message::SaysWins, &[("name", &session.Name(winner)), ("color", &ColorName(winner))])),
+  }
+}
+
+// Describes the move just played
+fn DisplayMove(session: &Session, outcome: &MoveOutcome) {
+  session.Renderer.Announce(session, GameEvent::Moved(outcome));
+}
+
+// Shows what the game's observer was told since it was last shown: each move played or played
+// again, with the computer's name first in `chose` when it picked it, each move turned away,
+// and the new board after a reset. Undos show the board themselves once the whole turn is
+// taken back, and how a finished game is shown is left to the play loop, which stops there.
+fn ShowEvents(session: &mut Session, chose: Option<&str>) {
+  while let Ok(event) = session.Events.try_recv() {
+    match event {
+      observer::Event::MoveMade { Player: player, Column: column, Row: row } => {
+        let outcome = MovedOutcome(&session.Game, player, column, row);
+        LogMove(&session.Game, &outcome);
+        AnimateDrop(session, &outcome);
+        DisplayBoard(session);
+        if let Some(name) = chose {
+          println!("{}", locale::Say(Message::BotChose, &[("name", &name), ("column", &(column + 1))]));
+        }
+        DisplayMove(session, &outcome);
+      }
+      observer::Event::MoveRedone { Player: player, Column: column, Row: row } => {
+        let outcome = MovedOutcome(&session.Game, player, column, row);
+        DisplayBoard(session);
+        DisplayMove(session, &outcome);
+      }
+      observer::Event::MoveRejected { Error: err } => DisplayError(session, err.Localized()),
+      observer::Event::GameReset => DisplayBoard(session),
+      observer::Event::MoveUndone { .. } | observer::Event::GameReopened | observer::Event::GameFinished { .. } => {}
+    }
+  }
+}
+
+// The outcome of the move just made, from what the observer was told and how the game stands
+fn MovedOutcome(game: &Game, player: Player, column: usize, row: usize) -> MoveOutcome {
+  let result = match (game.IsFinished(), game.Winner()) {
+    (false, _) => MoveResult::Continue,
+    (true, Player::None) => MoveResult::Draw,
+    (true, winner) => MoveResult::Win(winner),
+  };
+  MoveOutcome { Row: row, Column: column, Player: player, Result: result }
+}
+
+// Logs the move just played, for `-v`
+fn LogMove(game: &Game, outcome: &MoveOutcome) {
+  let what = match game.History().last() {
+    Some(Move::Pop(_)) => "pops from",
+    Some(Move::Bomb(_)) => "bombs",
+    Some(Move::Place(..)) => "places in",
+    _ => "drops in",
+  };
+  log::Info(format_args!(
+    "Move {}: {:?} {} column {}, row {}; {:?}",
+    game.History().len(),
+    outcome.Player,
+    what,
+    outcome.Column + 1,
+    game.Height() - outcome.Row,
+    outcome.Result
+  ));
+}
+
+// Columns are labelled with letters when asked for and there are enough letters
+fn UseLetters(session: &Session) -> bool {
+  session.Letters && session.Game.Width() <= 26
+}
+
+// Label of a 0-based column: "a".."z" or the 1-based number
+fn ColumnLabel(column: usize, letters: bool) -> String {
+  match letters {
+    true => ((b'a' + column as u8) as char).to_string(),
+    false => (column + 1).to_string(),
+  }
+}
+
+// Row of column labels lined up with the cells: right-aligned over two-character cells,
+// centered on big ones, and in the compact layout one character each, numbers past 9
+// starting again from 0
+fn ColumnHeader(game: &Game, letters: bool) -> String {
+  let layout = GameLayout(game);
+  let labels: Vec<String> = (0..game.Width())
+      .map(|column| match layout {
+        Layout::Compact if !letters => ((column + 1) % 10).to_string(),
+        Layout::Compact => ColumnLabel(column, letters),
+        Layout::Wide => format!("{:>2}", ColumnLabel(column, letters)),
+        Layout::Big => format!("{:^BLOCK_WIDTH$}", ColumnLabel(column, letters)),
+      })
+      .collect();
+  // Room for the cylinder's edge markers or the frame
+  let margin = " ".repeat(layout.Edges(game.Wrap()).0.len());
+  format!("{}{}{}{}", Accent(), margin, labels.join(layout.Gap()), Reset())
+}
+
+// How to type a command in prompts: its shortcut, or its name while letters are columns
+fn Shortcut<'a>(session: &Session, short: &'a str, name: &'a str) -> &'a str {
+  if UseLetters(session) {
+    name
+  } else {
+    short
+  }
+}
+
+// Reads one line from stdin; Ctrl+C while waiting quits
+fn ReadLine() -> String {
+  match input::NextLine(None, &mut || ()) {
+    Ok(line) => line,
+    Err(_) => Quit(),
+  }
+}
+
+// Waits for the next line of input until the deadline, if there is one. `tick` is called
+// every so often while waiting. Once a script runs out the program leaves, as after Ctrl+C.
+fn ReadLineBy(deadline: Option<Instant>, tick: &mut dyn FnMut()) -> Wait {
+  match input::NextLine(deadline, tick) {
+    Ok(line) => Wait::Line(line),
+    Err(InputError::TimedOut) => Wait::TimedOut,
+    Err(_) => Wait::Interrupted,
+  }
+}
+
+// Leaves after Ctrl+C or a confirmed quit with the terminal back to normal. The game in
+// progress is already in the autosave, which is written before every prompt.
+fn Quit() -> ! {
+  let _ = std::io::stdout().flush();
+  terminal::Restore();
+  print!("{}\n\n", Reset());
+  match save::AutosavePath().filter(|path| path.exists()) {
+    Some(path) => println!("{}", locale::Say(Message::SavedBye, &[("path", &path.display())])),
+    None => println!("{}", locale::Text(Message::Bye)),
+  }
+  std::process::exit(0);
+}
+
+// How much to log: `-v`, `-vv` or `-vvv`, or else a level such as CONNECTFOUR_LOG=debug
+fn LogLevel() -> Result<Level, String> {
+  let flags = [("-vvv", Level::Trace), ("-vv", Level::Debug), ("-v", Level::Info)];
+  if let Some(&(_, level)) = flags.iter().find(|(flag, _)| Flag(flag)) {
+    return Ok(level);
+  }
+  match std::env::var("CONNECTFOUR_LOG") {
+    Ok(name) => Level::FromName(&name).ok_or(format!("CONNECTFOUR_LOG is off, info, debug or trace, not '{}'", name)),
+    Err(_) => Ok(Level::Off),
+  }
+}
+
+// Returns the value following a `--name value` command line argument, or else the value from
+// the config file
+fn ArgValue(name: &str) -> Option<String> {
+  let args: Vec<String> = std::env::args().collect();
+  args
+      .iter()
+      .position(|arg| arg == name)
+      .and_then(|index| args.get(index + 1))
+      .cloned()
+      .or_else(|| config::Value(name))
+}
+
+// Returns true if a switch such as `--popout` is on the command line or in the config file
+fn Flag(name: &str) -> bool {
+  std::env::args().any(|arg| arg == name) || config::Switch(name)
+}
+
+// The seed everything random in the program comes from: `--seed N`, or else one drawn from
+// the clock and shown, so the same game can be played again
+fn Seed() -> u64 {
+  *SEED.get_or_init(|| {
+    ArgValue("--seed").and_then(|value| value.parse().ok()).unwrap_or_else(|| Rng::FromTime().NextU64())
+  })
+}
+
+// Seed for the next thing that needs randomness, such as a computer opponent or handicap
+// pieces. They're all drawn in turn from one generator seeded with Seed(), so the same seed,
+// options and input give the same game.
+fn NextSeed() -> u64 {
+  RANDOM.get_or_init(|| Mutex::new(Rng::new(Seed()))).lock().expect("random seed lock").NextU64()
+}
+
+// Shows the seed when it was drawn from the clock; one from `--seed` is already known
+fn ShowSeed() {
+  if ArgValue("--seed").is_none() {
+    println!("{}{}{}", Accent(), locale::Say(Message::Seed, &[("seed", &Seed())]), Reset());
+  }
+}
+
+// Opening book from `--book <file>`, the built-in one, or none with `--no-book`
+fn BookFromArgs() -> Option<Book> {
+  if Flag("--no-book") {
+    return None;
+  }
+  match ArgValue("--book") {
+    Some(path) => match Book::Load(&path) {
+      Ok(book) => Some(book),
+      Err(err) => {
+        println!("{}{}{}", Alert(), locale::Say(Message::BuiltInBook, &[("error", &err)]), Reset());
+        Some(Book::Embedded())
+      }
+    },
+    None => Some(Book::Embedded()),
+  }
+}
+
+// Builds an engine by name, using `--depth N`, `--iters N`, `--tt-mb N` and `--threads N`
+// for its settings. A strength given, as in ai:minimax:6, takes the place of the depth or
+// iterations.
+fn MakeEngine(name: &str, strength: Option<usize>, seed: u64) -> Box<dyn Engine> {
+  let number = |name: &str, default: usize| {
+    strength.or_else(|| ArgValue(name).and_then(|value| value.parse().ok())).unwrap_or(default)
+  };
+  match name {
+    "random" | "easy" => Box::new(RandomBot::new(seed)),
+    "mcts" => Box::new(Mcts::new(number("--iters", 20000), seed)),
+    _ => {
+      let mut bot = Bot::new(number("--depth", 8));
+      if let Some(megabytes) = ArgValue("--tt-mb").and_then(|value| value.parse().ok()) {
+        bot = bot.WithTable(TranspositionTable::WithMegabytes(megabytes));
+      }
+      bot = bot.WithThreads(number("--threads", 1));
+      match BookFromArgs() {
+        Some(book) => Box::new(bot.WithBook(book)),
+        None => Box::new(bot),
+      }
+    }
+  }
+}
+
+// Picks the computer opponent from `--engine` or `--difficulty`, or asks for a difficulty
+fn AskForEngine() -> (Box<dyn Engine>, Option<Difficulty>) {
+  if let Some(name) = ArgValue("--engine") {
+    return (MakeEngine(&name, None, NextSeed()), None);
+  }
+  let mut choice = ArgValue("--difficulty").and_then(|name| Difficulty::FromName(&name));
+  while choice.is_none() {
+    println!("{}", locale::Text(Message::ChooseDifficulty));
+    choice = Difficulty::FromName(&ReadLine());
+    if choice.is_none() {
+      println!("{}", locale::Text(Message::InvalidInput));
+    }
+  }
+  let difficulty = choice.unwrap();
+  (difficulty.MakeEngineWith(Blunders(), BookFromArgs(), NextSeed()), Some(difficulty))
+}
+
+// Chance of a medium computer playing a random move, from `--blunders`
+fn Blunders() -> f64 {
+  ArgValue("--blunders")
+      .and_then(|value| value.parse().ok())
+      .unwrap_or(ConnectFour::difficulty::MEDIUM_BLUNDER_PROBABILITY)
+}
+
+// A computer player from what follows `ai:` in `--p1 ai:hard`: a difficulty, or an engine
+// such as mcts, with its depth or iterations after another colon as in minimax:6. Returns
+// it with the name it plays under.
+fn AiPlayer(spec: &str, seed: u64) -> Result<(Box<dyn Engine>, String), String> {
+  if let Some(difficulty) = Difficulty::FromName(spec) {
+    let name = locale::Say(Message::ComputerLevel, &[("level", &difficulty.Name())]);
+    return Ok((difficulty.MakeEngineWith(Blunders(), BookFromArgs(), seed), name));
+  }
+  let (name, strength) = match spec.split_once(':') {
+    Some((name, strength)) => match strength.parse().ok().filter(|&strength: &usize| strength > 0) {
+      Some(strength) => (name, Some(strength)),
+      None => return Err(locale::Say(Message::UnknownAi, &[("name", &spec)])),
+    },
+    None => (spec, None),
+  };
+  match name {
+    "random" | "mcts" | "minimax" => {
+      let engine = MakeEngine(name, strength, seed);
+      let name = engine.Name();
+      Ok((engine, name))
+    }
+    _ => Err(locale::Say(Message::UnknownAi, &[("name", &spec)])),
+  }
+}
+
+// Creates an empty game with the size from `--width N` and `--height N` (default 7x6, or
+// 9x7 for three players), the win length from `--connect N` (default 4) and the number of
+// players from `--players N` (default 2); `--popout` turns on the PopOut rules and
+// `--no-gravity` lets pieces be placed on any empty cell; `--cylinder` wraps lines around the
+// side edges and `--bombs` gives each player one bomb. `--handicap N` starts every game with
+// N pieces for the player from `--handicap-player P` (default 1), laid out by
+// `--handicap-seed S` (default a random seed, shown on screen so the layout can be replayed).
+fn GameFromArgs() -> Result<Game, ConfigError> {
+  let number = |name: &str, default: usize| {
+    ArgValue(name).and_then(|value| value.parse().ok()).unwrap_or(default)
+  };
+  // `--players 1` is a two-player game against the computer
+  let players = number("--players", 2).max(2);
+  let (width, height) = if players == 3 { (9, 7) } else { (BOARD_WIDTH, BOARD_HEIGHT) };
+  let game = Game::new(number("--width", width), number("--height", height))?
+      .WithWinLength(number("--connect", WIN_LENGTH))?
+      .WithPlayers(players)?;
+  let game = game
+      .WithPopOut(Flag("--popout"))
+      .WithGravity(!Flag("--no-gravity"))
+      .WithWrap(Flag("--cylinder"))
+      .WithBombs(Flag("--bombs") as u8);
+  // A chess clock such as `--clock 180`, `--clock 60+2` or `--clock 60d3`
+  let game = match ArgValue("--clock") {
+    Some(text) => game.WithClock(clock::ParseTimeControl(&text)?),
+    None => game,
+  };
+  match number("--handicap", 0) {
+    0 => Ok(game),
+    pieces => {
+      let player = Player::FromInt(number("--handicap-player", 1) as u8);
+      let seed = ArgValue("--handicap-seed")
+          .and_then(|value| value.parse().ok())
+          .unwrap_or_else(NextSeed);
+      game.WithHandicap(player, pieces, seed)
+    }
+  }
+}
+
+// The game computer players play each other in, from the same options as any other and
+// starting from `--moves` if it's given
+fn ComputerGame() -> Result<Game, String> {
+  let mut game = GameFromArgs().map_err(|err| err.to_string())?;
+  if !engine::Supports(&game) {
+    return Err(locale::Text(Message::AiUnsupported).to_string());
+  }
+  if let Some(moves) = ArgValue("--moves") {
+    game.PlayMoves(&moves).map_err(|err| err.to_string())?;
+  }
+  Ok(game)
+}
+
+// Player whose pieces have the color starting with the letter, e.g. 'r' for Red
+fn PlayerFromLetter(game: &Game, letter: char) -> Option<Player> {
+  let player = match letter {
+    'r' => Player::One,
+    'y' => Player::Two,
+    'g' => Player::Three,
+    _ => return None,
+  };
+  (player as usize <= game.Players()).then_some(player)
+}
+
+// Lets the players build a starting position by hand before play begins, then checks it
+// with Game::SetPosition. Pieces go in column by column or cell by cell.
+fn SetupPosition(game: &mut Game) {
+  let (width, height) = (game.Width(), game.Height());
+  let mut board = game.Board().clone();
+  let mut ToMove = Player::One;
+  let mut error: Option<String> = None;
+  loop {
+    ClearScreen();
+    println!("{}", Separator(game));
+    println!("{}{}{}", Accent(), locale::Say(Message::SetupTitle, &[("player", &ColorName(ToMove))]), Reset());
+    println!("{}", Separator(game));
+    println!("{}", ColumnHeader(game, false));
+    print!("{}", RenderRows(&board, game.Wrap(), &Overlay::default()));
+    println!("{}", Separator(game));
+    let drop = if game.Players() == 3 { "r4 / y4 / g4" } else { "r4 / y4" };
+    for (command, message) in [
+      (drop, Message::SetupDrop),
+      ("r4,3", Message::SetupPlace),
+      ("x4 / x4,3", Message::SetupRemove),
+      ("turn y", Message::SetupTurn),
+      ("clear", Message::SetupClear),
+      ("play", Message::SetupPlay),
+    ] {
+      println!("{:<10} {}", command, locale::Text(message));
+    }
+    if let Some(message) = error.take() {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &message)]), Reset());
+    }
+
+    let input = ReadLine().trim().to_lowercase();
+    let mut chars = input.chars();
+    let first = chars.next().unwrap_or(' ');
+    let rest = chars.as_str().trim();
+    match input.as_str() {
+      "play" => match game.SetPosition(board.clone(), ToMove) {
+        Ok(()) => return,
+        Err(err) => error = Some(err.to_string()),
+      },
+      "clear" => board.Clear(),
+      _ if input.starts_with("turn") => {
+        match input["turn".len()..].trim().chars().next().and_then(|letter| PlayerFromLetter(game, letter)) {
+          Some(player) => ToMove = player,
+          None => error = Some(locale::Text(Message::SetupChoosePlayer).to_string()),
+        }
+      }
+      _ if first == 'x' || PlayerFromLetter(game, first).is_some() => {
+        let piece = PlayerFromLetter(game, first).map_or(0, |player| player as u8);
+        let number = |text: &str, limit: usize| {
+          text.trim().parse::<usize>().ok().filter(|num| (1..=limit).contains(num))
+        };
+        // Either a cell as "column,row" or a whole column
+        let cell = match rest.split_once(',') {
+          Some((column, row)) => number(column, width).zip(number(row, height)).map(|(c, r)| (height - r, c - 1)),
+          None => number(rest, width).and_then(|column| {
+            let column = column - 1;
+            let row = match piece {
+              0 => (0..height).find(|&row| board[row][column] != 0), // Top piece
+              _ => (0..height).rev().find(|&row| board[row][column] == 0), // Lowest empty cell
+            };
+            row.map(|row| (row, column))
+          }),
+        };
+        match cell {
+          Some((row, column)) => board[row][column] = piece,
+          None => error = Some(locale::Text(Message::SetupNoCell).to_string()),
+        }
+      }
+      _ => error = Some(locale::Text(Message::SetupUnknown).to_string()),
+    }
+  }
+}
+
+// Asks whether one or two humans are playing, unless `--players` says, then the humans'
+// names; one player means Player Two is the computer. Variants the engines can't play, such
+// as three-player games, are always between humans.
+fn NewSession(game: Game) -> Session {
+  let given = ArgValue("--players");
+  let mut session = loop {
+    if !engine::Supports(&game) {
+      break Session::new(game, None, None);
+    }
+    let answer = match &given {
+      Some(players) => players.clone(),
+      None => {
+        println!("{}", locale::Text(Message::HowManyPlayers));
+        ReadLine()
+      }
+    };
+    match answer.trim() {
+      "1" => {
+        let (bot, difficulty) = AskForEngine();
+        break Session::new(game, Some(bot), difficulty);
+      }
+      "2" => break Session::new(game, None, None),
+      _ => println!("{}", locale::Text(Message::InvalidInput)),
+    }
+  };
+  AskNames(&mut session);
+  session.LogStart();
+  session
+}
+
+// Hosts a network game, or joins the one at the address or else one found on the local
+// network, with the local player named by `--p1` when hosting and `--p2` when joining, or with
+// `--watch` only watches it. None if it couldn't be set up, after saying why.
+fn NetworkSession(command: &str, address: Option<&str>) -> Option<Session> {
+  let hosting = command == "host";
+  let watch = !hosting && Flag("--watch");
+  let (flag, default) = match hosting {
+    true => ("--p1", Message::PlayerOne),
+    false => ("--p2", Message::PlayerTwo),
+  };
+  let name = ArgValue(flag).and_then(|name| CleanName(&name)).unwrap_or_else(|| locale::Text(default).to_string());
+  let token = match ArgValue("--resume").map(|token| u64::from_str_radix(token.trim(), 16)) {
+    Some(Ok(token)) => Some(token),
+    Some(Err(_)) => {
+      let err = locale::Text(Message::ResumeNeedsToken);
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return None;
+    }
+    None => None,
+  };
+  let connected = match (hosting, address) {
+    (true, _) => net::Host(&name),
+    (false, Some(address)) => net::Join(address, &name, watch, token),
+    (false, None) => net::FindGame().and_then(|address| net::Join(&address, &name, watch, token)),
+  };
+  let (game, remote) = match connected {
+    Ok(connected) => connected,
+    Err(err) => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return None;
+    }
+  };
+  if !InitTheme(&game) {
+    return None;
+  }
+  let mut session = Session::new(game, None, None);
+  for (index, name) in remote.Names.iter().enumerate() {
+    if !name.is_empty() {
+      session.Names[index] = name.clone();
+    }
+  }
+  let connected = match remote.Seat {
+    Player::None => locale::Say(Message::Watching, &[("one", &session.Name(Player::One)), ("two", &session.Name(Player::Two))]),
+    seat => locale::Say(Message::Connected, &[("name", &session.Name(seat)), ("first", &session.Name(Player::One))]),
+  };
+  println!("{}", connected);
+  if remote.Joined() && remote.Seat != Player::None {
+    println!("{}", locale::Say(Message::ResumeToken, &[("token", &format!("{:016x}", remote.Token))]));
+  }
+  session.Remote = Some(remote);
+  session.LogStart();
+  Some(session)
+}
+
+// Shows a network game being watched, each move as it's played, until it's over or the
+// connection to the host is lost
+fn WatchGame(mut session: Session) {
+  DisplayBoard(&session);
+  while !session.Game.IsFinished() {
+    let remote = session.Remote.as_mut().expect("a network game");
+    let names = session.Names.clone();
+    match remote.ReceiveMove(&mut session.Game, &mut |said| println!("{}", SaidLine(&names, said))) {
+      Ok(outcome) => {
+        AnimateDrop(&mut session, &outcome);
+        DisplayBoard(&session);
+        DisplayMove(&session, &outcome);
+      }
+      Err(_) if interrupt::Interrupted() => Quit(),
+      Err(err) => {
+        let lost = locale::Say(Message::ConnectionLost, &[("name", &session.Name(Player::One)), ("error", &err)]);
+        println!("{}{}{}", Alert(), lost, Reset());
+        return;
+      }
+    }
+  }
+  Celebrate(&mut session);
+  session.Renderer.Announce(&session, GameEvent::Finished);
+  if let Some(moves) = session.Game.ToMoveString() {
+    println!("{}", locale::Say(Message::Moves, &[("moves", &moves)]));
+  }
+}
+
+// Enum representing who chooses the moves of the player on turn
+#[derive(Clone, Copy, PartialEq)]
+enum Seat {
+  Human,    // Someone at this keyboard
+  Computer, // The computer opponent
+  Remote,   // The other end of a network game
+}
+
+fn SeatOf(session: &Session, player: Player) -> Seat {
+  if session.Remote.as_ref().is_some_and(|remote| remote.Seat == player) {
+    Seat::Remote
+  } else if session.Bot.is_some() && player == Player::Two {
+    Seat::Computer
+  } else {
+    Seat::Human
+  }
+}
+
+// Asks the player at the keyboard for their move, with the advice and hints for the turn;
+// None when the turn went to answering the other player's draw offer
+fn HumanTurn(session: &mut Session) -> Option<PlayerAction> {
+  if !Accessible() {
+    println!("\n");
+  }
+  let player = session.Game.CurrentPlayer();
+  if session.Game.DrawOffer().is_some_and(|offerer| offerer != player) {
+    AnswerDraw(session);
+    return None;
+  }
+  if session.Teaching[player as usize - 1] && !session.Competitive {
+    TeachingWarnings(&session.Game);
+  }
+  session.Renderer.Announce(session, GameEvent::Turn);
+  if session.TakebacksUsed.iter().any(|&used| used > 0) && !Accessible() {
+    let used: Vec<String> = (1..=session.Game.Players())
+        .map(|number| {
+          let player = Player::FromInt(number as u8);
+          format!("{} {}/{}", session.Name(player), session.TakebacksUsed[number - 1], session.TakebackLimit)
+        })
+        .collect();
+    println!("{}", locale::Say(Message::TakebacksUsed, &[("used", &used.join(", "))]));
+  }
+  if !Accessible() {
+    if session.Game.RedoCount() > 0 {
+      let redo = locale::Say(
+        Message::RedoAvailable,
+        &[("count", &session.Game.RedoCount()), ("redo", &Shortcut(session, "r", "redo"))],
+      );
+      println!("{}", redo);
+    }
+    if session.Game.PopOut() {
+      println!("{}", locale::Text(Message::PopHint));
+    }
+    if session.Game.BombsLeft(player) > 0 {
+      println!("{}", locale::Text(Message::BombHint));
+    }
+    let commands = locale::Say(
+      Message::SaveHint,
+      &[("history", &Shortcut(session, "m", "history")), ("threats", &Shortcut(session, "t", "threats"))],
+    );
+    println!("{}", commands);
+  }
+  let started = Instant::now();
+  let deadline = Deadline(session);
+  if let Some(deadline) = deadline {
+    let left = deadline.saturating_duration_since(started).as_secs_f64().ceil();
+    let left = locale::Say(Message::SecondsToMove, &[("seconds", &left)]);
+    println!("{}{}{}{}", Accent(), theme::Current().Mark(Mark::Clock), left, Reset());
+  }
+  // The game clock runs only while the player on turn is at the move prompt
+  let flag_fall = session.Game.Clock().map(|clock| started + clock.TimeToFlag(player));
+  let deadline = deadline.into_iter().chain(flag_fall).min();
+  let columns = match UseLetters(session) {
+    true => format!("a-{}", ColumnLabel(session.Game.Width() - 1, true)),
+    false => format!("1-{}", session.Game.Width()),
+  };
+  if terminal::ReadingKeys() {
+    println!("{}", locale::Text(Message::RawKeys));
+  }
+  if Accessible() {
+    // The accessible prompt has already said what to type
+  } else if session.Game.Gravity() && UseLetters(session) {
+    println!("{}", locale::Say(Message::EnterColumnLetters, &[("columns", &columns)]));
+  } else if session.Game.Gravity() {
+    println!("{}", locale::Say(Message::EnterColumnNumbers, &[("columns", &columns)]));
+  } else {
+    println!("{}", locale::Say(Message::EnterCell, &[("columns", &columns), ("rows", &session.Game.Height())]));
+  }
+  let mut shown = ClockLine(session, Duration::ZERO).unwrap_or_default();
+  let action = HumanController {
+    Letters: UseLetters(session),
+    Deadline: deadline,
+    Tick: &mut || TickClock(session, started, &mut shown),
+  }
+  .ChooseMove(&session.Game);
+  // A move typed after the clock ran out doesn't count
+  let flagged = session.Game.SpendTime(started.elapsed());
+  Some(match action {
+    PlayerAction::Quit => PlayerAction::Quit,
+    _ if flagged => PlayerAction::TimedOut,
+    action => action,
+  })
+}
+
+// Lets the computer opponent choose its move; None when it answered a draw offer instead, or
+// its clock ran out while it thought
+fn BotTurn(session: &mut Session) -> Option<PlayerAction> {
+  let bot = session.Bot.as_mut().expect("a computer opponent");
+  let name = bot.Name();
+  if session.Game.DrawOffer().is_some() {
+    // The computer takes a draw unless it thinks it's winning
+    let winning = Bot::new(10)
+        .WithTimeLimit(Duration::from_millis(500))
+        .Search(&session.Game)
+        .is_some_and(|result| result.Score > 0);
+    let _ = if winning { session.Game.DeclineDraw() } else { session.Game.AcceptDraw() };
+    DisplayBoard(session);
+    let answer = if winning { Message::BotDeclinesDraw } else { Message::BotAcceptsDraw };
+    println!("{}", locale::Say(answer, &[("name", &name)]));
+    return None;
+  }
+  let started = Instant::now();
+  let action = BotController { Engine: bot.as_mut(), Name: &name }.ChooseMove(&session.Game);
+  if session.Game.SpendTime(started.elapsed()) {
+    DisplayBoard(session);
+    return None;
+  }
+  Some(action)
+}
+
+// Waits for the move of the player at the other end of a network game, sending the moves
+// made here first
+fn RemoteTurn(session: &mut Session) -> PlayerAction {
+  let seat = session.Remote.as_ref().expect("a network game").Seat;
+  println!("{}", locale::Say(Message::WaitingForMove, &[("name", &session.Name(seat))]));
+  let names = session.Names.clone();
+  let remote = session.Remote.as_mut().expect("a network game");
+  RemoteController { Remote: remote, Names: &names }.ChooseMove(&session.Game)
+}
+
+// Tells the controllers of the players who didn't make the move about it, so the other end
+// of a network game sees it straight away
+fn Notify(session: &mut Session, mover: Player, played: Move) {
+  let names = session.Names.clone();
+  if let Some(remote) = session.Remote.as_mut().filter(|remote| remote.Seat != mover) {
+    RemoteController { Remote: remote, Names: &names }.Observe(&session.Game, played);
+  }
+  if let Some(bot) = session.Bot.as_mut().filter(|_| mover != Player::Two) {
+    let name = bot.Name();
+    BotController { Engine: bot.as_mut(), Name: &name }.Observe(&session.Game, played);
+  }
+}
+
+// Carries on a network game whose connection was lost: the player who joined connects again,
+// and the host waits for them, the game going to the host if they don't come back in time
+fn Reconnect(session: &mut Session, err: &str) {
+  let seat = session.Remote.as_ref().expect("a network game").Seat;
+  let name = session.Name(seat).to_string();
+  println!("{}{}{}", Alert(), locale::Say(Message::ConnectionLost, &[("name", &name), ("error", &err)]), Reset());
+  log::Info(format_args!("Network game: connection lost: {}", err));
+  let remote = session.Remote.as_mut().expect("a network game");
+  let back = match remote.Joined() {
+    true => remote.Rejoin(&mut session.Game, &mut |left| {
+      println!("{}", locale::Say(Message::Reconnecting, &[("name", &name), ("seconds", &left)]))
+    }),
+    false => remote.AwaitReturn(&mut |left| {
+      println!("{}", locale::Say(Message::WaitingForReturn, &[("name", &name), ("seconds", &left)]))
+    }),
+  };
+  match back {
+    Ok(()) => {
+      DisplayBoard(session);
+      println!("{}", locale::Say(Message::Reconnected, &[("name", &name)]));
+    }
+    Err(_) if interrupt::Interrupted() => Quit(),
+    Err(err) if remote.Joined() => LostConnection(session, &err),
+    Err(err) => {
+      log::Info(format_args!("Network game: {} forfeits: {}", name, err));
+      remote.Abandoned = true;
+      let _ = session.Game.ForfeitBy(seat);
+      DisplayBoard(session);
+    }
+  }
+}
+
+// A chat message as it's shown, after the name of who said it
+fn SaidLine(names: &[String], said: &net::Said) -> String {
+  let name = names.get((said.By as usize).wrapping_sub(1)).map_or("", String::as_str);
+  locale::Say(Message::ChatLine, &[("name", &name), ("text", &said.Text)])
+}
+
+// Says something to the other end of a network game, then shows the board with it above
+fn SayToRemote(session: &mut Session, text: &str) {
+  let Some(remote) = &mut session.Remote else {
+    return DisplayError(session, locale::Text(Message::ChatOnlyOverNetwork).to_string());
+  };
+  match remote.Say(&session.Game, text) {
+    Ok(said) if said.is_empty() => DisplayError(session, locale::Say(Message::NeedChatText, &[("command", &"say")])),
+    Ok(said) => {
+      log::Debug(format_args!("Network game: said {}", said));
+      DisplayBoard(session);
+    }
+    Err(err) => LostConnection(session, &err),
+  }
+}
+
+// Lists what's been said in the network game
+fn ShowChat(session: &Session) {
+  let Some(remote) = &session.Remote else {
+    return DisplayError(session, locale::Text(Message::ChatOnlyOverNetwork).to_string());
+  };
+  if remote.Chat.is_empty() {
+    println!("{}", locale::Text(Message::NoChat));
+  }
+  for said in &remote.Chat {
+    println!("{}", SaidLine(&session.Names, said));
+  }
+}
+
+// Says the connection to the other end is gone and offers to save the game before leaving
+fn LostConnection(session: &Session, err: &str) -> ! {
+  let name = session.Remote.as_ref().map_or("", |remote| session.Name(remote.Seat));
+  println!("{}{}{}", Alert(), locale::Say(Message::ConnectionLost, &[("name", &name), ("error", &err)]), Reset());
+  log::Info(format_args!("Network game: connection lost: {}", err));
+  println!("{}", locale::Text(Message::SaveBeforeLeaving));
+  let path = ReadLine();
+  if !path.trim().is_empty() {
+    SaveGame(session, path.trim());
+  }
+  Quit();
+}
+
+// Asks each human player without a `--p1`, `--p2` or `--p3` flag for their name
+fn AskNames(session: &mut Session) {
+  let humans = if session.Bot.is_some() { 1 } else { session.Game.Players() };
+  for index in 0..humans {
+    if ArgValue(["--p1", "--p2", "--p3"][index]).is_some() {
+      continue;
+    }
+    let player = Player::FromInt(index as u8 + 1);
+    loop {
+      println!("{}", locale::Say(Message::NamePrompt, &[("color", &ColorName(player)), ("name", &session.Name(player))]));
+      let Some(name) = CleanName(&ReadLine()) else { break };
+      if session.Names[..index].contains(&name) {
+        println!("{}", locale::Say(Message::NameTaken, &[("name", &name)]));
+        continue;
+      }
+      session.Names[index] = name;
+      break;
+    }
+  }
+  session.Ratings = Ratings(session);
+}
+
+// A name as typed, trimmed and cut short so the board header doesn't wrap; None if blank
+fn CleanName(text: &str) -> Option<String> {
+  let name: String = text.trim().chars().take(MAX_NAME_LENGTH).collect();
+  let name = name.trim_end();
+  (!name.is_empty()).then(|| name.to_string())
+}
+
+// Undoes the last move, and the computer's reply before it so the human is back on turn
+fn UndoTurn(session: &mut Session) {
+  match session.Game.UndoMove() {
+    Ok(_) => {
+      session.MoveClock = None; // A fresh turn gets fresh time
+      if session.Bot.is_some() && session.Game.CurrentPlayer() == Player::Two {
+        let _ = session.Game.UndoMove();
+      }
+      DisplayBoard(session)
+    }
+    Err(err) => DisplayError(session, err.Localized()),
+  }
+}
+
+// Suggests a move for the player on turn, counting it against their hint allowance
+fn ShowHint(session: &mut Session) {
+  if session.Game.IsFinished() {
+    println!("{}", locale::Text(Message::HintsOver));
+    return;
+  }
+  if !engine::Supports(&session.Game) {
+    DisplayError(session, locale::Text(Message::HintsUnavailable).to_string());
+    return;
+  }
+  if session.Competitive {
+    DisplayError(session, locale::Text(Message::HintsCompetitive).to_string());
+    return;
+  }
+  let player = session.Game.CurrentPlayer() as usize - 1;
+  if session.HintsUsed[player] >= session.HintLimit {
+    DisplayError(session, locale::Text(Message::NoHintsLeft).to_string());
+    return;
+  }
+
+  // The solver is exact but only fast enough once a few pieces are down
+  let column = if Flag("--solver-hints")
+      && session.Game.CurrentMove() >= SOLVE_FROM_MOVE
+      && Solver::Supports(&session.Game)
+  {
+    Solver::new().ChooseMove(&session.Game)
+  } else {
+    Bot::new(10).WithTimeLimit(Duration::from_secs(1)).ChooseMove(&session.Game)
+  };
+  session.HintsUsed[player] += 1;
+
+  DisplayBoard(session);
+  if let Some(column) = column {
+    let left = session.HintLimit - session.HintsUsed[player];
+    println!("{}", locale::Say(Message::Hint, &[("column", &(column + 1)), ("left", &left)]));
+  }
+}
+
+// Prints a per-move table of engine evaluations for the game so far, flagging blunders
+fn ShowAnalysis(session: &Session) {
+  if !engine::Supports(&session.Game) {
+    println!("{}", locale::Text(Message::AnalysisUnavailable));
+    return;
+  }
+  let budget = ArgValue("--analysis-ms")
+      .and_then(|value| value.parse().ok())
+      .unwrap_or(300);
+  println!("{}", locale::Say(Message::Analyzing, &[("moves", &session.Game.History().len())]));
+  let report = analysis::Analyze(
+    &session.Game,
+    Duration::from_millis(budget),
+    analysis::DEFAULT_THRESHOLD,
+  );
+
+  println!("{}{}{}", Accent(), locale::Text(Message::AnalysisHeading), Reset());
+  for entry in &report {
+    println!(
+      "{:<5} {:<7} {:<7} {:<7} {} ({}){}",
+      entry.MoveNumber,
+      ColorName(entry.Player),
+      entry.Column + 1,
+      ScoreWords(entry.Score),
+      entry.BestColumn + 1,
+      ScoreWords(entry.BestScore),
+      if entry.IsBlunder { "  ??" } else { "" }
+    );
+  }
+  let comments: Vec<String> = report.iter().filter_map(Comment).collect();
+  if comments.is_empty() {
+    println!("{}", locale::Text(Message::NoBlunders));
+  }
+  for comment in comments {
+    println!("{}{}{}", Alert(), comment, Reset());
+  }
+}
+
+// Describes an engine score in words: a win, a loss or the heuristic number
+fn ScoreWords(score: i32) -> String {
+  match analysis::Classify(score) {
+    ScoreKind::Win => locale::Text(Message::ScoreWin).to_string(),
+    ScoreKind::Loss => locale::Text(Message::ScoreLoss).to_string(),
+    ScoreKind::Heuristic(score) => format!("{:+}", score),
+  }
+}
+
+// Says what the engine's column would have done instead of the move, if it was a blunder
+fn Comment(entry: &analysis::MoveAnalysis) -> Option<String> {
+  let better = locale::Text(match entry.Better()? {
+    Better::KeptTheWin => Message::KeptTheWin,
+    Better::AvoidedTheLoss => Message::AvoidedTheLoss,
+    Better::WasBetter => Message::WasBetter,
+  });
+  Some(locale::Say(
+    Message::Blunder,
+    &[
+      ("move", &entry.MoveNumber),
+      ("column", &(entry.Column + 1)),
+      ("best", &(entry.BestColumn + 1)),
+      ("better", &better),
+    ],
+  ))
+}
+
+// Writes the game to a JSON file for `--load` or the 'load' command
+fn SaveGame(session: &Session, path: &str) {
+  match save::SaveFile(&session.Game, path) {
+    Ok(()) => println!("{}", locale::Say(Message::GameSaved, &[("path", &path)])),
+    Err(err) => {
+      let error = locale::Say(Message::CouldNotSave, &[("path", &path), ("error", &err)]);
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset())
+    }
+  }
+}
+
+// Keeps the autosave in step with the game: a game under way is written after every move and
+// a finished or untouched one clears it. Failing to write it isn't worth interrupting play.
+fn Autosave(game: &Game) {
+  if game.IsFinished() || (game.History().is_empty() && !game.IsSetUp()) {
+    save::ClearAutosave();
+  } else {
+    let _ = save::WriteAutosave(game);
+  }
+}
+
+// Who a player is in the statistics: the name their results are kept under, and for the
+// named computer levels a fixed rating
+fn Participant(session: &Session, player: Player) -> stats::Participant {
+  let anchor = match (&session.Bot, session.Difficulty) {
+    (Some(_), Some(difficulty)) if player == Player::Two => Some(difficulty.Rating()),
+    _ => None,
+  };
+  stats::Participant { Name: session.Name(player).to_string(), Anchor: anchor }
+}
+
+// Both players' ratings from the statistics, for the banner before a two-player game
+fn Ratings(session: &Session) -> Option<(u64, u64)> {
+  if session.Game.Players() != 2 || Flag("--no-stats") {
+    return None;
+  }
+  let totals = stats::LoadFile(stats::StatsPath()?).ok()?;
+  let rating = |player| totals.Rating(&Participant(session, player));
+  Some((rating(Player::One), rating(Player::Two)))
+}
+
+// Returns true unless animations are turned off with `--no-animation` or would only clutter
+// output that isn't going to a terminal, e.g. when piped or scripted
+fn Animated() -> bool {
+  !Flag("--no-animation") && !Accessible() && !NoClear() && std::io::stdout().is_terminal()
+}
+
+// Runs the computer's search with a spinner saying it's thinking, unless animations are off
+fn Thinking<T>(name: &str, search: impl FnOnce() -> T) -> T {
+  if !Animated() || !terminal::Escapes() {
+    return search();
+  }
+  let done = AtomicBool::new(false);
+  std::thread::scope(|scope| {
+    scope.spawn(|| {
+      for frame in ['|', '/', '-', '\\'].iter().cycle() {
+        std::thread::sleep(SPINNER_FRAME_TIME);
+        if done.load(Ordering::SeqCst) {
+          break;
+        }
+        print!("\r{}", locale::Say(Message::IsThinking, &[("name", &name), ("spinner", frame)]));
+        let _ = std::io::stdout().flush();
+      }
+      print!("{}", terminal::ClearLine());
+      let _ = std::io::stdout().flush();
+    });
+    let result = search();
+    done.store(true, Ordering::SeqCst);
+    result
+  })
+}
+
+// Shows a piece that has just been dropped falling down its column to the row it landed in,
+// with `--drop-delay` milliseconds between frames (30 by default)
+fn AnimateDrop(session: &mut Session, outcome: &MoveOutcome) {
+  if !Animated() || !matches!(session.Game.History().last(), Some(Move::Drop(_))) {
+    return;
+  }
+  let delay = ArgValue("--drop-delay").and_then(|value| value.parse().ok()).unwrap_or(30);
+  for row in 0..outcome.Row {
+    session.FallingRow = Some(row);
+    DisplayBoard(session);
+    let _ = std::io::stdout().flush();
+    std::thread::sleep(Duration::from_millis(delay));
+  }
+  session.FallingRow = None;
+}
+
+// Flashes the winning line for a moment when a game has just been won, unless
+// `--no-animation` is given or the output isn't a terminal. Entering anything skips it.
+fn Celebrate(session: &mut Session) {
+  if session.Celebrated || session.Game.WinningLine().is_empty() {
+    return;
+  }
+  session.Celebrated = true;
+  if !Animated() {
+    return;
+  }
+  for frame in 0..ANIMATION_FRAMES {
+    session.HideWinning = frame % 2 == 0;
+    DisplayBoard(session);
+    let _ = std::io::stdout().flush();
+    if interrupt::Pause(ANIMATION_FRAME_TIME) {
+      break;
+    }
+  }
+  session.HideWinning = false;
+  DisplayBoard(session);
+}
+
+// Adds the finished game to the lifetime statistics, once, unless `--no-stats` is given.
+// Failing to update them isn't worth more than a warning.
+fn RecordStats(session: &mut Session) {
+  if session.StatsRecorded {
+    return;
+  }
+  session.StatsRecorded = true;
+  session.LogResult();
+  ArchiveGame(session);
+  if Flag("--no-stats") {
+    return;
+  }
+  let Some(path) = stats::StatsPath() else { return };
+  let mut totals = stats::LoadFile(&path).unwrap_or_else(|err| {
+    Warn(locale::Say(Message::StatsStartingOver, &[("path", &path.display()), ("error", &err)]));
+    stats::Stats::default()
+  });
+  let players: Vec<stats::Participant> = (1..=session.Game.Players())
+      .map(|number| Participant(session, Player::FromInt(number as u8)))
+      .collect();
+  let k = ArgValue("--k-factor")
+      .and_then(|value| value.parse().ok())
+      .filter(|&k: &f64| k > 0.0)
+      .unwrap_or(rating::DEFAULT_K_FACTOR);
+  totals.Add(&session.Game, &players, k);
+  if let Err(err) = stats::SaveFile(&totals, &path) {
+    Warn(locale::Say(Message::StatsNotUpdated, &[("path", &path.display()), ("error", &err)]));
+  }
+}
+
+// Prints a warning that doesn't stop anything, such as a file that couldn't be kept
+fn Warn(warning: String) {
+  println!("{}{}{}", Accent(), locale::Say(Message::Warning, &[("warning", &warning)]), Reset());
+}
+
+// Where the games archive is kept: `--db`, or else the data folder
+fn ArchivePath() -> Option<PathBuf> {
+  ArgValue("--db").map(PathBuf::from).or_else(archive::ArchivePath)
 }
 
-impl Player {
-  // Converts an integer value to a Player enum
-  fn FromInt(value: u8) -> Player {
-    match value {
-      1 => Player::One,
-      2 => Player::Two,
-      _ => Player::None,
+// Adds the finished game to the games archive. It's kept even with `--no-stats`, and failing
+// to keep it isn't worth more than a warning.
+fn ArchiveGame(session: &Session) {
+  let Some(path) = ArchivePath() else { return };
+  let mut games = match archive::LoadFile(&path) {
+    Ok(games) => games,
+    Err(err) => {
+      // Starting over would throw the games in it away, so leave it for the player to look at
+      Warn(locale::Say(Message::ArchiveUnreadable, &[("path", &path.display()), ("error", &err)]));
+      return;
     }
+  };
+  let players: Vec<String> = (1..=session.Game.Players())
+      .map(|number| session.Name(Player::FromInt(number as u8)).to_string())
+      .collect();
+  let finished = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map_or(0, |elapsed| elapsed.as_secs());
+  let Some(id) = games.Add(&session.Game, &players, finished) else { return };
+  match archive::SaveFile(&games, &path) {
+    Ok(()) => log::Info(format_args!("Game {} added to {}", id, path.display())),
+    Err(err) => Warn(locale::Say(Message::ArchiveNotAdded, &[("path", &path.display()), ("error", &err)])),
   }
 }
 
-// Enum representing possible errors when making a move
-#[derive(Debug)]
-enum MoveError {
-  GameFinished, // The game has already ended
-  InvalidColumn, // The column number is invalid
-  ColumnFull,    // The selected column is full
+// Looks into the games archive: `games list` shows every game, `games show <id>` one of them
+// and `games replay <id>` steps through it as `replay` does a saved game
+fn RunGames(command: Option<&str>, id: Option<&str>) {
+  let fail = |error: &dyn std::fmt::Display| {
+    println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", error)]), Reset())
+  };
+  let Some(path) = ArchivePath() else {
+    return fail(&locale::Text(Message::NoHomeForArchive));
+  };
+  let games = match archive::LoadFile(&path) {
+    Ok(games) => games,
+    Err(err) => return fail(&locale::Say(Message::CouldNotRead, &[("path", &path.display()), ("error", &err)])),
+  };
+  let entry = || {
+    let id = id.ok_or_else(|| locale::Text(Message::NeedGameNumber).to_string())?;
+    let number: u64 = id.trim_start_matches('#').parse().map_err(|_| locale::Say(Message::NotAGameNumber, &[("id", &id)]))?;
+    games.Find(number).ok_or_else(|| locale::Say(Message::NoSuchGame, &[("number", &number), ("path", &path.display())]))
+  };
+  let shown = match command.unwrap_or("list") {
+    "list" => {
+      print!("{}", games.List());
+      Ok(())
+    }
+    "show" => entry().map(|entry| print!("{}", entry.Details())),
+    "replay" => entry().map(|entry| ReplayGame(entry.Game.clone())),
+    other => Err(locale::Say(Message::UnknownGamesCommand, &[("command", &other)])),
+  };
+  if let Err(err) = shown {
+    fail(&err);
+  }
 }
 
-impl std::fmt::Display for MoveError {
-  // Provides a user-friendly description for each error
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    match self {
-      MoveError::GameFinished => write!(f, "Game is already finished"),
-      MoveError::InvalidColumn => write!(f, "Invalid column"),
-      MoveError::ColumnFull => write!(f, "Column is full"),
+// Prints the lifetime statistics for `stats` or `--stats`, or with `ratings` only how the
+// players rank
+fn ShowStats(ratings: bool) {
+  let Some(path) = stats::StatsPath() else {
+    let error = locale::Text(Message::NoHomeForStats);
+    println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset());
+    return;
+  };
+  match stats::LoadFile(&path) {
+    Ok(totals) if ratings => print!("{}", totals.Ratings()),
+    Ok(totals) => print!("{}", totals.Summary()),
+    Err(err) => {
+      let error = locale::Say(Message::CouldNotRead, &[("path", &path.display()), ("error", &err)]);
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset())
+    }
+  }
+}
+
+// Offers to pick up an unfinished game left by an earlier run. A damaged autosave is reported
+// and thrown away.
+fn ResumeAutosave() -> Option<Game> {
+  let path = save::AutosavePath().filter(|path| path.exists())?;
+  let game = match save::LoadFile(&path) {
+    Ok(game) if !game.IsFinished() => game,
+    Ok(_) => {
+      save::ClearAutosave();
+      return None;
+    }
+    Err(err) => {
+      Warn(locale::Say(Message::AutosaveSkipped, &[("path", &path.display()), ("error", &err)]));
+      save::ClearAutosave();
+      return None;
     }
+  };
+  loop {
+    println!("{}", locale::Text(Message::ResumeGame));
+    match ReadLine().trim().to_lowercase().as_str() {
+      "y" => return Some(game),
+      "n" => {
+        save::ClearAutosave();
+        return None;
+      }
+      _ => println!("{}", locale::Text(Message::InvalidInput)),
+    }
+  }
+}
+
+// Gentle advice for a player in teaching mode: columns where the next player already
+// threatens to win, or else the columns that would hand them a win by filling the cell below
+// it. Only advice; any legal move is still accepted.
+fn TeachingWarnings(game: &Game) {
+  let mover = game.CurrentPlayer();
+  let next = mover.Next(game.Players());
+  let name = |(row, column): (usize, usize)| match game.Gravity() {
+    true => locale::Say(Message::ColumnNumber, &[("column", &(column + 1))]),
+    false => format!("{},{}", column + 1, game.Height() - row),
+  };
+  let threats = game.ThreatCells(next);
+  if !threats.is_empty() {
+    let cells: Vec<String> = threats.into_iter().map(name).collect();
+    let warning = locale::Say(
+      Message::CarefulThreat,
+      &[
+        ("dash", &theme::Current().Mark(Mark::Dash)),
+        ("color", &ColorName(next)),
+        ("cells", &cells.join(locale::Text(Message::And))),
+      ],
+    );
+    println!("{}{}{}", Accent(), warning, Reset());
+    return;
+  }
+  if !game.Gravity() {
+    return;
+  }
+  let careless: Vec<String> = game
+      .LegalMoves()
+      .into_iter()
+      .filter(|&column| !game.WouldWin(column, mover))
+      .filter(|&column| {
+        let mut probe = game.clone();
+        probe.MakeMove(column).is_ok() && !probe.ThreatCells(next).is_empty()
+      })
+      .map(|column| (column + 1).to_string())
+      .collect();
+  if !careless.is_empty() {
+    let warning = locale::Say(
+      Message::CarefulMove,
+      &[
+        ("dash", &theme::Current().Mark(Mark::Dash)),
+        ("columns", &careless.join(locale::Text(Message::Or))),
+        ("color", &ColorName(next)),
+      ],
+    );
+    println!("{}{}{}", Accent(), warning, Reset());
   }
 }
 
-// Struct representing the state of the game
-struct Game {
-  CurrentMove: u8,      // Counter for the current move
-  CurrentPlayer: Player, // The player whose turn it is
-  Board: Board,         // The game board
-  IsFinished: bool,     // Flag indicating if the game is finished
-  Winner: Player,       // The winner of the game (if any)
+// Shows where a dropped piece would land and waits for Enter to drop it there, or another
+// column to look at instead. Other moves, and drops into full columns, go ahead unchanged.
+// Returns None if the player typed something that isn't a move.
+fn PreviewDrop(session: &mut Session, played: Move) -> Option<Move> {
+  let Move::Drop(mut column) = played else {
+    return Some(played);
+  };
+  if !session.Preview {
+    return Some(played);
+  }
+  loop {
+    let Some(row) = session.Game.LandingRow(column) else {
+      return Some(Move::Drop(column));
+    };
+    session.Ghost = Some((row, column));
+    DisplayBoard(session);
+    session.Ghost = None;
+    println!("{}", locale::Say(Message::PreviewPrompt, &[("column", &(column + 1))]));
+    let input = ReadLine();
+    if input.trim().is_empty() {
+      return Some(Move::Drop(column));
+    }
+    match input::ParseMove(&session.Game, &input, UseLetters(session)) {
+      Ok(Move::Drop(other)) => column = other,
+      Ok(other) => return Some(other),
+      Err(err) => {
+        DisplayError(session, err);
+        return None;
+      }
+    }
+  }
 }
 
-impl Game {
-  // Creates a new game instance with initial settings
-  fn new() -> Game {
-    Game {
-      CurrentMove: 0,
-      CurrentPlayer: Player::One,
-      Board: [[0; BOARD_WIDTH]; BOARD_HEIGHT],
-      IsFinished: false,
-      Winner: Player::None,
+// Takes back the requesting player's last move, and any replies to it, once the opponent
+// agrees. The computer always agrees. Each player has a limited number per game.
+fn RequestTakeback(session: &mut Session) {
+  let requester = session.Game.CurrentPlayer();
+  let index = requester as usize - 1;
+  if session.TakebacksUsed[index] >= session.TakebackLimit {
+    DisplayError(session, locale::Text(Message::NoTakebacksLeft).to_string());
+    return;
+  }
+  // Undo on a copy first to count the moves back to the requester's own
+  let mut probe = session.Game.clone();
+  let mut count = 0;
+  loop {
+    if probe.UndoMove().is_err() {
+      DisplayError(session, locale::Text(Message::NothingToTakeBack).to_string());
+      return;
+    }
+    count += 1;
+    if probe.CurrentPlayer() == requester {
+      break;
     }
   }
 
-  fn ClearScreen() {
-    print!("\x1B[2J\x1B[1;1H"); // ANSI escape code to clear the screen
+  let approver = requester.Next(session.Game.Players());
+  let allowed = session.Bot.is_some()
+      || Confirm(
+        session,
+        &locale::Say(
+          Message::TakebackRequest,
+          &[
+            ("name", &session.Name(requester)),
+            ("dash", &theme::Current().Mark(Mark::Dash)),
+            ("other", &session.Name(approver)),
+          ],
+        ),
+      );
+  if !allowed {
+    DisplayBoard(session);
+    println!("{}", locale::Say(Message::TakebackRefused, &[("name", &session.Name(approver))]));
+    return;
   }
+  for _ in 0..count {
+    let _ = session.Game.UndoMove();
+  }
+  session.TakebacksUsed[index] += 1;
+  DisplayBoard(session);
+  let granted = locale::Say(
+    Message::TakebackGranted,
+    &[
+      ("used", &session.TakebacksUsed[index]),
+      ("limit", &session.TakebackLimit),
+      ("name", &session.Name(requester)),
+    ],
+  );
+  println!("{}", granted);
+}
 
-  // Displays the game board and game state
-  fn DisplayBoard(&self) {
-    Self::ClearScreen();
-    println!("{}--------------------{}", YELLOW, RESET);
-    println!("{}Connect 4  (Move: {}){}", YELLOW, self.CurrentMove, RESET);
-    println!("{}--------------------{}", YELLOW, RESET);
-    for row in self.Board {
-      let RowStr: String = row
-          .iter()
-          .map(|cell| match Player::FromInt(*cell) {
-            Player::One => "🔴".to_string(),
-            Player::Two => "🟡".to_string(),
-            Player::None => "🔵".to_string(),
-          })
-          .collect::<Vec<String>>()
-          .join(" ");
-      println!("{}", RowStr);
-    }
-    println!("{}--------------------{}", YELLOW, RESET);
-    if self.IsFinished {
-      match self.Winner {
-        Player::One => println!("{}🔴 Player One Wins!{}", YELLOW, RESET),
-        Player::Two => println!("{}🟡 Player Two Wins!{}", YELLOW, RESET),
-        Player::None => println!("{}It's a Draw!{}", YELLOW, RESET),
-      }
-    }
-    println!("{}--------------------{}", YELLOW, RESET);
-  }
-
-  // Displays an error message along with the current board state
-  fn DisplayError(&self, error: String) {
-    self.DisplayBoard();
-    println!("{}Error: {}{}", RED, error, RESET);
-  }
-
-  // Checks for a winner by scanning the board
-  fn FindWinner(&mut self) -> Player {
-    if self.CurrentMove < 7 {
-      return Player::None; // Not enough moves for a winner
-    }
-
-    let directions = [(0, 1), (1, 0), (1, 1), (1, -1)]; // Directions to check for a win
-    for row in 0..BOARD_HEIGHT {
-      for column in 0..BOARD_WIDTH {
-        let cell = self.Board[row][column];
-        if cell != 0 {
-          for &(RowStep, ColStep) in &directions {
-            let mut count = 1;
-            let mut r = row as isize + RowStep;
-            let mut c = column as isize + ColStep;
-
-            while r >= 0
-                && r < BOARD_HEIGHT as isize
-                && c >= 0
-                && c < BOARD_WIDTH as isize
-                && self.Board[r as usize][c as usize] == cell
-            {
-              count += 1;
-              if count == 4 {
-                return Player::FromInt(cell); // Return the winning player
-              }
-              r += RowStep;
-              c += ColStep;
-            }
+// Asks a yes or no question until it gets an answer
+fn Confirm(session: &Session, question: &str) -> bool {
+  loop {
+    println!("{}", locale::Say(Message::YesNo, &[("question", &question)]));
+    match input::ParseCommand(&session.Game, &ReadLine(), false) {
+      Ok(Command::Yes) => return true,
+      Ok(Command::No) => return false,
+      _ => println!("{}", locale::Text(Message::InvalidInput)),
+    }
+  }
+}
+
+// Checks the players really want to abandon the game, and whether to keep it in the autosave
+// to resume next time. Returns only if they change their mind.
+fn ConfirmQuit(session: &Session) {
+  if !Confirm(session, locale::Text(Message::QuitQuestion)) {
+    DisplayBoard(session);
+    return;
+  }
+  if !Confirm(session, locale::Text(Message::SaveQuestion)) {
+    save::ClearAutosave();
+  }
+  Quit();
+}
+
+// Asks the player on turn whether they take the draw their opponent offered
+fn AnswerDraw(session: &mut Session) {
+  let offerer = session.Game.CurrentPlayer().Other();
+  loop {
+    println!("{}", locale::Say(Message::AcceptDraw, &[("name", &session.Name(offerer))]));
+    match input::ParseCommand(&session.Game, &ReadLine(), false) {
+      Ok(Command::Yes) => {
+        let _ = session.Game.AcceptDraw();
+        DisplayBoard(session);
+        return;
+      }
+      Ok(Command::No) => {
+        let _ = session.Game.DeclineDraw();
+        DisplayBoard(session);
+        println!("{}", locale::Text(Message::DrawDeclined));
+        return;
+      }
+      _ => println!("{}", locale::Text(Message::InvalidInput)),
+    }
+  }
+}
+
+// Lists the commands understood at the prompt
+fn ShowHelp(session: &Session) {
+  let game = &session.Game;
+  println!("{}{}{}", Accent(), locale::Text(Message::Commands), Reset());
+  if !game.IsFinished() {
+    let moves = match (game.Gravity(), UseLetters(session)) {
+      (false, _) => ("4,3, col 4 row 3", Message::HelpPlace),
+      (true, true) => ("d, c4, col 4", Message::HelpDropLetters),
+      (true, false) => ("4, c4, col 4", Message::HelpDropNumbers),
+    };
+    println!("  {}{:<18}{} {}", Accent(), moves.0, Reset(), locale::Text(moves.1));
+    if game.PopOut() {
+      println!("  {}{:<18}{} {}", Accent(), "p4, pop 4", Reset(), locale::Text(Message::HelpPop));
+    }
+    if game.BombsLeft(game.CurrentPlayer()) > 0 {
+      println!("  {}{:<18}{} {}", Accent(), "b4, bomb 4", Reset(), locale::Text(Message::HelpBomb));
+    }
+  }
+  for (index, (_, _, description, command)) in input::COMMANDS.iter().enumerate() {
+    // Aliases are listed with the first entry for their command
+    if input::COMMANDS[..index].iter().any(|entry| entry.3 == *command) || !Available(session, command) {
+      continue;
+    }
+    let mut names = Vec::new();
+    for (name, short, _, _) in input::COMMANDS.iter().filter(|entry| entry.3 == *command) {
+      names.push(match command {
+        Command::Save(_) | Command::Load(_) => format!("{} <file>", name),
+        Command::Theme(_) => format!("{} <name>", name),
+        Command::Say(_) => format!("{} <message>", name),
+        _ => name.to_string(),
+      });
+      if !short.is_empty() && (!UseLetters(session) || *short == "?" || game.IsFinished()) {
+        names.push(short.to_string());
+      }
+    }
+    let player = game.CurrentPlayer() as usize - 1;
+    let left = match command {
+      Command::Hint => session.HintLimit.saturating_sub(session.HintsUsed[player]),
+      Command::Takeback => session.TakebackLimit.saturating_sub(session.TakebacksUsed[player]),
+      _ => {
+        println!("  {}{:<18}{} {}", Accent(), names.join(", "), Reset(), locale::Text(*description));
+        continue;
+      }
+    };
+    let left = locale::Say(Message::HelpLeft, &[("left", &left)]);
+    println!("  {}{:<18}{} {} {}", Accent(), names.join(", "), Reset(), locale::Text(*description), left);
+  }
+}
+
+// Returns true if the command does something at the prompt the players are at, given the
+// variant and the session's settings
+fn Available(session: &Session, command: &Command) -> bool {
+  let game = &session.Game;
+  if game.IsFinished() {
+    // The play-again prompt
+    return matches!(
+      command,
+      Command::Undo
+          | Command::History
+          | Command::Board
+          | Command::Save(_)
+          | Command::Load(_)
+          | Command::Theme(_)
+          | Command::Analysis
+          | Command::Transcript
+          | Command::Yes
+          | Command::No
+          | Command::Help
+          | Command::Quit
+    );
+  }
+  match command {
+    Command::Hint => engine::Supports(game) && session.HintLimit > 0 && !session.Competitive,
+    Command::Eval => engine::Supports(game),
+    Command::Teach => !session.Competitive,
+    Command::Draw => game.Players() == 2,
+    Command::Takeback => session.TakebackLimit > 0,
+    Command::Say(_) | Command::Chat => session.Remote.is_some(),
+    Command::Analysis | Command::Transcript | Command::Yes | Command::No => false,
+    _ => true,
+  }
+}
+
+// Prints the numbered moves so far under the board, leaving the screen as it is
+fn ShowHistory(game: &Game) {
+  if game.History().is_empty() {
+    println!("{}", locale::Text(Message::NoMovesYet));
+  } else {
+    println!("{}{}{}", Accent(), locale::Text(Message::MovesSoFar), Reset());
+    println!("{}", transcript::MoveList(game));
+  }
+}
+
+// Writes a plain-text record of the game to a file named by the player, today's date by default
+fn SaveTranscript(session: &Session) {
+  let default = transcript::DefaultFileName();
+  println!("{}", locale::Say(Message::TranscriptName, &[("default", &default)]));
+  let input = ReadLine();
+  let path = match input.trim() {
+    "" => default.as_str(),
+    name => name,
+  };
+  match std::fs::write(path, transcript::Transcript(&session.Game)) {
+    Ok(()) => println!("{}", locale::Say(Message::TranscriptSaved, &[("path", &path)])),
+    Err(err) => {
+      let error = locale::Say(Message::CouldNotWrite, &[("path", &path), ("error", &err)]);
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset())
+    }
+  }
+}
+
+// Replaces the game in progress with one read from a JSON file. A computer opponent stays
+// on only if it can play the loaded variant.
+fn LoadGame(session: &mut Session, path: &str) {
+  match save::LoadFile(path) {
+    Ok(game) => {
+      if !engine::Supports(&game) {
+        session.Bot = None;
+        session.Difficulty = None;
+        session.Eval = None;
+      }
+      *session.Game = game;
+      session.MoveClock = None;
+      session.StatsRecorded = session.Game.IsFinished();
+      session.TakebacksUsed = [0; MAX_PLAYERS];
+      session.HintsUsed = [0; MAX_PLAYERS];
+      DisplayBoard(session);
+      println!("{}", locale::Say(Message::GameLoaded, &[("path", &path)]));
+    }
+    Err(err) => DisplayError(session, err.to_string()),
+  }
+}
+
+// Plays a move sequence such as "4453" or "1. d d 2. e c" on an empty board of the size given
+// on the command line, printing an error if it doesn't fit the rules
+fn GameFromMoves(moves: &str) -> Option<Game> {
+  let mut game = match GameFromArgs() {
+    Ok(game) => game,
+    Err(err) => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return None;
+    }
+  };
+  if let Err(err) = game.PlayMoves(moves) {
+    println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+    return None;
+  }
+  Some(game)
+}
+
+// Names the position a tool was run on by its moves
+fn ShowPosition(moves: &str) {
+  let empty = locale::Text(Message::EmptyPosition);
+  println!("{}", locale::Say(Message::Position, &[("moves", &if moves.is_empty() { empty } else { moves })]));
+}
+
+// Counts move sequences to each depth up to `--depth N` (default 6), e.g. `bench 44`.
+// With `--threads N` it also times a fixed-depth search on one thread and on N threads.
+fn RunBench(moves: &str) {
+  let Some(mut game) = GameFromMoves(moves) else {
+    return;
+  };
+  let depth = ArgValue("--depth").and_then(|value| value.parse().ok()).unwrap_or(6);
+
+  ShowPosition(moves);
+  for level in 1..=depth {
+    let start = std::time::Instant::now();
+    let nodes = perft::Perft(&mut game, level);
+    let elapsed = start.elapsed();
+    let values: [(&str, &dyn std::fmt::Display); 4] = [
+      ("depth", &format!("{:>2}", level)),
+      ("nodes", &format!("{:>12}", nodes)),
+      ("seconds", &format!("{:>8.3}", elapsed.as_secs_f64())),
+      ("rate", &format!("{:.0}", nodes as f64 / elapsed.as_secs_f64().max(1e-9))),
+    ];
+    println!("{}", locale::Say(Message::BenchDepth, &values));
+  }
+
+  let Some(threads) = ArgValue("--threads").and_then(|value| value.parse::<usize>().ok()) else {
+    return;
+  };
+  let mut single = None;
+  for count in [1, threads] {
+    let start = std::time::Instant::now();
+    let Some(result) = Bot::new(10).WithThreads(count).Search(&game) else {
+      return; // Finished position, nothing to search
+    };
+    let elapsed = start.elapsed().as_secs_f64();
+    let speedup = single.map_or(String::new(), |time: f64| format!(", {:.2}x", time / elapsed));
+    let values: [(&str, &dyn std::fmt::Display); 6] = [
+      ("threads", &count),
+      ("column", &(result.BestMove + 1)),
+      ("score", &format!("{:+}", result.Score)),
+      ("seconds", &format!("{:.3}", elapsed)),
+      ("nodes", &result.Nodes),
+      ("speedup", &speedup),
+    ];
+    println!("{}", locale::Say(Message::BenchSearch, &values));
+    single.get_or_insert(elapsed);
+  }
+}
+
+// Steps through a saved game, e.g. `replay game.json`: 'n' or Enter for the next move, 'p' for
+// the previous one, a number to jump to that move, 'a' to play the rest automatically with
+// `--delay` milliseconds (default 1000) or 'a 500' between moves, and 'q' to leave.
+fn RunReplay(path: &str) {
+  match save::LoadFile(path) {
+    Ok(game) => ReplayGame(game),
+    Err(err) => println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset()),
+  }
+}
+
+// Steps through a finished or saved game from its first move
+fn ReplayGame(mut game: Game) {
+  if !InitTheme(&game) {
+    return;
+  }
+  // Rewind to the start; the moves stay available to redo
+  let total = game.History().len();
+  while game.UndoMove().is_ok() {}
+  let mut session = Session::new(game, None, None);
+  session.Eval = None;
+  let mut delay = ArgValue("--delay").and_then(|value| value.parse().ok()).unwrap_or(1000);
+
+  // Goes to the position after `step` moves and shows it, the last of them marked
+  let show = |session: &mut Session, step: usize| {
+    while session.Game.History().len() > step {
+      let _ = session.Game.UndoMove();
+    }
+    let mut outcome = None;
+    while session.Game.History().len() < step {
+      outcome = session.Game.RedoMove().ok();
+    }
+    if outcome.is_none() && step > 0 {
+      // Replay the last move to find out where it landed
+      let _ = session.Game.UndoMove();
+      outcome = session.Game.RedoMove().ok();
+    }
+    // The viewer shows each position itself, so what the observer was told goes unread
+    while session.Events.try_recv().is_ok() {}
+    DisplayBoard(session);
+    println!("{}", locale::Say(Message::ReplayStep, &[("step", &step), ("total", &total)]));
+    if let Some(outcome) = outcome {
+      DisplayMove(session, &outcome);
+    }
+  };
+
+  let mut step = 0;
+  show(&mut session, step);
+  loop {
+    println!("{}", locale::Text(Message::ReplayKeys));
+    let input = ReadLine().trim().to_lowercase();
+    match input.as_str() {
+      "" | "n" => step = (step + 1).min(total),
+      "p" => step = step.saturating_sub(1),
+      "q" => return,
+      _ if input.starts_with('a') => {
+        if let Ok(ms) = input[1..].trim().parse() {
+          delay = ms;
+        }
+        while step < total {
+          step += 1;
+          show(&mut session, step);
+          if step < total {
+            std::thread::sleep(Duration::from_millis(delay));
           }
         }
+        continue;
+      }
+      _ => match input.parse::<usize>() {
+        Ok(number) if number <= total => step = number,
+        _ => {
+          let error = locale::Say(Message::ReplayNoSuchMove, &[("total", &total)]);
+          println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset());
+          continue;
+        }
+      },
+    }
+    show(&mut session, step);
+  }
+}
+
+// Struct adding up the games of a simulation, seen from the side of `--p1`
+#[derive(Default)]
+struct Tally {
+  Results: [[u64; 3]; 2],       // Wins, draws and losses of --p1 as Player One, who moves first, and as Player Two
+  Moves: u64,                   // Moves played in all the games
+  MovesBy: [u64; 2],            // Moves chosen by --p1 and by --p2
+  Thinking: [Duration; 2],      // Time --p1 and --p2 spent choosing them
+  Problems: Vec<(usize, String)>, // Games that went wrong, by number, and how
+}
+
+impl Tally {
+  // Adds another tally's games to this one
+  fn Add(&mut self, other: Tally) {
+    for (side, results) in other.Results.iter().enumerate() {
+      for (outcome, count) in results.iter().enumerate() {
+        self.Results[side][outcome] += count;
       }
     }
+    self.Moves += other.Moves;
+    for side in 0..2 {
+      self.MovesBy[side] += other.MovesBy[side];
+      self.Thinking[side] += other.Thinking[side];
+    }
+    self.Problems.extend(other.Problems);
+  }
+}
+
+// Plays computer games without showing them, as in `simulate --games 1000 --p1 ai:mcts
+// --p2 ai:minimax:6`, and sums them up. The players take turns at moving first. With
+// `--jobs N` the games are shared out between N threads, game i going to thread i mod N, so
+// a seed and job count always give the same results. Exits with 1 if a game went wrong,
+// which makes it a stress test of the rules as well.
+fn RunSimulate() {
+  let specs = ["--p1", "--p2"].map(|flag| ArgValue(flag).and_then(|name| name.strip_prefix("ai:").map(str::to_string)));
+  let [Some(one), Some(two)] = specs else {
+    println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &locale::Text(Message::BothAi))]), Reset());
+    return;
+  };
+  let template = ComputerGame().and_then(|game| {
+    // Made once here to check the players, and again in each thread
+    let names = [AiPlayer(&one, 0)?.1, AiPlayer(&two, 0)?.1];
+    Ok((game, names))
+  });
+  let (template, names) = match template {
+    Ok(template) => template,
+    Err(err) => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return;
+    }
+  };
+  let number = |name: &str, default: usize| ArgValue(name).and_then(|value| value.parse().ok()).unwrap_or(default);
+  let games = number("--games", 100);
+  let jobs = number("--jobs", 1).clamp(1, games.max(1));
+  log::Info(format_args!("Simulating {} games of {} against {} on {} thread(s)", games, names[0], names[1], jobs));
+  let csv = match ArgValue("--csv").map(|path| csv::Csv::Create(&path)).transpose() {
+    Ok(csv) => csv,
+    Err(err) => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return;
+    }
+  };
 
-    if self.CurrentMove as usize == BOARD_WIDTH * BOARD_HEIGHT {
-      self.IsFinished = true; // Game ends in a draw
+  // Seeds are drawn up front, so they don't depend on which thread starts first
+  let seeds: Vec<[u64; 2]> = (0..jobs).map(|_| [NextSeed(), NextSeed()]).collect();
+  let started = Instant::now();
+  let mut tally = Tally::default();
+  std::thread::scope(|scope| {
+    let workers: Vec<_> = seeds
+        .iter()
+        .enumerate()
+        .map(|(job, seeds)| {
+          let (template, one, two, csv) = (&template, &one, &two, csv.as_ref());
+          scope.spawn(move || {
+            let mut tally = Tally::default();
+            let mut engines = [
+              AiPlayer(one, seeds[0]).expect("checked above").0,
+              AiPlayer(two, seeds[1]).expect("checked above").0,
+            ];
+            for number in (job..games).step_by(jobs) {
+              SimulateGame(template, &mut engines, [one, two], number, &mut tally, csv);
+            }
+            tally
+          })
+        })
+        .collect();
+    for (job, worker) in workers.into_iter().enumerate() {
+      match worker.join() {
+        Ok(other) => tally.Add(other),
+        Err(_) => tally.Problems.push((job, format!("thread {} panicked", job + 1))),
+      }
     }
+  });
+  let elapsed = started.elapsed();
+
+  let played: u64 = tally.Results.iter().flatten().sum();
+  let seconds = format!("{:.2}", elapsed.as_secs_f64());
+  let values: [(&str, &dyn std::fmt::Display); 4] = [("games", &played), ("seconds", &seconds), ("one", &names[0]), ("two", &names[1])];
+  println!("{}", locale::Say(Message::GamesPlayed, &values));
+  println!();
+  // Columns as wide as their headings, which are longer in some languages
+  let headings = [Message::TallyWins, Message::TallyDraws, Message::TallyLosses].map(locale::Text);
+  let width = headings.iter().map(|heading| heading.chars().count()).max().unwrap_or(0).max(6);
+  let line = |label: &str, row: [&dyn std::fmt::Display; 3]| {
+    println!("{:<28} {:>width$} {:>width$} {:>width$}", label, row[0], row[1], row[2], width = width);
+  };
+  line("", [&headings[0], &headings[1], &headings[2]]);
+  let [won, drawn, lost] = [0, 1, 2].map(|outcome| tally.Results[0][outcome] + tally.Results[1][outcome]);
+  for (side, name) in names.iter().enumerate() {
+    let row = if side == 0 { [won, drawn, lost] } else { [lost, drawn, won] };
+    line(&format!("p{} {}", side + 1, name), [&row[0], &row[1], &row[2]]);
+  }
+  println!();
+  println!("{}", locale::Text(Message::MovingFirst));
+  for (side, name) in names.iter().enumerate() {
+    // --p1 moves first in Results[0], --p2 in Results[1], where --p2's wins are --p1's losses
+    let [won, drawn, lost] = tally.Results[side];
+    let row = if side == 0 { [won, drawn, lost] } else { [lost, drawn, won] };
+    line(&format!("p{} {}", side + 1, name), [&row[0], &row[1], &row[2]]);
+  }
+  println!();
+  let average = format!("{:.1}", tally.Moves as f64 / played.max(1) as f64);
+  println!("{}", locale::Say(Message::AverageGame, &[("moves", &average)]));
+  for (side, name) in names.iter().enumerate() {
+    let average = tally.Thinking[side].as_secs_f64() * 1000.0 / tally.MovesBy[side].max(1) as f64;
+    let player = format!("p{} {}", side + 1, name);
+    println!("{}", locale::Say(Message::AverageMoveTime, &[("player", &player), ("ms", &format!("{:.2}", average))]));
+  }
+  ShowSeed();
 
-    Player::None // No winner yet
+  if !tally.Problems.is_empty() {
+    tally.Problems.sort();
+    println!();
+    for (number, problem) in &tally.Problems {
+      println!("{}{}{}", Alert(), locale::Say(Message::GameProblem, &[("number", &(number + 1)), ("problem", problem)]), Reset());
+    }
+    std::process::exit(1);
   }
+}
 
-  // Processes a move by a player
-  fn MakeMove(&mut self, column: usize) -> Result<(), MoveError> {
-    if self.IsFinished {
-      return Err(MoveError::GameFinished); // Game is over
+// Plays one game of a simulation from the template position, adding its row to the CSV file
+// if there is one. `engines[0]`, from `--p1`, plays Player One in even-numbered games and
+// Player Two in odd ones.
+fn SimulateGame(
+  template: &Game,
+  engines: &mut [Box<dyn Engine>; 2],
+  specs: [&str; 2],
+  number: usize,
+  tally: &mut Tally,
+  csv: Option<&csv::Csv>,
+) {
+  let swapped = number % 2 == 1;
+  let [one, two] = engines;
+  let mut thinking = [Duration::ZERO; 2];
+  let mut moves = [0; 2];
+  let started = Instant::now();
+  let played = match swapped {
+    false => PlayOut(template, [one, two], &mut thinking, &mut moves),
+    true => PlayOut(template, [two, one], &mut thinking, &mut moves),
+  };
+  let players = if swapped { [specs[1], specs[0]] } else { specs };
+  if let Some(Err(err)) = csv.map(|csv| csv.Game(number + 1, Seed(), players, &played, started.elapsed())) {
+    tally.Problems.push((number, err));
+  }
+  if swapped {
+    thinking.swap(0, 1);
+    moves.swap(0, 1);
+  }
+  for side in 0..2 {
+    tally.Thinking[side] += thinking[side];
+    tally.MovesBy[side] += moves[side];
+  }
+  let game = match played {
+    Ok(game) => game,
+    Err(problem) => {
+      tally.Problems.push((number, problem));
+      return;
     }
+  };
+  let first = if swapped { Player::Two } else { Player::One };
+  let outcome = match game.Winner() {
+    Player::None => 1,
+    winner if winner == first => 0,
+    _ => 2,
+  };
+  tally.Results[usize::from(swapped)][outcome] += 1;
+  tally.Moves += (game.History().len() - template.History().len()) as u64;
+  log::Debug(format_args!("Game {} finished: {:?} after {} moves", number + 1, game.Winner(), game.CurrentMove()));
+}
+
+// Plays a computer game out from the template position, `engines[0]` playing Player One,
+// adding up the time each engine spends choosing moves and how many it chooses. Returns the
+// finished game, or what went wrong with it.
+fn PlayOut(
+  template: &Game,
+  engines: [&mut Box<dyn Engine>; 2],
+  thinking: &mut [Duration; 2],
+  moves: &mut [u64; 2],
+) -> Result<Game, String> {
+  let mut game = template.clone();
+  while !game.IsFinished() {
+    let player = game.CurrentPlayer();
+    let side = usize::from(player == Player::Two);
+    let started = Instant::now();
+    let column = engines[side].ChooseMove(&game).ok_or(format!("{:?} found no move in an unfinished game", player))?;
+    thinking[side] += started.elapsed();
+    moves[side] += 1;
+    game.MakeMove(column).map_err(|err| format!("{:?} chose column {}: {}", player, column + 1, err))?;
+  }
+  match Inconsistency(template, &game) {
+    Some(problem) => Err(problem),
+    None => Ok(game),
+  }
+}
 
-    if column >= BOARD_WIDTH {
-      return Err(MoveError::InvalidColumn); // Invalid column
+// What's wrong with a finished game, if anything: the winner has to be the one the board
+// shows, a draw needs a full board, and its moves played again have to end the same way
+fn Inconsistency(template: &Game, game: &Game) -> Option<String> {
+  let shown = game.ScanForWinner();
+  if shown != game.Winner() {
+    return Some(format!("the winner is {:?} but the board shows {:?}", game.Winner(), shown));
+  }
+  if game.Winner() == Player::None && !game.LegalMoves().is_empty() {
+    return Some("drawn with moves still left".to_string());
+  }
+  let mut replay = template.clone();
+  for &played in &game.History()[template.History().len()..] {
+    if let Err(err) = replay.Play(played) {
+      return Some(format!("replaying {:?} failed: {}", played, err));
     }
+  }
+  match (replay.IsFinished(), replay.Winner()) {
+    (true, winner) if winner == game.Winner() => None,
+    (finished, winner) => Some(format!("replayed, it ends {:?} (finished: {}) instead of {:?}", winner, finished, game.Winner())),
+  }
+}
 
-    // Find the first available row in the column
-    if let Some(row) = (0..BOARD_HEIGHT).rev().find(|&row| self.Board[row][column] == 0) {
-      self.Board[row][column] = self.CurrentPlayer as u8; // Place the piece
-      self.CurrentMove += 1;
-    } else {
-      return Err(MoveError::ColumnFull); // Column is full
+// Lets two computer players, from `--p1 ai:hard --p2 ai:mcts`, play a game out, each move
+// shown for `--delay` milliseconds (default 500). p pauses and q quits, as single keys when
+// the input is a terminal.
+fn RunExhibition(one: &str, two: &str) {
+  let started = ComputerGame().and_then(|game| Ok((game, AiPlayer(one, NextSeed())?, AiPlayer(two, NextSeed())?)));
+  let (game, (first, FirstName), (second, SecondName)) = match started {
+    Ok(started) => started,
+    Err(err) => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return;
+    }
+  };
+  if !InitTheme(&game) {
+    return;
+  }
+  let mut session = Session::new(game, None, None);
+  session.Names[0] = FirstName;
+  session.Names[1] = SecondName;
+  let mut engines = [first, second];
+  let delay = Duration::from_millis(ArgValue("--delay").and_then(|value| value.parse().ok()).unwrap_or(500));
+  terminal::ReadKeys();
+  session.LogStart();
+  DisplayBoard(&session);
+  while !session.Game.IsFinished() {
+    println!("{}", locale::Text(Message::ExhibitionKeys));
+    if !ExhibitionWait(delay) {
+      break;
+    }
+    let mover = session.Game.CurrentPlayer();
+    let name = session.Name(mover).to_string();
+    let started = Instant::now();
+    let action = BotController { Engine: engines[mover as usize - 1].as_mut(), Name: &name }.ChooseMove(&session.Game);
+    if session.Game.SpendTime(started.elapsed()) {
+      DisplayBoard(&session);
+      continue;
+    }
+    let PlayerAction::Play(played) = action else {
+      continue;
     };
+    session.Game.Play(played).expect("bot picks a legal move");
+    ShowEvents(&mut session, Some(&name));
+    let other = mover.Other();
+    let name = session.Name(other).to_string();
+    BotController { Engine: engines[other as usize - 1].as_mut(), Name: &name }.Observe(&session.Game, played);
+  }
+  if session.Game.IsFinished() {
+    Celebrate(&mut session);
+    session.LogResult();
+    println!("{}", locale::Say(Message::ExhibitionOver, &[("moves", &session.Game.History().len())]));
+    if let Some(moves) = session.Game.ToMoveString() {
+      println!("{}", locale::Say(Message::Moves, &[("moves", &moves)]));
+    }
+    ShowSeed();
+  }
+  terminal::Restore();
+}
 
-    let FoundWinner = self.FindWinner();
-    if FoundWinner != Player::None {
-      self.Winner = FoundWinner; // Set the winner
-      self.IsFinished = true;   // Mark the game as finished
-    } else {
-      // Switch to the next player
-      self.CurrentPlayer = match self.CurrentPlayer {
-        Player::One => Player::Two,
-        _ => Player::One,
-      };
+// Waits between the moves of a computer game. p pauses until it's pressed again; returns
+// false if q is pressed.
+fn ExhibitionWait(delay: Duration) -> bool {
+  let mut deadline = Some(Instant::now() + delay);
+  loop {
+    match ReadLineBy(deadline, &mut || ()) {
+      Wait::Interrupted => Quit(),
+      Wait::TimedOut => return true,
+      Wait::Line(line) => match line.trim() {
+        "q" => return false,
+        "p" if deadline.is_some() => {
+          deadline = None;
+          println!("{}{}{}", Accent(), locale::Text(Message::ExhibitionPaused), Reset());
+        }
+        "p" => return true,
+        // Nothing more will be typed, so there's no pausing to wait for
+        _ if interrupt::InputEnded() => return true,
+        _ => {}
+      },
     }
-    Ok(())
+  }
+}
+
+// Solves the position reached by a move sequence, e.g. `solve 4453` or `solve "1. d d 2. e c"`
+fn RunSolve(moves: &str) {
+  let Some(game) = GameFromMoves(moves) else {
+    return;
+  };
+  let megabytes = ArgValue("--tt-mb")
+      .and_then(|value| value.parse().ok())
+      .unwrap_or(DEFAULT_TABLE_MB);
+  let mut solver = Solver::WithMemory(megabytes);
+  let Some(evaluation) = solver.Solve(&game) else {
+    let error = locale::Say(
+      Message::SolverUnsupported,
+      &[("length", &WIN_LENGTH), ("width", &BOARD_WIDTH), ("height", &BOARD_HEIGHT)],
+    );
+    println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset());
+    return;
+  };
+  let side = locale::Text(match game.CurrentPlayer() {
+    Player::One => Message::PlayerOne,
+    _ => Message::PlayerTwo,
+  });
+
+  ShowPosition(moves);
+  match evaluation.Outcome {
+    Outcome::Win => println!("{}", locale::Say(Message::ToMoveWins, &[("player", &side), ("moves", &evaluation.MovesToEnd)])),
+    Outcome::Loss => println!("{}", locale::Say(Message::ToMoveLoses, &[("player", &side), ("moves", &evaluation.MovesToEnd)])),
+    Outcome::Draw => println!("{}", locale::Text(Message::DrawWithPerfectPlay)),
+  }
+  let columns: Vec<String> = evaluation.BestMoves.iter().map(|column| (column + 1).to_string()).collect();
+  println!("{}", locale::Say(Message::BestColumns, &[("columns", &columns.join(" "))]));
+  println!("{}", locale::Say(Message::PositionsSearched, &[("nodes", &solver.Nodes())]));
+}
+
+// Writes a config file to start from with `config init`, or says where the config file goes
+fn RunConfig(action: Option<&str>) {
+  let fail = |error: &dyn std::fmt::Display| {
+    println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", error)]), Reset())
+  };
+  match action {
+    Some("init") => match config::Init(ArgValue("--config")) {
+      Ok(path) => println!("{}", locale::Say(Message::ConfigWritten, &[("path", &path.display())])),
+      Err(err) => fail(&err),
+    },
+    Some(other) => fail(&locale::Say(Message::UnknownConfigAction, &[("action", &other)])),
+    None => match config::ConfigPath() {
+      Some(path) => println!("{}", locale::Say(Message::ConfigReadFrom, &[("path", &path.display())])),
+      None => fail(&locale::Text(Message::NoHomeForConfig)),
+    },
   }
 }
 
 fn main() {
-  let mut game = Game::new();
-  game.DisplayBoard();
+  interrupt::Install();
+  let args: Vec<String> = std::env::args().collect();
+  if cli::HelpWanted(&args) {
+    println!("{}", cli::Help());
+    return;
+  }
+  if cli::VersionWanted(&args) {
+    println!("{}", cli::Version());
+    return;
+  }
+  // With --json stdout carries nothing but events, so messages for people go to stderr
+  let json = args.iter().any(|arg| arg == "--json");
+  let report = |text: String| match json {
+    true => eprintln!("{}", text),
+    false => println!("{}{}{}", Alert(), text, Reset()),
+  };
+  if let Err(err) = cli::Check(&args) {
+    report(locale::Say(Message::Error, &[("error", &err)]));
+    return;
+  }
+  // Checked before the config file is read, so a broken one can be replaced; the language
+  // then comes only from `--lang` or the locale
+  if args.get(1).map(String::as_str) == Some("config") {
+    match locale::Init(ArgValue("--lang").as_deref()) {
+      Ok(()) => RunConfig(args.get(2).map(String::as_str)),
+      Err(err) => report(locale::Say(Message::Error, &[("error", &err)])),
+    }
+    return;
+  }
+  match config::Load(ArgValue("--config")) {
+    Ok(warnings) => {
+      for warning in warnings {
+        report(warning);
+      }
+    }
+    Err(err) => {
+      report(locale::Say(Message::Error, &[("error", &err)]));
+      return;
+    }
+  }
+  if let Err(err) = LogLevel().and_then(|level| log::Init(level, ArgValue("--log-file").as_deref())) {
+    report(locale::Say(Message::Error, &[("error", &err)]));
+    return;
+  }
+  log::Info(format_args!("{} started: {}", cli::Version(), args[1..].join(" ")));
+  // Messages are in the language from `--lang`, or else the locale's
+  if let Err(err) = locale::Init(ArgValue("--lang").as_deref()) {
+    report(locale::Say(Message::Error, &[("error", &err)]));
+    return;
+  }
+  // Moves and answers come from the script instead of the keyboard
+  if let Some(path) = ArgValue("--script") {
+    match input::Scripted::FromFile(&path) {
+      Ok(script) => input::Use(Box::new(script)),
+      Err(err) => {
+        report(locale::Say(Message::Error, &[("error", &err)]));
+        return;
+      }
+    }
+  }
+  if json {
+    events::Run();
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("replay") {
+    match args.get(2) {
+      Some(path) => RunReplay(path),
+      None => {
+        let error = locale::Text(Message::NoReplayFile);
+        println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &error)]), Reset())
+      }
+    }
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("stats") || args.iter().any(|arg| arg == "--stats") {
+    ShowStats(false);
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("games") {
+    let rest: Vec<&str> = args[2..].iter().map(String::as_str).take_while(|arg| !arg.starts_with("--")).collect();
+    RunGames(rest.first().copied(), rest.get(1).copied());
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("ratings") {
+    ShowStats(true);
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("solve") {
+    RunSolve(args.get(2).map(String::as_str).unwrap_or(""));
+    return;
+  }
+  if matches!(args.get(1).map(String::as_str), Some("bench" | "perft")) {
+    // A flag right after the command means the starting position
+    let moves = args.get(2).filter(|arg| !arg.starts_with("--"));
+    RunBench(moves.map(String::as_str).unwrap_or(""));
+    return;
+  }
+
+  if args.get(1).map(String::as_str) == Some("engine") {
+    protocol::Run();
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("serve") {
+    match ArgValue("--http") {
+      Some(_) => http::Run(),
+      None => server::Run(),
+    }
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("simulate") {
+    RunSimulate();
+    return;
+  }
+  if args.get(1).map(String::as_str) == Some("tournament") {
+    tournament::Run(args.get(2).map(String::as_str).filter(|arg| !arg.starts_with("--")));
+    return;
+  }
+  // Two computer players, as in `--p1 ai:hard --p2 ai:easy`, play each other
+  let ai = |flag: &str| ArgValue(flag).and_then(|name| name.strip_prefix("ai:").map(str::to_string));
+  match (ai("--p1"), ai("--p2")) {
+    (Some(one), Some(two)) => {
+      RunExhibition(&one, &two);
+      return;
+    }
+    (None, None) => {}
+    _ => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &locale::Text(Message::BothAi))]), Reset());
+      return;
+    }
+  }
+  // A game over the network, the host choosing the rules
+  if matches!(args.get(1).map(String::as_str), Some("host" | "join")) {
+    let address = args.get(2).filter(|arg| !arg.starts_with('-')).map(String::as_str);
+    match NetworkSession(&args[1], address) {
+      Some(session) if session.Remote.as_ref().is_some_and(|remote| remote.Seat == Player::None) => WatchGame(session),
+      Some(session) => Play(session),
+      None => {}
+    }
+    return;
+  }
+  // A saved game brings its own size and rules. Without one, an unfinished game from last
+  // time can be picked up unless the command line asks for a particular start.
+  let fresh = ["--moves", "--setup"].iter().all(|flag| !args.iter().any(|arg| arg == flag));
+  let game = match ArgValue("--load") {
+    Some(path) => save::LoadFile(&path).map_err(|err| err.to_string()),
+    None => match fresh.then(ResumeAutosave).flatten() {
+      Some(game) => Ok(game),
+      None => GameFromArgs().map_err(|err| err.to_string()),
+    },
+  };
+  let mut game = match game {
+    Ok(game) => game,
+    Err(err) => {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return;
+    }
+  };
+  if !InitTheme(&game) {
+    return;
+  }
+  if Flag("--big") {
+    if let Err(err) = BigFits(game.Width(), game.Height(), game.Wrap()) {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return;
+    }
+  }
+  // Start from a shared position such as `--moves 44435` or `--moves "1. d d 2. c"`
+  if let Some(moves) = ArgValue("--moves") {
+    if let Err(err) = game.PlayMoves(&moves) {
+      println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+      return;
+    }
+  }
+  if Flag("--setup") {
+    SetupPosition(&mut game);
+  }
+  log::Info(format_args!("Seed {}", Seed()));
+  ShowSeed();
+  Play(NewSession(game));
+}
+
+// Plays the session's game, and more with the same players for as long as they like
+fn Play(mut session: Session) {
+  if Flag("--tui") && session.Remote.is_none() {
+    tui::Run(&mut session);
+  }
+  // Single keys make moves once the names and opponent are settled
+  if Flag("--raw-input") && !terminal::ReadKeys() {
+    println!("{}{}{}", Alert(), locale::Text(Message::RawInputNeedsTerminal), Reset());
+  }
+  DisplayBoard(&session);
   loop {
-    while !game.IsFinished {
-      println!("\n");
-      match game.CurrentPlayer {
-        Player::One => println!("Player 1"),
-        Player::Two => println!("Player 2"),
-        _ => (),
-      }
-      println!("Enter a column number (1-7): ");
-      let mut input = String::new();
-      io::stdin()
-          .read_line(&mut input)
-          .expect("Failed to read line");
-
-      let input: usize = match input.trim().parse() {
-        Ok(num) => {
-          if num < 1 || num > 7 {
-            game.DisplayError("Invalid column number".to_string());
+    while !session.Game.IsFinished() {
+      Autosave(&session.Game);
+      let player = session.Game.CurrentPlayer();
+      let seat = SeatOf(&session, player);
+      let action = match seat {
+        Seat::Remote => RemoteTurn(&mut session),
+        Seat::Computer => match BotTurn(&mut session) {
+          Some(action) => action,
+          None => continue,
+        },
+        Seat::Human => match HumanTurn(&mut session) {
+          Some(action) => action,
+          None => continue,
+        },
+      };
+
+      let played = match action {
+        PlayerAction::Play(played) | PlayerAction::Command(Command::Play(played)) => played,
+        PlayerAction::Quit => Quit(),
+        PlayerAction::Lost(err) => {
+          Reconnect(&mut session, &err);
+          continue;
+        }
+        PlayerAction::TimedOut => {
+          let _ = session.Game.Forfeit(); // Already over if the clock ran out
+          DisplayBoard(&session);
+          println!("{}", locale::Text(Message::TimeUp));
+          continue;
+        }
+        PlayerAction::Command(
+          Command::Undo | Command::Redo | Command::Takeback | Command::Draw | Command::Load(_),
+        ) if session.Remote.is_some() => {
+          DisplayError(&session, locale::Text(Message::NotOverNetwork).to_string());
+          continue;
+        }
+        PlayerAction::Command(Command::Undo) => {
+          UndoTurn(&mut session);
+          continue;
+        }
+        PlayerAction::Command(Command::Redo) => {
+          match session.Game.RedoMove() {
+            Ok(_) => ShowEvents(&mut session, None),
+            Err(err) => DisplayError(&session, err.Localized()),
+          }
+          continue;
+        }
+        PlayerAction::Command(Command::Eval) => {
+          if !engine::Supports(&session.Game) {
+            DisplayError(&session, locale::Text(Message::EvalUnavailable).to_string());
             continue;
+          }
+          // Toggle the evaluation bar; it spoils competitive games so it starts off
+          session.Eval = match session.Eval {
+            Some(_) => None,
+            None => Some(EvalBar::new()),
+          };
+          DisplayBoard(&session);
+          continue;
+        }
+        PlayerAction::Command(Command::Hint) => {
+          ShowHint(&mut session);
+          continue;
+        }
+        PlayerAction::Command(Command::History) => {
+          ShowHistory(&session.Game);
+          continue;
+        }
+        PlayerAction::Command(Command::Board) => {
+          DisplayBoard(&session);
+          DescribeBoard(&session.Game);
+          continue;
+        }
+        PlayerAction::Command(Command::Teach) => {
+          // Each player decides for themselves whether they want the advice
+          DisplayBoard(&session);
+          if session.Competitive {
+            println!("{}{}{}", Alert(), locale::Text(Message::TeachingCompetitive), Reset());
           } else {
-            num
+            let teaching = &mut session.Teaching[player as usize - 1];
+            *teaching = !*teaching;
+            let message = if *teaching { Message::TeachingOn } else { Message::TeachingOff };
+            println!("{}", locale::Say(message, &[("color", &ColorName(player))]));
+          }
+          continue;
+        }
+        PlayerAction::Command(Command::Takeback) => {
+          RequestTakeback(&mut session);
+          continue;
+        }
+        PlayerAction::Command(Command::Draw) => {
+          match session.Game.OfferDraw(player) {
+            Ok(()) => {
+              DisplayBoard(&session);
+              let offer =
+                  locale::Say(Message::DrawOffered, &[("name", &session.Name(player)), ("other", &session.Name(player.Other()))]);
+              println!("{}", offer);
+            }
+            Err(err) => DisplayError(&session, err.Localized()),
           }
+          continue;
+        }
+        PlayerAction::Command(Command::Threats) => {
+          session.Threats = !session.Threats;
+          DisplayBoard(&session);
+          continue;
+        }
+        PlayerAction::Command(Command::Save(path)) => {
+          SaveGame(&session, &path);
+          continue;
+        }
+        PlayerAction::Command(Command::Load(path)) => {
+          LoadGame(&mut session, &path);
+          continue;
+        }
+        PlayerAction::Command(Command::Theme(name)) => {
+          SwitchTheme(&session, &name);
+          continue;
+        }
+        PlayerAction::Command(Command::Help) => {
+          DisplayBoard(&session);
+          ShowHelp(&session);
+          continue;
+        }
+        PlayerAction::Command(Command::Quit) => {
+          ConfirmQuit(&session);
+          continue;
         }
-        Err(err) => {
-          game.DisplayError(err.to_string());
+        PlayerAction::Command(Command::Say(text)) => {
+          SayToRemote(&mut session, &text);
+          continue;
+        }
+        PlayerAction::Command(Command::Chat) => {
+          ShowChat(&session);
+          continue;
+        }
+        PlayerAction::Command(Command::Analysis | Command::Transcript | Command::Yes | Command::No) => {
+          DisplayError(&session, locale::Text(Message::OnlyWhenOver).to_string());
+          continue;
+        }
+        PlayerAction::Invalid(err) => {
+          DisplayError(&session, err);
           continue;
         }
       };
-      match game.MakeMove(input - 1) {
-        Ok(_) => game.DisplayBoard(),
-        Err(err) => game.DisplayError(err.to_string()),
-      }
-    }
-    println!("Do you want to play again? (y/n)");
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
-    match input.trim().to_lowercase().as_str() {
-      "y" => {
-        game = Game::new();
-        game.DisplayBoard();
-      }
-      "n" => break,
-      _ => println!("Invalid input"),
+      // Only a move typed here is shown before it's dropped
+      let played = match seat {
+        Seat::Human => match PreviewDrop(&mut session, played) {
+          Some(played) => played,
+          None => continue,
+        },
+        _ => played,
+      };
+      // How the move went is shown from what the game's observer was told
+      let moved = session.Game.Play(played).is_ok();
+      let chose = session.Bot.as_ref().filter(|_| seat == Seat::Computer).map(|bot| bot.Name());
+      ShowEvents(&mut session, chose.as_deref());
+      if moved {
+        Notify(&mut session, player, played);
+      }
+    }
+    if let Some(remote) = session.Remote.as_mut().filter(|remote| !remote.Abandoned) {
+      // The other end needs the last move to see the game end too
+      if let Err(err) = remote.SendMoves(&session.Game) {
+        let name = &session.Names[remote.Seat as usize - 1];
+        let lost = locale::Say(Message::ConnectionLost, &[("name", name), ("error", &err)]);
+        println!("{}{}{}", Alert(), lost, Reset());
+      }
+    }
+    Celebrate(&mut session);
+    session.Renderer.Announce(&session, GameEvent::Finished);
+    Autosave(&session.Game);
+    RecordStats(&mut session);
+    if let Some(moves) = session.Game.ToMoveString() {
+      println!("{}", locale::Say(Message::Moves, &[("moves", &moves)]));
+    }
+    if let Some(notation) = session.Game.ToNotation() {
+      println!("{}", locale::Say(Message::Notation, &[("notation", &notation)]));
+    }
+    ShowSeed();
+    if session.Remote.is_some() {
+      // Another game means hosting and joining again
+      return;
+    }
+    println!("{}", locale::Text(Message::PlayAgain));
+    let input = ReadLine();
+    // No moves to type here, so the shortcut letters always work
+    match input::ParseCommand(&session.Game, &input, false) {
+      Ok(Command::Yes) => {
+        session.Restart();
+        ShowEvents(&mut session, None);
+      }
+      Ok(Command::Hint) => ShowHint(&mut session),
+      Ok(Command::Analysis) => ShowAnalysis(&session),
+      Ok(Command::Transcript) => SaveTranscript(&session),
+      Ok(Command::History) => ShowHistory(&session.Game),
+      Ok(Command::Board) => DescribeBoard(&session.Game),
+      Ok(Command::Save(path)) => SaveGame(&session, &path),
+      Ok(Command::Load(path)) => LoadGame(&mut session, &path),
+      Ok(Command::Theme(name)) => SwitchTheme(&session, &name),
+      Ok(Command::Help) => ShowHelp(&session),
+      Ok(Command::No | Command::Quit) => break,
+      Ok(Command::Undo) => {
+        // Taking back the final move resumes the game
+        UndoTurn(&mut session);
+      }
+      Ok(_) => println!("{}", locale::Text(Message::InvalidInput)),
+      Err(err) => println!("{}", err),
     }
   }
 }
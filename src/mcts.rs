@@ -0,0 +1,242 @@
+use std::time::{Duration, Instant};
+
+use crate::engine::Engine;
+use crate::log;
+use crate::rng::Rng;
+use crate::{Game, Player};
+
+// Exploration constant for UCT; sqrt(2) is the textbook choice
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+// Struct representing one node of the search tree
+struct Node {
+  Move: Option<usize>,      // Column played to reach this node (None for the root)
+  Parent: Option<usize>,    // Index of the parent node
+  Children: Vec<usize>,     // Indices of expanded children
+  Untried: Vec<usize>,      // Columns not yet expanded
+  Mover: Player,            // Player who played `Move`
+  Visits: u32,              // Number of playouts through this node
+  Score: f64,               // Playout results from the point of view of `Mover`
+}
+
+impl Node {
+  fn new(game: &Game, column: Option<usize>, parent: Option<usize>, mover: Player) -> Node {
+    Node {
+      Move: column,
+      Parent: parent,
+      Children: Vec::new(),
+      Untried: game.LegalMoves(),
+      Mover: mover,
+      Visits: 0,
+      Score: 0.0,
+    }
+  }
+}
+
+// Struct representing a Monte Carlo tree search opponent
+pub struct Mcts {
+  Iterations: usize,           // Playouts to run per move
+  TimeLimit: Option<Duration>, // Optional wall-clock budget that stops the search early
+  Rng: Rng,                    // Randomness for expansion and rollouts
+}
+
+impl Mcts {
+  // Creates a searcher that runs the given number of playouts per move
+  pub fn new(iterations: usize, seed: u64) -> Mcts {
+    Mcts { Iterations: iterations.max(1), TimeLimit: None, Rng: Rng::new(seed) }
+  }
+
+  // Stops searching once the time budget is spent
+  pub fn WithTimeLimit(mut self, limit: Duration) -> Mcts {
+    self.TimeLimit = Some(limit);
+    self
+  }
+
+  // Runs the search and returns the most visited column
+  pub fn Search(&mut self, game: &Game) -> Option<usize> {
+    if game.LegalMoves().is_empty() {
+      return None;
+    }
+
+    let started = Instant::now();
+    let deadline = self.TimeLimit.map(|limit| Instant::now() + limit);
+    let mut tree = vec![Node::new(game, None, None, Player::None)];
+    let mut playouts = 0;
+    for _ in 0..self.Iterations {
+      if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        break;
+      }
+      playouts += 1;
+      let mut scratch = game.clone();
+      let leaf = self.SelectAndExpand(&mut tree, &mut scratch);
+      let winner = self.Rollout(&mut scratch);
+      Backpropagate(&mut tree, leaf, winner);
+    }
+
+    let best = tree[0].Children.iter().copied().max_by_key(|&child| tree[child].Visits);
+    if let Some(child) = best {
+      let node = &tree[child];
+      log::Debug(format_args!(
+        "MCTS ran {} of {} playouts in {:?}: column {:?}, scored {:.1} in {} visits",
+        playouts,
+        self.Iterations,
+        started.elapsed(),
+        node.Move.map(|column| column + 1),
+        node.Score,
+        node.Visits
+      ));
+    }
+    best.and_then(|child| tree[child].Move)
+  }
+
+  // Walks down the tree by UCT, then expands one untried move; returns the new leaf
+  fn SelectAndExpand(&mut self, tree: &mut Vec<Node>, game: &mut Game) -> usize {
+    let mut node = 0;
+    while tree[node].Untried.is_empty() && !tree[node].Children.is_empty() {
+      node = BestChild(tree, node);
+      game.MakeMove(tree[node].Move.expect("child has a move")).expect("tree move is legal");
+    }
+
+    if tree[node].Untried.is_empty() {
+      return node; // Terminal position
+    }
+    let index = self.Rng.Below(tree[node].Untried.len());
+    let column = tree[node].Untried.swap_remove(index);
+    let mover = game.CurrentPlayer();
+    game.MakeMove(column).expect("untried move is legal");
+
+    tree.push(Node::new(game, Some(column), Some(node), mover));
+    let child = tree.len() - 1;
+    tree[node].Children.push(child);
+    child
+  }
+
+  // Plays random moves until the game ends and returns the winner
+  fn Rollout(&mut self, game: &mut Game) -> Player {
+    while !game.IsFinished() {
+      let column = self.Rng.Pick(&game.LegalMoves());
+      game.MakeMove(column).expect("random legal move");
+    }
+    game.Winner()
+  }
+}
+
+impl Engine for Mcts {
+  fn ChooseMove(&mut self, game: &Game) -> Option<usize> {
+    self.Search(game)
+  }
+
+  fn Name(&self) -> String {
+    format!("MCTS ({} playouts)", self.Iterations)
+  }
+
+  fn LimitTime(&mut self, limit: Duration) {
+    self.TimeLimit = Some(limit);
+  }
+}
+
+// Picks the child with the highest upper confidence bound
+fn BestChild(tree: &[Node], node: usize) -> usize {
+  let ParentVisits = (tree[node].Visits.max(1) as f64).ln();
+  let uct = |child: usize| {
+    let child = &tree[child];
+    let visits = child.Visits.max(1) as f64;
+    child.Score / visits + EXPLORATION * (ParentVisits / visits).sqrt()
+  };
+  *tree[node]
+      .Children
+      .iter()
+      .max_by(|&&a, &&b| uct(a).total_cmp(&uct(b)))
+      .expect("node has children")
+}
+
+// Credits the playout result to every node on the path back to the root
+fn Backpropagate(tree: &mut [Node], leaf: usize, winner: Player) {
+  let mut node = Some(leaf);
+  while let Some(index) = node {
+    let current = &mut tree[index];
+    current.Visits += 1;
+    current.Score += if winner == Player::None {
+      0.5 // Draw
+    } else if winner == current.Mover {
+      1.0
+    } else {
+      0.0
+    };
+    node = current.Parent;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A node reached by `mover` playing `column`, with the visits and score it's collected
+  fn Visited(column: usize, parent: usize, mover: Player, visits: u32, score: f64) -> Node {
+    let mut node = Node::new(&Game::default(), Some(column), Some(parent), mover);
+    node.Visits = visits;
+    node.Score = score;
+    node
+  }
+
+  #[test]
+  fn BestChildPicksTheHigherBound() {
+    let mut root = Node::new(&Game::default(), None, None, Player::None);
+    root.Visits = 10;
+    root.Children = vec![1, 2];
+    let tree = vec![root, Visited(0, 0, Player::One, 5, 1.0), Visited(3, 0, Player::One, 5, 4.0)];
+    assert_eq!(BestChild(&tree, 0), 2);
+
+    // A child seen far less often wins on exploration alone
+    let mut root = Node::new(&Game::default(), None, None, Player::None);
+    root.Visits = 101;
+    root.Children = vec![1, 2];
+    let tree = vec![root, Visited(0, 0, Player::One, 100, 60.0), Visited(3, 0, Player::One, 1, 0.0)];
+    assert_eq!(BestChild(&tree, 0), 2);
+  }
+
+  #[test]
+  fn BackpropagateCreditsEachMover() {
+    let root = Node::new(&Game::default(), None, None, Player::None);
+    let mut tree = vec![root, Visited(3, 0, Player::One, 0, 0.0), Visited(3, 1, Player::Two, 0, 0.0)];
+    tree[0].Children.push(1);
+    tree[1].Children.push(2);
+    let scores = |tree: &[Node]| tree.iter().map(|node| (node.Visits, node.Score)).collect::<Vec<_>>();
+
+    Backpropagate(&mut tree, 2, Player::One);
+    assert_eq!(scores(&tree), [(1, 0.0), (1, 1.0), (1, 0.0)]);
+    Backpropagate(&mut tree, 2, Player::Two);
+    assert_eq!(scores(&tree), [(2, 0.0), (2, 1.0), (2, 1.0)]);
+    Backpropagate(&mut tree, 2, Player::None);
+    assert_eq!(scores(&tree), [(3, 0.5), (3, 1.5), (3, 1.5)]);
+    // A playout ending at the middle node leaves the one below it alone
+    Backpropagate(&mut tree, 1, Player::One);
+    assert_eq!(scores(&tree), [(4, 0.5), (4, 2.5), (3, 1.5)]);
+  }
+
+  #[test]
+  fn SelectAndExpandLinksTheNewChild() {
+    let game = Game::default();
+    let mut search = Mcts::new(1, 7);
+    let mut tree = vec![Node::new(&game, None, None, Player::None)];
+    let mut scratch = game.clone();
+    let child = search.SelectAndExpand(&mut tree, &mut scratch);
+
+    assert_eq!(child, 1);
+    assert_eq!(tree[0].Children, [1]);
+    assert_eq!(tree[1].Parent, Some(0));
+    assert_eq!(tree[1].Mover, Player::One);
+    let column = tree[1].Move.unwrap();
+    assert_eq!(tree[0].Untried.len(), 6);
+    assert!(!tree[0].Untried.contains(&column));
+    assert_eq!(scratch.History().len(), 1);
+    assert_eq!(tree[1].Untried, scratch.LegalMoves());
+  }
+
+  #[test]
+  fn SearchTakesAnImmediateWin() {
+    // Red has three in the bottom row and wins in column 4
+    let game = Game::FromMoveString("112233").unwrap();
+    assert_eq!(Mcts::new(2000, 42).Search(&game), Some(3));
+  }
+}
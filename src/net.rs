@@ -0,0 +1,1000 @@
+// Network games, `host` at one end and `join <address>` at the other. The host chooses the
+// rules and plays first. Everything sent is a message: its length in two bytes, big-endian,
+// then a byte saying what it is, then its fields:
+//
+//   'H' hello     version, width, height, pieces in a row, variants (1: cylinder, 2: PopOut),
+//                 then the host's name, from the host as soon as someone connects
+//   'W' welcome   version, the widest and highest board the joining end can play, the variants
+//                 it can play, the width, height and pieces in a row it asks for (0 for any),
+//                 the variants it asks for, then the joiner's name, in answer
+//   'A' accepted  the name of the player who joined, from the host once the game is on
+//   'M' move      move number in two bytes, counting from 1, 'd' for a drop or 'p' for a pop,
+//                 then the column from 0
+//   'V' watch     as a welcome, from a spectator
+//   'E' refused   why the host won't have the connection, before closing it
+//   'C' chat      the player who said it, 1 or 2, then what they said, from the player on turn
+//                 to the other, and from the host on to the spectators
+//   'T' token     the game's token in eight bytes, then the seconds the host waits for the
+//                 player to come back in two, from the host to the player who joined
+//   'R' resume    version, then the token, instead of a welcome, to come back to the game
+//
+// The version comes first in everything sent before the game starts, so ends of different
+// versions can tell, and say so, whatever else has changed. The host turns away anyone of
+// another version, who can't play its rules or who asked for other ones, saying why before
+// anything of the game is sent, and goes on waiting for someone who can. Both ends then make
+// their game from the hello, so they play by exactly the same rules.
+//
+// Once the game has two players the host goes on taking connections, for spectators: each is
+// sent the hello, and once it answers with a watch, the moves so far, then the accepted
+// message. After that it's sent every move and chat message as they
+// come, and never read from, so it can't play or chat.
+//
+// If the player who joined loses their connection, the host keeps the game for `--grace`
+// seconds, letting in whoever comes back with the game's token: they're sent the moves so far,
+// as a spectator would be, and the host plays on over the new connection. A player who isn't
+// back in time forfeits. The player's end tries to connect again for as long as the host
+// waits, and `join --resume <token>` comes back from another run of the program.
+//
+// While waiting for someone to join, the host also announces its game to the local network
+// every couple of seconds, in a UDP broadcast to DISCOVERY_PORT: "C4", the version, the port
+// in two bytes, the length of the host's name in a byte, the name, then the rules as text.
+// `join` without an address listens for these and lists the games heard.
+//
+// Moves received are played like any typed at the prompt, so the other end can't play out of
+// turn, into a full column or anything else the rules don't allow. Chat is cut
+// short and stripped of control characters at both ends, so it can't reach the terminal as
+// escape sequences.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ConnectFour::rng::Rng;
+use ConnectFour::{archive, log, Game, Move, MoveOutcome, Player, MAX_BOARD_SIZE};
+
+use crate::interrupt;
+use crate::{locale, ArgValue, Message};
+
+// Version of the messages, which both ends have to agree on
+const NET_VERSION: u8 = 3;
+
+// Variants in a hello, and in what a welcome can play and asks for
+const CYLINDER: u8 = 1;
+const POPOUT: u8 = 2;
+
+// Port hosted on and joined without `--port`
+pub const DEFAULT_PORT: u16 = 4444;
+
+// How often waiting for the other end stops to see whether Ctrl-C was pressed
+const POLL: Duration = Duration::from_millis(100);
+
+// How long joining waits for the host to answer
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Longest message either end sends, name and all
+const MAX_MESSAGE: usize = 256;
+
+// Longest chat message, in bytes so it always fits in a message
+pub const MAX_CHAT: usize = 200;
+
+// Port games are announced on
+const DISCOVERY_PORT: u16 = 4445;
+
+// What an announcement starts with, to tell it from anything else sent to the port
+const ANNOUNCEMENT: &[u8] = b"C4";
+
+// How often a host announces its game, and how long `join` listens for them
+const ANNOUNCE_EVERY: Duration = Duration::from_secs(2);
+const DISCOVERY_TIME: Duration = Duration::from_secs(3);
+
+// How long someone connecting has to say whether they play or watch
+const ADMIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How long the host waits for a player who lost their connection, without `--grace`
+pub const DEFAULT_GRACE: u64 = 60;
+
+// How often a player whose connection was lost tries to connect again
+const RETRY_EVERY: Duration = Duration::from_secs(1);
+
+// Struct representing the other end of a network game
+pub struct Remote {
+  pub Seat: Player,          // Player the other end plays, or Player::None when watching
+  pub Names: [String; 2],    // What the two players are called, where they said
+  Stream: TcpStream,
+  Received: Vec<u8>,         // Bytes read that don't make up a whole message yet
+  Sent: usize,               // Moves of the game sent so far, or received from the other end
+  Gallery: Option<Arc<Mutex<Gallery>>>, // The host's spectators
+  pub Chat: Vec<Said>,       // What's been said this game, oldest first
+  pub Token: u64,            // The game's token, for the player who joined to come back with
+  Grace: Duration,           // How long the host waits for the player who joined to come back
+  Address: Option<SocketAddr>, // Where the host is, for the player who joined
+  pub Abandoned: bool,       // Set when the other end didn't come back in time
+}
+
+// Struct representing a chat message
+pub struct Said {
+  pub By: Player,   // Who said it
+  pub Text: String, // What they said, printable and no longer than MAX_CHAT
+  pub Move: usize,  // Moves played when it was said
+}
+
+// Struct holding what the host shares with the thread letting spectators in
+#[derive(Default)]
+struct Gallery {
+  Moves: Vec<Move>,      // Moves played so far, for spectators who come late
+  Watching: Vec<Remote>, // Connections to the spectators
+  Closed: bool,          // Set when the game is over, for the thread to stop
+  Token: u64,            // The game's token
+  Returned: Option<Remote>, // The player who joined, come back on a new connection
+}
+
+// Struct representing a game heard announced on the local network
+struct Announcement {
+  Address: SocketAddr, // Where to join it
+  Name: String,        // The host's name
+  Game: String,        // Its rules, e.g. "7x6, connect 4"
+}
+
+// Struct representing the thread announcing a game, which stops when it's dropped
+struct Announcer(Arc<AtomicBool>);
+
+impl Drop for Announcer {
+  fn drop(&mut self) {
+    self.0.store(true, Ordering::Relaxed);
+  }
+}
+
+// Struct representing what the end joining can play and what it asks for, in a welcome or watch
+struct Offer {
+  MaxWidth: u8,  // Widest board it can play
+  MaxHeight: u8, // Highest board it can play
+  Supports: u8,  // Variants it can play
+  Width: u8,     // Board it asks for, 0 for whatever the host plays
+  Height: u8,
+  Connect: u8,   // Pieces in a row it asks for, 0 for any
+  Wants: u8,     // Variants it asks for
+  Name: String,
+}
+
+// Enum representing a message between the two ends
+enum Packet {
+  Hello { Width: u8, Height: u8, Connect: u8, Flags: u8, Name: String },
+  Welcome(Offer),
+  Accepted { Name: String },
+  Move { Number: u16, Played: Move },
+  Watch(Offer),
+  Refused { Reason: String },
+  Chat { By: u8, Text: String },
+  Token { Token: u64, Grace: u16 },
+  Resume { Token: u64 },
+  Foreign { Version: u8 }, // A hello, welcome, watch or resume from another version
+}
+
+// Waits on `--port` for someone to join, then tells them the rules from the options
+pub fn Host(name: &str) -> Result<(Game, Remote), String> {
+  let game = crate::GameFromArgs().map_err(|err| err.to_string())?;
+  let unsupported = [
+    (game.Players() != 2, "--players 3"),
+    (!game.Gravity(), "--no-gravity"),
+    (game.HasBombs(), "--bombs"),
+    (game.Handicap().is_some(), "--handicap"),
+    (game.Clock().is_some(), "--clock"),
+    (ArgValue("--moves").is_some(), "--moves"),
+    (ArgValue("--load").is_some(), "--load"),
+  ];
+  if let Some((_, option)) = unsupported.iter().find(|(given, _)| *given) {
+    return Err(format!("{} can't be used in a network game, where the only moves are drops and pops", option));
+  }
+  let size = |value: usize| u8::try_from(value).map_err(|_| "the board is too big for a network game".to_string());
+  let hello = Packet::Hello {
+    Width: size(game.Width())?,
+    Height: size(game.Height())?,
+    Connect: size(game.WinLength())?,
+    Flags: Variants(&game),
+    Name: name.to_string(),
+  };
+  let game = Agreed(&hello)?;
+
+  let port = ArgValue("--port").and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_PORT);
+  let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|err| format!("can't listen on port {}: {}", port, err))?;
+  // Accepted without blocking, so Ctrl-C still works while nobody has come
+  listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+  println!("{}", locale::Say(Message::Hosting, &[("port", &port)]));
+  let announcer = Announce(port, name, &archive::Settings(&game));
+  let mut remote = loop {
+    let (stream, address) = match listener.accept() {
+      Ok(accepted) => accepted,
+      Err(err) if err.kind() == ErrorKind::WouldBlock => {
+        if interrupt::Interrupted() {
+          return Err("stopped waiting for someone to join".to_string());
+        }
+        std::thread::sleep(POLL);
+        continue;
+      }
+      Err(err) => return Err(err.to_string()),
+    };
+    log::Info(format_args!("Network game: {} connected", address));
+    match Meet(stream, &hello, &game) {
+      Ok(remote) => break remote,
+      Err(_) if interrupt::Interrupted() => return Err("stopped waiting for someone to join".to_string()),
+      Err(err) => {
+        println!("{}", locale::Say(Message::TurnedAway, &[("address", &address), ("reason", &err)]));
+        log::Info(format_args!("Network game: {} turned away: {}", address, err));
+      }
+    }
+  };
+  drop(announcer);
+  remote.Names[0] = name.to_string();
+  remote.Token = Rng::FromTime().NextU64();
+  remote.Grace = Grace();
+  remote.Send(&Packet::Accepted { Name: remote.Names[1].clone() })?;
+  remote.Send(&remote.TokenPacket())?;
+  let gallery = Arc::new(Mutex::new(Gallery { Token: remote.Token, ..Gallery::default() }));
+  remote.Gallery = Some(Arc::clone(&gallery));
+  let joined = remote.Names[1].clone();
+  let token = remote.TokenPacket();
+  let rules = game.clone();
+  std::thread::spawn(move || {
+    while !gallery.lock().unwrap().Closed {
+      match listener.accept() {
+        Ok((stream, address)) => {
+          if let Err(err) = Admit(stream, &hello, &rules, &gallery, &joined, &token) {
+            log::Info(format_args!("Network game: {} not let in: {}", address, err));
+          }
+        }
+        Err(_) => std::thread::sleep(POLL),
+      }
+    }
+  });
+  Ok((game, remote))
+}
+
+// Says hello to someone who connects to play, and has them if they can play the game hosted
+fn Meet(stream: TcpStream, hello: &Packet, game: &Game) -> Result<Remote, String> {
+  let mut remote = Remote::new(stream, Player::Two)?;
+  remote.Send(hello)?;
+  let refusal = match remote.ReceiveBy(Some(Instant::now() + ADMIT_TIMEOUT))? {
+    Packet::Welcome(offer) => match Incompatible(game, &offer) {
+      None => {
+        remote.Names[1] = offer.Name;
+        return Ok(remote);
+      }
+      Some(reason) => reason,
+    },
+    Packet::Foreign { Version } => Mismatch(Version),
+    Packet::Watch(_) => "nobody has joined to play yet, so there's nothing to watch".to_string(),
+    Packet::Resume { .. } => "there's no game to come back to here".to_string(),
+    _ => "the other end didn't answer as expected".to_string(),
+  };
+  let _ = remote.Send(&Packet::Refused { Reason: refusal.clone() });
+  Err(refusal)
+}
+
+// Why the end joining can't have the game hosted, if it can't: the board or a variant is more
+// than it can play, or it asked for other rules
+fn Incompatible(game: &Game, offer: &Offer) -> Option<String> {
+  let (width, height, connect, flags) = (game.Width(), game.Height(), game.WinLength(), Variants(game));
+  if width > offer.MaxWidth as usize || height > offer.MaxHeight as usize {
+    return Some(format!("the game is {}x{} and the other end plays boards of up to {}x{}", width, height, offer.MaxWidth, offer.MaxHeight));
+  }
+  if flags & !offer.Supports != 0 {
+    return Some(format!("the game is played {}, which the other end can't play", VariantNames(flags & !offer.Supports)));
+  }
+  let asked = |asked: u8, hosted: usize| if asked == 0 { hosted } else { asked as usize };
+  let (asked_width, asked_height) = (asked(offer.Width, width), asked(offer.Height, height));
+  if (asked_width, asked_height) != (width, height) {
+    return Some(format!("the game hosted is {}x{}, not the {}x{} asked for", width, height, asked_width, asked_height));
+  }
+  if asked(offer.Connect, connect) != connect {
+    return Some(format!("the game hosted is connect {}, not the connect {} asked for", connect, offer.Connect));
+  }
+  if offer.Wants & !flags != 0 {
+    return Some(format!("the game hosted isn't played {}, as asked for", VariantNames(offer.Wants & !flags)));
+  }
+  None
+}
+
+// The variants the game is played with, as a hello has them
+fn Variants(game: &Game) -> u8 {
+  (game.Wrap() as u8 * CYLINDER) | (game.PopOut() as u8 * POPOUT)
+}
+
+// The variants named, e.g. "on a cylinder with PopOut"
+fn VariantNames(flags: u8) -> String {
+  let names: Vec<&str> = [(CYLINDER, "on a cylinder"), (POPOUT, "with PopOut")]
+      .iter()
+      .filter(|(flag, _)| flags & flag != 0)
+      .map(|(_, name)| *name)
+      .collect();
+  names.join(" ")
+}
+
+// The game by the rules in the hello. Both ends make theirs with this, so they're the same.
+fn Agreed(hello: &Packet) -> Result<Game, String> {
+  let Packet::Hello { Width, Height, Connect, Flags, .. } = *hello else {
+    return Err("the host didn't start as expected".to_string());
+  };
+  let game = Game::new(Width as usize, Height as usize)
+      .and_then(|game| game.WithWinLength(Connect as usize))
+      .map_err(|err| format!("the host's rules can't be played: {}", err))?;
+  Ok(game.WithWrap(Flags & CYLINDER != 0).WithPopOut(Flags & POPOUT != 0))
+}
+
+// What this end can play, and what it asks for with `--width`, `--height`, `--connect`,
+// `--cylinder` and `--popout`
+fn LocalOffer(name: &str) -> Offer {
+  let asked = |option: &str| ArgValue(option).and_then(|value| value.parse::<u8>().ok()).unwrap_or(0);
+  Offer {
+    MaxWidth: MAX_BOARD_SIZE as u8,
+    MaxHeight: MAX_BOARD_SIZE as u8,
+    Supports: CYLINDER | POPOUT,
+    Width: asked("--width"),
+    Height: asked("--height"),
+    Connect: asked("--connect"),
+    Wants: (crate::Flag("--cylinder") as u8 * CYLINDER) | (crate::Flag("--popout") as u8 * POPOUT),
+    Name: name.to_string(),
+  }
+}
+
+// Lets someone who connects once the game has its players in watch, if they can show the game,
+// or come back to play with the game's token, bringing them up to date with the moves so far
+fn Admit(stream: TcpStream, hello: &Packet, game: &Game, gallery: &Mutex<Gallery>, joined: &str, token: &Packet) -> Result<(), String> {
+  let mut spectator = Remote::new(stream, Player::None)?;
+  spectator.Send(hello)?;
+  let refusal = match spectator.ReceiveBy(Some(Instant::now() + ADMIT_TIMEOUT))? {
+    Packet::Watch(offer) => match Incompatible(game, &offer) {
+      Some(reason) => reason,
+      None => {
+        // Held while the moves so far are sent, so none is sent twice or missed
+        let mut gallery = gallery.lock().unwrap();
+        spectator.CatchUp(&gallery.Moves, joined)?;
+        log::Info(format_args!("Network game: {} is watching", Some(offer.Name).filter(|name| !name.is_empty()).unwrap_or("someone".to_string())));
+        gallery.Watching.push(spectator);
+        return Ok(());
+      }
+    },
+    Packet::Resume { Token } => {
+      let mut gallery = gallery.lock().unwrap();
+      if Token != gallery.Token {
+        let reason = "that token isn't this game's";
+        let _ = spectator.Send(&Packet::Refused { Reason: reason.to_string() });
+        return Err(reason.to_string());
+      }
+      spectator.CatchUp(&gallery.Moves, joined)?;
+      spectator.Send(token)?;
+      spectator.Seat = Player::Two;
+      log::Info(format_args!("Network game: {} is back", joined));
+      gallery.Returned = Some(spectator);
+      return Ok(());
+    }
+    Packet::Foreign { Version } => Mismatch(Version),
+    _ => "the game already has two players; add --watch to watch it".to_string(),
+  };
+  let _ = spectator.Send(&Packet::Refused { Reason: refusal.clone() });
+  Err(refusal)
+}
+
+// Broadcasts the game to the local network until the announcer is dropped. Hosting goes on
+// without it if broadcasts can't be sent, only without being found.
+fn Announce(port: u16, name: &str, game: &str) -> Announcer {
+  let stop = Arc::new(AtomicBool::new(false));
+  let name: Vec<u8> = name.bytes().take(u8::MAX as usize).collect();
+  let announcement = [ANNOUNCEMENT, &[NET_VERSION], &port.to_be_bytes(), &[name.len() as u8], &name, game.as_bytes()].concat();
+  let socket = UdpSocket::bind(("0.0.0.0", 0)).and_then(|socket| socket.set_broadcast(true).map(|()| socket));
+  let socket = match socket {
+    Ok(socket) => socket,
+    Err(err) => {
+      log::Info(format_args!("Network game: can't announce the game: {}", err));
+      return Announcer(stop);
+    }
+  };
+  let stopped = Arc::clone(&stop);
+  std::thread::spawn(move || {
+    while !stopped.load(Ordering::Relaxed) {
+      if let Err(err) = socket.send_to(&announcement, ("255.255.255.255", DISCOVERY_PORT)) {
+        log::Debug(format_args!("Network game: announcement not sent: {}", err));
+      }
+      let next = Instant::now() + ANNOUNCE_EVERY;
+      while Instant::now() < next && !stopped.load(Ordering::Relaxed) {
+        std::thread::sleep(POLL);
+      }
+    }
+  });
+  Announcer(stop)
+}
+
+// Listens for games announced on the local network, lists them and asks which to join.
+// Returns its address, or why there's none.
+pub fn FindGame() -> Result<String, String> {
+  let socket = UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT))
+      .map_err(|err| format!("can't listen for games on port {}: {}; give the host's address instead", DISCOVERY_PORT, err))?;
+  socket.set_read_timeout(Some(POLL)).map_err(|err| err.to_string())?;
+  println!("{}", locale::Text(Message::LookingForGames));
+  let mut games: Vec<Announcement> = Vec::new();
+  let mut buffer = [0u8; MAX_MESSAGE * 2];
+  let until = Instant::now() + DISCOVERY_TIME;
+  while Instant::now() < until {
+    if interrupt::Interrupted() {
+      return Err("stopped looking for games".to_string());
+    }
+    let Ok((read, from)) = socket.recv_from(&mut buffer) else { continue };
+    // Each host is heard every couple of seconds, but listed once
+    match Announced(&buffer[..read], from) {
+      Some(game) if !games.iter().any(|known| known.Address == game.Address) => games.push(game),
+      _ => {}
+    }
+  }
+  if games.is_empty() {
+    return Err("no games found on the local network; give the host's address, e.g. join 192.168.1.5:4444".to_string());
+  }
+  for (index, game) in games.iter().enumerate() {
+    let number = index + 1;
+    println!(
+      "{}",
+      locale::Say(Message::FoundGame, &[("number", &number), ("name", &game.Name), ("game", &game.Game), ("address", &game.Address)])
+    );
+  }
+  loop {
+    println!("{}", locale::Text(Message::ChooseGame));
+    let line = crate::ReadLine();
+    if line.is_empty() && interrupt::InputEnded() {
+      return Err("no game was chosen".to_string());
+    }
+    let chosen = line.trim().parse::<usize>().ok().filter(|number| (1..=games.len()).contains(number));
+    match chosen {
+      Some(number) => return Ok(games[number - 1].Address.to_string()),
+      None => println!("{}", locale::Text(Message::InvalidInput)),
+    }
+  }
+}
+
+// The game in an announcement sent from `from`, or None if it isn't one, or is from a version
+// that can't be played with
+fn Announced(bytes: &[u8], from: SocketAddr) -> Option<Announcement> {
+  let [version, high, low, length, rest @ ..] = bytes.strip_prefix(ANNOUNCEMENT)? else { return None };
+  if *version != NET_VERSION {
+    log::Debug(format_args!("Network game: ignored {}, which speaks version {}", from, version));
+    return None;
+  }
+  let (name, game) = rest.split_at_checked(*length as usize)?;
+  let port = u16::from_be_bytes([*high, *low]);
+  Some(Announcement {
+    Address: SocketAddr::new(from.ip(), port),
+    Name: Some(Name(name)).filter(|name| !name.is_empty()).unwrap_or_else(|| locale::Text(Message::PlayerOne).to_string()),
+    Game: Printable(game),
+  })
+}
+
+// Connects to a host, given as "address:port" or just the address for the default port, and
+// plays by the rules it sends, or with `watch` watches the game from the moves played so far.
+// With the token of a game left, comes back to it.
+pub fn Join(address: &str, name: &str, watch: bool, token: Option<u64>) -> Result<(Game, Remote), String> {
+  let address = match address.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+    true => address.to_string(),
+    false => format!("{}:{}", address, DEFAULT_PORT),
+  };
+  println!("{}", locale::Say(Message::Connecting, &[("address", &address)]));
+  let resolved = address.to_socket_addrs().map_err(|err| format!("can't find {}: {}", address, err))?;
+  let mut failure = format!("can't find {}", address);
+  let mut connected = None;
+  for candidate in resolved {
+    match TcpStream::connect_timeout(&candidate, CONNECT_TIMEOUT) {
+      Ok(stream) => {
+        connected = Some((stream, candidate));
+        break;
+      }
+      Err(err) => failure = format!("can't connect to {}: {}", address, err),
+    }
+  }
+  let (stream, host) = connected.ok_or(failure)?;
+  let mut remote = Remote::new(stream, if watch { Player::None } else { Player::One })?;
+  remote.Address = Some(host);
+  let answer = match (watch, token) {
+    (true, _) => Packet::Watch(LocalOffer(name)),
+    (false, Some(token)) => Packet::Resume { Token: token },
+    (false, None) => Packet::Welcome(LocalOffer(name)),
+  };
+  let game = remote.Greet(&answer)?;
+  log::Info(format_args!("Network game: {} {}", if watch { "watching" } else { "joined" }, address));
+  Ok((game, remote))
+}
+
+impl Remote {
+  // Reads the rules from the host's hello and answers it, then plays the moves so far the host
+  // sends back. A player is given the game's token last.
+  fn Greet(&mut self, answer: &Packet) -> Result<Game, String> {
+    let hello = self.Receive()?;
+    let mut game = match &hello {
+      Packet::Hello { Name, .. } => {
+        self.Names[0] = Name.clone();
+        Agreed(&hello)?
+      }
+      Packet::Foreign { Version } => return Err(Mismatch(*Version)),
+      _ => return Err("the host didn't start as expected".to_string()),
+    };
+    self.Send(answer)?;
+    // The moves so far for a spectator or a player coming back, then the name of the player
+    // who joined the host, or the host turning the connection away
+    loop {
+      match self.Receive()? {
+        Packet::Move { Number, Played } => self.Play(&mut game, Number, Played).map(|_| ())?,
+        Packet::Accepted { Name } => {
+          self.Names[1] = Name;
+          break;
+        }
+        _ => return Err("the host didn't send the game as expected".to_string()),
+      }
+    }
+    if self.Seat != Player::None {
+      let Packet::Token { Token, Grace } = self.Receive()? else {
+        return Err("the host didn't send the game's token".to_string());
+      };
+      self.Token = Token;
+      self.Grace = Duration::from_secs(Grace as u64);
+    }
+    Ok(game)
+  }
+
+  // Sends the moves so far and the name of the player who joined, as a spectator or a player
+  // coming back is brought up to date
+  fn CatchUp(&mut self, moves: &[Move], joined: &str) -> Result<(), String> {
+    for (index, &played) in moves.iter().enumerate() {
+      self.Send(&Packet::Move { Number: (index + 1) as u16, Played: played })?;
+    }
+    self.Send(&Packet::Accepted { Name: joined.to_string() })
+  }
+
+  fn TokenPacket(&self) -> Packet {
+    Packet::Token { Token: self.Token, Grace: self.Grace.as_secs().min(u16::MAX as u64) as u16 }
+  }
+
+  // Whether this is the end that joined, and so the one to come back when the connection is lost
+  pub fn Joined(&self) -> bool {
+    self.Address.is_some()
+  }
+
+  // Plays on over the connection of the player who came back, if they have. The host only.
+  fn Rejoined(&mut self) -> bool {
+    let Some(gallery) = &self.Gallery else { return false };
+    let mut gallery = gallery.lock().unwrap();
+    let Some(mut returned) = gallery.Returned.take() else { return false };
+    std::mem::swap(&mut self.Stream, &mut returned.Stream);
+    self.Received = std::mem::take(&mut returned.Received);
+    // They were sent the moves both ends had; any played here since go with the next ones
+    self.Sent = gallery.Moves.len();
+    true
+  }
+
+  // Waits for the player who lost their connection to come back, saying how long is left
+  // every so often, for as long as `--grace` allows. The host only.
+  pub fn AwaitReturn(&mut self, waiting: &mut dyn FnMut(u64)) -> Result<(), String> {
+    let deadline = Instant::now() + self.Grace;
+    let mut said: Option<u64> = None;
+    while !self.Rejoined() {
+      let left = SecondsLeft(deadline);
+      if Instant::now() >= deadline {
+        return Err("they didn't come back in time".to_string());
+      }
+      if interrupt::Interrupted() {
+        return Err("interrupted".to_string());
+      }
+      // The first time, then on every ten seconds left
+      if said.is_none_or(|said| said.div_ceil(10) != left.div_ceil(10)) {
+        waiting(left);
+        said = Some(left);
+      }
+      std::thread::sleep(POLL);
+    }
+    Ok(())
+  }
+
+  // Connects to the host again after losing the connection, and takes up the game as the host
+  // has it, trying for as long as the host waits. The player who joined only.
+  pub fn Rejoin(&mut self, game: &mut Game, waiting: &mut dyn FnMut(u64)) -> Result<(), String> {
+    let Some(host) = self.Address else {
+      return Err("only the player who joined can connect again".to_string());
+    };
+    let deadline = Instant::now() + self.Grace;
+    let mut failure = "the host didn't let the game be taken up again".to_string();
+    let mut said: Option<u64> = None;
+    while Instant::now() < deadline {
+      if interrupt::Interrupted() {
+        return Err("interrupted".to_string());
+      }
+      let left = SecondsLeft(deadline);
+      if said.is_none_or(|said| said.div_ceil(10) != left.div_ceil(10)) {
+        waiting(left);
+        said = Some(left);
+      }
+      let started = Instant::now();
+      let attempt = TcpStream::connect_timeout(&host, RETRY_EVERY).map_err(|err| err.to_string()).and_then(|stream| {
+        let mut returned = Remote::new(stream, self.Seat)?;
+        let game = returned.Greet(&Packet::Resume { Token: self.Token })?;
+        Ok((game, returned))
+      });
+      match attempt {
+        Ok((resumed, mut returned)) => {
+          std::mem::swap(&mut self.Stream, &mut returned.Stream);
+          self.Received = std::mem::take(&mut returned.Received);
+          self.Sent = returned.Sent;
+          // The host's moves are the ones that count; one played here that it never got is gone
+          *game = resumed;
+          log::Info(format_args!("Network game: back in the game with {}", host));
+          return Ok(());
+        }
+        Err(err) => failure = err,
+      }
+      std::thread::sleep(RETRY_EVERY.saturating_sub(started.elapsed()));
+    }
+    Err(failure)
+  }
+
+  fn new(stream: TcpStream, seat: Player) -> Result<Remote, String> {
+    stream.set_nonblocking(false).map_err(|err| err.to_string())?;
+    // Reads give up every so often, so Ctrl-C still works while waiting for a move
+    stream.set_read_timeout(Some(POLL)).map_err(|err| err.to_string())?;
+    stream.set_nodelay(true).map_err(|err| err.to_string())?;
+    Ok(Remote {
+      Seat: seat,
+      Names: Default::default(),
+      Stream: stream,
+      Received: Vec::new(),
+      Sent: 0,
+      Gallery: None,
+      Chat: Vec::new(),
+      Token: 0,
+      Grace: Duration::ZERO,
+      Address: None,
+      Abandoned: false,
+    })
+  }
+
+  // How many are watching the game hosted here
+  pub fn Spectators(&self) -> usize {
+    self.Gallery.as_ref().map_or(0, |gallery| gallery.lock().unwrap().Watching.len())
+  }
+
+  // Sends the spectators the moves they haven't seen, letting go of any who have gone
+  fn Share(&mut self, game: &Game) {
+    let Some(gallery) = &self.Gallery else { return };
+    let mut gallery = gallery.lock().unwrap();
+    for (index, &played) in game.History().iter().enumerate().skip(gallery.Moves.len()) {
+      gallery.Moves.push(played);
+      let packet = Packet::Move { Number: (index + 1) as u16, Played: played };
+      gallery.Watching.retain_mut(|spectator| spectator.Send(&packet).is_ok());
+    }
+  }
+
+  // Sends the spectators a chat message
+  fn ShareChat(&mut self, packet: &Packet) {
+    let Some(gallery) = &self.Gallery else { return };
+    gallery.lock().unwrap().Watching.retain_mut(|spectator| spectator.Send(packet).is_ok());
+  }
+
+  // Says something to the other end, and to the spectators. Returns what was said, as it went,
+  // which is nothing if there was nothing printable to say.
+  pub fn Say(&mut self, game: &Game, text: &str) -> Result<String, String> {
+    self.Rejoined();
+    let text = ChatText(text.as_bytes());
+    if text.is_empty() {
+      return Ok(text);
+    }
+    let by = if self.Seat == Player::One { Player::Two } else { Player::One };
+    let packet = Packet::Chat { By: by as u8, Text: text.clone() };
+    self.Send(&packet)?;
+    self.ShareChat(&packet);
+    self.Chat.push(Said { By: by, Text: text.clone(), Move: game.History().len() });
+    Ok(text)
+  }
+
+  // Sends the moves made here since the last ones sent
+  pub fn SendMoves(&mut self, game: &Game) -> Result<(), String> {
+    self.Rejoined();
+    for (index, &played) in game.History().iter().enumerate().skip(self.Sent) {
+      self.Send(&Packet::Move { Number: (index + 1) as u16, Played: played })?;
+    }
+    self.Sent = game.History().len();
+    self.Share(game);
+    Ok(())
+  }
+
+  // Waits for the other end's move and plays it, if it's the move due and a legal one. Chat
+  // that comes first is kept and handed to `heard` as it arrives.
+  pub fn ReceiveMove(&mut self, game: &mut Game, heard: &mut dyn FnMut(&Said)) -> Result<MoveOutcome, String> {
+    loop {
+      // A player who comes back is caught up with the moves this end had sent
+      if self.Rejoined() {
+        self.SendMoves(game)?;
+      }
+      let Some(packet) = self.TryReceive()? else {
+        if interrupt::Interrupted() {
+          return Err("interrupted".to_string());
+        }
+        continue;
+      };
+      match packet {
+        Packet::Move { Number, Played } => {
+          let outcome = self.Play(game, Number, Played)?;
+          self.Share(game);
+          return Ok(outcome);
+        }
+        Packet::Chat { By, Text } => {
+          self.ShareChat(&Packet::Chat { By, Text: Text.clone() });
+          let said = Said { By: Player::FromInt(By), Text, Move: game.History().len() };
+          heard(&said);
+          self.Chat.push(said);
+        }
+        _ => return Err("the other end sent something other than a move".to_string()),
+      }
+    }
+  }
+
+  // Plays a move received, if it's the move due and a legal one
+  fn Play(&mut self, game: &mut Game, number: u16, played: Move) -> Result<MoveOutcome, String> {
+    let due = game.History().len() + 1;
+    if number as usize != due {
+      return Err(format!("the other end sent move {} when move {} was due", number, due));
+    }
+    let outcome = game.Play(played).map_err(|err| format!("the other end sent a move that can't be played: {}", err))?;
+    self.Sent = game.History().len();
+    Ok(outcome)
+  }
+
+  fn Send(&mut self, packet: &Packet) -> Result<(), String> {
+    let body = Encode(packet);
+    let mut message = (body.len() as u16).to_be_bytes().to_vec();
+    message.extend(body);
+    self.Stream.write_all(&message).and_then(|()| self.Stream.flush()).map_err(|err| err.to_string())
+  }
+
+  // Reads the next whole message
+  fn Receive(&mut self) -> Result<Packet, String> {
+    self.ReceiveBy(None)
+  }
+
+  // Reads the next whole message, giving up at the deadline if there is one. A refusal from
+  // the other end is returned as the error.
+  fn ReceiveBy(&mut self, deadline: Option<Instant>) -> Result<Packet, String> {
+    loop {
+      if let Some(packet) = self.TryReceive()? {
+        return Ok(packet);
+      }
+      if interrupt::Interrupted() {
+        return Err("interrupted".to_string());
+      }
+      if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        return Err("the other end didn't answer in time".to_string());
+      }
+    }
+  }
+
+  // The next whole message, reading for up to POLL, or None if it hasn't all come yet
+  fn TryReceive(&mut self) -> Result<Option<Packet>, String> {
+    if self.Received.len() < 2 || self.Received.len() < 2 + u16::from_be_bytes([self.Received[0], self.Received[1]]) as usize {
+      let mut buffer = [0u8; MAX_MESSAGE];
+      match self.Stream.read(&mut buffer) {
+        Ok(0) => return Err("the other end closed the connection".to_string()),
+        Ok(read) => self.Received.extend_from_slice(&buffer[..read]),
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => {}
+        Err(err) => return Err(err.to_string()),
+      }
+    }
+    let [high, low, ..] = self.Received[..] else { return Ok(None) };
+    let length = u16::from_be_bytes([high, low]) as usize;
+    if length == 0 || length > MAX_MESSAGE {
+      return Err("the other end sent a message that doesn't make sense".to_string());
+    }
+    if self.Received.len() < 2 + length {
+      return Ok(None);
+    }
+    let body: Vec<u8> = self.Received.drain(..2 + length).skip(2).collect();
+    match Decode(&body) {
+      Some(Packet::Refused { Reason }) => Err(Reason),
+      Some(packet) => Ok(Some(packet)),
+      None => Err("the other end sent a message that doesn't make sense".to_string()),
+    }
+  }
+}
+
+impl Drop for Remote {
+  // Stops letting spectators in once the game is done with, and lets those watching go
+  fn drop(&mut self) {
+    if let Some(gallery) = &self.Gallery {
+      let mut gallery = gallery.lock().unwrap();
+      gallery.Closed = true;
+      gallery.Watching.clear();
+    }
+  }
+}
+
+// How long a player who lost their connection has to come back, from `--grace`
+pub fn Grace() -> Duration {
+  Duration::from_secs(ArgValue("--grace").and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_GRACE))
+}
+
+// Whole seconds to the deadline, rounded up so the last one is still counted
+pub fn SecondsLeft(deadline: Instant) -> u64 {
+  deadline.saturating_duration_since(Instant::now()).as_secs_f64().ceil() as u64
+}
+
+// The error for the other end speaking another version of the messages
+fn Mismatch(version: u8) -> String {
+  format!("the other end speaks version {} of the network game and this one speaks {}", version, NET_VERSION)
+}
+
+// A message's kind and fields, without its length
+fn Encode(packet: &Packet) -> Vec<u8> {
+  // Names are cut short so a message always fits
+  let name = |name: &str| name.bytes().take(MAX_MESSAGE - 8).collect::<Vec<u8>>();
+  let offer = |kind: u8, offer: &Offer| {
+    let fields = [kind, NET_VERSION, offer.MaxWidth, offer.MaxHeight, offer.Supports, offer.Width, offer.Height, offer.Connect, offer.Wants];
+    [fields.to_vec(), name(&offer.Name)].concat()
+  };
+  match packet {
+    Packet::Hello { Width, Height, Connect, Flags, Name } => {
+      [vec![b'H', NET_VERSION, *Width, *Height, *Connect, *Flags], name(Name)].concat()
+    }
+    Packet::Welcome(welcome) => offer(b'W', welcome),
+    Packet::Accepted { Name } => [vec![b'A'], name(Name)].concat(),
+    Packet::Move { Number, Played } => {
+      let [high, low] = Number.to_be_bytes();
+      match *Played {
+        Move::Pop(column) => vec![b'M', high, low, b'p', column as u8],
+        Move::Drop(column) | Move::Bomb(column) | Move::Place(_, column) => vec![b'M', high, low, b'd', column as u8],
+      }
+    }
+    Packet::Watch(watch) => offer(b'V', watch),
+    Packet::Refused { Reason } => [vec![b'E'], name(Reason)].concat(),
+    Packet::Chat { By, Text } => [vec![b'C', *By], Text.bytes().take(MAX_CHAT).collect()].concat(),
+    Packet::Token { Token, Grace } => [vec![b'T'], Token.to_be_bytes().to_vec(), Grace.to_be_bytes().to_vec()].concat(),
+    Packet::Resume { Token } => [vec![b'R', NET_VERSION], Token.to_be_bytes().to_vec()].concat(),
+    // Only ever made from what's received, never sent
+    Packet::Foreign { Version } => vec![b'?', *Version],
+  }
+}
+
+// The message in the bytes, or None if they aren't one
+fn Decode(body: &[u8]) -> Option<Packet> {
+  let offer = |fields: &[u8]| match fields {
+    [max_width, max_height, supports, width, height, connect, wants, name @ ..] => Some(Offer {
+      MaxWidth: *max_width,
+      MaxHeight: *max_height,
+      Supports: *supports,
+      Width: *width,
+      Height: *height,
+      Connect: *connect,
+      Wants: *wants,
+      Name: Name(name),
+    }),
+    _ => None,
+  };
+  match body {
+    // Whatever else another version changed, its version is read from the same place
+    [b'H' | b'W' | b'V' | b'R', version, ..] if *version != NET_VERSION => Some(Packet::Foreign { Version: *version }),
+    [b'H', _, width, height, connect, flags, name @ ..] => {
+      Some(Packet::Hello { Width: *width, Height: *height, Connect: *connect, Flags: *flags, Name: Name(name) })
+    }
+    [b'W', _, fields @ ..] => offer(fields).map(Packet::Welcome),
+    [b'A', name @ ..] => Some(Packet::Accepted { Name: Name(name) }),
+    [b'M', high, low, kind, column] => {
+      let played = match kind {
+        b'd' => Move::Drop(*column as usize),
+        b'p' => Move::Pop(*column as usize),
+        _ => return None,
+      };
+      Some(Packet::Move { Number: u16::from_be_bytes([*high, *low]), Played: played })
+    }
+    [b'V', _, fields @ ..] => offer(fields).map(Packet::Watch),
+    [b'E', reason @ ..] => Some(Packet::Refused { Reason: Printable(reason) }),
+    [b'C', by @ (1 | 2), text @ ..] => Some(Packet::Chat { By: *by, Text: ChatText(text) }),
+    [b'T', rest @ ..] if rest.len() == 10 => Some(Packet::Token {
+      Token: u64::from_be_bytes(rest[..8].try_into().ok()?),
+      Grace: u16::from_be_bytes([rest[8], rest[9]]),
+    }),
+    [b'R', _, token @ ..] => Some(Packet::Resume { Token: u64::from_be_bytes(token.try_into().ok()?) }),
+    _ => None,
+  }
+}
+
+// A name sent by the other end, kept to printable characters so it can't play tricks with the
+// terminal, and no longer than one typed in. Empty if nothing is left, for the usual name to be used.
+fn Name(bytes: &[u8]) -> String {
+  crate::CleanName(&Printable(bytes)).unwrap_or_default()
+}
+
+// Text sent by the other end without anything that isn't printed as it is
+fn Printable(bytes: &[u8]) -> String {
+  String::from_utf8_lossy(bytes).chars().filter(|c| !c.is_control()).collect()
+}
+
+// A chat message as it's sent and shown: printable, and cut short at a character boundary to
+// at most MAX_CHAT bytes
+pub fn ChatText(bytes: &[u8]) -> String {
+  let mut text = String::new();
+  for c in Printable(bytes).trim().chars() {
+    if text.len() + c.len_utf8() > MAX_CHAT {
+      break;
+    }
+    text.push(c);
+  }
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // What an end able to play anything offers, asking for the given board (0 for any)
+  fn Asking(width: u8, height: u8) -> Offer {
+    Offer {
+      MaxWidth: MAX_BOARD_SIZE as u8,
+      MaxHeight: MAX_BOARD_SIZE as u8,
+      Supports: CYLINDER | POPOUT,
+      Width: width,
+      Height: height,
+      Connect: 0,
+      Wants: 0,
+      Name: "Bob".to_string(),
+    }
+  }
+
+  fn Hello(width: u8, height: u8) -> Packet {
+    Packet::Hello { Width: width, Height: height, Connect: 4, Flags: 0, Name: "Ann".to_string() }
+  }
+
+  #[test]
+  fn IncompatibleBoards() {
+    let game = Game::default();
+    assert_eq!(Incompatible(&game, &Asking(0, 0)), None);
+    assert_eq!(Incompatible(&game, &Asking(7, 6)), None);
+    assert_eq!(
+      Incompatible(&game, &Asking(9, 7)),
+      Some("the game hosted is 7x6, not the 9x7 asked for".to_string())
+    );
+    let small = Offer { MaxWidth: 6, MaxHeight: 6, ..Asking(0, 0) };
+    assert_eq!(
+      Incompatible(&game, &small),
+      Some("the game is 7x6 and the other end plays boards of up to 6x6".to_string())
+    );
+    let flat = Offer { Supports: 0, ..Asking(0, 0) };
+    assert!(Incompatible(&game.clone().WithPopOut(true), &flat).is_some_and(|reason| reason.contains("with PopOut")));
+  }
+
+  #[test]
+  fn AgreedRulesMatchTheHello() {
+    let game = Agreed(&Hello(9, 7)).unwrap();
+    assert_eq!((game.Width(), game.Height(), game.WinLength()), (9, 7, 4));
+    assert!(Agreed(&Hello(2, 6)).is_err_and(|err| err.starts_with("the host's rules can't be played")));
+    assert!(Agreed(&Hello(MAX_BOARD_SIZE as u8 + 1, 6)).is_err());
+  }
+
+  #[test]
+  fn UnsupportedBoardIsTurnedAway() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let joining = std::thread::spawn(move || {
+      let mut remote = Remote::new(TcpStream::connect(address).unwrap(), Player::One).unwrap();
+      remote.Greet(&Packet::Welcome(Asking(9, 7))).err()
+    });
+    let (stream, _) = listener.accept().unwrap();
+    let hosted = Meet(stream, &Hello(7, 6), &Game::default()).err();
+
+    // Both ends hear why, before anything of the game is sent
+    let reason = "the game hosted is 7x6, not the 9x7 asked for".to_string();
+    assert_eq!(hosted, Some(reason.clone()));
+    assert_eq!(joining.join().unwrap(), Some(reason));
+  }
+
+  #[test]
+  fn OffersSurviveTheWire() {
+    let sent = Offer { Width: 9, Height: 7, Connect: 5, Wants: POPOUT, ..Asking(0, 0) };
+    let Some(Packet::Welcome(offer)) = Decode(&Encode(&Packet::Welcome(sent))) else { panic!("not a welcome") };
+    assert_eq!((offer.Width, offer.Height, offer.Connect, offer.Wants), (9, 7, 5, POPOUT));
+    assert_eq!(offer.Name, "Bob");
+    let mut foreign = Encode(&Hello(7, 6));
+    foreign[1] = NET_VERSION + 1;
+    assert!(matches!(Decode(&foreign), Some(Packet::Foreign { Version }) if Version == NET_VERSION + 1));
+  }
+}
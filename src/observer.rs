@@ -0,0 +1,204 @@
+// Observers told about a game as it's played, for front-ends and embedders that react to the
+// game rather than poll it. Game stays a plain value that the engines clone freely while they
+// search, so the observers live on an ObservedGame wrapped around it instead: its moves,
+// undos and redos, resets and every other way the game can end tell every observer, in the
+// order they subscribed, before returning. Everything else goes straight to the game
+// underneath.
+
+use std::ops::{Deref, DerefMut};
+
+use std::time::Duration;
+
+use crate::{DrawError, Game, Move, MoveError, MoveOutcome, MoveResult, Player, RedoError, UndoError};
+
+// Enum representing something that happened in an observed game
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+  MoveMade { Player: Player, Column: usize, Row: usize },  // A move was played; rows from the top
+  MoveUndone { Player: Player, Column: usize },            // The player's last move was taken back
+  MoveRedone { Player: Player, Column: usize, Row: usize }, // A move taken back was played again
+  GameFinished { Result: MoveResult },                     // The game ended: a Win, including on time or by forfeit, or a Draw
+  GameReopened,                                            // Undoing took back how the game ended, not a move
+  MoveRejected { Error: MoveError },                       // A move couldn't be played, and the game is unchanged
+  GameReset,                                               // The board was cleared for a new game
+}
+
+// A closure told about each event
+pub type Observer = Box<dyn FnMut(&Event) + Send>;
+
+// Struct holding a game and the closures observing it
+pub struct ObservedGame {
+  Game: Game,
+  Observers: Vec<Observer>,
+}
+
+impl ObservedGame {
+  pub fn new(game: Game) -> ObservedGame {
+    ObservedGame { Game: game, Observers: Vec::new() }
+  }
+
+  // Adds an observer, told about every event from now on after those added before it
+  pub fn Subscribe(&mut self, observer: impl FnMut(&Event) + Send + 'static) {
+    self.Observers.push(Box::new(observer));
+  }
+
+  // Drops a piece into the column, as Game::MakeMove, telling the observers how it went
+  pub fn MakeMove(&mut self, column: usize) -> Result<MoveOutcome, MoveError> {
+    self.Play(Move::Drop(column))
+  }
+
+  // Plays any kind of move, as Game::Play, telling the observers how it went
+  pub fn Play(&mut self, played: Move) -> Result<MoveOutcome, MoveError> {
+    let result = self.Game.Play(played);
+    match &result {
+      Ok(outcome) => {
+        self.Tell(Event::MoveMade { Player: outcome.Player, Column: outcome.Column, Row: outcome.Row });
+        if outcome.Result != MoveResult::Continue {
+          self.Tell(Event::GameFinished { Result: outcome.Result });
+        }
+      }
+      Err(err) => self.Tell(Event::MoveRejected { Error: err.clone() }),
+    }
+    result
+  }
+
+  // Clears the board for a new game, as Game::Reset, then tells the observers
+  pub fn Reset(&mut self) {
+    self.Game.Reset();
+    self.Tell(Event::GameReset);
+  }
+
+  // Takes back the last move, or how the game ended, as Game::UndoMove, telling the observers
+  pub fn UndoMove(&mut self) -> Result<(), UndoError> {
+    let played = self.Game.History().len();
+    let last = self.Game.History().last().map(|undone| undone.Column());
+    self.Game.UndoMove()?;
+    match last {
+      Some(column) if self.Game.History().len() < played => {
+        self.Tell(Event::MoveUndone { Player: self.Game.CurrentPlayer(), Column: column })
+      }
+      _ => self.Tell(Event::GameReopened),
+    }
+    Ok(())
+  }
+
+  // Plays the last move taken back again, as Game::RedoMove, telling the observers
+  pub fn RedoMove(&mut self) -> Result<MoveOutcome, RedoError> {
+    let outcome = self.Game.RedoMove()?;
+    self.Tell(Event::MoveRedone { Player: outcome.Player, Column: outcome.Column, Row: outcome.Row });
+    if outcome.Result != MoveResult::Continue {
+      self.Tell(Event::GameFinished { Result: outcome.Result });
+    }
+    Ok(outcome)
+  }
+
+  // Accepts the waiting draw offer, as Game::AcceptDraw, telling the observers the game is drawn
+  pub fn AcceptDraw(&mut self) -> Result<(), DrawError> {
+    self.Game.AcceptDraw()?;
+    self.Finished();
+    Ok(())
+  }
+
+  // Ends the game with the player on turn forfeiting, as Game::Forfeit
+  pub fn Forfeit(&mut self) -> Result<(), MoveError> {
+    self.ForfeitBy(self.Game.CurrentPlayer())
+  }
+
+  // Ends the game with the player forfeiting, as Game::ForfeitBy, telling the observers who won
+  pub fn ForfeitBy(&mut self, player: Player) -> Result<(), MoveError> {
+    self.Game.ForfeitBy(player)?;
+    self.Finished();
+    Ok(())
+  }
+
+  // Takes the time off the clock of the player on turn, as Game::SpendTime, telling the
+  // observers if they lost on time
+  pub fn SpendTime(&mut self, elapsed: Duration) -> bool {
+    let flagged = self.Game.SpendTime(elapsed);
+    if flagged {
+      self.Finished();
+    }
+    flagged
+  }
+
+  // Tells the observers how the game just ended other than by a move
+  fn Finished(&mut self) {
+    let result = match self.Game.Winner() {
+      Player::None => MoveResult::Draw,
+      winner => MoveResult::Win(winner),
+    };
+    self.Tell(Event::GameFinished { Result: result });
+  }
+
+  fn Tell(&mut self, event: Event) {
+    for observer in &mut self.Observers {
+      observer(&event);
+    }
+  }
+}
+
+impl Deref for ObservedGame {
+  type Target = Game;
+
+  fn deref(&self) -> &Game {
+    &self.Game
+  }
+}
+
+// Changes made through the game underneath, rather than the methods above, aren't observed;
+// replacing it keeps the observers
+impl DerefMut for ObservedGame {
+  fn deref_mut(&mut self) -> &mut Game {
+    &mut self.Game
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::TimeControl;
+  use std::sync::{Arc, Mutex};
+
+  // A game with an observer keeping everything it's told
+  fn Watched(game: Game) -> (ObservedGame, Arc<Mutex<Vec<Event>>>) {
+    let told = Arc::new(Mutex::new(Vec::new()));
+    let mut observed = ObservedGame::new(game);
+    let kept = Arc::clone(&told);
+    observed.Subscribe(move |event| kept.lock().unwrap().push(event.clone()));
+    (observed, told)
+  }
+
+  #[test]
+  fn UndoAndRedoAreObserved() {
+    let (mut game, told) = Watched(Game::default());
+    game.MakeMove(3).unwrap();
+    game.UndoMove().unwrap();
+    game.RedoMove().unwrap();
+    assert_eq!(
+      *told.lock().unwrap(),
+      [
+        Event::MoveMade { Player: Player::One, Column: 3, Row: 5 },
+        Event::MoveUndone { Player: Player::One, Column: 3 },
+        Event::MoveRedone { Player: Player::One, Column: 3, Row: 5 },
+      ]
+    );
+  }
+
+  #[test]
+  fn EndingsOtherThanMovesAreObserved() {
+    let (mut game, told) = Watched(Game::default());
+    game.ForfeitBy(Player::One).unwrap();
+    assert_eq!(told.lock().unwrap().last(), Some(&Event::GameFinished { Result: MoveResult::Win(Player::Two) }));
+    game.UndoMove().unwrap();
+    assert_eq!(told.lock().unwrap().last(), Some(&Event::GameReopened));
+
+    game.OfferDraw(Player::One).unwrap();
+    game.MakeMove(0).unwrap();
+    game.AcceptDraw().unwrap();
+    assert_eq!(told.lock().unwrap().last(), Some(&Event::GameFinished { Result: MoveResult::Draw }));
+
+    let (mut game, told) = Watched(Game::default().WithClock(TimeControl::new(Duration::from_secs(1))));
+    assert!(game.SpendTime(Duration::from_secs(2)));
+    assert_eq!(*told.lock().unwrap(), [Event::GameFinished { Result: MoveResult::Win(Player::Two) }]);
+  }
+}
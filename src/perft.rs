@@ -0,0 +1,38 @@
+use crate::Game;
+
+// Counts the move sequences of exactly `depth` plies from the position, walking the tree with
+// LegalMoves, MakeMove and UndoMove. Finished games aren't extended, so lines that end early
+// don't count. From the empty board depth 1 gives 7 and depth 2 gives 49.
+pub fn Perft(game: &mut Game, depth: usize) -> u64 {
+  if depth == 0 {
+    return 1;
+  }
+  let moves = game.LegalMoves();
+  if depth == 1 {
+    return moves.len() as u64; // Leaves don't need to be played out
+  }
+  let mut nodes = 0;
+  for column in moves {
+    game.MakeMove(column).expect("legal move");
+    nodes += Perft(game, depth - 1);
+    game.UndoMove().expect("move was just made");
+  }
+  nodes
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn EmptyBoardCounts() {
+    let mut game = Game::default();
+    assert_eq!(Perft(&mut game, 0), 1);
+    assert_eq!(Perft(&mut game, 1), 7);
+    assert_eq!(Perft(&mut game, 2), 49);
+    assert_eq!(Perft(&mut game, 4), 2401);
+    // The seven lines filling a column in the first six plies have one move fewer at the seventh
+    assert_eq!(Perft(&mut game, 7), 823536);
+    assert!(game.History().is_empty());
+  }
+}
@@ -0,0 +1,205 @@
+// Engine mode, `engine`: the search driven by another program, such as a GUI or a test
+// harness, one command a line on stdin and one reply a line on stdout, in the spirit of UCI.
+// Columns are numbered from 1 as in move strings. The commands:
+//
+//   isready                        answered with "readyok", even while a search runs
+//   newgame                        go back to the starting position
+//   position [startpos] [moves M]  the starting position, after the moves M if given, written
+//                                  together ("4453") or apart ("4 4 5 3")
+//   go [depth N] [movetime MS] [infinite]
+//                                  search the position to depth N (default --depth, or 10),
+//                                  for at most MS milliseconds, or with "infinite" until it's
+//                                  stopped or the result is forced
+//   stop                           end the search; its best move so far is sent as usual
+//   quit                           stop any search and leave
+//
+// and the replies:
+//
+//   info depth D score S nodes N time MS pv C C ...
+//                                  after each depth searched. S is "win N" or "loss N" for a
+//                                  result forced N moves from now, counting both sides' moves,
+//                                  or else the evaluation for the side to move
+//   bestmove C                     when a search ends
+//   error <what's wrong>           for a command that can't be carried out; nothing else
+//                                  changes
+//
+// The rules come from the usual options, e.g. `engine --width 8 --connect 5`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use ConnectFour::bot::{Bot, SearchResult, WIN_SCORE};
+use ConnectFour::engine;
+use ConnectFour::{log, Game};
+
+use crate::interrupt::{self, Wait};
+use crate::{locale, ArgValue, Message};
+
+// Depth searched by a `go` that doesn't say, unless `--depth` does
+const DEFAULT_DEPTH: usize = 10;
+
+// Struct representing the search running on its worker thread
+struct Search {
+  Stop: Arc<AtomicBool>, // Set to end it early
+  Done: Arc<AtomicBool>, // Set by the worker just before it sends the best move
+  Worker: JoinHandle<()>, // Sends the info lines and the best move
+}
+
+// Answers commands until `quit` or the end of the input
+pub fn Run() {
+  let start = match crate::GameFromArgs() {
+    Ok(game) if engine::Supports(&game) => game,
+    Ok(_) => {
+      Reply(&format!("error {}", locale::Text(Message::AiUnsupported)));
+      return;
+    }
+    Err(err) => {
+      Reply(&format!("error {}", err));
+      return;
+    }
+  };
+  let mut game = start.clone();
+  let mut search: Option<Search> = None;
+  loop {
+    let line = match interrupt::ReadLineBy(None, &mut || ()) {
+      Wait::Line(line) if line.is_empty() && interrupt::InputEnded() => break,
+      Wait::Line(line) => line,
+      _ => break,
+    };
+    log::Debug(format_args!("Engine command: {}", line.trim()));
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((&command, arguments)) = words.split_first() else { continue };
+    // A search is over once its best move is sent, though its thread may still be ending
+    let searching = search.as_ref().is_some_and(|search| !search.Done.load(Ordering::Acquire));
+    let answered = match command {
+      "isready" => {
+        Reply("readyok");
+        Ok(())
+      }
+      "stop" => {
+        Stop(&mut search);
+        Ok(())
+      }
+      "quit" => break,
+      "newgame" | "position" | "go" if searching => Err(format!("'{}' while searching; send stop first", command)),
+      "newgame" => {
+        game = start.clone();
+        Ok(())
+      }
+      "position" => Position(&start, arguments).map(|position| game = position),
+      "go" => Limits(&game, arguments).map(|(depth, time)| {
+        // The last search has finished by now, but its thread is still to be joined
+        Stop(&mut search);
+        search = Some(Go(&game, depth, time));
+      }),
+      _ => Err(format!("unknown command '{}'", command)),
+    };
+    if let Err(err) = answered {
+      Reply(&format!("error {}", err));
+    }
+  }
+  Stop(&mut search);
+}
+
+// The position from the starting one, `startpos` being optional, and the moves after `moves`
+fn Position(start: &Game, arguments: &[&str]) -> Result<Game, String> {
+  let arguments = arguments.strip_prefix(&["startpos"][..]).unwrap_or(arguments);
+  let mut game = start.clone();
+  match arguments.split_first() {
+    None => {}
+    Some((&"moves", moves)) => game.PlayMoves(&moves.concat()).map_err(|err| err.to_string())?,
+    Some((word, _)) => return Err(format!("expected 'startpos' or 'moves', not '{}'", word)),
+  }
+  Ok(game)
+}
+
+// The depth and time a `go` asks for
+fn Limits(game: &Game, arguments: &[&str]) -> Result<(usize, Option<Duration>), String> {
+  if game.IsFinished() {
+    return Err("the game is over; there's nothing to search".to_string());
+  }
+  let mut depth = None;
+  let mut time = None;
+  let mut words = arguments.iter();
+  while let Some(&word) = words.next() {
+    let mut number = || {
+      let value = words.next().ok_or(format!("'{}' needs a number after it", word))?;
+      value.parse::<u64>().ok().filter(|&number| number > 0).ok_or(format!("'{}' isn't a number for {}", value, word))
+    };
+    match word {
+      "depth" => depth = Some(number()? as usize),
+      "movetime" => time = Some(Duration::from_millis(number()?)),
+      "infinite" => depth = Some(game.Width() * game.Height()),
+      _ => return Err(format!("unknown go option '{}'", word)),
+    }
+  }
+  let depth = depth.unwrap_or_else(|| match time {
+    Some(_) => game.Width() * game.Height(),
+    None => ArgValue("--depth").and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_DEPTH),
+  });
+  Ok((depth, time))
+}
+
+// Starts searching the position on a worker thread, which sends an info line for each depth
+// and the best move once it's done or stopped
+fn Go(game: &Game, depth: usize, time: Option<Duration>) -> Search {
+  let stop = Arc::new(AtomicBool::new(false));
+  let threads = ArgValue("--threads").and_then(|value| value.parse().ok()).unwrap_or(1);
+  let mut bot = Bot::new(depth).WithThreads(threads).WithStop(Arc::clone(&stop));
+  if let Some(limit) = time {
+    bot = bot.WithTimeLimit(limit);
+  }
+  let game = game.clone();
+  let done = Arc::new(AtomicBool::new(false));
+  let finished = Arc::clone(&done);
+  let worker = std::thread::spawn(move || {
+    let started = Instant::now();
+    let cells = game.Width() * game.Height();
+    let best = bot.SearchReporting(&game, &mut |result, line| {
+      let line: Vec<String> = line.iter().map(|column| (column + 1).to_string()).collect();
+      Reply(&format!(
+        "info depth {} score {} nodes {} time {} pv {}",
+        result.Depth,
+        Score(result, cells),
+        result.Nodes,
+        started.elapsed().as_millis(),
+        line.join(" ")
+      ));
+    });
+    finished.store(true, Ordering::Release);
+    match best {
+      Some(best) => Reply(&format!("bestmove {}", best.BestMove + 1)),
+      None => Reply("error there's no legal move"),
+    }
+  });
+  Search { Stop: stop, Done: done, Worker: worker }
+}
+
+// Ends the search, if one is running, once it's sent its best move
+fn Stop(search: &mut Option<Search>) {
+  if let Some(search) = search.take() {
+    search.Stop.store(true, Ordering::Relaxed);
+    let _ = search.Worker.join();
+  }
+}
+
+// The score of an info line: a forced result with how many moves away it is, or the evaluation
+fn Score(result: &SearchResult, cells: usize) -> String {
+  // A win found on the k-th move from here scores WIN_SCORE + depth - k + 1
+  let forced = WIN_SCORE - cells as i32;
+  let distance = |score: i32| result.Depth as i32 - (score - WIN_SCORE) + 1;
+  if result.Score >= forced {
+    format!("win {}", distance(result.Score))
+  } else if result.Score <= -forced {
+    format!("loss {}", distance(-result.Score))
+  } else {
+    result.Score.to_string()
+  }
+}
+
+// Sends a line
+fn Reply(line: &str) {
+  println!("{}", line);
+}
@@ -0,0 +1,88 @@
+use crate::engine::Engine;
+use crate::log;
+use crate::rng::Rng;
+use crate::Game;
+
+// Struct representing a beginner-friendly opponent that plays random legal columns
+pub struct RandomBot {
+  Rng: Rng,       // Source of the random choices
+  TakeWins: bool, // Whether to grab an immediate win when one is available
+}
+
+impl RandomBot {
+  // Creates a random bot; the seed makes its games reproducible
+  pub fn new(seed: u64) -> RandomBot {
+    RandomBot { Rng: Rng::new(seed), TakeWins: true }
+  }
+
+  // Toggles whether the bot still plays a winning move when it sees one
+  pub fn WithTakeWins(mut self, TakeWins: bool) -> RandomBot {
+    self.TakeWins = TakeWins;
+    self
+  }
+}
+
+impl Engine for RandomBot {
+  fn ChooseMove(&mut self, game: &Game) -> Option<usize> {
+    let moves = game.LegalMoves();
+    if moves.is_empty() {
+      return None;
+    }
+
+    if self.TakeWins {
+      let me = game.CurrentPlayer();
+      let winning = moves.iter().copied().find(|&column| game.WouldWin(column, me));
+      if winning.is_some() {
+        log::Debug(format_args!("Random takes the win in column {:?}", winning.map(|column| column + 1)));
+        return winning;
+      }
+    }
+    let column = self.Rng.Pick(&moves);
+    log::Debug(format_args!("Random picks column {} of {} legal ones", column + 1, moves.len()));
+    Some(column)
+  }
+
+  fn Name(&self) -> String {
+    "Easy bot".to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // The moves of a game between two random bots seeded from `seed`, as 1-based columns
+  fn SelfPlay(seed: u64) -> String {
+    let mut game = Game::default();
+    let mut bots = [RandomBot::new(seed), RandomBot::new(seed + 1)];
+    while let Some(column) = bots[game.History().len() % 2].ChooseMove(&game) {
+      game.MakeMove(column).unwrap();
+    }
+    game.ToMoveString().unwrap()
+  }
+
+  #[test]
+  fn SameSeedPlaysTheSameGame() {
+    assert_eq!(SelfPlay(5), SelfPlay(5));
+    assert_ne!(SelfPlay(5), SelfPlay(6));
+  }
+
+  #[test]
+  fn PicksOnlyLegalColumns() {
+    let game = Game::FromMoveString("111111").unwrap();
+    let mut bot = RandomBot::new(3);
+    assert!((0..100).all(|_| bot.ChooseMove(&game).is_some_and(|column| column != 0)));
+    let full = Game::FromMoveString("512414477327537234737551113144622532666665").unwrap();
+    assert_eq!(bot.ChooseMove(&full), None);
+  }
+
+  #[test]
+  fn TakesAnImmediateWin() {
+    // Red wins in column 4, whatever the seed
+    let game = Game::FromMoveString("112233").unwrap();
+    assert!((0..20).all(|seed| RandomBot::new(seed).ChooseMove(&game) == Some(3)));
+    // Without TakeWins it's one column of seven
+    let mut bot = RandomBot::new(1).WithTakeWins(false);
+    assert!((0..50).any(|_| bot.ChooseMove(&game) != Some(3)));
+  }
+}
@@ -0,0 +1,83 @@
+// Elo ratings: a player's expected score against an opponent follows from the difference in
+// their ratings, and each game moves the rating by the K-factor times how far the actual
+// score (1 for a win, 0.5 for a draw, 0 for a loss) was from the expected one
+
+// Rating a new player starts with
+pub const INITIAL_RATING: u64 = 1200;
+
+// How far a single game can move a rating, unless `--k-factor` says otherwise
+pub const DEFAULT_K_FACTOR: f64 = 32.0;
+
+// Rounds of adjustment Estimate makes
+const ESTIMATE_STEPS: usize = 200;
+
+// Expected score, between 0 and 1, of a player rated `rating` against one rated `opponent`
+pub fn ExpectedScore(rating: u64, opponent: u64) -> f64 {
+  1.0 / (1.0 + 10f64.powf((opponent as f64 - rating as f64) / 400.0))
+}
+
+// The player's new rating after scoring `score` against the opponent, rounded to a whole
+// number and never below zero
+pub fn Updated(rating: u64, opponent: u64, score: f64, k: f64) -> u64 {
+  let change = k * (score - ExpectedScore(rating, opponent));
+  (rating as f64 + change).round().max(0.0) as u64
+}
+
+// Ratings that account for the results of a round robin, where `points[i][j]` is what i
+// scored in `games[i][j]` games against j. Everyone starts at INITIAL_RATING and each rating
+// is moved towards the one that would have expected the score it got, all together so the
+// average stays put. A perfect score has no such rating, so the steps stop after a while.
+pub fn Estimate(points: &[Vec<f64>], games: &[Vec<u64>]) -> Vec<u64> {
+  let expected = |rating: f64, opponent: f64| 1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0));
+  let mut ratings = vec![INITIAL_RATING as f64; points.len()];
+  for _ in 0..ESTIMATE_STEPS {
+    let steps: Vec<f64> = (0..ratings.len())
+        .map(|player| {
+          let played: u64 = games[player].iter().sum();
+          let surprise: f64 = (0..ratings.len())
+              .map(|opponent| points[player][opponent] - games[player][opponent] as f64 * expected(ratings[player], ratings[opponent]))
+              .sum();
+          DEFAULT_K_FACTOR * surprise / played.max(1) as f64
+        })
+        .collect();
+    ratings.iter_mut().zip(steps).for_each(|(rating, step)| *rating += step);
+  }
+  ratings.iter().map(|&rating| rating.round().max(0.0) as u64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ExpectedScoreFollowsTheDifference() {
+    assert_eq!(ExpectedScore(1200, 1200), 0.5);
+    // 400 points apart is ten to one
+    assert!((ExpectedScore(1600, 1200) - 10.0 / 11.0).abs() < 1e-12);
+    assert!((ExpectedScore(1200, 1600) - 1.0 / 11.0).abs() < 1e-12);
+    assert!((ExpectedScore(1500, 1300) + ExpectedScore(1300, 1500) - 1.0).abs() < 1e-12);
+  }
+
+  #[test]
+  fn UpdatedMovesByTheSurprise() {
+    let k = DEFAULT_K_FACTOR;
+    assert_eq!(Updated(1200, 1200, 1.0, k), 1216);
+    assert_eq!(Updated(1200, 1200, 0.5, k), 1200);
+    assert_eq!(Updated(1200, 1200, 0.0, k), 1184);
+    assert_eq!(Updated(1600, 1200, 1.0, k), 1603);
+    assert_eq!(Updated(1200, 1600, 1.0, k), 1229);
+    assert_eq!(Updated(1200, 1600, 0.0, k), 1197);
+    assert_eq!(Updated(1200, 1200, 1.0, 16.0), 1208);
+    // Ratings stop at zero
+    assert_eq!(Updated(5, 5, 0.0, k), 0);
+  }
+
+  #[test]
+  fn EstimateMatchesTheScores() {
+    // 7.5 out of 10 is what a 191 point lead expects, split evenly around the start
+    let ratings = Estimate(&[vec![0.0, 7.5], vec![2.5, 0.0]], &[vec![0, 10], vec![10, 0]]);
+    assert_eq!(ratings, [1295, 1105]);
+    let ratings = Estimate(&[vec![0.0, 3.0], vec![3.0, 0.0]], &[vec![0, 6], vec![6, 0]]);
+    assert_eq!(ratings, [INITIAL_RATING, INITIAL_RATING]);
+  }
+}
@@ -0,0 +1,140 @@
+// Ways of showing the game to the players, picked once when a session starts: the usual board
+// drawn with the theme's colors, plain letters for terminals and logs that can't take escape
+// codes (`--plain`), or sentences for screen readers (`--accessible`). The play loop only goes
+// through the Renderer, so each way of showing the game lives in one place.
+
+use ConnectFour::{Move, MoveOutcome};
+
+use crate::locale::{self, Message};
+use crate::{ClearScreen, ColorName, Flag, Session};
+
+// Enum representing what happened in the game, for the renderer to tell the players
+pub enum GameEvent<'a> {
+  Moved(&'a MoveOutcome), // A move was played, after the board was drawn again
+  Turn,                   // The player on turn is about to be asked for a move
+  Finished,               // The game is over, after the last board was drawn
+}
+
+// Trait for showing a game; the board and announcements only use the game's public accessors
+pub trait Renderer {
+  // Shows the board and the game's state
+  fn DrawBoard(&self, session: &Session);
+
+  // Shows an error, with the board again where the screen is redrawn
+  fn ShowError(&self, session: &Session, error: &str);
+
+  // Tells the players about something that happened
+  fn Announce(&self, session: &Session, event: GameEvent);
+}
+
+// The renderer asked for on the command line
+pub fn Chosen() -> Box<dyn Renderer> {
+  if crate::Accessible() {
+    Box::new(Spoken)
+  } else if Flag("--plain") {
+    Box::new(Plain)
+  } else {
+    Box::new(Screen)
+  }
+}
+
+// Struct drawing the board in the theme's colors and layout, clearing the screen between boards
+pub struct Screen;
+
+impl Renderer for Screen {
+  fn DrawBoard(&self, session: &Session) {
+    ClearScreen();
+    print!("{}", crate::RenderBoard(session));
+  }
+
+  // With `--no-clear` the board is still just above, so the error goes on the next line
+  fn ShowError(&self, session: &Session, error: &str) {
+    if !crate::NoClear() {
+      self.DrawBoard(session);
+    }
+    println!("{}", crate::RenderError(error));
+  }
+
+  fn Announce(&self, session: &Session, event: GameEvent) {
+    match event {
+      GameEvent::Moved(outcome) => println!("{}", Described(session, outcome)),
+      GameEvent::Turn => println!("{}", TurnLine(session)),
+      // The board already shows the result
+      GameEvent::Finished => {}
+    }
+  }
+}
+
+// Struct writing the board as plain letters, the way the game formats itself, one board below
+// the other
+pub struct Plain;
+
+impl Renderer for Plain {
+  fn DrawBoard(&self, session: &Session) {
+    println!();
+    for said in crate::RecentChat(session) {
+      println!("{}", crate::SaidLine(&session.Names, said));
+    }
+    println!("{}", *session.Game);
+  }
+
+  fn ShowError(&self, _session: &Session, error: &str) {
+    println!("{}", locale::Say(Message::Error, &[("error", &error)]));
+  }
+
+  fn Announce(&self, session: &Session, event: GameEvent) {
+    match event {
+      GameEvent::Moved(outcome) => println!("{}", Described(session, outcome)),
+      GameEvent::Turn => println!("{}", TurnLine(session)),
+      GameEvent::Finished => {}
+    }
+  }
+}
+
+// Struct saying what happens in sentences for screen readers, drawing nothing: the board is
+// described on request
+pub struct Spoken;
+
+impl Renderer for Spoken {
+  fn DrawBoard(&self, _session: &Session) {}
+
+  fn ShowError(&self, _session: &Session, error: &str) {
+    println!("{}", locale::Say(Message::Error, &[("error", &error)]));
+  }
+
+  fn Announce(&self, session: &Session, event: GameEvent) {
+    match event {
+      GameEvent::Moved(outcome) => crate::AnnounceMove(&session.Game, outcome),
+      GameEvent::Turn => crate::AccessiblePrompt(session),
+      GameEvent::Finished => crate::AnnounceResult(session),
+    }
+  }
+}
+
+// Where the last piece landed, counting rows from the bottom
+fn Described(session: &Session, outcome: &MoveOutcome) -> String {
+  let game = &session.Game;
+  let name = ColorName(outcome.Player);
+  let row = game.Height() - outcome.Row;
+  match game.History().last() {
+    Some(Move::Pop(column)) => locale::Say(Message::Popped, &[("name", &name), ("column", &(column + 1))]),
+    Some(Move::Bomb(column)) => locale::Say(Message::Bombed, &[("name", &name), ("column", &(column + 1)), ("row", &row)]),
+    Some(Move::Place(..)) => {
+      locale::Say(Message::Placed, &[("name", &name), ("column", &(outcome.Column + 1)), ("row", &row)])
+    }
+    _ => locale::Say(Message::Dropped, &[("name", &name), ("column", &(outcome.Column + 1)), ("row", &row)]),
+  }
+}
+
+// Whose turn it is, with the bombs they have left in bomb games
+fn TurnLine(session: &Session) -> String {
+  let game = &session.Game;
+  let player = game.CurrentPlayer();
+  match game.HasBombs() {
+    true => locale::Say(
+      Message::TurnWithBombs,
+      &[("name", &session.Name(player)), ("color", &ColorName(player)), ("bombs", &game.BombsLeft(player))],
+    ),
+    false => locale::Say(Message::Turn, &[("name", &session.Name(player)), ("color", &ColorName(player))]),
+  }
+}
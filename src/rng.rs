@@ -0,0 +1,50 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Small seedable pseudo-random number generator (xorshift64*), good enough for games
+#[derive(Clone, Debug)]
+pub struct Rng {
+  State: u64,
+}
+
+impl Rng {
+  // Creates a generator from a seed; equal seeds produce equal sequences
+  pub fn new(seed: u64) -> Rng {
+    // Scramble the seed with SplitMix64 so small seeds still give well mixed states
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    Rng { State: if z == 0 { 1 } else { z } } // xorshift must never hold zero
+  }
+
+  // Creates a generator seeded from the system clock
+  pub fn FromTime() -> Rng {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0);
+    Rng::new(nanos)
+  }
+
+  pub fn NextU64(&mut self) -> u64 {
+    self.State ^= self.State >> 12;
+    self.State ^= self.State << 25;
+    self.State ^= self.State >> 27;
+    self.State.wrapping_mul(0x2545_F491_4F6C_DD1D)
+  }
+
+  // Returns a number in 0..bound (bound must be non-zero)
+  pub fn Below(&mut self, bound: usize) -> usize {
+    (self.NextU64() % bound as u64) as usize
+  }
+
+  // Returns a number in [0, 1)
+  pub fn NextF64(&mut self) -> f64 {
+    (self.NextU64() >> 11) as f64 / (1u64 << 53) as f64
+  }
+
+  // Picks a random element of a non-empty slice
+  pub fn Pick<T: Copy>(&mut self, items: &[T]) -> T {
+    items[self.Below(items.len())]
+  }
+}
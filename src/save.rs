@@ -0,0 +1,509 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::json::{self, Value};
+use crate::{Board, ConfigError, Game, Move, MoveError, Player, PositionError, TimeControl};
+
+// Version written into save files, bumped when the layout changes
+pub const FORMAT_VERSION: u64 = 1;
+
+// Enum representing the reasons a saved game can't be loaded
+#[derive(Debug)]
+pub enum LoadError {
+  Io(std::io::Error),                            // The file couldn't be read
+  Syntax(json::SyntaxError),                     // The file isn't valid JSON
+  UnsupportedVersion(u64),                       // Written by a newer version of the game
+  MissingField(&'static str),                    // A field is absent or has the wrong type
+  BadBoard(String),                              // The board doesn't fit the saved size or players
+  Config(ConfigError),                           // The saved rules aren't a valid game
+  Position(PositionError),                       // The saved starting position isn't valid
+  IllegalMove { Index: usize, Error: MoveError }, // A move in the history can't be replayed
+  Mismatch(&'static str),                        // Replaying the history gives a different state
+}
+
+impl std::fmt::Display for LoadError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LoadError::Io(err) => write!(f, "Couldn't read the file: {}", err),
+      LoadError::Syntax(err) => write!(f, "Not a valid save file: {}", err),
+      LoadError::UnsupportedVersion(version) => write!(f, "Save file version {} isn't supported", version),
+      LoadError::MissingField(name) => write!(f, "Missing or invalid field '{}'", name),
+      LoadError::BadBoard(reason) => write!(f, "Invalid board: {}", reason),
+      LoadError::Config(err) => write!(f, "Invalid rules: {}", err),
+      LoadError::Position(err) => write!(f, "Invalid starting position: {}", err),
+      LoadError::IllegalMove { Index, Error } => write!(f, "Move {} can't be replayed: {}", Index + 1, Error),
+      LoadError::Mismatch(name) => write!(f, "Saved '{}' doesn't match the move history", name),
+    }
+  }
+}
+
+// Writes the game as a JSON document holding its rules, any starting position, the board and
+// turn state, and the moves played. Rows are listed from the top, indices are 0-based.
+//
+// The pieces of the document are written the same way wherever they appear, also in the
+// archive and over the network:
+//   player      its number, 1 to 3, with 0 for nobody (no winner, an empty cell)
+//   board       an array of rows from the top, each an array of players
+//   move        {"drop": column}, {"pop": column}, {"bomb": column} or {"place": [row, column]}
+//   move error  a name such as "column_full", see MoveErrorName
+pub fn ToJson(game: &Game) -> String {
+  let handicap = match game.Handicap() {
+    Some(handicap) => format!(
+      "{{\"player\": {}, \"pieces\": {}, \"seed\": {}}}",
+      handicap.Player as u8, handicap.Pieces, handicap.Seed
+    ),
+    None => "null".to_string(),
+  };
+  let start = match game.Start() {
+    Some((board, ToMove)) => format!(
+      "{{\"to_move\": {}, \"board\": {}}}",
+      ToMove as u8,
+      BoardToJson(board, "      ")
+    ),
+    None => "null".to_string(),
+  };
+  let clock = match game.Clock() {
+    Some(clock) => {
+      let remaining: Vec<String> = (1..=game.Players())
+          .map(|number| clock.Remaining(Player::FromInt(number as u8)).as_millis().to_string())
+          .collect();
+      let control = clock.Control();
+      format!(
+        "{{\"budget_ms\": {}, \"increment_ms\": {}, \"delay_ms\": {}, \"remaining_ms\": [{}]}}",
+        control.Budget.as_millis(),
+        control.Increment.as_millis(),
+        control.Delay.as_millis(),
+        remaining.join(", ")
+      )
+    }
+    None => "null".to_string(),
+  };
+  let history: Vec<String> = game.History().iter().map(|&played| MoveToJson(played)).collect();
+
+  let mut text = String::from("{\n");
+  text += &format!("  \"version\": {},\n", FORMAT_VERSION);
+  text += &format!("  \"width\": {},\n", game.Width());
+  text += &format!("  \"height\": {},\n", game.Height());
+  text += &format!("  \"win_length\": {},\n", game.WinLength());
+  text += &format!("  \"players\": {},\n", game.Players());
+  text += &format!("  \"gravity\": {},\n", game.Gravity());
+  text += &format!("  \"wrap\": {},\n", game.Wrap());
+  text += &format!("  \"pop_out\": {},\n", game.PopOut());
+  text += &format!("  \"bombs\": {},\n", game.BombsPerPlayer());
+  text += &format!("  \"handicap\": {},\n", handicap);
+  text += &format!("  \"start\": {},\n", start);
+  text += &format!("  \"clock\": {},\n", clock);
+  text += &format!("  \"board\": {},\n", BoardToJson(game.Board(), "    "));
+  text += &format!("  \"current_move\": {},\n", game.CurrentMove());
+  text += &format!("  \"current_player\": {},\n", game.CurrentPlayer() as u8);
+  text += &format!("  \"is_finished\": {},\n", game.IsFinished());
+  text += &format!("  \"winner\": {},\n", game.Winner() as u8);
+  text += &format!("  \"draw_agreed\": {},\n", game.IsDrawAgreed());
+  text += &format!("  \"forfeited\": {},\n", game.Forfeited().map_or(0, |player| player as u8));
+  text += &format!("  \"history\": [{}]\n", history.join(", "));
+  text += "}\n";
+  text
+}
+
+// Rebuilds a game from a document written by ToJson. The rules and starting position are set
+// up first and the history replayed on top, so the saved board, move counter, turn and result
+// are checked against what the moves actually produce.
+pub fn FromJson(text: &str) -> Result<Game, LoadError> {
+  FromValue(&json::Parse(text).map_err(LoadError::Syntax)?)
+}
+
+// Rebuilds a game from a document written by ToJson that's already been parsed, such as one
+// kept in the games archive
+pub fn FromValue(document: &Value) -> Result<Game, LoadError> {
+  let version = Field(document, "version", Value::AsU64)?;
+  if version > FORMAT_VERSION {
+    return Err(LoadError::UnsupportedVersion(version));
+  }
+
+  let width = Field(document, "width", Value::AsUsize)?;
+  let height = Field(document, "height", Value::AsUsize)?;
+  let players = Field(document, "players", Value::AsUsize)?;
+  let length = Field(document, "win_length", Value::AsUsize)?;
+  let mut game = Game::new(width, height)
+      .and_then(|game| game.WithWinLength(length))
+      .and_then(|game| game.WithPlayers(players))
+      .map_err(LoadError::Config)?
+      .WithGravity(Field(document, "gravity", Value::AsBool)?)
+      .WithWrap(Field(document, "wrap", Value::AsBool)?)
+      .WithPopOut(Field(document, "pop_out", Value::AsBool)?)
+      .WithBombs(Field(document, "bombs", |value| value.AsU64().and_then(|bombs| u8::try_from(bombs).ok()))?);
+
+  // Older files have no clock
+  let clock = document.Get("clock").filter(|clock| **clock != Value::Null);
+  if let Some(clock) = clock {
+    let millis = |name| clock.Get(name).and_then(Value::AsU64).map_or(Duration::ZERO, Duration::from_millis);
+    game = game.WithClock(TimeControl {
+      Budget: Duration::from_millis(Field(clock, "budget_ms", Value::AsU64)?),
+      Increment: millis("increment_ms"),
+      Delay: millis("delay_ms"),
+    });
+  }
+
+  match document.Get("handicap") {
+    Some(Value::Null) => {}
+    Some(handicap) => {
+      let player = Field(handicap, "player", Value::AsU64)?;
+      let pieces = Field(handicap, "pieces", Value::AsUsize)?;
+      let seed = Field(handicap, "seed", Value::AsU64)?;
+      game = game
+          .WithHandicap(PlayerFromNumber(player, players, "handicap")?, pieces, seed)
+          .map_err(LoadError::Config)?;
+    }
+    None => return Err(LoadError::MissingField("handicap")),
+  }
+  match document.Get("start") {
+    Some(Value::Null) => {}
+    Some(start) => {
+      let board = BoardFromJson(start.Get("board"), width, height, players)?;
+      let ToMove = PlayerFromNumber(Field(start, "to_move", Value::AsU64)?, players, "to_move")?;
+      game.SetPosition(board, ToMove).map_err(LoadError::Position)?;
+    }
+    None => return Err(LoadError::MissingField("start")),
+  }
+
+  // The saved state is checked against the history below, but impossible boards are turned
+  // away first, with a reason that says what's wrong with them
+  let board = BoardFromJson(document.Get("board"), width, height, players)?;
+  let history = Field(document, "history", Value::AsArray)?;
+  CheckBoard(&game, &board, Field(document, "current_move", Value::AsUsize)?)?;
+  for (index, entry) in history.iter().enumerate() {
+    let played = MoveFromJson(entry).ok_or(LoadError::MissingField("history"))?;
+    game.Play(played).map_err(|err| LoadError::IllegalMove { Index: index, Error: err })?;
+  }
+
+  // Time is spent between moves, so the clocks are restored as they were
+  if let Some(clock) = clock {
+    let remaining = Field(clock, "remaining_ms", Value::AsArray)?;
+    if remaining.len() != players {
+      return Err(LoadError::MissingField("remaining_ms"));
+    }
+    for (index, time) in remaining.iter().enumerate() {
+      let time = time.AsU64().ok_or(LoadError::MissingField("remaining_ms"))?;
+      game.SetTimeLeft(Player::FromInt(index as u8 + 1), Duration::from_millis(time));
+    }
+  }
+
+  // An agreed draw isn't a move, so it's restored on its own; older files don't have it
+  if document.Get("draw_agreed").and_then(Value::AsBool) == Some(true) {
+    if game.IsFinished() {
+      return Err(LoadError::Mismatch("draw_agreed"));
+    }
+    game.AgreeDraw();
+  }
+  // As is a loss on time, which is always the player on turn
+  match document.Get("forfeited").and_then(Value::AsU64) {
+    None | Some(0) => {}
+    Some(number) => {
+      let player = PlayerFromNumber(number, players, "forfeited")?;
+      if player != game.CurrentPlayer() || game.Forfeit().is_err() {
+        return Err(LoadError::Mismatch("forfeited"));
+      }
+    }
+  }
+
+  // The saved state must be exactly what the moves produce
+  if &board != game.Board() {
+    return Err(LoadError::Mismatch("board"));
+  }
+  if Field(document, "current_move", Value::AsUsize)? != game.CurrentMove() {
+    return Err(LoadError::Mismatch("current_move"));
+  }
+  if Field(document, "current_player", Value::AsU64)? != game.CurrentPlayer() as u64 {
+    return Err(LoadError::Mismatch("current_player"));
+  }
+  if Field(document, "is_finished", Value::AsBool)? != game.IsFinished() {
+    return Err(LoadError::Mismatch("is_finished"));
+  }
+  if Field(document, "winner", Value::AsU64)? != game.Winner() as u64 {
+    return Err(LoadError::Mismatch("winner"));
+  }
+  Ok(game)
+}
+
+// Turns away a saved board no game could reach: with gravity no piece may float above an empty
+// cell, and when every move adds exactly one piece (no pops, bombs or pieces placed before the
+// game) there must be as many pieces as moves, shared out by turn
+fn CheckBoard(game: &Game, board: &Board, moves: usize) -> Result<(), LoadError> {
+  if game.Gravity() {
+    for row in 0..board.Height() - 1 {
+      for column in 0..board.Width() {
+        if board[row][column] != 0 && board[row + 1][column] == 0 {
+          return Err(LoadError::BadBoard(format!(
+            "the piece in row {}, column {} is floating above an empty cell",
+            row + 1,
+            column + 1
+          )));
+        }
+      }
+    }
+  }
+  if game.PopOut() || game.HasBombs() || game.IsSetUp() || game.Handicap().is_some() {
+    return Ok(());
+  }
+  let players = game.Players();
+  let mut counts = vec![0; players];
+  board.Cells().iter().filter(|&&cell| cell != 0).for_each(|&cell| counts[cell as usize - 1] += 1);
+  let total: usize = counts.iter().sum();
+  if total != moves {
+    return Err(LoadError::BadBoard(format!("{} pieces on the board after {} moves", total, moves)));
+  }
+  // Turns go round in order, so the first (moves % players) players have one extra piece
+  for (index, &count) in counts.iter().enumerate() {
+    let expected = moves / players + (index < moves % players) as usize;
+    if count != expected {
+      return Err(LoadError::BadBoard(format!(
+        "Player {} has {} pieces after {} moves instead of {}",
+        index + 1,
+        count,
+        moves,
+        expected
+      )));
+    }
+  }
+  Ok(())
+}
+
+// Writes the game to a file
+pub fn SaveFile(game: &Game, path: impl AsRef<Path>) -> std::io::Result<()> {
+  std::fs::write(path, ToJson(game))
+}
+
+// Reads a game saved with SaveFile
+pub fn LoadFile(path: impl AsRef<Path>) -> Result<Game, LoadError> {
+  let text = std::fs::read_to_string(path).map_err(LoadError::Io)?;
+  FromJson(&text)
+}
+
+// The game's folder for files kept between runs, following each platform's convention for
+// application data: $XDG_DATA_HOME or ~/.local/share on Linux, ~/Library/Application Support
+// on macOS and %APPDATA% on Windows. None if the home directory can't be found.
+pub fn DataDir() -> Option<PathBuf> {
+  let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+  let base = if cfg!(windows) {
+    var("APPDATA")?
+  } else if cfg!(target_os = "macos") {
+    var("HOME")?.join("Library").join("Application Support")
+  } else {
+    var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local").join("share")))?
+  };
+  Some(base.join("connectfour"))
+}
+
+// Where the game in progress is saved between runs
+pub fn AutosavePath() -> Option<PathBuf> {
+  DataDir().map(|folder| folder.join("autosave.json"))
+}
+
+// Writes the game to the autosave file, creating its folder if needed
+pub fn WriteAutosave(game: &Game) -> std::io::Result<()> {
+  let path = AutosavePath().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no home directory"))?;
+  if let Some(folder) = path.parent() {
+    std::fs::create_dir_all(folder)?;
+  }
+  SaveFile(game, path)
+}
+
+// Deletes the autosave file, if there is one
+pub fn ClearAutosave() {
+  if let Some(path) = AutosavePath() {
+    let _ = std::fs::remove_file(path);
+  }
+}
+
+// Reads a field with the given accessor, failing if it's missing or of the wrong type
+pub(crate) fn Field<'a, T>(object: &'a Value, name: &'static str, read: impl Fn(&'a Value) -> Option<T>) -> Result<T, LoadError> {
+  object.Get(name).and_then(read).ok_or(LoadError::MissingField(name))
+}
+
+// Writes a player as its number
+pub fn PlayerToJson(player: Player) -> String {
+  (player as u8).to_string()
+}
+
+// Reads a player of a game with the given number of players; 0 gives Player::None
+pub fn PlayerFromJson(value: &Value, players: usize) -> Result<Player, LoadError> {
+  match value.AsU64() {
+    Some(0) => Ok(Player::None),
+    Some(number) => PlayerFromNumber(number, players, "player"),
+    None => Err(LoadError::MissingField("player")),
+  }
+}
+
+// Name each move error is written as
+pub fn MoveErrorName(err: &MoveError) -> &'static str {
+  match err {
+    MoveError::GameFinished => "game_finished",
+    MoveError::InvalidColumn { .. } => "invalid_column",
+    MoveError::ColumnFull { .. } => "column_full",
+    MoveError::PopOutDisabled => "pop_out_disabled",
+    MoveError::CannotPop => "cannot_pop",
+    MoveError::GravityOn => "gravity_on",
+    MoveError::GravityOff => "gravity_off",
+    MoveError::InvalidRow => "invalid_row",
+    MoveError::CellOccupied => "cell_occupied",
+    MoveError::BombsDisabled => "bombs_disabled",
+    MoveError::NoBombsLeft => "no_bombs_left",
+  }
+}
+
+pub fn MoveErrorToJson(err: &MoveError) -> String {
+  json::Quote(MoveErrorName(err))
+}
+
+// Reads a move error from its name. The name leaves out which column was at fault, so those
+// errors come back with column 0.
+pub fn MoveErrorFromJson(value: &Value) -> Option<MoveError> {
+  let name = value.AsStr()?;
+  [
+    MoveError::GameFinished,
+    MoveError::InvalidColumn { Given: 0, Max: 0 },
+    MoveError::ColumnFull { Column: 0 },
+    MoveError::PopOutDisabled,
+    MoveError::CannotPop,
+    MoveError::GravityOn,
+    MoveError::GravityOff,
+    MoveError::InvalidRow,
+    MoveError::CellOccupied,
+    MoveError::BombsDisabled,
+    MoveError::NoBombsLeft,
+  ]
+  .into_iter()
+  .find(|err| MoveErrorName(err) == name)
+}
+
+fn PlayerFromNumber(number: u64, players: usize, field: &'static str) -> Result<Player, LoadError> {
+  match number {
+    1..=3 if number as usize <= players => Ok(Player::FromInt(number as u8)),
+    _ => Err(LoadError::MissingField(field)),
+  }
+}
+
+// Writes a board as rows from the top, each row on its own line after the indent (which is
+// at least two spaces, the closing bracket going two spaces further out)
+pub fn BoardToJson(board: &Board, indent: &str) -> String {
+  let rows: Vec<String> = board
+      .Rows()
+      .map(|row| {
+        let cells: Vec<String> = row.iter().map(|cell| cell.to_string()).collect();
+        format!("{}[{}]", indent, cells.join(", "))
+      })
+      .collect();
+  format!("[\n{}\n{}]", rows.join(",\n"), &indent[2..])
+}
+
+// Reads a board of rows listed from the top, checking its size and that cells hold players
+pub fn BoardFromJson(value: Option<&Value>, width: usize, height: usize, players: usize) -> Result<Board, LoadError> {
+  let rows = value.and_then(Value::AsArray).ok_or(LoadError::MissingField("board"))?;
+  if rows.len() != height {
+    return Err(LoadError::BadBoard(format!("{} rows instead of {}", rows.len(), height)));
+  }
+  let mut board = Board::new(width, height);
+  for (row, cells) in rows.iter().enumerate() {
+    let cells = cells.AsArray().ok_or(LoadError::MissingField("board"))?;
+    if cells.len() != width {
+      return Err(LoadError::BadBoard(format!("row {} has {} cells instead of {}", row + 1, cells.len(), width)));
+    }
+    for (column, cell) in cells.iter().enumerate() {
+      match cell.AsU64() {
+        Some(value) if value as usize <= players => board[row][column] = value as u8,
+        _ => {
+          return Err(LoadError::BadBoard(format!(
+            "row {}, column {} doesn't hold a player of this game",
+            row + 1,
+            column + 1
+          )))
+        }
+      }
+    }
+  }
+  Ok(board)
+}
+
+pub fn MoveToJson(played: Move) -> String {
+  match played {
+    Move::Drop(column) => format!("{{\"drop\": {}}}", column),
+    Move::Pop(column) => format!("{{\"pop\": {}}}", column),
+    Move::Bomb(column) => format!("{{\"bomb\": {}}}", column),
+    Move::Place(row, column) => format!("{{\"place\": [{}, {}]}}", row, column),
+  }
+}
+
+pub fn MoveFromJson(value: &Value) -> Option<Move> {
+  if let Some(column) = value.Get("drop") {
+    return column.AsUsize().map(Move::Drop);
+  }
+  if let Some(column) = value.Get("pop") {
+    return column.AsUsize().map(Move::Pop);
+  }
+  if let Some(column) = value.Get("bomb") {
+    return column.AsUsize().map(Move::Bomb);
+  }
+  match value.Get("place")?.AsArray()? {
+    [row, column] => Some(Move::Place(row.AsUsize()?, column.AsUsize()?)),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // The game after the moves, as 1-based columns
+  fn MidGame() -> Game {
+    Game::FromMoveString("4453362").unwrap()
+  }
+
+  #[test]
+  fn MidGameRoundTrip() {
+    let game = MidGame();
+    let loaded = FromJson(&ToJson(&game)).unwrap();
+    assert_eq!(ToJson(&loaded), ToJson(&game));
+    assert_eq!(loaded.History(), game.History());
+    assert_eq!(loaded.CurrentPlayer(), Player::Two);
+    assert!(!loaded.IsFinished());
+  }
+
+  #[test]
+  fn RulesAndClockRoundTrip() {
+    let control = TimeControl { Budget: Duration::from_secs(60), Increment: Duration::from_secs(2), Delay: Duration::ZERO };
+    let mut game = Game::new(9, 7).unwrap().WithWinLength(5).unwrap().WithPlayers(3).unwrap().WithPopOut(true).WithClock(control);
+    game.PlayMoveString("123").unwrap();
+    game.SpendTime(Duration::from_millis(1500));
+    let loaded = FromJson(&ToJson(&game)).unwrap();
+    assert_eq!(ToJson(&loaded), ToJson(&game));
+  }
+
+  #[test]
+  fn FileRoundTrip() {
+    let path = std::env::temp_dir().join(format!("ConnectFour-save-test-{}.json", std::process::id()));
+    let game = MidGame();
+    SaveFile(&game, &path).unwrap();
+    let loaded = LoadFile(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(ToJson(&loaded.unwrap()), ToJson(&game));
+  }
+
+  #[test]
+  fn CorruptFilesAreRejected() {
+    let saved = ToJson(&MidGame());
+    assert!(matches!(FromJson(&saved[..saved.len() / 2]), Err(LoadError::Syntax(_))));
+    assert!(matches!(FromJson("[1, 2, 3]"), Err(LoadError::MissingField("version"))));
+    let newer = saved.replace("\"version\": 1,", "\"version\": 99,");
+    assert!(matches!(FromJson(&newer), Err(LoadError::UnsupportedVersion(99))));
+    let no_width = saved.replace("\"width\": 7,", "");
+    assert!(matches!(FromJson(&no_width), Err(LoadError::MissingField("width"))));
+    let tiny = saved.replace("\"width\": 7,", "\"width\": 2,");
+    assert!(matches!(FromJson(&tiny), Err(LoadError::Config(_))));
+    let off_board = saved.replace("{\"drop\": 1}", "{\"drop\": 9}");
+    assert!(matches!(FromJson(&off_board), Err(LoadError::IllegalMove { Index: 6, Error: MoveError::InvalidColumn { .. } })));
+    let other_turn = saved.replace("\"current_player\": 2,", "\"current_player\": 1,");
+    assert!(matches!(FromJson(&other_turn), Err(LoadError::Mismatch(_))));
+    let won = saved.replace("\"is_finished\": false,", "\"is_finished\": true,");
+    assert!(matches!(FromJson(&won), Err(LoadError::Mismatch(_))));
+  }
+}
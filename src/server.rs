@@ -0,0 +1,454 @@
+// WebSocket server mode, `serve --ws 0.0.0.0:9001`, for browsers and other programs: two
+// players to a room, any number of rooms at once, each with its own game played by the rules
+// from the usual options. Every message is a JSON object, clients sending commands and the
+// server events in the same shape as `--json` writes them, rows and columns from 0:
+//
+//   {"command":"join","room":"kitchen","name":"Ann"}     take the next free seat in the room
+//   {"command":"join","room":"kitchen","as":"spectator"} watch the game, which can't be moved in
+//   {"command":"join","room":"kitchen","token":"3f2a..."} come back to the seat the token is for
+//   {"command":"move","column":3}                        with "kind" and "row" as for --json
+//   {"command":"say","text":"good game"}                 chat to everyone in the room
+//
+//   {"event":"joined","room":"kitchen","player":1,"token":"3f2a..."}   player 0 for a spectator
+//   {"event":"state","room":"kitchen","players":["Ann","Bob"],"board":[[0,...],...],"turn":1,
+//     "move_number":0,"legal":[0,...],"finished":false,"spectators":0}
+//   {"event":"move","player":1,"kind":"drop","column":3,"row":5,"move_number":1}
+//   {"event":"game_over","winner":1,"line":[[5,0],[5,1],[5,2],[5,3]]}    winner 0 for a draw
+//   {"event":"chat","player":1,"name":"Ann","text":"good game"}
+//   {"event":"reconnected","player":2}
+//   {"event":"error","code":"not_your_turn","message":"..."}
+//
+// The state goes to both players and the spectators once the room is full and after every
+// move. A spectator who comes once the game is under way is first sent its moves so far, as
+// move events, and so is a player coming back. The game the server keeps is the one that
+// counts: moves are checked against it, so a client can't move for the other player, out of
+// turn or against the rules. The error codes are bad_message, unknown_command,
+// already_joined, room_full, bad_token, not_joined, waiting_for_opponent,
+// waiting_for_reconnect, not_your_turn, spectator_cannot_move, spectator_cannot_chat,
+// illegal_move, game_over, opponent_disconnected and opponent_left. Chat is cut short and
+// stripped of control characters as in `host` games.
+//
+// A player whose connection drops mid-game keeps their seat for `--grace` seconds, the other
+// player being told with opponent_disconnected and kept from moving meanwhile. Joining the
+// room again with the token from their joined event puts them back where they were; if they
+// don't, they forfeit and the game is over.
+
+use std::collections::HashMap;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ConnectFour::json::{self, Value};
+use ConnectFour::rng::Rng;
+use ConnectFour::{log, Game, Move, MoveOutcome, Player};
+
+use crate::events;
+use crate::interrupt;
+use crate::net;
+use crate::websocket;
+use crate::{locale, ArgValue, Message};
+
+// How often the server stops waiting for connections to see whether Ctrl-C was pressed
+const POLL: Duration = Duration::from_millis(100);
+
+// How long a message to a client may take before it's given up on, so one that stops reading
+// can't hold up its room
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Longest room name taken
+const MAX_ROOM_LENGTH: usize = 64;
+
+// Struct representing a connected client who has joined a room
+struct Seat {
+  Connection: u64,               // Number of the connection, which no other has
+  Name: String,                  // What they're called
+  Out: Arc<Mutex<TcpStream>>,    // Where messages for them are written
+  Token: String,                 // What a player comes back with, empty for a spectator
+  Away: Option<Instant>,         // When a player whose connection dropped forfeits
+}
+
+// Struct representing a room: its game, the players in it, in turn order, and those watching
+struct Room {
+  Game: Game,
+  Seats: Vec<Seat>,
+  Spectators: Vec<Seat>,
+}
+
+// Every room, by name
+type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+// Accepts connections on `--ws` until Ctrl-C, each on a thread of its own
+pub fn Run() {
+  let started = crate::GameFromArgs().map_err(|err| err.to_string()).and_then(|game| match game.Players() {
+    2 => Ok(game),
+    _ => Err("a room has two players".to_string()),
+  });
+  let start = match started {
+    Ok(game) => game,
+    Err(err) => return Failed(&err),
+  };
+  let Some(address) = ArgValue("--ws") else {
+    return Failed("serve needs an address to listen on, e.g. serve --ws 0.0.0.0:9001");
+  };
+  let listener = match TcpListener::bind(&address).and_then(|listener| listener.set_nonblocking(true).map(|()| listener)) {
+    Ok(listener) => listener,
+    Err(err) => return Failed(&format!("can't listen on {}: {}", address, err)),
+  };
+  println!("{}", locale::Say(Message::ServingWebSocket, &[("address", &address)]));
+  let rooms: Rooms = Arc::default();
+  let connections = AtomicU64::new(0);
+  while !interrupt::Interrupted() {
+    match listener.accept() {
+      Ok((stream, peer)) => {
+        let connection = connections.fetch_add(1, Ordering::Relaxed) + 1;
+        log::Info(format_args!("Connection {} from {}", connection, peer));
+        let rooms = Arc::clone(&rooms);
+        let start = start.clone();
+        std::thread::spawn(move || Serve(stream, connection, &rooms, &start));
+      }
+      Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(POLL),
+      Err(err) => log::Info(format_args!("Connection not accepted: {}", err)),
+    }
+  }
+}
+
+fn Failed(err: &str) {
+  println!("{}{}{}", crate::Alert(), locale::Say(Message::Error, &[("error", &err)]), crate::Reset());
+}
+
+// Talks to one client until it goes, then lets the other player in its room know
+fn Serve(mut stream: TcpStream, connection: u64, rooms: &Rooms, start: &Game) {
+  let setup = stream
+      .set_nonblocking(false)
+      .and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+      .map_err(|err| err.to_string())
+      .and_then(|()| websocket::Handshake(&mut stream))
+      .and_then(|()| stream.try_clone().map_err(|err| err.to_string()));
+  let out = match setup {
+    Ok(out) => Arc::new(Mutex::new(out)),
+    Err(err) => {
+      log::Info(format_args!("Connection {} refused: {}", connection, err));
+      return;
+    }
+  };
+  // The room joined and the player taken in it
+  let mut seat: Option<(String, Player)> = None;
+  while let Some(text) = websocket::ReadText(&mut stream, &out) {
+    log::Debug(format_args!("Connection {}: {}", connection, text));
+    let handled = json::Parse(&text).map_err(|err| ("bad_message", err.to_string())).and_then(|command| {
+      match command.Get("command").and_then(Value::AsStr) {
+        Some("join") => Join(rooms, start, &command, connection, &out, &mut seat),
+        Some("move") => Play(rooms, &command, connection, &mut seat),
+        Some("say") => Chat(rooms, &command, connection, &mut seat),
+        Some(other) => Err(("unknown_command", format!("unknown command \"{}\"", other))),
+        None => Err(("bad_message", "the object needs a \"command\"".to_string())),
+      }
+    });
+    if let Err((code, message)) = handled {
+      Send(&out, &events::Line("error", &[("code", json::Quote(code)), ("message", json::Quote(&message))]));
+    }
+  }
+  log::Info(format_args!("Connection {} closed", connection));
+  if let Some((name, _)) = seat {
+    Leave(rooms, &name, connection);
+  }
+}
+
+// Seats the client in the room named, the room being made if it's new, and starts its game
+// once both players are there. A spectator is brought up to date with the game instead.
+fn Join(
+  rooms: &Rooms,
+  start: &Game,
+  command: &Value,
+  connection: u64,
+  out: &Arc<Mutex<TcpStream>>,
+  seat: &mut Option<(String, Player)>,
+) -> Result<(), (&'static str, String)> {
+  if let Some((room, _)) = seat {
+    return Err(("already_joined", format!("already in room \"{}\"", room)));
+  }
+  if let Some(token) = command.Get("token").and_then(Value::AsStr) {
+    return Resume(rooms, command, token, connection, out, seat);
+  }
+  let name = command
+      .Get("room")
+      .and_then(Value::AsStr)
+      .map(str::trim)
+      .filter(|name| !name.is_empty() && name.chars().count() <= MAX_ROOM_LENGTH)
+      .ok_or(("bad_message", format!("the command needs a \"room\" of 1 to {} characters", MAX_ROOM_LENGTH)))?;
+  let mut rooms = rooms.lock().unwrap();
+  let room = rooms.entry(name.to_string()).or_insert_with(|| Room { Game: start.clone(), Seats: Vec::new(), Spectators: Vec::new() });
+  if command.Get("as").and_then(Value::AsStr) == Some("spectator") {
+    let spectator = Seat { Connection: connection, Name: String::new(), Out: Arc::clone(out), Token: String::new(), Away: None };
+    *seat = Some((name.to_string(), Player::None));
+    Send(out, &events::Line("joined", &[("room", json::Quote(name)), ("player", "0".to_string())]));
+    Replay(&room.Game, out);
+    room.Spectators.push(spectator);
+    Broadcast(room, &State(name, room));
+    log::Info(format_args!("Connection {} is watching room {}", connection, name));
+    return Ok(());
+  }
+  if room.Seats.len() == 2 {
+    return Err(("room_full", format!("room \"{}\" already has two players; join as a spectator to watch", name)));
+  }
+  let player = Player::FromInt(room.Seats.len() as u8 + 1);
+  let default = [Message::PlayerOne, Message::PlayerTwo][player as usize - 1];
+  let token = format!("{:016x}", Rng::FromTime().NextU64() ^ connection);
+  room.Seats.push(Seat {
+    Connection: connection,
+    Name: command
+        .Get("name")
+        .and_then(Value::AsStr)
+        .and_then(crate::CleanName)
+        .unwrap_or_else(|| locale::Text(default).to_string()),
+    Out: Arc::clone(out),
+    Token: token.clone(),
+    Away: None,
+  });
+  *seat = Some((name.to_string(), player));
+  Send(out, &events::Line("joined", &[
+    ("room", json::Quote(name)),
+    ("player", (player as u8).to_string()),
+    ("token", json::Quote(&token)),
+  ]));
+  log::Info(format_args!("Connection {} joined room {} as player {}", connection, name, player as u8));
+  if room.Seats.len() == 2 {
+    Broadcast(room, &State(name, room));
+  }
+  Ok(())
+}
+
+// Puts a player back in the seat the token is for, and brings them up to date
+fn Resume(
+  rooms: &Rooms,
+  command: &Value,
+  token: &str,
+  connection: u64,
+  out: &Arc<Mutex<TcpStream>>,
+  seat: &mut Option<(String, Player)>,
+) -> Result<(), (&'static str, String)> {
+  let name = command.Get("room").and_then(Value::AsStr).map(str::trim).unwrap_or_default();
+  let mut rooms = rooms.lock().unwrap();
+  let bad_token = ("bad_token", format!("nobody in room \"{}\" has that token", name));
+  let room = rooms.get_mut(name).ok_or(bad_token.clone())?;
+  let index = room.Seats.iter().position(|seat| !token.is_empty() && seat.Token == token).ok_or(bad_token)?;
+  let player = Player::FromInt(index as u8 + 1);
+  // Whichever connection had the seat before gives it up
+  let taken = &mut room.Seats[index];
+  taken.Connection = connection;
+  taken.Out = Arc::clone(out);
+  taken.Away = None;
+  *seat = Some((name.to_string(), player));
+  Send(out, &events::Line("joined", &[
+    ("room", json::Quote(name)),
+    ("player", (player as u8).to_string()),
+    ("token", json::Quote(token)),
+  ]));
+  Replay(&room.Game, out);
+  Broadcast(room, &events::Line("reconnected", &[("player", (player as u8).to_string())]));
+  Broadcast(room, &State(name, room));
+  log::Info(format_args!("Connection {} is back in room {} as player {}", connection, name, player as u8));
+  Ok(())
+}
+
+// Sends the moves of the game so far, as move events
+fn Replay(game: &Game, out: &Mutex<TcpStream>) {
+  let mut replay = game.clone();
+  replay.Reset();
+  for &played in game.History() {
+    if let Ok(outcome) = replay.Play(played) {
+      Send(out, &Moved(&replay, &outcome));
+    }
+  }
+}
+
+// Plays the client's move in its room's game, if it's their turn and the move is legal, and
+// tells both players
+fn Play(rooms: &Rooms, command: &Value, connection: u64, seat: &mut Option<(String, Player)>) -> Result<(), (&'static str, String)> {
+  let played = events::MoveFrom(command).map_err(|err| ("bad_message", err))?;
+  let not_joined = ("not_joined", "join a room first".to_string());
+  let (name, player) = seat.clone().ok_or(not_joined.clone())?;
+  // The whole move happens under the lock, so moves sent at once by both players are taken
+  // one after the other against the game as it is then
+  let mut rooms = rooms.lock().unwrap();
+  let Some(room) = rooms.get_mut(&name).filter(|room| room.Seats.iter().chain(&room.Spectators).any(|seat| seat.Connection == connection)) else {
+    // The room closed when a player left
+    *seat = None;
+    return Err(not_joined);
+  };
+  if player == Player::None {
+    return Err(("spectator_cannot_move", "spectators can't move".to_string()));
+  }
+  if room.Seats.len() < 2 {
+    return Err(("waiting_for_opponent", "the game starts once a second player joins".to_string()));
+  }
+  if let Some((away, until)) = room.Seats.iter().find_map(|seat| Some((&seat.Name, seat.Away?))) {
+    return Err(("waiting_for_reconnect", format!("waiting for {} to reconnect ({}s)", away, net::SecondsLeft(until))));
+  }
+  if room.Game.IsFinished() {
+    return Err(("game_over", "the game is over".to_string()));
+  }
+  if room.Game.CurrentPlayer() != player {
+    return Err(("not_your_turn", format!("it's player {}'s turn", room.Game.CurrentPlayer() as u8)));
+  }
+  let outcome = room.Game.Play(played).map_err(|err| ("illegal_move", err.to_string()))?;
+  Broadcast(room, &Moved(&room.Game, &outcome));
+  Broadcast(room, &State(&name, room));
+  if room.Game.IsFinished() {
+    GameOver(&name, room);
+  }
+  Ok(())
+}
+
+// Tells everyone in the room the game is over
+fn GameOver(name: &str, room: &Room) {
+  let line: Vec<String> = room.Game.WinningLine().iter().map(|(row, column)| format!("[{},{}]", row, column)).collect();
+  let over = events::Line("game_over", &[
+    ("winner", (room.Game.Winner() as u8).to_string()),
+    ("line", format!("[{}]", line.join(","))),
+  ]);
+  Broadcast(room, &over);
+  log::Info(format_args!("Room {}: game over, winner {:?}", name, room.Game.Winner()));
+}
+
+// Passes what the client says on to everyone in its room
+fn Chat(rooms: &Rooms, command: &Value, connection: u64, seat: &mut Option<(String, Player)>) -> Result<(), (&'static str, String)> {
+  let text = command.Get("text").and_then(Value::AsStr).map(|text| net::ChatText(text.as_bytes())).unwrap_or_default();
+  if text.is_empty() {
+    return Err(("bad_message", "the command needs some \"text\" to say".to_string()));
+  }
+  let not_joined = ("not_joined", "join a room first".to_string());
+  let (name, player) = seat.clone().ok_or(not_joined.clone())?;
+  let rooms = rooms.lock().unwrap();
+  let Some(room) = rooms.get(&name).filter(|room| room.Seats.iter().chain(&room.Spectators).any(|seat| seat.Connection == connection)) else {
+    *seat = None;
+    return Err(not_joined);
+  };
+  let Some(speaker) = room.Seats.iter().find(|seat| seat.Connection == connection) else {
+    return Err(("spectator_cannot_chat", "spectators can't chat".to_string()));
+  };
+  Broadcast(room, &events::Line("chat", &[
+    ("player", (player as u8).to_string()),
+    ("name", json::Quote(&speaker.Name)),
+    ("text", json::Quote(&text)),
+  ]));
+  Ok(())
+}
+
+// Takes the client out of its room. A player whose game is under way keeps their seat for a
+// while, to come back to; any other player leaving closes the room, since its game can't go
+// on, and everyone else there is told, and can join another.
+fn Leave(rooms: &Rooms, name: &str, connection: u64) {
+  let shared = Arc::clone(rooms);
+  let mut rooms = rooms.lock().unwrap();
+  if let Some(room) = rooms.get_mut(name).filter(|room| room.Spectators.iter().any(|seat| seat.Connection == connection)) {
+    room.Spectators.retain(|seat| seat.Connection != connection);
+    return;
+  }
+  let playing = |room: &&mut Room| room.Seats.len() == 2 && !room.Game.IsFinished();
+  if let Some(room) = rooms.get_mut(name).filter(playing) {
+    if let Some(away) = room.Seats.iter_mut().find(|seat| seat.Connection == connection) {
+      let grace = net::Grace();
+      away.Away = Some(Instant::now() + grace);
+      let dropped = events::Line("error", &[
+        ("code", json::Quote("opponent_disconnected")),
+        ("message", json::Quote(&format!("{} lost the connection; waiting {}s for them to come back", away.Name, grace.as_secs()))),
+      ]);
+      let token = away.Token.clone();
+      Broadcast(room, &dropped);
+      let held = name.to_string();
+      std::thread::spawn(move || {
+        std::thread::sleep(grace);
+        Expire(&shared, &held, &token);
+      });
+      log::Info(format_args!("Room {}: connection {} dropped, holding its seat", name, connection));
+      return;
+    }
+  }
+  // The room may have closed already, and another opened under its name
+  let Some(leaving) = rooms.get(name).and_then(|room| room.Seats.iter().find(|seat| seat.Connection == connection)) else {
+    return;
+  };
+  let left = events::Line("error", &[
+    ("code", json::Quote("opponent_left")),
+    ("message", json::Quote(&format!("{} left the room", leaving.Name))),
+  ]);
+  let Some(room) = rooms.remove(name) else { return };
+  for seat in room.Seats.iter().chain(&room.Spectators).filter(|seat| seat.Connection != connection) {
+    Send(&seat.Out, &left);
+  }
+  log::Info(format_args!("Room {} closed", name));
+}
+
+// Ends the room's game with the player the token is for forfeiting, unless they've come back
+fn Expire(rooms: &Rooms, name: &str, token: &str) {
+  let mut rooms = rooms.lock().unwrap();
+  let Some(room) = rooms.get_mut(name) else { return };
+  let Some(index) = room.Seats.iter().position(|seat| seat.Token == token && seat.Away.is_some_and(|until| until <= Instant::now())) else {
+    return;
+  };
+  if room.Game.ForfeitBy(Player::FromInt(index as u8 + 1)).is_ok() {
+    log::Info(format_args!("Room {}: {} didn't come back and forfeits", name, room.Seats[index].Name));
+    Broadcast(room, &State(name, room));
+    GameOver(name, room);
+  }
+}
+
+// The room's state event: the players, the board, whose turn it is and what they can play
+fn State(name: &str, room: &Room) -> String {
+  let game = &room.Game;
+  let players: Vec<String> = room.Seats.iter().map(|seat| json::Quote(&seat.Name)).collect();
+  let rows: Vec<String> = game
+      .Board()
+      .Rows()
+      .map(|row| format!("[{}]", row.iter().map(u8::to_string).collect::<Vec<_>>().join(",")))
+      .collect();
+  let legal: Vec<String> = match game.IsFinished() {
+    true => Vec::new(),
+    false => game.LegalMoves().iter().map(usize::to_string).collect(),
+  };
+  events::Line("state", &[
+    ("room", json::Quote(name)),
+    ("width", game.Width().to_string()),
+    ("height", game.Height().to_string()),
+    ("connect", game.WinLength().to_string()),
+    ("players", format!("[{}]", players.join(","))),
+    ("board", format!("[{}]", rows.join(","))),
+    ("turn", (game.CurrentPlayer() as u8).to_string()),
+    ("move_number", game.History().len().to_string()),
+    ("legal", format!("[{}]", legal.join(","))),
+    ("finished", game.IsFinished().to_string()),
+    ("spectators", room.Spectators.len().to_string()),
+  ])
+}
+
+// The move event for a move just made, as `--json` writes it
+fn Moved(game: &Game, outcome: &MoveOutcome) -> String {
+  let kind = match game.History().last() {
+    Some(Move::Pop(_)) => "pop",
+    Some(Move::Bomb(_)) => "bomb",
+    Some(Move::Place(..)) => "place",
+    _ => "drop",
+  };
+  events::Line("move", &[
+    ("player", (outcome.Player as u8).to_string()),
+    ("kind", json::Quote(kind)),
+    ("column", outcome.Column.to_string()),
+    ("row", outcome.Row.to_string()),
+    ("move_number", game.History().len().to_string()),
+  ])
+}
+
+// Sends the message to everyone in the room, spectators too
+fn Broadcast(room: &Room, text: &str) {
+  for seat in room.Seats.iter().chain(&room.Spectators) {
+    Send(&seat.Out, text);
+  }
+}
+
+// Sends the message to one client; one that can't be reached is found out when its
+// connection's read fails
+fn Send(out: &Mutex<TcpStream>, text: &str) {
+  let _ = websocket::WriteText(&mut out.lock().unwrap(), text);
+}
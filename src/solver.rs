@@ -0,0 +1,431 @@
+use std::time::{Duration, Instant};
+
+use crate::bitboard::{BottomCell, ColumnMask, TopCell, BOARD_MASK, BOTTOM, H1};
+use crate::book::Book;
+use crate::bot::Bot;
+use crate::engine::Engine;
+use crate::log;
+use crate::{Game, Player, BOARD_HEIGHT, BOARD_WIDTH};
+
+// Number of cells on the board
+const CELLS: i32 = (BOARD_WIDTH * BOARD_HEIGHT) as i32;
+
+// Lowest possible score of a searched position (see Evaluation::Score)
+const MIN_SCORE: i32 = -CELLS / 2 + 3;
+
+// Default transposition table size in megabytes
+pub const DEFAULT_TABLE_MB: usize = 64;
+
+// Openings take minutes to solve, so the engine searches positions with fewer stones than this
+pub const SOLVE_FROM_MOVE: usize = 10;
+
+// Enum representing the result of perfect play, from the side to move's point of view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+  Win,
+  Loss,
+  Draw,
+}
+
+// Struct describing the exact value of a position
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+  pub Outcome: Outcome,       // Result with perfect play by both sides
+  pub Score: i32,             // Positive: wins with that many stones to spare; negative: loses likewise; 0: draw
+  pub MovesToEnd: usize,      // Plies (both players' moves) until the game is decided
+  pub BestMoves: Vec<usize>,  // Every column that achieves the result
+}
+
+// Compact board for searching, in the same bit layout as BitBoard
+#[derive(Clone, Copy)]
+struct Position {
+  Current: u64, // Stones of the player to move
+  Mask: u64,    // Stones of both players
+  Moves: i32,   // Stones played so far
+}
+
+// Marks every empty cell that would complete a line of four for `position`
+fn WinningCells(position: u64, mask: u64) -> u64 {
+  // Vertical
+  let mut r = (position << 1) & (position << 2) & (position << 3);
+
+  // Horizontal and both diagonals share the same pattern with different shifts
+  for shift in [H1, H1 - 1, H1 + 1] {
+    let p = (position << shift) & (position << (2 * shift));
+    r |= p & (position << (3 * shift));
+    r |= p & (position >> shift);
+    let p = (position >> shift) & (position >> (2 * shift));
+    r |= p & (position << shift);
+    r |= p & (position >> (3 * shift));
+  }
+  r & (BOARD_MASK ^ mask)
+}
+
+impl Position {
+  // Takes the game's bitboard from the point of view of the player to move, if it has one
+  fn FromGame(game: &Game) -> Option<Position> {
+    let bits = game.Bits()?;
+    Some(Position {
+      Current: bits.Stones(game.CurrentPlayer()),
+      Mask: bits.Occupied(),
+      Moves: bits.Count() as i32,
+    })
+  }
+
+  fn CanPlay(&self, column: usize) -> bool {
+    self.Mask & TopCell(column) == 0
+  }
+
+  fn Play(&mut self, cell: u64) {
+    self.Current ^= self.Mask; // The other player is now to move
+    self.Mask |= cell;
+    self.Moves += 1;
+  }
+
+  fn PlayColumn(&mut self, column: usize) {
+    self.Play((self.Mask + BottomCell(column)) & ColumnMask(column));
+  }
+
+  // Unique key for the transposition table
+  fn Key(&self) -> u64 {
+    self.Current + self.Mask
+  }
+
+  // Cells that can be played right now
+  fn Possible(&self) -> u64 {
+    (self.Mask + BOTTOM) & BOARD_MASK
+  }
+
+  fn CanWinNext(&self) -> bool {
+    WinningCells(self.Current, self.Mask) & self.Possible() != 0
+  }
+
+  fn IsWinningColumn(&self, column: usize) -> bool {
+    WinningCells(self.Current, self.Mask) & self.Possible() & ColumnMask(column) != 0
+  }
+
+  // Playable cells that don't let the opponent win straight away
+  fn NonLosingMoves(&self) -> u64 {
+    let mut possible = self.Possible();
+    let OpponentWins = WinningCells(self.Current ^ self.Mask, self.Mask);
+    let forced = possible & OpponentWins;
+    if forced != 0 {
+      if forced & (forced - 1) != 0 {
+        return 0; // Two threats at once can't both be blocked
+      }
+      possible = forced;
+    }
+    possible & !(OpponentWins >> 1) // Never play directly below an opponent's winning cell
+  }
+
+  // Ordering heuristic: number of winning cells a move creates
+  fn MoveScore(&self, cell: u64) -> u32 {
+    WinningCells(self.Current | cell, self.Mask).count_ones()
+  }
+}
+
+// Struct representing a fixed-size transposition table storing upper bounds
+struct Table {
+  Keys: Vec<u64>,
+  Values: Vec<u8>,
+}
+
+impl Table {
+  fn new(megabytes: usize) -> Table {
+    let entries = (megabytes.max(1) * 1024 * 1024 / 9).max(1); // 8 bytes of key + 1 of value
+    Table { Keys: vec![0; entries], Values: vec![0; entries] }
+  }
+
+  // Always replaces whatever was stored in the slot
+  fn Put(&mut self, key: u64, value: u8) {
+    let index = (key % self.Keys.len() as u64) as usize;
+    self.Keys[index] = key;
+    self.Values[index] = value;
+  }
+
+  fn Get(&self, key: u64) -> Option<u8> {
+    let index = (key % self.Keys.len() as u64) as usize;
+    (self.Keys[index] == key && self.Values[index] != 0).then_some(self.Values[index])
+  }
+
+  fn Clear(&mut self) {
+    self.Keys.iter_mut().for_each(|key| *key = 0);
+    self.Values.iter_mut().for_each(|value| *value = 0);
+  }
+}
+
+// Struct representing an exact solver for standard-size positions
+pub struct Solver {
+  Table: Table, // Remembers upper bounds of searched positions
+  Nodes: u64,   // Positions visited by the last solve
+  Book: Option<Book>, // Opening book used by the engine before positions become solvable
+}
+
+impl Default for Solver {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Solver {
+  // Creates a solver with the default transposition table size
+  pub fn new() -> Solver {
+    Solver::WithMemory(DEFAULT_TABLE_MB)
+  }
+
+  // Creates a solver whose transposition table uses about the given number of megabytes
+  pub fn WithMemory(megabytes: usize) -> Solver {
+    Solver { Table: Table::new(megabytes), Nodes: 0, Book: None }
+  }
+
+  // Plays straight from the opening book whenever it knows the position
+  pub fn WithBook(mut self, book: Book) -> Solver {
+    self.Book = Some(book);
+    self
+  }
+
+  // Positions visited by the most recent call to Solve
+  pub fn Nodes(&self) -> u64 {
+    self.Nodes
+  }
+
+  // Forgets everything learned from earlier positions
+  pub fn Reset(&mut self) {
+    self.Table.Clear();
+  }
+
+  // Returns true if the solver can handle the game; it is built for classic Connect 4 on the
+  // standard 7x6 board
+  pub fn Supports(game: &Game) -> bool {
+    game.IsStandard()
+  }
+
+  // Computes the exact value of the position and every column that keeps it, or None for a
+  // game the solver doesn't support (see Supports)
+  pub fn Solve(&mut self, game: &Game) -> Option<Evaluation> {
+    self.Nodes = 0;
+    if !Self::Supports(game) {
+      return None;
+    }
+    let position = Position::FromGame(game)?;
+    if game.IsFinished() {
+      // Nothing left to play; a winner means the side to move has lost
+      let outcome = if game.Winner() == Player::None { Outcome::Draw } else { Outcome::Loss };
+      return Some(Evaluation { Outcome: outcome, Score: 0, MovesToEnd: 0, BestMoves: Vec::new() });
+    }
+
+    let best = self.SolvePosition(&position);
+
+    // Collect the columns that keep the result: an immediate win if there is one, otherwise
+    // any win for a won position or any draw for a drawn one. A lost position keeps only the
+    // columns that hold out the longest.
+    let mut BestMoves = Vec::new();
+    for column in (0..BOARD_WIDTH).filter(|&column| position.CanPlay(column)) {
+      let keeps = if position.CanWinNext() {
+        position.IsWinningColumn(column)
+      } else {
+        let mut child = position;
+        child.PlayColumn(column);
+        if child.CanWinNext() {
+          -((CELLS + 1 - child.Moves) / 2) == best
+        } else if best > 0 {
+          self.Negamax(&child, -1, 0) <= -1
+        } else if best == 0 {
+          self.Negamax(&child, 0, 1) <= 0
+        } else {
+          self.Negamax(&child, -best, -best + 1) <= -best
+        }
+      };
+      if keeps {
+        BestMoves.push(column);
+      }
+    }
+    Some(MakeEvaluation(best, position.Moves, BestMoves))
+  }
+
+  // Exact score of a position, narrowing the window with null-window searches
+  fn SolvePosition(&mut self, position: &Position) -> i32 {
+    if position.CanWinNext() {
+      return (CELLS + 1 - position.Moves) / 2;
+    }
+    let mut min = -(CELLS - position.Moves) / 2;
+    let mut max = (CELLS + 1 - position.Moves) / 2;
+    while min < max {
+      let mut med = min + (max - min) / 2;
+      // Probe closer to zero first, where most positions end up
+      if med <= 0 && min / 2 < med {
+        med = min / 2;
+      } else if med >= 0 && max / 2 > med {
+        med = max / 2;
+      }
+      let score = self.Negamax(position, med, med + 1);
+      if score <= med {
+        max = score;
+      } else {
+        min = score;
+      }
+    }
+    min
+  }
+
+  // Alpha-beta search; the side to move must not have an immediate win
+  fn Negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+    self.Nodes += 1;
+
+    let next = position.NonLosingMoves();
+    if next == 0 {
+      return -(CELLS - position.Moves) / 2; // Every move loses
+    }
+    if position.Moves >= CELLS - 2 {
+      return 0; // Neither player can win with the last two stones
+    }
+
+    let min = -(CELLS - 2 - position.Moves) / 2;
+    if alpha < min {
+      alpha = min;
+      if alpha >= beta {
+        return alpha;
+      }
+    }
+    let mut max = (CELLS - 1 - position.Moves) / 2;
+    if let Some(value) = self.Table.Get(position.Key()) {
+      max = value as i32 + MIN_SCORE - 1;
+    }
+    if beta > max {
+      beta = max;
+      if alpha >= beta {
+        return beta;
+      }
+    }
+
+    // Try the moves that create the most threats first, preferring the center on ties
+    let mut moves: Vec<(u64, u32)> = CenterOrder()
+        .filter_map(|column| {
+          let cell = next & ColumnMask(column);
+          (cell != 0).then(|| (cell, position.MoveScore(cell)))
+        })
+        .collect();
+    moves.sort_by_key(|&(_, score)| std::cmp::Reverse(score)); // Stable, keeps center first on ties
+
+    for (cell, _) in moves {
+      let mut child = *position;
+      child.Play(cell);
+      let score = -self.Negamax(&child, -beta, -alpha);
+      if score >= beta {
+        return score;
+      }
+      alpha = alpha.max(score);
+    }
+    self.Table.Put(position.Key(), (alpha - MIN_SCORE + 1) as u8);
+    alpha
+  }
+}
+
+impl Engine for Solver {
+  fn ChooseMove(&mut self, game: &Game) -> Option<usize> {
+    if let Some(column) = self.Book.as_ref().and_then(|book| book.Lookup(game)) {
+      log::Debug(format_args!("Solver plays column {} from the opening book", column + 1));
+      return Some(column);
+    }
+    if game.CurrentMove() < SOLVE_FROM_MOVE || !Self::Supports(game) {
+      return Bot::new(12).WithTimeLimit(Duration::from_secs(2)).ChooseMove(game);
+    }
+    // Among equally good moves, the most central one
+    let started = Instant::now();
+    let evaluation = self.Solve(game)?;
+    let column = CenterOrder().find(|column| evaluation.BestMoves.contains(column));
+    log::Debug(format_args!(
+      "Solver found a {:?} in {} moves in {:?} after {} nodes: column {:?} of {:?}",
+      evaluation.Outcome,
+      evaluation.MovesToEnd,
+      started.elapsed(),
+      self.Nodes,
+      column.map(|column| column + 1),
+      evaluation.BestMoves.iter().map(|column| column + 1).collect::<Vec<_>>()
+    ));
+    column
+  }
+
+  fn Name(&self) -> String {
+    "Perfect solver".to_string()
+  }
+}
+
+// Columns from the center outwards
+fn CenterOrder() -> impl Iterator<Item = usize> {
+  (0..BOARD_WIDTH).map(|index| {
+    let offset = index.div_ceil(2);
+    if index % 2 == 1 {
+      BOARD_WIDTH / 2 - offset
+    } else {
+      BOARD_WIDTH / 2 + offset
+    }
+  })
+}
+
+// Builds an evaluation from a score, given how many stones were already played
+fn MakeEvaluation(score: i32, moves: i32, BestMoves: Vec<usize>) -> Evaluation {
+  let stones = CELLS / 2 + 1; // Stones per player, plus one
+  let (outcome, MovesToEnd) = match score {
+    0 => (Outcome::Draw, CELLS - moves),
+    // The side to move wins with its (stones - score)-th stone
+    s if s > 0 => (Outcome::Win, 2 * (stones - s - moves / 2) - 1),
+    // The opponent wins with its (stones + score)-th stone
+    s => (Outcome::Loss, (2 * (stones + s - (moves + 1) / 2)).max(0)),
+  };
+  Evaluation { Outcome: outcome, Score: score, MovesToEnd: MovesToEnd as usize, BestMoves }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::WIN_SCORE;
+
+  fn Solved(moves: &str) -> Evaluation {
+    Solver::new().Solve(&Game::FromMoveString(moves).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn ImmediateWin() {
+    // Red has three in the bottom row and wins in column 4
+    let evaluation = Solved("112233");
+    assert_eq!((evaluation.Outcome, evaluation.MovesToEnd), (Outcome::Win, 1));
+    assert_eq!(evaluation.BestMoves, [3]);
+  }
+
+  #[test]
+  fn ForcedLoss() {
+    // Red's three in the bottom row are open at both ends, so Yellow can block only one
+    let evaluation = Solved("22334");
+    assert_eq!((evaluation.Outcome, evaluation.MovesToEnd), (Outcome::Loss, 2));
+    assert_eq!(evaluation.BestMoves.len(), BOARD_WIDTH);
+  }
+
+  #[test]
+  fn FirstPlayerWinsLateInTheGame() {
+    let game = Game::FromMoveString("51241447732753723473").unwrap();
+    let evaluation = Solver::new().Solve(&game).unwrap();
+    assert_eq!((evaluation.Outcome, evaluation.MovesToEnd), (Outcome::Win, 19));
+    assert_eq!(evaluation.BestMoves, [4]);
+
+    // A search through to the end of the game agrees
+    let searched = Bot::new(BOARD_WIDTH * BOARD_HEIGHT - 20).Search(&game).unwrap();
+    assert!(searched.Score >= WIN_SCORE);
+    assert_eq!(searched.BestMove, 4);
+  }
+
+  #[test]
+  fn FinishedGamesHaveNoMoves() {
+    let evaluation = Solved("1212121");
+    assert_eq!((evaluation.Outcome, evaluation.MovesToEnd), (Outcome::Loss, 0));
+    assert!(evaluation.BestMoves.is_empty());
+  }
+
+  #[test]
+  fn UnsupportedGamesAreNotSolved() {
+    let mut solver = Solver::new();
+    assert_eq!(solver.Solve(&Game::new(9, 7).unwrap()), None);
+    assert_eq!(solver.Solve(&Game::default().WithPlayers(3).unwrap()), None);
+    assert_eq!(solver.Solve(&Game::default().WithPopOut(true)), None);
+  }
+}
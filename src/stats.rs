@@ -0,0 +1,243 @@
+use std::path::{Path, PathBuf};
+
+use crate::json::{self, Value};
+use crate::rating::{self, INITIAL_RATING};
+use crate::save::{self, Field, LoadError};
+use crate::{Game, Move, Player};
+
+// Version written into the statistics file, bumped when the layout changes
+pub const STATS_VERSION: u64 = 1;
+
+// Struct holding one player's results over every recorded game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Record {
+  pub Wins: u64,
+  pub Losses: u64,
+  pub Draws: u64,
+  pub Rating: u64, // Elo rating
+}
+
+impl Default for Record {
+  fn default() -> Self {
+    Record { Wins: 0, Losses: 0, Draws: 0, Rating: INITIAL_RATING }
+  }
+}
+
+// Struct naming a player of a game being recorded
+pub struct Participant {
+  pub Name: String,
+  pub Anchor: Option<u64>, // Fixed rating, for computer opponents that don't change strength
+}
+
+// Struct holding lifetime statistics across every finished game
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+  pub Games: u64,                     // Finished games recorded
+  pub Players: Vec<(String, Record)>, // Results by player name, in the order first seen
+  pub LongestGame: usize,             // Most moves in a game
+  pub ShortestWin: Option<usize>,     // Fewest moves in a game someone won
+  pub FirstColumns: Vec<u64>,         // How often each column was played first, by index
+}
+
+impl Stats {
+  // Counts a finished game. `players` are in turn order. In two-player games both ratings
+  // move by up to `k` points, except anchored ones.
+  pub fn Add(&mut self, game: &Game, players: &[Participant], k: f64) {
+    if !game.IsFinished() {
+      return;
+    }
+    self.Games += 1;
+    let moves = game.History().len();
+    self.LongestGame = self.LongestGame.max(moves);
+    if game.Winner() != Player::None {
+      self.ShortestWin = Some(self.ShortestWin.map_or(moves, |shortest| shortest.min(moves)));
+    }
+    if let Some(&(Move::Drop(column) | Move::Place(_, column))) = game.History().first() {
+      if self.FirstColumns.len() <= column {
+        self.FirstColumns.resize(column + 1, 0);
+      }
+      self.FirstColumns[column] += 1;
+    }
+    let players = &players[..game.Players().min(players.len())];
+    let ratings: Vec<u64> = players.iter().map(|player| self.Rating(player)).collect();
+    for (index, participant) in players.iter().enumerate() {
+      let player = Player::FromInt(index as u8 + 1);
+      let score = match game.Winner() {
+        Player::None => 0.5,
+        winner if winner == player => 1.0,
+        _ => 0.0,
+      };
+      let record = self.PlayerRecord(&participant.Name);
+      match game.Winner() {
+        Player::None => record.Draws += 1,
+        winner if winner == player => record.Wins += 1,
+        _ => record.Losses += 1,
+      }
+      record.Rating = match (participant.Anchor, &ratings[..]) {
+        (Some(anchor), _) => anchor,
+        (None, &[first, second]) => {
+          let (own, opponent) = if index == 0 { (first, second) } else { (second, first) };
+          rating::Updated(own, opponent, score, k)
+        }
+        (None, _) => record.Rating,
+      };
+    }
+  }
+
+  // The player's current rating: their anchor, what they've earned, or a newcomer's rating
+  pub fn Rating(&self, player: &Participant) -> u64 {
+    player.Anchor.unwrap_or_else(|| {
+      self.Players
+          .iter()
+          .find(|(name, _)| *name == player.Name)
+          .map_or(INITIAL_RATING, |(_, record)| record.Rating)
+    })
+  }
+
+  // Everyone who has played, best rated first
+  pub fn Ratings(&self) -> String {
+    if self.Players.is_empty() {
+      return "No rated players yet\n".to_string();
+    }
+    let mut players: Vec<&(String, Record)> = self.Players.iter().collect();
+    players.sort_by_key(|(_, record)| std::cmp::Reverse(record.Rating));
+    let width = players.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0).max(6);
+    let mut text = format!("{:>4}  {:<width$}  {:>6}  {:>5}\n", "#", "Player", "Rating", "Games");
+    for (rank, (name, record)) in players.into_iter().enumerate() {
+      let games = record.Wins + record.Losses + record.Draws;
+      text += &format!("{:>4}  {:<width$}  {:>6}  {:>5}\n", rank + 1, name, record.Rating, games);
+    }
+    text
+  }
+
+  // The column played first most often, with how many times, if any game has been recorded
+  pub fn FavoriteFirstColumn(&self) -> Option<(usize, u64)> {
+    self.FirstColumns
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .max_by_key(|&(column, count)| (count, std::cmp::Reverse(column)))
+  }
+
+  // A table of the statistics for printing
+  pub fn Summary(&self) -> String {
+    if self.Games == 0 {
+      return "No games recorded yet\n".to_string();
+    }
+    let mut text = format!("Games played: {}\n", self.Games);
+    text += &format!("Longest game: {} moves\n", self.LongestGame);
+    if let Some(shortest) = self.ShortestWin {
+      text += &format!("Shortest win: {} moves\n", shortest);
+    }
+    if let Some((column, count)) = self.FavoriteFirstColumn() {
+      text += &format!("Favorite first column: {} ({} times)\n", column + 1, count);
+    }
+    let width = self.Players.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0).max(6);
+    text += &format!("\n{:<width$}  {:>5}  {:>6}  {:>5}  {:>6}\n", "Player", "Wins", "Losses", "Draws", "Rating");
+    for (name, record) in &self.Players {
+      text += &format!(
+        "{:<width$}  {:>5}  {:>6}  {:>5}  {:>6}\n",
+        name, record.Wins, record.Losses, record.Draws, record.Rating
+      );
+    }
+    text
+  }
+
+  fn PlayerRecord(&mut self, name: &str) -> &mut Record {
+    let index = match self.Players.iter().position(|(known, _)| known == name) {
+      Some(index) => index,
+      None => {
+        self.Players.push((name.to_string(), Record::default()));
+        self.Players.len() - 1
+      }
+    };
+    &mut self.Players[index].1
+  }
+}
+
+// Where the statistics are kept, next to the autosave
+pub fn StatsPath() -> Option<PathBuf> {
+  save::DataDir().map(|folder| folder.join("stats.json"))
+}
+
+// Writes the statistics as a JSON document
+pub fn ToJson(stats: &Stats) -> String {
+  let players: Vec<String> = stats
+      .Players
+      .iter()
+      .map(|(name, record)| {
+        format!(
+          "    {{\"name\": {}, \"wins\": {}, \"losses\": {}, \"draws\": {}, \"rating\": {}}}",
+          json::Quote(name),
+          record.Wins,
+          record.Losses,
+          record.Draws,
+          record.Rating
+        )
+      })
+      .collect();
+  let columns: Vec<String> = stats.FirstColumns.iter().map(u64::to_string).collect();
+  let mut text = String::from("{\n");
+  text += &format!("  \"version\": {},\n", STATS_VERSION);
+  text += &format!("  \"games\": {},\n", stats.Games);
+  text += &format!("  \"longest_game\": {},\n", stats.LongestGame);
+  text += &format!("  \"shortest_win\": {},\n", stats.ShortestWin.map_or("null".to_string(), |moves| moves.to_string()));
+  text += &format!("  \"first_columns\": [{}],\n", columns.join(", "));
+  text += &format!("  \"players\": [\n{}\n  ]\n", players.join(",\n"));
+  text += "}\n";
+  text
+}
+
+// Reads statistics written by ToJson
+pub fn FromJson(text: &str) -> Result<Stats, LoadError> {
+  let document = json::Parse(text).map_err(LoadError::Syntax)?;
+  let version = Field(&document, "version", Value::AsU64)?;
+  if version > STATS_VERSION {
+    return Err(LoadError::UnsupportedVersion(version));
+  }
+  let mut stats = Stats {
+    Games: Field(&document, "games", Value::AsU64)?,
+    LongestGame: Field(&document, "longest_game", Value::AsUsize)?,
+    ShortestWin: document.Get("shortest_win").and_then(Value::AsUsize),
+    ..Stats::default()
+  };
+  for count in Field(&document, "first_columns", Value::AsArray)? {
+    stats.FirstColumns.push(count.AsU64().ok_or(LoadError::MissingField("first_columns"))?);
+  }
+  for player in Field(&document, "players", Value::AsArray)? {
+    let record = Record {
+      Wins: Field(player, "wins", Value::AsU64)?,
+      Losses: Field(player, "losses", Value::AsU64)?,
+      Draws: Field(player, "draws", Value::AsU64)?,
+      // Files from before ratings were kept start everyone afresh
+      Rating: player.Get("rating").and_then(Value::AsU64).unwrap_or(INITIAL_RATING),
+    };
+    stats.Players.push((Field(player, "name", Value::AsStr)?.to_string(), record));
+  }
+  Ok(stats)
+}
+
+// Reads the statistics file; a file that doesn't exist yet holds no games
+pub fn LoadFile(path: impl AsRef<Path>) -> Result<Stats, LoadError> {
+  match std::fs::read_to_string(path) {
+    Ok(text) => FromJson(&text),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Stats::default()),
+    Err(err) => Err(LoadError::Io(err)),
+  }
+}
+
+// Writes the statistics file whole or not at all: the new contents go to a temporary file
+// that then replaces the old one, so a crash or another game finishing at the same moment
+// can't leave it half written
+pub fn SaveFile(stats: &Stats, path: impl AsRef<Path>) -> std::io::Result<()> {
+  let path = path.as_ref();
+  if let Some(folder) = path.parent() {
+    std::fs::create_dir_all(folder)?;
+  }
+  let temporary = path.with_extension(format!("json.{}.tmp", std::process::id()));
+  std::fs::write(&temporary, ToJson(stats))?;
+  std::fs::rename(&temporary, path).inspect_err(|_| {
+    let _ = std::fs::remove_file(&temporary);
+  })
+}
@@ -0,0 +1,475 @@
+// Size of the terminal the game is drawn in, the control sequences it's driven with, and
+// taking it over for the full-screen mode
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::interrupt;
+use ConnectFour::log;
+
+// Clear the screen and go to its top left corner
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[1;1H";
+
+// Back to the default colors, for leaving in a hurry
+pub const RESET: &str = "\x1b[0m";
+
+// Switch to the alternate screen and hide the cursor, and the reverse
+const ENTER_FULL_SCREEN: &[u8] = b"\x1b[?1049h\x1b[?25l";
+const LEAVE_FULL_SCREEN: &[u8] = b"\x1b[0m\x1b[?25h\x1b[?1049l";
+
+// Report mouse clicks, with their position given as decimal numbers, and stop again
+const CAPTURE_MOUSE: &[u8] = b"\x1b[?1000h\x1b[?1006h";
+const RELEASE_MOUSE: &[u8] = b"\x1b[?1006l\x1b[?1000l";
+
+// How long to wait for the rest of an escape sequence before taking ESC as the Escape key
+const ESCAPE_TIME: Duration = Duration::from_millis(30);
+
+static FULL_SCREEN: AtomicBool = AtomicBool::new(false); // Whether the terminal has been taken over
+static MOUSE: AtomicBool = AtomicBool::new(false);       // Whether mouse clicks are being captured
+static KEYS: AtomicBool = AtomicBool::new(false);        // Whether keys are read as they're pressed
+
+// Returns true if the terminal understands escape sequences for colors and moving the cursor.
+// Windows consoles have to be told to first; older ones can't be, and get plain text. Output
+// that isn't going to a terminal gets them as it would anywhere else.
+pub fn Escapes() -> bool {
+  static ESCAPES: OnceLock<bool> = OnceLock::new();
+  *ESCAPES.get_or_init(platform::EnableEscapes)
+}
+
+// Returns true if the terminal is likely to have a font with emoji. Windows consoles other
+// than Windows Terminal and editors' built-in terminals usually don't.
+pub fn Emoji() -> bool {
+  Escapes() && platform::Emoji()
+}
+
+// Moves the cursor to a 1-based line and column
+pub fn MoveTo(line: usize, column: usize) -> String {
+  format!("\x1b[{};{}H", line, column)
+}
+
+// The text written over a 1-based line of the screen, leaving the cursor where it was
+pub fn PaintLine(line: usize, text: &str) -> String {
+  format!("\x1b7{}\x1b[2K{}\x1b8", MoveTo(line, 1), text)
+}
+
+// Goes back to the start of the line and clears it
+pub fn ClearLine() -> &'static str {
+  "\r\x1b[2K"
+}
+
+// Rings the terminal's bell, which many terminals show as a flash
+pub fn Bell() -> &'static str {
+  "\x07"
+}
+
+// Width of the terminal in columns, asked of the terminal itself and otherwise taken from the
+// COLUMNS environment variable. None when output isn't going to a terminal and COLUMNS isn't
+// set, e.g. when piped to a file.
+pub fn Width() -> Option<usize> {
+  platform::Size().map(|(columns, _)| columns).filter(|&columns| columns > 0).or_else(|| Variable("COLUMNS"))
+}
+
+// Height of the terminal in lines, found the same way from the terminal or LINES
+pub fn Height() -> Option<usize> {
+  platform::Size().map(|(_, lines)| lines).filter(|&lines| lines > 0).or_else(|| Variable("LINES"))
+}
+
+fn Variable(name: &str) -> Option<usize> {
+  std::env::var(name).ok().and_then(|value| value.trim().parse().ok())
+}
+
+// Reads keys as they're pressed, without echoing them, until the terminal is restored.
+// Ctrl+C still interrupts. Returns false if the input isn't a terminal that can do this.
+pub fn ReadKeys() -> bool {
+  if KEYS.load(Ordering::SeqCst) {
+    return true;
+  }
+  if !platform::ReadKeys() {
+    return false;
+  }
+  KEYS.store(true, Ordering::SeqCst);
+  // Put the terminal back however the program ends, even with a panic
+  static HOOK: Once = Once::new();
+  HOOK.call_once(|| {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+      Restore();
+      previous(info);
+    }));
+  });
+  true
+}
+
+// Returns true while keys are read as they're pressed
+pub fn ReadingKeys() -> bool {
+  KEYS.load(Ordering::SeqCst)
+}
+
+// Takes over the terminal: keys are read as they're pressed, and the game is drawn on the
+// alternate screen so the scrollback is left as it was. Returns false if the input isn't a
+// terminal that can do this.
+pub fn EnterFullScreen() -> bool {
+  if !Escapes() || !ReadKeys() {
+    return false;
+  }
+  if !FULL_SCREEN.swap(true, Ordering::SeqCst) {
+    platform::Write(ENTER_FULL_SCREEN);
+  }
+  true
+}
+
+// Gives the terminal back as it was found. Safe to call from a signal handler, and does
+// nothing if the terminal wasn't changed.
+pub fn Restore() {
+  if MOUSE.swap(false, Ordering::SeqCst) {
+    platform::Write(RELEASE_MOUSE);
+  }
+  if FULL_SCREEN.swap(false, Ordering::SeqCst) {
+    platform::Write(LEAVE_FULL_SCREEN);
+  }
+  if KEYS.swap(false, Ordering::SeqCst) {
+    platform::ReadLines();
+  }
+}
+
+// Has the terminal report mouse clicks, which then come in as keys. Selecting text with the
+// mouse stops working until the terminal is given back.
+pub fn CaptureMouse() {
+  if FULL_SCREEN.load(Ordering::SeqCst) && !MOUSE.swap(true, Ordering::SeqCst) {
+    platform::Write(CAPTURE_MOUSE);
+  }
+}
+
+// Enum representing a key pressed, or the mouse clicked, in the full-screen mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+  Left,
+  Right,
+  Up,
+  Down,
+  Enter,
+  Space,
+  Escape,
+  Backspace,
+  Char(char),
+  Click(usize, usize), // Left button pressed at a 1-based column and line of the screen
+}
+
+// The next key pressed, waiting until the deadline for it. None if it passes first, Ctrl+C
+// is pressed or the input ends. Keys the game doesn't know are skipped.
+pub fn ReadKey(deadline: Instant) -> Option<Key> {
+  loop {
+    let key = match interrupt::NextByte(deadline)? {
+      0x1b => Escaped(),
+      b'\r' | b'\n' => Some(Key::Enter),
+      b' ' => Some(Key::Space),
+      0x7f | 0x08 => Some(Key::Backspace),
+      byte if byte.is_ascii_graphic() => Some(Key::Char(byte as char)),
+      _ => None,
+    };
+    if let Some(key) = key {
+      log::Trace(format_args!("Read the key {:?}", key));
+      return Some(key);
+    }
+  }
+}
+
+// The key an escape sequence stands for, reading the rest of it: ESC [ C for the right
+// arrow, or ESC O C as some terminals send it. A lone ESC is the Escape key.
+fn Escaped() -> Option<Key> {
+  let Some(b'[' | b'O') = interrupt::NextByte(Instant::now() + ESCAPE_TIME) else {
+    return Some(Key::Escape);
+  };
+  let mut parameters = String::new();
+  // Parameters, as in ESC [ 1 ; 5 C for Ctrl+Right, come before the final letter
+  loop {
+    match interrupt::NextByte(Instant::now() + ESCAPE_TIME)? {
+      byte @ (b'0'..=b'9' | b';' | b'<') => parameters.push(byte as char),
+      b'A' => return Some(Key::Up),
+      b'B' => return Some(Key::Down),
+      b'C' => return Some(Key::Right),
+      b'D' => return Some(Key::Left),
+      b'M' => return Clicked(&parameters),
+      _ => return None,
+    }
+  }
+}
+
+// The click a mouse report stands for, given its parameters: ESC [ < 0 ; 12 ; 5 M is the
+// left button pressed at column 12, line 5. Other buttons, dragging and releases are left out.
+fn Clicked(parameters: &str) -> Option<Key> {
+  let numbers: Vec<usize> = parameters.strip_prefix('<')?.split(';').map(|number| number.parse().ok()).collect::<Option<_>>()?;
+  match numbers[..] {
+    [0, column, line] => Some(Key::Click(column, line)),
+    _ => None,
+  }
+}
+
+#[cfg(unix)]
+mod platform {
+  // Struct filled in by the TIOCGWINSZ request
+  #[repr(C)]
+  #[derive(Default)]
+  struct WindowSize {
+    Rows: u16,
+    Columns: u16,
+    XPixels: u16,
+    YPixels: u16,
+  }
+
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  const TIOCGWINSZ: std::os::raw::c_ulong = 0x5413;
+  #[cfg(not(any(target_os = "linux", target_os = "android")))]
+  const TIOCGWINSZ: std::os::raw::c_ulong = 0x40087468;
+
+  // Struct the terminal settings are read into and written from, laid out as each system
+  // has it
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct Termios {
+    InputFlags: u32,
+    OutputFlags: u32,
+    ControlFlags: u32,
+    LocalFlags: u32,
+    Line: u8,
+    Chars: [u8; 32],
+    InputSpeed: u32,
+    OutputSpeed: u32,
+  }
+
+  #[cfg(not(any(target_os = "linux", target_os = "android")))]
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct Termios {
+    InputFlags: Flag,
+    OutputFlags: Flag,
+    ControlFlags: Flag,
+    LocalFlags: Flag,
+    Chars: [u8; 20],
+    InputSpeed: Flag,
+    OutputSpeed: Flag,
+  }
+
+  #[cfg(any(target_os = "macos", target_os = "ios"))]
+  type Flag = std::os::raw::c_ulong;
+  #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios")))]
+  type Flag = u32;
+
+  // Line-by-line input and echoing of what's typed, and where the minimum number of bytes a
+  // read waits for and its timeout are kept
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  mod flags {
+    pub const ICANON: u32 = 0o2;
+    pub const ECHO: u32 = 0o10;
+    pub const VMIN: usize = 6;
+    pub const VTIME: usize = 5;
+  }
+  #[cfg(not(any(target_os = "linux", target_os = "android")))]
+  mod flags {
+    pub const ICANON: super::Flag = 0x100;
+    pub const ECHO: super::Flag = 0x8;
+    pub const VMIN: usize = 16;
+    pub const VTIME: usize = 17;
+  }
+
+  const TCSANOW: i32 = 0;
+
+  extern "C" {
+    fn ioctl(fd: i32, request: std::os::raw::c_ulong, ...) -> i32;
+    fn isatty(fd: i32) -> i32;
+    fn tcgetattr(fd: i32, settings: *mut Termios) -> i32;
+    fn tcsetattr(fd: i32, when: i32, settings: *const Termios) -> i32;
+    fn write(fd: i32, buf: *const u8, count: usize) -> isize;
+  }
+
+  // The settings the terminal had before keys were read one at a time
+  static SAVED: std::sync::OnceLock<Termios> = std::sync::OnceLock::new();
+
+  // Turns off line-by-line input and echoing. Returns false if stdin isn't a terminal.
+  pub fn ReadKeys() -> bool {
+    let mut settings = std::mem::MaybeUninit::<Termios>::uninit();
+    if unsafe { isatty(0) == 0 || isatty(1) == 0 || tcgetattr(0, settings.as_mut_ptr()) != 0 } {
+      return false;
+    }
+    let mut settings = unsafe { settings.assume_init() };
+    let _ = SAVED.set(settings);
+    settings.LocalFlags &= !(flags::ICANON | flags::ECHO);
+    settings.Chars[flags::VMIN] = 1;
+    settings.Chars[flags::VTIME] = 0;
+    unsafe { tcsetattr(0, TCSANOW, &settings) == 0 }
+  }
+
+  // Puts the settings back. Only async-signal-safe calls, for Ctrl+C.
+  pub fn ReadLines() {
+    if let Some(settings) = SAVED.get() {
+      unsafe {
+        tcsetattr(0, TCSANOW, settings);
+      }
+    }
+  }
+
+  pub fn Write(text: &[u8]) {
+    unsafe {
+      write(1, text.as_ptr(), text.len());
+    }
+  }
+
+  // Terminals understand escape sequences, except one that says it's dumb
+  pub fn EnableEscapes() -> bool {
+    std::env::var("TERM").map_or(true, |term| term != "dumb")
+  }
+
+  pub fn Emoji() -> bool {
+    true
+  }
+
+  // Columns and lines
+  pub fn Size() -> Option<(usize, usize)> {
+    let mut size = WindowSize::default();
+    match unsafe { ioctl(1, TIOCGWINSZ, &mut size as *mut WindowSize) } {
+      0 => Some((size.Columns as usize, size.Rows as usize)),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(windows)]
+mod platform {
+  // Structs filled in by GetConsoleScreenBufferInfo
+  #[repr(C)]
+  #[derive(Default)]
+  struct Coord {
+    X: i16,
+    Y: i16,
+  }
+
+  #[repr(C)]
+  #[derive(Default)]
+  struct Rect {
+    Left: i16,
+    Top: i16,
+    Right: i16,
+    Bottom: i16,
+  }
+
+  #[repr(C)]
+  #[derive(Default)]
+  struct ScreenBufferInfo {
+    Size: Coord,
+    CursorPosition: Coord,
+    Attributes: u16,
+    Window: Rect,
+    MaximumWindowSize: Coord,
+  }
+
+  const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+
+  const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+
+  // Console input modes: whole lines, echoed, and arrow keys as escape sequences
+  const ENABLE_LINE_INPUT: u32 = 0x2;
+  const ENABLE_ECHO_INPUT: u32 = 0x4;
+  const ENABLE_VIRTUAL_TERMINAL_INPUT: u32 = 0x200;
+
+  // Console output mode that makes escape sequences work, and the UTF-8 code page
+  const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x4;
+  const CP_UTF8: u32 = 65001;
+
+  extern "system" {
+    fn GetStdHandle(which: u32) -> *mut u8;
+    fn GetConsoleScreenBufferInfo(console: *mut u8, info: *mut ScreenBufferInfo) -> i32;
+    fn GetConsoleMode(console: *mut u8, mode: *mut u32) -> i32;
+    fn SetConsoleMode(console: *mut u8, mode: u32) -> i32;
+    fn SetConsoleOutputCP(page: u32) -> i32;
+  }
+
+  // Turns on escape sequences for the console, and UTF-8 so the pieces aren't mangled.
+  // Returns false for consoles too old to have them.
+  pub fn EnableEscapes() -> bool {
+    let mut mode = 0;
+    unsafe {
+      let console = GetStdHandle(STD_OUTPUT_HANDLE);
+      if GetConsoleMode(console, &mut mode) == 0 {
+        return true; // Not a console, e.g. redirected to a file
+      }
+      SetConsoleOutputCP(CP_UTF8);
+      mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+          || SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+  }
+
+  // Windows Terminal sets WT_SESSION, and editors' terminals TERM_PROGRAM
+  pub fn Emoji() -> bool {
+    std::env::var_os("WT_SESSION").is_some() || std::env::var_os("TERM_PROGRAM").is_some()
+  }
+
+  // The input mode before keys were read one at a time
+  static SAVED: std::sync::OnceLock<u32> = std::sync::OnceLock::new();
+
+  // Turns off line input and echoing. Returns false if stdin isn't a console.
+  pub fn ReadKeys() -> bool {
+    let mut mode = 0;
+    unsafe {
+      let console = GetStdHandle(STD_INPUT_HANDLE);
+      if GetConsoleMode(console, &mut mode) == 0 {
+        return false;
+      }
+      let _ = SAVED.set(mode);
+      let keys = (mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT)) | ENABLE_VIRTUAL_TERMINAL_INPUT;
+      SetConsoleMode(console, keys) != 0
+    }
+  }
+
+  // Puts the input mode back
+  pub fn ReadLines() {
+    if let Some(&mode) = SAVED.get() {
+      unsafe {
+        SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), mode);
+      }
+    }
+  }
+
+  pub fn Write(text: &[u8]) {
+    use std::io::Write;
+    let mut out = std::io::stdout();
+    let _ = out.write_all(text);
+    let _ = out.flush();
+  }
+
+  // Columns and lines of the visible window
+  pub fn Size() -> Option<(usize, usize)> {
+    let mut info = ScreenBufferInfo::default();
+    match unsafe { GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) } {
+      0 => None,
+      _ => Some((
+        (info.Window.Right - info.Window.Left + 1).max(0) as usize,
+        (info.Window.Bottom - info.Window.Top + 1).max(0) as usize,
+      )),
+    }
+  }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+  pub fn Size() -> Option<(usize, usize)> {
+    None
+  }
+
+  pub fn ReadKeys() -> bool {
+    false
+  }
+
+  pub fn EnableEscapes() -> bool {
+    false
+  }
+
+  pub fn Emoji() -> bool {
+    false
+  }
+
+  pub fn ReadLines() {}
+
+  pub fn Write(_: &[u8]) {}
+}
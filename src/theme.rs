@@ -0,0 +1,497 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use ConnectFour::{Player, MAX_PLAYERS};
+
+use crate::locale::{self, Message};
+
+// ANSI codes that don't depend on the theme's colors
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const REVERSE: &str = "\x1b[7m";
+const BLINK_REVERSE: &str = "\x1b[5;7m";
+
+// Enum representing the shapes a piece can be drawn as
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+  Circle, // Emoji circle
+  Square, // Emoji square
+  Letter, // The color's initial in ANSI color, for terminals without emoji
+  Dot,    // A dim dot, for empty cells only
+}
+
+// Enum representing the colors available for every shape
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+  Red,
+  Orange,
+  Yellow,
+  Green,
+  Blue,
+  Purple,
+  Brown,
+  Black,
+  White,
+}
+
+// Each color's name, circle and square emoji, ANSI code for letters, and ANSI code for
+// filling a block in the big layout
+const COLORS: [(Color, &str, &str, &str, &str, &str); 9] = [
+  (Color::Red, "Red", "🔴", "🟥", "\x1b[31m", "\x1b[30;41m"),
+  (Color::Orange, "Orange", "🟠", "🟧", "\x1b[38;5;208m", "\x1b[30;48;5;208m"),
+  (Color::Yellow, "Yellow", "🟡", "🟨", "\x1b[33m", "\x1b[30;43m"),
+  (Color::Green, "Green", "🟢", "🟩", "\x1b[32m", "\x1b[30;42m"),
+  (Color::Blue, "Blue", "🔵", "🟦", "\x1b[34m", "\x1b[97;44m"),
+  (Color::Purple, "Purple", "🟣", "🟪", "\x1b[35m", "\x1b[97;45m"),
+  (Color::Brown, "Brown", "🟤", "🟫", "\x1b[38;5;94m", "\x1b[97;48;5;94m"),
+  (Color::Black, "Black", "⚫", "⬛", "\x1b[90m", "\x1b[97;100m"),
+  (Color::White, "White", "⚪", "⬜", "\x1b[37m", "\x1b[30;47m"),
+];
+
+// Size of a cell in the big layout, in characters across and lines down
+pub const BLOCK_WIDTH: usize = 6;
+pub const BLOCK_HEIGHT: usize = 3;
+
+// Enum representing the marks drawn besides pieces: on cells, and ahead of status lines
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mark {
+  Win,       // Empty cell that wins at once for the player on turn
+  Danger,    // Empty cell where the next player would win
+  Ghost,     // Piece being previewed where it would land
+  Clock,     // Ahead of the clocks and the move timer
+  Timeout,   // Ahead of a loss on time
+  Agreement, // Ahead of a draw by agreement
+  Dash,      // Between the parts of a sentence
+}
+
+// Each mark with emoji and in plain ASCII. Cell marks are two columns wide like pieces; the
+// others end in a space, or are empty, so they can go right before a message.
+const MARKS: [(Mark, &str, &str); 7] = [
+  (Mark::Win, "⭐", " *"),
+  (Mark::Danger, "❌", " x"),
+  (Mark::Ghost, "⚪", " o"),
+  (Mark::Clock, "⏱  ", "Clock: "),
+  (Mark::Timeout, "⏰ ", ""),
+  (Mark::Agreement, "🤝 ", ""),
+  (Mark::Dash, " — ", ", "),
+];
+
+// Struct representing how one kind of cell is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+  pub Shape: Shape,
+  pub Color: Color,
+}
+
+impl Style {
+  fn Entry(self) -> (Color, &'static str, &'static str, &'static str, &'static str, &'static str) {
+    *COLORS.iter().find(|entry| entry.0 == self.Color).expect("every color is listed")
+  }
+
+  // Name of the color, e.g. "Red"
+  pub fn ColorName(self) -> &'static str {
+    self.Entry().1
+  }
+
+  // The cell as drawn, always two columns wide so emoji and letters line up. A letter sits
+  // in the right-hand column, under the column label. With `ascii` every shape is drawn with
+  // a letter or a full stop, and without `color` so is every shape but the dot, since emoji
+  // pieces differ only by their color.
+  fn Glyph(self, letter: char, ascii: bool, color: bool) -> String {
+    let (_, _, circle, square, ansi, _) = self.Entry();
+    let (dim, ansi, reset) = if color { (DIM, ansi, RESET) } else { ("", "", "") };
+    match (self.Shape, ascii || !color) {
+      (Shape::Circle, false) => circle.to_string(),
+      (Shape::Square, false) => square.to_string(),
+      (Shape::Dot, _) if !ascii => format!(" {}·{}", dim, reset),
+      (Shape::Dot, _) => format!(" {}.{}", dim, reset),
+      (_, _) => format!(" {}{}{}", ansi, letter, reset),
+    }
+  }
+}
+
+// Struct representing how the game is drawn: a style for each player's pieces and one for
+// empty cells, the ANSI colors of headings and of warnings and errors, and whether the board
+// is framed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+  Pieces: [Style; MAX_PLAYERS],
+  Empty: Style,
+  Accent: &'static str, // Headings, banners and the board's frame
+  Alert: &'static str,  // Errors and warnings
+  Framed: bool,         // Whether the board has a +----+ frame around it
+  Ascii: bool,          // Whether to keep to ASCII, for terminals and fonts without emoji
+  Color: bool,          // Whether to write ANSI colors and screen clears at all
+}
+
+// Struct listing what makes a named theme, see THEMES
+struct Preset {
+  Pieces: [(Shape, Color); MAX_PLAYERS],
+  Empty: (Shape, Color),
+  Accent: &'static str,
+  Alert: &'static str,
+  Framed: bool,
+  Ascii: bool,
+}
+
+// The themes that can be chosen by name with `--theme` or the theme command, with what each
+// looks like. The first is the default.
+const THEMES: [(&str, &str, Preset); 5] = [
+  (
+    "classic",
+    "emoji circles on blue",
+    Preset {
+      Pieces: [(Shape::Circle, Color::Red), (Shape::Circle, Color::Yellow), (Shape::Circle, Color::Green)],
+      Empty: (Shape::Circle, Color::Blue),
+      Accent: "\x1b[33m",
+      Alert: "\x1b[31m",
+      Framed: false,
+      Ascii: false,
+    },
+  ),
+  (
+    "retro",
+    "plain ASCII letters in a frame",
+    Preset {
+      Pieces: [(Shape::Letter, Color::Red), (Shape::Letter, Color::Yellow), (Shape::Letter, Color::Green)],
+      Empty: (Shape::Letter, Color::Black),
+      Accent: "\x1b[32m",
+      Alert: "\x1b[1;31m",
+      Framed: true,
+      Ascii: true,
+    },
+  ),
+  (
+    "minimal",
+    "colored letters on dots",
+    Preset {
+      Pieces: [(Shape::Letter, Color::Red), (Shape::Letter, Color::Yellow), (Shape::Letter, Color::Green)],
+      Empty: (Shape::Dot, Color::Black),
+      Accent: "\x1b[1m",
+      Alert: "\x1b[1m",
+      Framed: false,
+      Ascii: false,
+    },
+  ),
+  (
+    "high-contrast",
+    "bright shapes that differ in form as well as color",
+    Preset {
+      Pieces: [(Shape::Square, Color::White), (Shape::Circle, Color::Yellow), (Shape::Square, Color::Purple)],
+      Empty: (Shape::Dot, Color::Black),
+      Accent: "\x1b[1;97m",
+      Alert: "\x1b[1;91m",
+      Framed: true,
+      Ascii: false,
+    },
+  ),
+  // Colors and shapes that stay apart with red-green color blindness (deuteranopia and
+  // protanopia), with no red or green anywhere
+  (
+    "colorblind",
+    "blue circles against orange squares",
+    Preset {
+      Pieces: [(Shape::Circle, Color::Blue), (Shape::Square, Color::Orange), (Shape::Square, Color::White)],
+      Empty: (Shape::Dot, Color::Black),
+      Accent: "\x1b[94m",
+      Alert: "\x1b[1;38;5;208m",
+      Framed: false,
+      Ascii: false,
+    },
+  ),
+];
+
+impl Default for Theme {
+  fn default() -> Self {
+    Theme::FromPreset(&THEMES[0].2)
+  }
+}
+
+impl Theme {
+  fn FromPreset(preset: &Preset) -> Theme {
+    let style = |(shape, color)| Style { Shape: shape, Color: color };
+    Theme {
+      Pieces: preset.Pieces.map(style),
+      Empty: style(preset.Empty),
+      Accent: preset.Accent,
+      Alert: preset.Alert,
+      Framed: preset.Framed,
+      Ascii: preset.Ascii,
+      Color: true,
+    }
+  }
+
+  // The theme with the given name, such as "retro"
+  pub fn Named(name: &str) -> Result<Theme, String> {
+    let name = name.trim().to_lowercase();
+    match THEMES.iter().find(|(known, _, _)| *known == name) {
+      Some((_, _, preset)) => Ok(Theme::FromPreset(preset)),
+      None => Err(format!("Unknown theme '{}': choose {}", name, ThemeNames().join(", "))),
+    }
+  }
+
+  // The same theme drawn in plain ASCII or not: pieces become their color's initial, empty
+  // cells full stops, marks letters or punctuation, and the board gets a frame
+  pub fn WithAscii(mut self, ascii: bool) -> Theme {
+    self.Ascii = ascii;
+    self
+  }
+
+  // The same theme with or without ANSI codes. Without them pieces are told apart by letter,
+  // and highlighted cells by a mark beside them.
+  pub fn WithColor(mut self, color: bool) -> Theme {
+    self.Color = color;
+    self
+  }
+
+  // Starts from `base` and applies `--piece1`, `--piece2`, `--piece3` and `--empty`, each a
+  // shape, a color or both such as "square:red". The players' pieces have to look different
+  // from each other and from an empty cell as they're drawn, which in ASCII means a different
+  // color.
+  pub fn FromArgs(base: Theme, players: usize, value: impl Fn(&str) -> Option<String>) -> Result<Theme, String> {
+    let mut theme = base;
+    for (index, flag) in ["--piece1", "--piece2", "--piece3"].iter().enumerate() {
+      if let Some(text) = value(flag) {
+        theme.Pieces[index] = ParseStyle(&text, theme.Pieces[index])?;
+      }
+    }
+    if let Some(text) = value("--empty") {
+      theme.Empty = ParseStyle(&text, theme.Empty)?;
+    }
+    for index in 0..players {
+      if theme.Pieces[index].Shape == Shape::Dot {
+        return Err(format!("Player {}'s pieces can't be dots, those are for empty cells", index + 1));
+      }
+      let cell = theme.Cell(Player::FromInt(index as u8 + 1));
+      if cell == theme.Cell(Player::None) {
+        return Err(format!("Player {}'s pieces would look like empty cells", index + 1));
+      }
+      if let Some(other) = (0..index).find(|&other| theme.Cell(Player::FromInt(other as u8 + 1)) == cell) {
+        return Err(format!("Players {} and {} would have the same pieces", other + 1, index + 1));
+      }
+    }
+    Ok(theme)
+  }
+
+  // A cell holding the player's piece, or an empty one for Player::None
+  pub fn Cell(&self, player: Player) -> String {
+    match player {
+      Player::None => self.Empty.Glyph('.', self.Ascii, self.Color),
+      player => {
+        let style = self.Pieces[player as usize - 1];
+        style.Glyph(style.ColorName().chars().next().unwrap_or('?'), self.Ascii, self.Color)
+      }
+    }
+  }
+
+  // A cell one column wide for the compact layout: the piece's letter, or a full stop
+  pub fn CompactCell(&self, player: Player) -> String {
+    self.clone().WithAscii(true).Cell(player).trim_start().to_string()
+  }
+
+  // A cell as a block of BLOCK_HEIGHT lines BLOCK_WIDTH wide for the big layout, filled with
+  // the piece's color, or without colors with its letter. Empty cells drawn as dots, or without
+  // colors, are left blank. `label` goes in the middle, by default the piece's letter or a
+  // full stop for a blank cell.
+  pub fn Block(&self, player: Player, label: Option<&str>) -> Vec<String> {
+    let style = match player {
+      Player::None => self.Empty,
+      player => self.Pieces[player as usize - 1],
+    };
+    let (fill, code, reset) = match (self.Color, player) {
+      (true, Player::None) if style.Shape == Shape::Dot => (' ', "", ""),
+      (true, _) => (' ', style.Entry().5, RESET),
+      (false, Player::None) => (' ', "", ""),
+      (false, player) => (self.Letter(player), "", ""),
+    };
+    let default = match (player, fill) {
+      (Player::None, ' ') if code.is_empty() => ".".to_string(),
+      (Player::None, _) => String::new(),
+      (player, _) => self.Letter(player).to_string(),
+    };
+    let label = label.map_or(default, str::to_string);
+    let side = BLOCK_WIDTH.saturating_sub(label.chars().count());
+    let middle = format!(
+      "{}{}{}",
+      fill.to_string().repeat(side / 2),
+      label,
+      fill.to_string().repeat(side - side / 2)
+    );
+    (0..BLOCK_HEIGHT)
+        .map(|line| {
+          let text = if line == BLOCK_HEIGHT / 2 { middle.clone() } else { fill.to_string().repeat(BLOCK_WIDTH) };
+          format!("{}{}{}", code, text, reset)
+        })
+        .collect()
+  }
+
+  // The letter standing for the player's pieces, or a full stop for an empty cell
+  pub fn Letter(&self, player: Player) -> char {
+    self.clone().WithColor(false).CompactCell(player).chars().next().unwrap_or('?')
+  }
+
+  // Name of the player's pieces in the players' language, e.g. "Red", or "Red square" if
+  // another player's are red too
+  pub fn Name(&self, player: Player) -> String {
+    let style = match player {
+      Player::None => return locale::Text(Message::Nobody).to_string(),
+      player => self.Pieces[player as usize - 1],
+    };
+    let color = locale::ColorWord(style.Color);
+    match self.Pieces.iter().filter(|other| other.Color == style.Color).count() > 1 {
+      false => color.to_string(),
+      true => locale::Say(Message::ColorAndShape, &[("color", &color), ("shape", &locale::ShapeWord(style.Shape))]),
+    }
+  }
+
+  // ANSI color for headings and banners
+  pub fn Accent(&self) -> &'static str {
+    self.Code(self.Accent)
+  }
+
+  // ANSI color for errors and warnings
+  pub fn Alert(&self) -> &'static str {
+    self.Code(self.Alert)
+  }
+
+  // ANSI code that ends a color or highlight
+  pub fn Reset(&self) -> &'static str {
+    self.Code(RESET)
+  }
+
+  // ANSI code for text in reverse video
+  pub fn Reverse(&self) -> &'static str {
+    self.Code(REVERSE)
+  }
+
+  // ANSI code that clears the screen and moves to the top
+  pub fn ClearScreen(&self) -> &'static str {
+    self.Code(crate::terminal::CLEAR_SCREEN)
+  }
+
+  // A cell picked out from the rest: in reverse video, blinking if `blink`, or without color
+  // with '>' or '*' in front of it, or in lower case if it's a compact cell with no room
+  pub fn Highlight(&self, cell: &str, blink: bool) -> String {
+    match (self.Color, blink) {
+      (false, _) if !cell.starts_with(' ') => cell.to_lowercase(),
+      (true, false) => format!("{}{}{}", REVERSE, cell, RESET),
+      (true, true) => format!("{}{}{}", BLINK_REVERSE, cell, RESET),
+      (false, false) => format!(">{}", cell.trim_start()),
+      (false, true) => format!("*{}", cell.trim_start()),
+    }
+  }
+
+  pub fn Color(&self) -> bool {
+    self.Color
+  }
+
+  fn Code(&self, code: &'static str) -> &'static str {
+    if self.Color {
+      code
+    } else {
+      ""
+    }
+  }
+
+  pub fn Ascii(&self) -> bool {
+    self.Ascii
+  }
+
+  // Whether the board is drawn in a +----+ frame, as it always is in ASCII
+  pub fn Framed(&self) -> bool {
+    self.Framed || self.Ascii
+  }
+
+  // The mark as drawn in a compact cell, always one ASCII character
+  pub fn CompactMark(&self, mark: Mark) -> &'static str {
+    self.clone().WithAscii(true).Mark(mark).trim_start()
+  }
+
+  // The mark as drawn in this theme
+  pub fn Mark(&self, mark: Mark) -> &'static str {
+    let &(_, emoji, ascii) = MARKS.iter().find(|entry| entry.0 == mark).expect("every mark is listed");
+    if self.Ascii {
+      ascii
+    } else {
+      emoji
+    }
+  }
+}
+
+// Returns true if the locale says the terminal can't show UTF-8, e.g. LANG=C or
+// LC_ALL=en_US.ISO-8859-1. The first of LC_ALL, LC_CTYPE and LANG that is set decides; with
+// none set nothing is assumed.
+pub fn NonUtf8Locale() -> bool {
+  let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+      .iter()
+      .filter_map(|name| std::env::var(name).ok())
+      .find(|value| !value.is_empty());
+  match locale {
+    Some(locale) => {
+      let locale = locale.to_lowercase();
+      !locale.contains("utf-8") && !locale.contains("utf8")
+    }
+    None => false,
+  }
+}
+
+// Parses a style such as "square", "red" or "square:red"; the part left out stays as in
+// `default`
+fn ParseStyle(text: &str, default: Style) -> Result<Style, String> {
+  let mut style = default;
+  for part in text.split(':') {
+    let part = part.trim().to_lowercase();
+    match part.as_str() {
+      "circle" | "circles" => style.Shape = Shape::Circle,
+      "square" | "squares" => style.Shape = Shape::Square,
+      "letter" | "letters" => style.Shape = Shape::Letter,
+      "dot" | "dots" => style.Shape = Shape::Dot,
+      name => match COLORS.iter().find(|entry| entry.1.eq_ignore_ascii_case(name)) {
+        Some(entry) => style.Color = entry.0,
+        None => {
+          let colors: Vec<&str> = COLORS.iter().map(|entry| entry.1).collect();
+          return Err(format!(
+            "Unknown style '{}': use circle, square, letter or dot and a color ({})",
+            part,
+            colors.join(", ").to_lowercase()
+          ));
+        }
+      },
+    }
+  }
+  Ok(style)
+}
+
+// Names of the themes, each with what it looks like, e.g. "retro (plain ASCII letters in a
+// frame)"
+pub fn ThemeNames() -> Vec<String> {
+  THEMES.iter().map(|(name, description, _)| format!("{} ({})", name, description)).collect()
+}
+
+// The theme the board is drawn with, chosen at startup and changed with the theme command
+static CURRENT: RwLock<Option<Theme>> = RwLock::new(None);
+
+// Draws everything from now on with the theme
+pub fn Set(theme: Theme) {
+  COLORED.store(theme.Color, Ordering::SeqCst);
+  *CURRENT.write().unwrap() = Some(theme);
+}
+
+// The theme in use: until Set is called, the default one in color unless colors are off
+pub fn Current() -> Theme {
+  CURRENT.read().unwrap().clone().unwrap_or_else(|| Theme::default().WithColor(ColorWanted()))
+}
+
+// Whether colors are on, readable from a signal handler
+static COLORED: AtomicBool = AtomicBool::new(true);
+
+pub fn Colored() -> bool {
+  COLORED.load(Ordering::SeqCst)
+}
+
+// Returns false if `--no-color` is given, the NO_COLOR environment variable is set to
+// anything but an empty string (see no-color.org), or the terminal can't show colors
+pub fn ColorWanted() -> bool {
+  let NoColor = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+  !NoColor && !crate::Flag("--no-color") && crate::terminal::Escapes()
+}
@@ -0,0 +1,310 @@
+// Round-robin tournaments between computer players: every entrant plays every other the same
+// number of games with each color, in shuffled order, and a crosstable sums it up. Each
+// result is added to a results file as soon as the game ends, so a tournament that's stopped
+// carries on from there when it's run again. A tournament file looks like this:
+//
+//   games = 4        # games each pairing plays with each color
+//   move-ms = 100    # time each player has per move
+//
+//   [entrants]
+//   fast = "mcts:2000"
+//   deep = "minimax:8"
+//   "the easy one" = "easy"
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ConnectFour::engine::Engine;
+use ConnectFour::log;
+use ConnectFour::rating;
+use ConnectFour::rng::Rng;
+use ConnectFour::Player;
+
+use crate::config::{self, Setting};
+use crate::csv::Csv;
+use crate::{interrupt, locale, Alert, ArgValue, Message, Reset};
+
+// Struct describing a tournament: who plays, how many games and how fast
+struct Tournament {
+  Entrants: Vec<(String, String)>, // Names, and the ai: players they stand for, such as minimax:6
+  Games: usize,                    // Games each pairing plays with each color
+  MoveTime: Option<Duration>,      // Time each player has per move
+  Results: PathBuf,                // File the results are kept in
+}
+
+impl Tournament {
+  // The first line of the results file, so results from another tournament aren't mixed in
+  fn Header(&self) -> String {
+    let entrants: Vec<&str> = self.Entrants.iter().map(|(_, player)| player.as_str()).collect();
+    format!("# ConnectFour tournament: {}; {} game(s)", entrants.join(", "), self.Games)
+  }
+}
+
+// Runs the tournament from the file, or from `--entrants`, printing the crosstable at the end
+pub fn Run(path: Option<&str>) {
+  let tournament = match path {
+    Some(path) => FromFile(Path::new(path)),
+    None => FromArgs(),
+  };
+  if let Err(err) = tournament.and_then(|tournament| Play(&tournament)) {
+    println!("{}{}{}", Alert(), locale::Say(Message::Error, &[("error", &err)]), Reset());
+  }
+}
+
+// Reads the tournament file. `--games`, `--move-ms` and `--results` win over its settings.
+fn FromFile(path: &Path) -> Result<Tournament, String> {
+  let name = path.display().to_string();
+  let text = std::fs::read_to_string(path).map_err(|err| format!("couldn't read {}: {}", name, err))?;
+  let mut tournament = Tournament {
+    Entrants: Vec::new(),
+    Games: 2,
+    MoveTime: None,
+    Results: path.with_extension("results"),
+  };
+  for line in config::Parse(&text, &name)? {
+    let at = |problem: String| format!("{} line {}: {}", name, line.Number, problem);
+    let number = || match line.Value {
+      Setting::Number(ref text) => text.parse::<u64>().map_err(|_| at(format!("{} is a whole number", line.Key))),
+      _ => Err(at(format!("{} is a whole number", line.Key))),
+    };
+    match (line.Section.as_str(), line.Key.as_str()) {
+      ("", "games") => tournament.Games = number()? as usize,
+      ("", "move-ms") => tournament.MoveTime = Some(Duration::from_millis(number()?)),
+      ("", "results") => tournament.Results = PathBuf::from(line.Value.Text()),
+      ("entrants", _) => tournament.Entrants.push((line.Key.clone(), line.Value.Text())),
+      _ => return Err(at(format!("unknown setting '{}'", line.Key))),
+    }
+  }
+  Ok(WithArgs(tournament))
+}
+
+// The tournament from `--entrants mcts:2000,minimax:8,easy`, each entrant named after its
+// player
+fn FromArgs() -> Result<Tournament, String> {
+  let entrants = ArgValue("--entrants").ok_or("give the players, e.g. tournament --entrants mcts,minimax:6,easy, or a tournament file")?;
+  let tournament = Tournament {
+    Entrants: entrants.split(',').map(|player| (player.trim().to_string(), player.trim().to_string())).collect(),
+    Games: 2,
+    MoveTime: None,
+    Results: PathBuf::from("tournament.results"),
+  };
+  Ok(WithArgs(tournament))
+}
+
+// The tournament with what the command line says instead
+fn WithArgs(mut tournament: Tournament) -> Tournament {
+  if let Some(games) = ArgValue("--games").and_then(|value| value.parse().ok()) {
+    tournament.Games = games;
+  }
+  if let Some(ms) = ArgValue("--move-ms").and_then(|value| value.parse().ok()) {
+    tournament.MoveTime = Some(Duration::from_millis(ms));
+  }
+  if let Some(path) = ArgValue("--results") {
+    tournament.Results = PathBuf::from(path);
+  }
+  tournament
+}
+
+// Plays the games that aren't in the results file yet, then prints the crosstable
+fn Play(tournament: &Tournament) -> Result<(), String> {
+  let count = tournament.Entrants.len();
+  if count < 2 {
+    return Err("a tournament needs at least two entrants".to_string());
+  }
+  let template = crate::ComputerGame()?;
+  let mut engines = Vec::new();
+  for (_, player) in &tournament.Entrants {
+    let (mut engine, _) = crate::AiPlayer(player, crate::NextSeed())?;
+    if let Some(limit) = tournament.MoveTime {
+      engine.LimitTime(limit);
+    }
+    engines.push(engine);
+  }
+
+  // Every pairing with each color, shuffled so no entrant meets the others in a fixed order
+  // and a tournament stopped early is still a fair sample
+  let mut schedule: Vec<(usize, usize, usize)> = (0..count)
+      .flat_map(|first| (0..count).filter(move |&second| second != first).map(move |second| (first, second)))
+      .flat_map(|(first, second)| (0..tournament.Games).map(move |round| (first, second, round)))
+      .collect();
+  let mut rng = Rng::new(crate::NextSeed());
+  for index in (1..schedule.len()).rev() {
+    schedule.swap(index, rng.Below(index + 1));
+  }
+
+  let mut results = LoadResults(tournament)?;
+  let mut file = OpenResults(tournament)?;
+  let csv = ArgValue("--csv").map(|path| Csv::Create(&path)).transpose()?;
+  let total = schedule.len();
+  if !results.is_empty() {
+    let path = tournament.Results.display();
+    println!("{}", locale::Say(Message::CarryingOn, &[("path", &path), ("played", &results.len()), ("total", &total)]));
+  }
+  let mut problems = Vec::new();
+  for (index, &(first, second, round)) in schedule.iter().enumerate() {
+    if results.contains_key(&(first, second, round)) {
+      continue;
+    }
+    if interrupt::Interrupted() {
+      println!("{}", locale::Say(Message::TournamentStopped, &[("played", &results.len()), ("total", &total)]));
+      return Ok(());
+    }
+    let mut thinking = [Duration::ZERO; 2];
+    let mut moves = [0; 2];
+    let [one, two] = Pair(&mut engines, first, second);
+    let names = (&tournament.Entrants[first].0, &tournament.Entrants[second].0);
+    let started = Instant::now();
+    let played = crate::PlayOut(&template, [one, two], &mut thinking, &mut moves);
+    if let Some(csv) = &csv {
+      // Numbered by place in the schedule, which stays the same when a tournament carries on
+      let players = [tournament.Entrants[first].1.as_str(), tournament.Entrants[second].1.as_str()];
+      csv.Game(index + 1, crate::Seed(), players, &played, started.elapsed())?;
+    }
+    let game = match played {
+      Ok(game) => game,
+      Err(problem) => {
+        problems.push(locale::Say(Message::PairingProblem, &[("one", names.0), ("two", names.1), ("problem", &problem)]));
+        continue;
+      }
+    };
+    let points = match game.Winner() {
+      Player::One => 1.0,
+      Player::None => 0.5,
+      _ => 0.0,
+    };
+    writeln!(file, "{} {} {} {}", first, second, round, points)
+        .and_then(|()| file.flush())
+        .map_err(|err| format!("couldn't write {}: {}", tournament.Results.display(), err))?;
+    results.insert((first, second, round), points);
+    let score = match game.Winner() {
+      Player::One => "1-0",
+      Player::None => "½-½",
+      _ => "0-1",
+    };
+    let values: [(&str, &dyn std::fmt::Display); 5] =
+      [("number", &results.len()), ("total", &total), ("one", names.0), ("two", names.1), ("score", &score)];
+    println!("{}", locale::Say(Message::TournamentGame, &values));
+    log::Info(format_args!("Tournament game {} - {}: {} after {} moves", names.0, names.1, score, game.CurrentMove()));
+  }
+  println!();
+  println!("{}", Crosstable(tournament, &results));
+  crate::ShowSeed();
+  if !problems.is_empty() {
+    for problem in problems {
+      println!("{}{}{}", Alert(), problem, Reset());
+    }
+    std::process::exit(1);
+  }
+  Ok(())
+}
+
+// Both engines of a game, the first playing Player One
+fn Pair(engines: &mut [Box<dyn Engine>], first: usize, second: usize) -> [&mut Box<dyn Engine>; 2] {
+  let (low, high) = (first.min(second), first.max(second));
+  let (left, right) = engines.split_at_mut(high);
+  let (low, high) = (&mut left[low], &mut right[0]);
+  if first < second {
+    [low, high]
+  } else {
+    [high, low]
+  }
+}
+
+// The games already played, by first player, second player and round, with the points the
+// first player scored. None yet if there's no results file.
+fn LoadResults(tournament: &Tournament) -> Result<HashMap<(usize, usize, usize), f64>, String> {
+  let path = &tournament.Results;
+  let Ok(text) = std::fs::read_to_string(path) else {
+    return Ok(HashMap::new());
+  };
+  let mut lines = text.lines();
+  if lines.next() != Some(tournament.Header().as_str()) {
+    return Err(format!("{} holds the results of another tournament; delete it or give --results another file", path.display()));
+  }
+  let mut results = HashMap::new();
+  for (index, line) in lines.enumerate() {
+    // A line cut short when the tournament was stopped is played again
+    let Some((game, points)) = ParseResult(line) else {
+      log::Debug(format_args!("{} line {} left out: {:?}", path.display(), index + 2, line));
+      continue;
+    };
+    results.insert(game, points);
+  }
+  Ok(results)
+}
+
+// Reads a line of the results file: first player, second player, round and the first
+// player's points
+fn ParseResult(line: &str) -> Option<((usize, usize, usize), f64)> {
+  let mut fields = line.split_whitespace();
+  let mut next = || fields.next()?.parse::<usize>().ok();
+  let game = (next()?, next()?, next()?);
+  let points = fields.next()?.parse().ok()?;
+  fields.next().is_none().then_some((game, points))
+}
+
+// Opens the results file to add to, starting it with the header if it's new, or finishing
+// a line cut short so the next result starts a line of its own
+fn OpenResults(tournament: &Tournament) -> Result<std::fs::File, String> {
+  let path = &tournament.Results;
+  let existing = std::fs::read(path).unwrap_or_default();
+  let mut file = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .map_err(|err| format!("couldn't open {}: {}", path.display(), err))?;
+  let start = match existing.last() {
+    None => format!("{}\n", tournament.Header()),
+    Some(b'\n') => String::new(),
+    Some(_) => "\n".to_string(),
+  };
+  file.write_all(start.as_bytes()).map_err(|err| format!("couldn't write {}: {}", path.display(), err))?;
+  Ok(file)
+}
+
+// The crosstable: the entrants from first to last place, what each scored against each
+// other, their total and a rating worked out from the results
+fn Crosstable(tournament: &Tournament, results: &HashMap<(usize, usize, usize), f64>) -> String {
+  let count = tournament.Entrants.len();
+  let mut points = vec![vec![0.0; count]; count];
+  let mut games = vec![vec![0u64; count]; count];
+  for (&(first, second, _), &scored) in results {
+    points[first][second] += scored;
+    points[second][first] += 1.0 - scored;
+    games[first][second] += 1;
+    games[second][first] += 1;
+  }
+  let ratings = rating::Estimate(&points, &games);
+  let totals: Vec<f64> = points.iter().map(|row| row.iter().sum()).collect();
+  let mut order: Vec<usize> = (0..count).collect();
+  order.sort_by(|&one, &other| totals[other].total_cmp(&totals[one]).then(ratings[other].cmp(&ratings[one])));
+
+  let entrant = locale::Text(Message::Entrant);
+  let width = tournament.Entrants.iter().map(|(name, _)| name.chars().count()).chain([entrant.chars().count()]).max().unwrap_or(0);
+  let mut table = locale::Say(Message::RoundRobin, &[("games", &tournament.Games)]);
+  if let Some(limit) = tournament.MoveTime {
+    table += &locale::Say(Message::TimePerMove, &[("ms", &limit.as_millis())]);
+  }
+  table += &format!("\n\n {:>2}  {:<width$}", "#", entrant, width = width);
+  for place in 1..=count {
+    table += &format!(" {:>7}", place);
+  }
+  let rating = locale::Text(Message::Rating);
+  let rated = rating.chars().count().max(6);
+  table += &format!(" {:>9} {:>rated$}\n", locale::Text(Message::Score), rating, rated = rated);
+  for (place, &entrant) in order.iter().enumerate() {
+    table += &format!(" {:>2}  {:<width$}", place + 1, tournament.Entrants[entrant].0, width = width);
+    for &opponent in &order {
+      let cell = match opponent == entrant {
+        true => "-".to_string(),
+        false => format!("{}/{}", points[entrant][opponent], games[entrant][opponent]),
+      };
+      table += &format!(" {:>7}", cell);
+    }
+    let played: u64 = games[entrant].iter().sum();
+    table += &format!(" {:>9} {:>rated$}\n", format!("{}/{}", totals[entrant], played), ratings[entrant], rated = rated);
+  }
+  table
+}
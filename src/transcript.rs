@@ -0,0 +1,205 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Board, Game, Move, Player};
+
+// Letter for a player's pieces in plain text: R(ed), Y(ellow), G(reen)
+pub fn PieceLetter(player: Player) -> char {
+  match player {
+    Player::One => 'R',
+    Player::Two => 'Y',
+    Player::Three => 'G',
+    Player::None => '.',
+  }
+}
+
+// Draws the board with letters and dots only, no colors or emoji, with column numbers below
+pub fn PlainBoard(board: &Board) -> String {
+  let mut text = String::new();
+  for row in board {
+    let cells: Vec<String> = row.iter().map(|&cell| PieceLetter(Player::FromInt(cell)).to_string()).collect();
+    text += &format!("|{}|\n", cells.join(" "));
+  }
+  text += &format!("+{}+\n", "-".repeat(board.Width() * 2 - 1));
+  let numbers: Vec<String> = (1..=board.Width()).map(|column| (column % 10).to_string()).collect();
+  text += &format!(" {}\n", numbers.join(" "));
+  text
+}
+
+// One line saying how the game stands: whose move it is, or how it ended
+pub fn Status(game: &Game) -> String {
+  match (game.IsFinished(), game.Winner()) {
+    (false, _) => format!("{} to move", ColorWord(game.CurrentPlayer())),
+    (true, Player::None) if game.IsDrawAgreed() => "Drawn by agreement".to_string(),
+    (true, Player::None) => "Draw".to_string(),
+    (true, winner) if game.Forfeited().is_some() => format!("{} wins on time", ColorWord(winner)),
+    (true, winner) => format!("{} wins", ColorWord(winner)),
+  }
+}
+
+// Writes a human-readable record of a game: the final board, the result, the numbered moves
+// ("1. R:col4  2. Y:col4 ...") and how many there were
+pub fn Transcript(game: &Game) -> String {
+  let mut text = format!("Connect {} on a {}x{} board\n\n", game.WinLength(), game.Width(), game.Height());
+  text += &PlainBoard(game.Board());
+  text += "\n";
+  text += &match (game.IsFinished(), game.Winner()) {
+    (false, _) => "Result: unfinished\n".to_string(),
+    (true, Player::None) if game.IsDrawAgreed() => "Result: draw by agreement\n".to_string(),
+    (true, Player::None) => "Result: draw\n".to_string(),
+    (true, winner) if game.Forfeited().is_some() => format!("Result: {} wins on time\n", ColorWord(winner)),
+    (true, winner) => format!("Result: {} wins\n", ColorWord(winner)),
+  };
+  text += &format!("\nMoves:\n{}\n", MoveList(game));
+  text += &format!("\nTotal moves: {}\n", game.History().len());
+  text
+}
+
+// Numbered moves played so far with who made each, e.g. "1. R:col4  2. Y:col4", wrapped
+// to fit a terminal
+pub fn MoveList(game: &Game) -> String {
+  // Replay from the start to learn who made each move
+  let mut replay = game.clone();
+  replay.Reset();
+  let mut entries = Vec::new();
+  for (index, &played) in game.History().iter().enumerate() {
+    let mover = PieceLetter(replay.CurrentPlayer());
+    let description = match played {
+      Move::Drop(column) => format!("col{}", column + 1),
+      Move::Pop(column) => format!("pop{}", column + 1),
+      Move::Bomb(column) => format!("bomb{}", column + 1),
+      Move::Place(row, column) => format!("col{}row{}", column + 1, game.Height() - row),
+    };
+    entries.push(format!("{}. {}:{}", index + 1, mover, description));
+    let _ = replay.Play(played);
+  }
+  WrapEntries(&entries, 72)
+}
+
+// Default transcript file name for today's date, e.g. "connectfour-2026-10-15.txt"
+pub fn DefaultFileName() -> String {
+  let seconds = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|elapsed| elapsed.as_secs())
+      .unwrap_or(0);
+  let (year, month, day) = CivilDate(seconds / 86_400);
+  format!("connectfour-{:04}-{:02}-{:02}.txt", year, month, day)
+}
+
+fn ColorWord(player: Player) -> &'static str {
+  match player {
+    Player::One => "Red",
+    Player::Three => "Green",
+    _ => "Yellow",
+  }
+}
+
+// Joins move entries with two spaces, starting a new line before `width` characters
+fn WrapEntries(entries: &[String], width: usize) -> String {
+  let mut lines: Vec<String> = Vec::new();
+  let mut line = String::new();
+  for entry in entries {
+    if !line.is_empty() && line.len() + 2 + entry.len() > width {
+      lines.push(std::mem::take(&mut line));
+    }
+    if !line.is_empty() {
+      line += "  ";
+    }
+    line += entry;
+  }
+  lines.push(line);
+  lines.join("\n")
+}
+
+// Converts days since 1970-01-01 to a (year, month, day) date in the proleptic Gregorian
+// calendar (Howard Hinnant's days-to-civil algorithm)
+pub fn CivilDate(days: u64) -> (u64, u64, u64) {
+  let z = days + 719_468;
+  let era = z / 146_097;
+  let day_of_era = z % 146_097;
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+  let year = year_of_era + era * 400 + (month <= 2) as u64;
+  (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EMPTY_ROWS: &str = "|. . . . . . .|\n|. . . . . . .|\n|. . . . . . .|\n|. . . . . . .|\n";
+  const FOOTER: &str = "+-------------+\n 1 2 3 4 5 6 7\n";
+
+  #[test]
+  fn BoardDisplay() {
+    let game = Game::FromMoveString("44536").unwrap();
+    let expected = format!("{}|. . . Y . . .|\n|. . Y R R R .|\n{}", EMPTY_ROWS, FOOTER);
+    assert_eq!(game.Board().to_string(), expected);
+    assert_eq!(PlainBoard(game.Board()), expected);
+  }
+
+  #[test]
+  fn WideBoardNumbersColumnsByTheirLastDigit() {
+    let game = Game::new(12, 4).unwrap();
+    let expected = format!("{}+{}+\n 1 2 3 4 5 6 7 8 9 0 1 2\n", "|. . . . . . . . . . . .|\n".repeat(4), "-".repeat(23));
+    assert_eq!(game.Board().to_string(), expected);
+  }
+
+  #[test]
+  fn GameDisplayEndsWithTheStatus() {
+    let going = Game::FromMoveString("44").unwrap();
+    assert_eq!(going.to_string(), format!("{}|. . . Y . . .|\n|. . . R . . .|\n{}Red to move", EMPTY_ROWS, FOOTER));
+
+    let won = Game::FromMoveString("1212121").unwrap();
+    assert!(won.to_string().ends_with(&format!("{}Red wins", FOOTER)));
+
+    let mut agreed = Game::FromMoveString("44").unwrap();
+    agreed.OfferDraw(Player::One).unwrap();
+    agreed.MakeMove(2).unwrap();
+    agreed.AcceptDraw().unwrap();
+    assert_eq!(Status(&agreed), "Drawn by agreement");
+
+    let mut forfeited = Game::FromMoveString("44").unwrap();
+    forfeited.Forfeit().unwrap();
+    assert_eq!(Status(&forfeited), "Yellow wins on time");
+
+    let drawn = Game::FromMoveString("512414477327537234737551113144622532666665").unwrap();
+    assert_eq!(Status(&drawn), "Draw");
+
+    let mut three = Game::new(7, 6).unwrap().WithPlayers(3).unwrap();
+    three.PlayMoveString("12").unwrap();
+    assert_eq!(Status(&three), "Green to move");
+  }
+
+  #[test]
+  fn TranscriptOfAWonGame() {
+    let game = Game::FromMoveString("1212121").unwrap();
+    let board = "|. . . . . . .|\n|. . . . . . .|\n|R . . . . . .|\n|R Y . . . . .|\n|R Y . . . . .|\n|R Y . . . . .|\n";
+    let expected = format!(
+      "Connect 4 on a 7x6 board\n\n{}{}\nResult: Red wins\n\nMoves:\n{}\n\nTotal moves: 7\n",
+      board,
+      FOOTER,
+      "1. R:col1  2. Y:col2  3. R:col1  4. Y:col2  5. R:col1  6. Y:col2\n7. R:col1"
+    );
+    assert_eq!(Transcript(&game), expected);
+  }
+
+  #[test]
+  fn MoveListWrapsBeforeSeventyTwoColumns() {
+    let game = Game::FromMoveString("123456712345671234567").unwrap();
+    let list = MoveList(&game);
+    assert!(list.lines().count() > 1);
+    assert!(list.lines().all(|line| line.len() <= 72), "{}", list);
+    assert!(list.starts_with("1. R:col1  2. Y:col2  3. R:col3"));
+    assert!(list.ends_with("21. R:col7"));
+  }
+
+  #[test]
+  fn CivilDates() {
+    assert_eq!(CivilDate(0), (1970, 1, 1));
+    assert_eq!(CivilDate(11_016), (2000, 2, 29));
+    assert_eq!(CivilDate(20_742), (2026, 10, 16));
+  }
+}
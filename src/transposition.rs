@@ -0,0 +1,120 @@
+// Default transposition table size for the search engine
+pub const DEFAULT_TABLE_ENTRIES: usize = 1 << 20;
+
+// Enum describing how a stored score relates to the true value of the position
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Bound {
+  #[default]
+  Exact, // The score is the value itself
+  Lower, // The search failed high, the value is at least the score
+  Upper, // The search failed low, the value is at most the score
+}
+
+// Struct representing one remembered search result
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Entry {
+  pub Key: u64,       // Zobrist hash of the position
+  pub Depth: u8,      // Remaining depth the position was searched to (0 marks an empty slot)
+  pub Score: i32,     // Score for the side to move
+  pub Bound: Bound,   // How the score relates to the true value
+  pub BestMove: u8,   // Column that produced the score
+  Generation: u8,     // Search the entry was stored in
+}
+
+// Struct representing a fixed-size hash table of search results.
+// A slot is replaced when it is empty, holds the same position, was stored by an earlier
+// search, or was searched less deeply than the new result.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable {
+  Entries: Vec<Entry>,
+  Generation: u8, // Bumped at the start of every search
+}
+
+impl Default for TranspositionTable {
+  fn default() -> Self {
+    Self::WithEntries(DEFAULT_TABLE_ENTRIES)
+  }
+}
+
+impl TranspositionTable {
+  // Creates a table with room for the given number of entries; zero disables it
+  pub fn WithEntries(entries: usize) -> TranspositionTable {
+    TranspositionTable { Entries: vec![Entry::default(); entries], Generation: 0 }
+  }
+
+  // Creates a table using about the given number of megabytes
+  pub fn WithMegabytes(megabytes: usize) -> TranspositionTable {
+    Self::WithEntries(megabytes * 1024 * 1024 / std::mem::size_of::<Entry>())
+  }
+
+  // Number of slots in the table
+  pub fn Capacity(&self) -> usize {
+    self.Entries.len()
+  }
+
+  // Marks everything stored so far as belonging to an older search
+  pub fn NewSearch(&mut self) {
+    self.Generation = self.Generation.wrapping_add(1);
+  }
+
+  // Forgets every entry
+  pub fn Clear(&mut self) {
+    self.Entries.iter_mut().for_each(|entry| *entry = Entry::default());
+  }
+
+  // Looks up the stored result for a position
+  pub fn Probe(&self, key: u64) -> Option<Entry> {
+    if self.Entries.is_empty() {
+      return None;
+    }
+    let entry = self.Entries[(key % self.Entries.len() as u64) as usize];
+    (entry.Depth > 0 && entry.Key == key).then_some(entry)
+  }
+
+  // Stores a result, subject to the replacement policy
+  pub fn Store(&mut self, key: u64, depth: u8, score: i32, bound: Bound, BestMove: u8) {
+    if self.Entries.is_empty() || depth == 0 {
+      return;
+    }
+    let index = (key % self.Entries.len() as u64) as usize;
+    let slot = &mut self.Entries[index];
+    if slot.Depth == 0 || slot.Key == key || slot.Generation != self.Generation || depth >= slot.Depth {
+      *slot = Entry { Key: key, Depth: depth, Score: score, Bound: bound, BestMove, Generation: self.Generation };
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Game;
+
+  #[test]
+  fn TranspositionsShareAnEntry() {
+    // Red in columns 1 and 3, Yellow in 2 and 4, played in different orders
+    let one = Game::FromMoveString("1234").unwrap();
+    let other = Game::FromMoveString("3412").unwrap();
+    let swapped = Game::FromMoveString("2143").unwrap();
+    assert_eq!(one.Hash(), other.Hash());
+    assert_ne!(one.Hash(), swapped.Hash());
+
+    let mut table = TranspositionTable::WithEntries(1024);
+    table.Store(one.Hash(), 5, 12, Bound::Lower, 3);
+    let entry = table.Probe(other.Hash()).unwrap();
+    assert_eq!((entry.Depth, entry.Score, entry.Bound, entry.BestMove), (5, 12, Bound::Lower, 3));
+    assert_eq!(table.Probe(swapped.Hash()), None);
+  }
+
+  #[test]
+  fn DeeperResultsWinTheSlot() {
+    let mut table = TranspositionTable::WithEntries(1);
+    table.Store(1, 6, 10, Bound::Exact, 2);
+    table.Store(2, 3, 20, Bound::Exact, 4);
+    assert_eq!(table.Probe(1).map(|entry| entry.Score), Some(10));
+    assert_eq!(table.Probe(2), None);
+    // A new search may replace anything left by an earlier one
+    table.NewSearch();
+    table.Store(2, 3, 20, Bound::Exact, 4);
+    assert_eq!(table.Probe(2).map(|entry| entry.Score), Some(20));
+  }
+}
@@ -0,0 +1,451 @@
+// Full-screen mode, `--tui`: the board with a cursor over the column to play, moved with the
+// arrow keys, and a side panel with whose turn it is, the clocks and the moves so far
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use ConnectFour::{clock, observer, transcript, Game, Move, MoveOutcome, Player};
+
+use crate::config;
+use crate::locale::{self, Localized, Message};
+use crate::terminal::{self, Key};
+use crate::theme::{self, Mark};
+use crate::{interrupt, Accent, Alert, Layout, Overlay, Reset, Session};
+
+// How often the terminal is checked for a new size and the running clocks brought up to date
+const TICK: Duration = Duration::from_millis(100);
+
+// Narrowest side panel worth drawing beside the board; a narrower terminal gets it below
+const PANEL_WIDTH: usize = 24;
+
+// Columns between the board and the side panel
+const PANEL_GAP: usize = 4;
+
+// Screen line the board's cells start on, below the title, a rule, the cursor and the labels
+const BOARD_LINE: usize = 5;
+
+// How long a click that can't be played is pointed out for
+const NUDGE_TIME: Duration = Duration::from_secs(1);
+
+// Struct holding what the full-screen mode shows besides the game itself
+struct Screen {
+  Column: usize,                   // Column the cursor is over
+  Message: Option<(String, bool)>, // Line under the board, and whether it's an error
+  Turn: (usize, Instant),          // Move number of the turn being timed, and when it began
+  Drawn: Option<(Option<usize>, Option<usize>, u64)>, // Terminal size and clock second last drawn
+  Dirty: bool,                     // Whether anything has changed since it was drawn
+  Expires: Option<Instant>,        // When the message goes away by itself, if it does
+}
+
+impl Screen {
+  // Shows a message under the board
+  fn Say(&mut self, text: String) {
+    self.Message = Some((text, false));
+    self.Expires = None;
+    self.Dirty = true;
+  }
+
+  fn Error(&mut self, text: String) {
+    self.Message = Some((text, true));
+    self.Expires = None;
+    self.Dirty = true;
+  }
+
+  // Points out a problem for a moment without it being an error
+  fn Nudge(&mut self, text: String) {
+    self.Message = Some((text, true));
+    self.Expires = Some(Instant::now() + NUDGE_TIME);
+    self.Dirty = true;
+  }
+}
+
+// Plays the session full screen until the players quit, then leaves the program. Returns at
+// once, so the game goes on line by line, if the game has no gravity, as cells can't be
+// picked with a column cursor, or stdin isn't a terminal.
+pub fn Run(session: &mut Session) {
+  if !session.Game.Gravity() {
+    println!("{}--tui needs a game with gravity, playing line by line{}", Alert(), Reset());
+    return;
+  }
+  if !terminal::EnterFullScreen() {
+    println!("{}--tui needs to be run in a terminal, playing line by line{}", Alert(), Reset());
+    return;
+  }
+  let mut screen = Screen {
+    Column: session.Game.Width() / 2,
+    Message: None,
+    Turn: (usize::MAX, Instant::now()),
+    Drawn: None,
+    Dirty: true,
+    Expires: None,
+  };
+  // Clicks are opt-in, as capturing them stops text being selected with the mouse
+  if crate::Flag("--mouse") {
+    terminal::CaptureMouse();
+  }
+  loop {
+    let game = &session.Game;
+    // Saved whenever a move is made or taken back, as at the line-by-line prompt
+    if screen.Turn.0 != game.CurrentMove() {
+      screen.Turn = (game.CurrentMove(), Instant::now());
+      crate::Autosave(game);
+    }
+    if game.IsFinished() {
+      crate::RecordStats(session);
+    }
+    if !session.Game.IsFinished() && session.Bot.is_some() && session.Game.CurrentPlayer() == Player::Two {
+      screen.Say(locale::Text(Message::Thinking).to_string());
+      Draw(session, &mut screen);
+      BotMove(session, &mut screen);
+      continue;
+    }
+    if !session.Game.IsFinished() && TimeUp(session, &screen) {
+      let _ = session.Game.SpendTime(screen.Turn.1.elapsed());
+      let _ = session.Game.Forfeit(); // Already over if the clock ran out
+      crate::Autosave(&session.Game);
+      screen.Error(locale::Text(Message::TimesUp).to_string());
+      continue;
+    }
+    if screen.Expires.is_some_and(|expires| Instant::now() >= expires) {
+      screen.Message = None;
+      screen.Expires = None;
+      screen.Dirty = true;
+    }
+    let size = (terminal::Width(), terminal::Height());
+    let second = screen.Turn.1.elapsed().as_secs();
+    let ticking = !session.Game.IsFinished() && (session.Game.Clock().is_some() || session.MoveTime.is_some());
+    if screen.Dirty || screen.Drawn.is_none_or(|(width, height, drawn)| (width, height) != size || (ticking && drawn != second)) {
+      Draw(session, &mut screen);
+      screen.Drawn = Some((size.0, size.1, second));
+    }
+    let Some(key) = terminal::ReadKey(Instant::now() + TICK) else {
+      if interrupt::Interrupted() || interrupt::InputEnded() {
+        break;
+      }
+      continue;
+    };
+    if !HandleKey(session, &mut screen, key) {
+      break;
+    }
+  }
+  crate::Autosave(&session.Game);
+  let _ = std::io::stdout().flush();
+  terminal::Restore();
+  crate::Quit();
+}
+
+// Acts on a key press. Returns false if it quits.
+fn HandleKey(session: &mut Session, screen: &mut Screen, key: Key) -> bool {
+  let width = session.Game.Width();
+  screen.Dirty = true;
+  match key {
+    Key::Left => screen.Column = (screen.Column + width - 1) % width,
+    Key::Right => screen.Column = (screen.Column + 1) % width,
+    Key::Char(digit @ '1'..='9') if (digit as usize - '1' as usize) < width => {
+      screen.Column = digit as usize - '1' as usize;
+    }
+    Key::Enter | Key::Space | Key::Down => Play(session, screen, Move::Drop(screen.Column)),
+    Key::Char(key) if key == config::Key("pop") && session.Game.PopOut() => Play(session, screen, Move::Pop(screen.Column)),
+    Key::Char(key) if key == config::Key("bomb") && session.Game.HasBombs() => {
+      Play(session, screen, Move::Bomb(screen.Column))
+    }
+    Key::Char(key) if key == config::Key("undo") => {
+      Charge(session, screen);
+      match session.Game.UndoMove() {
+        Ok(()) => {
+          session.MoveClock = None; // A fresh turn gets fresh time
+          if session.Bot.is_some() && session.Game.CurrentPlayer() == Player::Two {
+            let _ = session.Game.UndoMove();
+          }
+          screen.Say(locale::Text(Message::MoveUndone).to_string());
+        }
+        Err(err) => screen.Error(err.Localized()),
+      }
+    }
+    Key::Char(key) if key == config::Key("redo") => {
+      Charge(session, screen);
+      match session.Game.RedoMove() {
+        Ok(_) => Report(session, screen, None),
+        Err(err) => screen.Error(err.Localized()),
+      }
+    }
+    Key::Click(x, y) => match ClickedColumn(&session.Game, x, y) {
+      Some(column) if session.Game.IsFinished() || session.Game.IsColumnPlayable(column) => {
+        screen.Column = column;
+        Play(session, screen, Move::Drop(column));
+      }
+      Some(column) => {
+        screen.Column = column;
+        screen.Nudge(locale::Say(Message::FullColumn, &[("column", &(column + 1))]));
+      }
+      None => screen.Dirty = false,
+    },
+    Key::Char(key) if key == config::Key("threats") => session.Threats = !session.Threats,
+    Key::Char(key) if key == config::Key("new") && session.Game.IsFinished() => {
+      session.Restart();
+      Report(session, screen, None);
+    }
+    Key::Char(key) if key == config::Key("quit") => return false,
+    Key::Escape => return false,
+    _ => screen.Dirty = false,
+  }
+  true
+}
+
+// Plays a move for the player on turn, once their time is taken off the clock
+fn Play(session: &mut Session, screen: &mut Screen, played: Move) {
+  if session.Game.IsFinished() {
+    screen.Error(locale::Text(Message::GameOverKeys).to_string());
+    return;
+  }
+  if Charge(session, screen) {
+    screen.Error(locale::Text(Message::TimesUp).to_string());
+    return;
+  }
+  let _ = session.Game.Play(played);
+  Report(session, screen, None);
+}
+
+// Lets the computer move, as at the line-by-line prompt
+fn BotMove(session: &mut Session, screen: &mut Screen) {
+  let Some(bot) = &mut session.Bot else {
+    return;
+  };
+  let name = bot.Name();
+  if session.Game.DrawOffer().is_some() {
+    let _ = session.Game.DeclineDraw();
+    screen.Say(locale::Say(Message::BotDeclinesDraw, &[("name", &name)]));
+    return;
+  }
+  let started = Instant::now();
+  let column = bot.ChooseMove(&session.Game).expect("unfinished game has a legal move");
+  if session.Game.SpendTime(started.elapsed()) {
+    screen.Error(locale::Say(Message::RanOutOfTime, &[("name", &name)]));
+    return;
+  }
+  session.Game.MakeMove(column).expect("bot picks a legal column");
+  Report(session, screen, Some(&name));
+}
+
+// Puts what the game's observer was told on the message line: the move just played or played
+// again, after the computer's choice when `chose` names it, or why a move was turned away
+fn Report(session: &mut Session, screen: &mut Screen, chose: Option<&str>) {
+  while let Ok(event) = session.Events.try_recv() {
+    match event {
+      observer::Event::MoveMade { Player: player, Column: column, Row: row } => {
+        let outcome = crate::MovedOutcome(&session.Game, player, column, row);
+        crate::LogMove(&session.Game, &outcome);
+        let described = Describe(&session.Game, &outcome);
+        match chose {
+          Some(name) => {
+            let chose = locale::Say(Message::BotChose, &[("name", &name), ("column", &(column + 1))]);
+            screen.Say(format!("{}. {}", chose, described));
+          }
+          None => screen.Say(described),
+        }
+      }
+      observer::Event::MoveRedone { Player: player, Column: column, Row: row } => {
+        let outcome = crate::MovedOutcome(&session.Game, player, column, row);
+        screen.Say(Describe(&session.Game, &outcome));
+      }
+      observer::Event::MoveRejected { Error: err } => screen.Error(err.Localized()),
+      observer::Event::GameReset => screen.Message = None,
+      observer::Event::MoveUndone { .. } | observer::Event::GameReopened | observer::Event::GameFinished { .. } => {}
+    }
+  }
+}
+
+// Column of the board under a click at a 1-based screen column and line, if it's on one.
+// Clicks on the cursor line and the column labels count too, but not between the cells.
+fn ClickedColumn(game: &Game, x: usize, y: usize) -> Option<usize> {
+  let layout = crate::GameLayout(game);
+  let (left, _) = layout.Edges(game.Wrap());
+  // The big layout repeats the labels below the board
+  let labels_below = usize::from(layout == Layout::Big);
+  let last_line = BOARD_LINE + layout.BoardLines(game.Height()) - 1 + labels_below;
+  if !(BOARD_LINE - 2..=last_line).contains(&y) {
+    return None;
+  }
+  let across = x.checked_sub(1 + left.len())?;
+  let pitch = layout.CellWidth() + layout.Gap().len();
+  let column = across / pitch;
+  (column < game.Width() && across % pitch < layout.CellWidth()).then_some(column)
+}
+
+// Takes the time spent on this turn off the player's clock and starts timing afresh. Returns
+// true if it ran out.
+fn Charge(session: &mut Session, screen: &mut Screen) -> bool {
+  let flagged = session.Game.SpendTime(screen.Turn.1.elapsed());
+  screen.Turn.1 = Instant::now();
+  flagged
+}
+
+// Returns true once the player on turn has used up their time for the move or the game
+fn TimeUp(session: &mut Session, screen: &Screen) -> bool {
+  let player = session.Game.CurrentPlayer();
+  let flag_fall = session.Game.Clock().map(|clock| screen.Turn.1 + clock.TimeToFlag(player));
+  crate::Deadline(session).into_iter().chain(flag_fall).min().is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+// Where the last move's piece went, as the line-by-line mode says it
+fn Describe(game: &Game, outcome: &MoveOutcome) -> String {
+  let name = crate::ColorName(outcome.Player);
+  let row = game.Height() - outcome.Row;
+  match game.History().last() {
+    Some(Move::Pop(column)) => locale::Say(Message::Popped, &[("name", &name), ("column", &(column + 1))]),
+    Some(Move::Bomb(column)) => locale::Say(Message::Bombed, &[("name", &name), ("column", &(column + 1)), ("row", &row)]),
+    _ => locale::Say(Message::Dropped, &[("name", &name), ("column", &(outcome.Column + 1)), ("row", &row)]),
+  }
+}
+
+// Redraws the whole screen: the board with the cursor above it, the message and keys under
+// it, and the panel beside it, or below it if there's no room
+fn Draw(session: &Session, screen: &mut Screen) {
+  let game = &session.Game;
+  let theme = theme::Current();
+  let layout = crate::GameLayout(game);
+  screen.Column = screen.Column.min(game.Width() - 1);
+  screen.Dirty = false;
+  print!("{}", terminal::CLEAR_SCREEN);
+  let title = locale::Say(
+    Message::Title,
+    &[("length", &game.WinLength()), ("variants", &crate::VariantNames(game)), ("move", &game.CurrentMove())],
+  );
+  println!("{}{}{}", Accent(), crate::Centered(game, &title), Reset());
+  println!("{}", crate::Separator(game));
+
+  // The cursor over the middle of its column, lined up with the column labels
+  let (left, _) = layout.Edges(game.Wrap());
+  let middle = match layout {
+    Layout::Compact => 0,
+    Layout::Wide => 1,
+    Layout::Big => (theme::BLOCK_WIDTH - 1) / 2,
+  };
+  let offset = left.len() + screen.Column * (layout.CellWidth() + layout.Gap().len()) + middle;
+  let pointer = if theme.Ascii() { "v" } else { "▼" };
+  println!("{}{}{}{}", " ".repeat(offset), Accent(), pointer, Reset());
+  println!("{}", crate::ColumnHeader(game, crate::UseLetters(session)));
+  let mut overlay = Overlay {
+    Last: game.LastPlaced(),
+    Winning: game.WinningLine(),
+    // Where the piece would land, while there's a move to make
+    Ghost: game.LandingRow(screen.Column).filter(|_| session.Preview && !game.IsFinished()).map(|row| (row, screen.Column)),
+    ..Overlay::default()
+  };
+  if session.Threats {
+    overlay.Wins = game.ThreatCells(game.CurrentPlayer());
+    overlay.Dangers = game.ThreatCells(game.CurrentPlayer().Next(game.Players()));
+  }
+  print!("{}", crate::RenderRows(game.Board(), game.Wrap(), &overlay));
+  println!("{}", crate::Separator(game));
+  match &screen.Message {
+    Some((text, true)) => println!("{}", crate::RenderError(text)),
+    Some((text, false)) => println!("{}", text),
+    None => println!(),
+  }
+  println!("{}", KeyHelp(game, theme.Ascii()));
+
+  // Lines so far, as the panel goes below them if it doesn't fit beside the board
+  let used = 7 + layout.BoardLines(game.Height());
+  let board_width = layout.BoardWidth(game.Width(), game.Wrap());
+  let columns = terminal::Width().unwrap_or(80);
+  let lines = terminal::Height().unwrap_or(24);
+  let (top, left, width) = match columns >= board_width + PANEL_GAP + PANEL_WIDTH {
+    true => (1, board_width + PANEL_GAP + 1, columns - board_width - PANEL_GAP),
+    false => (used + 2, 1, columns.max(PANEL_WIDTH)),
+  };
+  for (index, line) in Panel(session, screen, width, lines.saturating_sub(top)).iter().enumerate() {
+    print!("{}{}", terminal::MoveTo(top + index, left), line);
+  }
+  let _ = std::io::stdout().flush();
+}
+
+// Keys that do something right now
+fn KeyHelp(game: &Game, ascii: bool) -> String {
+  let arrows = if ascii { "Left/Right" } else { "←/→" };
+  let mut keys = vec![locale::Say(Message::KeyMove, &[("arrows", &arrows)]), locale::Text(Message::KeyDrop).to_string()];
+  if crate::Flag("--mouse") {
+    keys.push(locale::Text(Message::KeyClick).to_string());
+  }
+  // The keys as bound in the config file
+  let key = |message, action| locale::Say(message, &[("key", &config::Key(action))]);
+  if game.PopOut() {
+    keys.push(key(Message::KeyPop, "pop"));
+  }
+  if game.BombsLeft(game.CurrentPlayer()) > 0 {
+    keys.push(key(Message::KeyBomb, "bomb"));
+  }
+  keys.extend([key(Message::KeyUndo, "undo"), key(Message::KeyRedo, "redo"), key(Message::KeyThreats, "threats")]);
+  if game.IsFinished() {
+    keys.push(key(Message::KeyNewGame, "new"));
+  }
+  keys.push(key(Message::KeyQuit, "quit"));
+  format!("{}{}{}", Accent(), keys.join("  "), Reset())
+}
+
+// Lines of the side panel: whose turn it is or how the game ended, the clocks, and as many of
+// the latest moves as fit
+fn Panel(session: &Session, screen: &Screen, width: usize, lines: usize) -> Vec<String> {
+  let game = &session.Game;
+  let theme = theme::Current();
+  let mut panel = Vec::new();
+  let player = game.CurrentPlayer();
+  if !game.IsFinished() {
+    let turn = locale::Say(Message::ToMove, &[("name", &session.Name(player))]);
+    panel.push(format!("{} {}{}{}", theme.CompactCell(player), Accent(), turn, Reset()));
+  } else if let Some(loser) = game.Forfeited() {
+    let forfeit = locale::Say(Message::ForfeitsOnTime, &[("name", &session.Name(loser))]);
+    panel.push(format!("{}{}{}{}", Alert(), theme.Mark(Mark::Timeout), forfeit, Reset()));
+  } else {
+    match game.Winner() {
+      Player::None if game.IsDrawAgreed() => {
+        panel.push(format!("{}{}{}", Accent(), locale::Text(Message::DrawByAgreement), Reset()))
+      }
+      Player::None => panel.push(format!("{}{}{}", Accent(), locale::Text(Message::ItsADraw), Reset())),
+      winner => {
+        let banner = locale::Say(Message::Wins, &[("name", &session.Name(winner))]);
+        panel.push(format!("{} {}{}{}", theme.CompactCell(winner), Accent(), banner, Reset()))
+      }
+    }
+  }
+  if let Some(difficulty) = session.Difficulty {
+    panel.push(locale::Say(Message::ComputerIs, &[("level", &difficulty.Name())]));
+  }
+  let elapsed = screen.Turn.1.elapsed();
+  if let Some(clock) = game.Clock() {
+    panel.push(String::new());
+    panel.push(format!("{}{}{}{}", Accent(), theme.Mark(Mark::Clock), clock.Control(), Reset()));
+    for number in 1..=game.Players() {
+      let someone = Player::FromInt(number as u8);
+      let running = someone == player && !game.IsFinished();
+      let left = match running {
+        true => clock.Remaining(someone).saturating_sub(elapsed),
+        false => clock.Remaining(someone),
+      };
+      let marker = if running { ">" } else { " " };
+      panel.push(format!("{} {} {}", marker, clock::Format(left), session.Name(someone)));
+    }
+  }
+  if let Some((_, deadline)) = session.MoveClock.filter(|_| !game.IsFinished()) {
+    let left = deadline.saturating_duration_since(Instant::now()).as_secs_f64().ceil();
+    let left = locale::Say(Message::SecondsToMove, &[("seconds", &left)]);
+    panel.push(format!("{}{}{}{}", Accent(), theme.Mark(Mark::Clock), left, Reset()));
+  }
+  panel.push(String::new());
+  panel.push(format!("{}{}{}", Accent(), locale::Text(Message::MovesHeading), Reset()));
+  // Moves run two to a line when there's room, the latest at the bottom
+  let moves = transcript::MoveList(game);
+  let entries: Vec<&str> = moves.split("  ").flat_map(str::lines).map(str::trim).filter(|entry| !entry.is_empty()).collect();
+  let mut history: Vec<String> = Vec::new();
+  for entry in entries {
+    match history.last_mut() {
+      Some(line) if line.chars().count() + 2 + entry.len() <= width && line.matches(':').count() < 2 => {
+        *line += &format!("  {}", entry)
+      }
+      _ => history.push(entry.to_string()),
+    }
+  }
+  let room = lines.saturating_sub(panel.len());
+  panel.extend(history.split_off(history.len().saturating_sub(room)));
+  panel
+}
@@ -0,0 +1,148 @@
+// Exports the game to JavaScript for the `wasm` feature. Rather than pull in wasm-bindgen,
+// the functions use the plain C ABI, which WebAssembly hands straight to JavaScript: a game is
+// a pointer (a number on the JS side), and examples/web/connectfour.js wraps the exports in a
+// Game class with makeMove, board, currentPlayer, winner and legalMoves.
+//
+// Build with:
+//   cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//
+// Only the library is built, so none of the terminal drawing or input (which live in the
+// binary) ends up in the module. The functions taking a game are unsafe, as in src/ffi.rs:
+// the pointer has to be null or a game from game_new that hasn't been freed.
+
+use crate::ffi::{self, GameAt, GameAtMut, Give, Take, CF_ERR_NULL_GAME, CF_OK};
+use crate::{Game, Player};
+
+// Message for the last error asked about; kept here so JavaScript can read it out of memory
+static mut MESSAGE: String = String::new();
+
+// Starts a game on a board of the given size, needing `connect` in a row to win; gives null
+// when the rules don't make a valid game
+#[no_mangle]
+pub extern "C" fn game_new(width: u32, height: u32, connect: u32) -> *mut Game {
+  match Game::new(width as usize, height as usize).and_then(|game| game.WithWinLength(connect as usize)) {
+    Ok(game) => Give(game),
+    Err(_) => std::ptr::null_mut(),
+  }
+}
+
+/// Frees a game made by game_new; the pointer mustn't be used again afterwards
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_free(game: *mut Game) {
+  drop(Take(game));
+}
+
+/// Drops a piece into the column (0 is the leftmost); gives 0 or one of the CF_ERR codes from
+/// src/ffi.rs
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_make_move(game: *mut Game, column: u32) -> i32 {
+  let Some(game) = GameAtMut(game) else { return CF_ERR_NULL_GAME };
+  match game.MakeMove(column as usize) {
+    Ok(_) => CF_OK,
+    Err(err) => ffi::ErrorCode(&err),
+  }
+}
+
+/// Row the last move's piece landed in (0 is the top row), or -1 before any move
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_last_row(game: *const Game) -> i32 {
+  GameAt(game).and_then(Game::LastPlaced).map_or(-1, |(row, _)| row as i32)
+}
+
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_width(game: *const Game) -> u32 {
+  GameAt(game).map_or(0, |game| game.Width() as u32)
+}
+
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_height(game: *const Game) -> u32 {
+  GameAt(game).map_or(0, |game| game.Height() as u32)
+}
+
+/// Pointer to the width * height cells, row by row from the top, each the number of the player
+/// on it or 0; only good until the next move
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_board(game: *const Game) -> *const u8 {
+  GameAt(game).map_or(std::ptr::null(), |game| game.Board().Cells().as_ptr())
+}
+
+/// Number of the player to move, 1 or 2
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_current_player(game: *const Game) -> u32 {
+  GameAt(game).map_or(0, |game| game.CurrentPlayer() as u32)
+}
+
+/// Number of the winner, or 0 while nobody has won (including after a draw)
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_winner(game: *const Game) -> u32 {
+  GameAt(game).map_or(0, |game| game.Winner() as u32)
+}
+
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_is_finished(game: *const Game) -> bool {
+  GameAt(game).is_none_or(Game::IsFinished)
+}
+
+/// Whether a piece can still be dropped into the column; legal moves are the columns where
+/// this holds
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_is_legal(game: *const Game, column: u32) -> bool {
+  GameAt(game).is_some_and(|game| !game.IsFinished() && game.IsColumnPlayable(column as usize))
+}
+
+/// Whether the last move won the game, drew it, or left it going: 0 going, 1 won, 2 drawn
+///
+/// # Safety
+/// The pointer is null or a live game from game_new.
+#[no_mangle]
+pub unsafe extern "C" fn game_result(game: *const Game) -> u32 {
+  match GameAt(game) {
+    Some(game) if game.IsFinished() && game.Winner() != Player::None => 1,
+    Some(game) if game.IsFinished() => 2,
+    _ => 0,
+  }
+}
+
+// Puts the message for an error code in memory and gives its length; read it from
+// error_message_ptr
+#[no_mangle]
+pub extern "C" fn error_message(code: i32) -> u32 {
+  let text = ffi::Message(code);
+  unsafe {
+    let message = &mut *std::ptr::addr_of_mut!(MESSAGE);
+    *message = text;
+    message.len() as u32
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn error_message_ptr() -> *const u8 {
+  unsafe { (*std::ptr::addr_of!(MESSAGE)).as_ptr() }
+}
@@ -0,0 +1,209 @@
+// Just enough of WebSocket (RFC 6455) for the server: the opening handshake, and text frames
+// each way, with the pings, pongs and closes that come with them. Messages from clients arrive
+// masked, possibly in fragments; the server's go out whole and unmasked.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+
+// Added to the client's key before hashing it for the handshake's answer, as the RFC says
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// Longest handshake request and message read, to keep a client from using up memory
+const MAX_REQUEST: usize = 8 * 1024;
+const MAX_MESSAGE: usize = 64 * 1024;
+
+// Frame opcodes
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+// Reads the HTTP request that opens a connection and answers it, switching to WebSocket.
+// A request that isn't a WebSocket one gets a 400 and the error says why.
+pub fn Handshake(stream: &mut TcpStream) -> Result<(), String> {
+  let mut request = Vec::new();
+  let mut byte = [0u8; 1];
+  while !request.ends_with(b"\r\n\r\n") {
+    if request.len() >= MAX_REQUEST {
+      return Refuse(stream, "the request is too long");
+    }
+    match stream.read(&mut byte) {
+      Ok(0) => return Err("the connection closed during the handshake".to_string()),
+      Ok(_) => request.push(byte[0]),
+      Err(err) => return Err(err.to_string()),
+    }
+  }
+  let request = String::from_utf8_lossy(&request);
+  let header = |name: &str| {
+    request.lines().skip(1).find_map(|line| {
+      let (key, value) = line.split_once(':')?;
+      key.trim().eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+    })
+  };
+  if !header("Upgrade").is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket")) {
+    return Refuse(stream, "only WebSocket connections are served here");
+  }
+  let Some(key) = header("Sec-WebSocket-Key") else {
+    return Refuse(stream, "the request has no Sec-WebSocket-Key");
+  };
+  let accept = Base64(&Sha1(format!("{}{}", key, HANDSHAKE_GUID).as_bytes()));
+  let answer = format!(
+    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+    accept
+  );
+  stream.write_all(answer.as_bytes()).map_err(|err| err.to_string())
+}
+
+// Answers a request that can't be served with a 400, and returns why as the error
+fn Refuse(stream: &mut TcpStream, reason: &str) -> Result<(), String> {
+  let answer = format!(
+    "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+    reason.len(),
+    reason
+  );
+  let _ = stream.write_all(answer.as_bytes());
+  Err(reason.to_string())
+}
+
+// Reads the next text message, answering pings on `out`, which other threads may be writing
+// to as well, along the way. None once the client has closed the connection, or sent
+// something that isn't WebSocket.
+pub fn ReadText(stream: &mut TcpStream, out: &Mutex<TcpStream>) -> Option<String> {
+  let mut message = Vec::new();
+  loop {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).ok()?;
+    let last = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let length = match header[1] & 0x7F {
+      126 => {
+        let mut bytes = [0u8; 2];
+        stream.read_exact(&mut bytes).ok()?;
+        u16::from_be_bytes(bytes) as usize
+      }
+      127 => {
+        let mut bytes = [0u8; 8];
+        stream.read_exact(&mut bytes).ok()?;
+        usize::try_from(u64::from_be_bytes(bytes)).ok()?
+      }
+      length => length as usize,
+    };
+    // Clients always mask what they send
+    if !masked || length > MAX_MESSAGE - message.len() {
+      Close(&mut out.lock().unwrap());
+      return None;
+    }
+    let mut mask = [0u8; 4];
+    stream.read_exact(&mut mask).ok()?;
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload).ok()?;
+    for (index, byte) in payload.iter_mut().enumerate() {
+      *byte ^= mask[index % 4];
+    }
+    match opcode {
+      TEXT | BINARY | CONTINUATION => {
+        message.extend(payload);
+        if last {
+          return Some(String::from_utf8_lossy(&message).into_owned());
+        }
+      }
+      PING => Frame(&mut out.lock().unwrap(), PONG, &payload).ok()?,
+      PONG => {}
+      _ => {
+        Close(&mut out.lock().unwrap());
+        return None;
+      }
+    }
+  }
+}
+
+// Sends a text message
+pub fn WriteText(out: &mut TcpStream, text: &str) -> std::io::Result<()> {
+  Frame(out, TEXT, text.as_bytes())
+}
+
+// Sends the frame ending the connection
+fn Close(out: &mut TcpStream) {
+  let _ = Frame(out, CLOSE, &[]);
+}
+
+// Sends one whole frame, unmasked as servers send them
+fn Frame(out: &mut TcpStream, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+  let mut frame = vec![0x80 | opcode];
+  match payload.len() {
+    length if length < 126 => frame.push(length as u8),
+    length if length <= u16::MAX as usize => {
+      frame.push(126);
+      frame.extend((length as u16).to_be_bytes());
+    }
+    length => {
+      frame.push(127);
+      frame.extend((length as u64).to_be_bytes());
+    }
+  }
+  frame.extend_from_slice(payload);
+  out.write_all(&frame)
+}
+
+// The SHA-1 hash of the bytes, which the handshake needs and nothing else does
+fn Sha1(bytes: &[u8]) -> [u8; 20] {
+  let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+  let mut padded = bytes.to_vec();
+  padded.push(0x80);
+  while padded.len() % 64 != 56 {
+    padded.push(0);
+  }
+  padded.extend((bytes.len() as u64 * 8).to_be_bytes());
+  for block in padded.chunks(64) {
+    let mut words = [0u32; 80];
+    for (index, word) in block.chunks(4).enumerate() {
+      words[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for index in 16..80 {
+      words[index] = (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16]).rotate_left(1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e] = state;
+    for (index, &word) in words.iter().enumerate() {
+      let (f, k) = match index {
+        0..=19 => ((b & c) | (!b & d), 0x5A827999),
+        20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+        40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+        _ => (b ^ c ^ d, 0xCA62C1D6),
+      };
+      let next = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+      e = d;
+      d = c;
+      c = b.rotate_left(30);
+      b = a;
+      a = next;
+    }
+    for (total, added) in state.iter_mut().zip([a, b, c, d, e]) {
+      *total = total.wrapping_add(added);
+    }
+  }
+  let mut hash = [0u8; 20];
+  for (index, word) in state.iter().enumerate() {
+    hash[index * 4..index * 4 + 4].copy_from_slice(&word.to_be_bytes());
+  }
+  hash
+}
+
+// The bytes in standard Base64, padded
+fn Base64(bytes: &[u8]) -> String {
+  const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+  let mut text = String::new();
+  for chunk in bytes.chunks(3) {
+    let group = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+    for index in 0..4 {
+      match index <= chunk.len() {
+        true => text.push(ALPHABET[(group >> (18 - 6 * index) & 0x3F) as usize] as char),
+        false => text.push('='),
+      }
+    }
+  }
+  text
+}
@@ -0,0 +1,18 @@
+use crate::{Player, MAX_PLAYERS};
+
+// SplitMix64 step; keys are derived from the cell index, so boards of any size share one scheme
+const fn Mix(index: u64) -> u64 {
+  let mut z = index.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(0x2545_F491_4F6C_DD1D);
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  z ^ (z >> 31)
+}
+
+// Key to XOR into a position's hash when `player` takes or leaves the cell
+// of a board `width` columns wide
+pub fn Key(row: usize, column: usize, width: usize, player: Player) -> u64 {
+  match player {
+    Player::None => 0,
+    _ => Mix(((row * width + column) * MAX_PLAYERS + player as usize) as u64),
+  }
+}
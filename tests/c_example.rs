@@ -0,0 +1,47 @@
+// examples/c/game.c, built against the library with the `ffi` feature and run, so the C
+// interface and its header are checked along with everything else. Skipped where there's no
+// C compiler to build it with.
+
+#![allow(non_snake_case)]
+#![cfg(unix)]
+
+use std::path::Path;
+use std::process::Command;
+
+// Runs the command, failing the test with its output if it doesn't succeed
+fn Run(command: &mut Command) {
+  let output = command.output().unwrap();
+  assert!(
+    output.status.success(),
+    "{:?} failed\n{}{}",
+    command,
+    String::from_utf8_lossy(&output.stdout),
+    String::from_utf8_lossy(&output.stderr)
+  );
+}
+
+#[test]
+fn CExamplePlaysThroughTheInterface() {
+  if Command::new("cc").arg("--version").output().is_err() {
+    eprintln!("no C compiler; examples/c/game.c not built");
+    return;
+  }
+  let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+  // A target directory of its own, since the one the tests were built in is locked
+  let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+  Run(Command::new(env!("CARGO"))
+      .args(["rustc", "--lib", "--features", "ffi", "--crate-type", "cdylib", "--target-dir"])
+      .arg(&target)
+      .current_dir(root));
+  let library = target.join("debug");
+  let program = target.join("cgame");
+  Run(Command::new("cc")
+      .arg("-I")
+      .arg(root.join("include"))
+      .arg(root.join("examples/c/game.c"))
+      .arg("-L")
+      .arg(&library)
+      .args(["-lConnectFour", "-o"])
+      .arg(&program));
+  Run(Command::new(&program).env("LD_LIBRARY_PATH", &library).env("DYLD_LIBRARY_PATH", &library));
+}
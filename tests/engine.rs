@@ -0,0 +1,115 @@
+// The `engine` command driven the way a GUI or harness would: a command written, its replies
+// read back, one line at a time.
+
+#![allow(non_snake_case)]
+
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+// The engine, with its input and output
+struct Engine {
+  Child: Child,
+  In: Option<ChildStdin>, // None once closed
+  Out: Lines<BufReader<ChildStdout>>,
+}
+
+impl Engine {
+  fn Start() -> Engine {
+    let home = std::env::temp_dir().join(format!("ConnectFour-engine-test-{}", std::process::id()));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ConnectFour"))
+        .arg("engine")
+        .env("XDG_CONFIG_HOME", &home)
+        .env("XDG_DATA_HOME", &home)
+        .env("LC_ALL", "C")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input = child.stdin.take().unwrap();
+    let output = BufReader::new(child.stdout.take().unwrap()).lines();
+    Engine { Child: child, In: Some(input), Out: output }
+  }
+
+  fn Send(&mut self, command: &str) {
+    writeln!(self.In.as_mut().unwrap(), "{}", command).unwrap();
+  }
+
+  // Ends the engine's input
+  fn Close(&mut self) {
+    self.In = None;
+  }
+
+  fn Reply(&mut self) -> String {
+    self.Out.next().expect("the engine answered").unwrap()
+  }
+
+  // The lines sent up to and including the bestmove ending a search
+  fn Search(&mut self) -> Vec<String> {
+    let mut lines = Vec::new();
+    while !lines.last().is_some_and(|line: &String| line.starts_with("bestmove ")) {
+      lines.push(self.Reply());
+    }
+    lines
+  }
+}
+
+impl Drop for Engine {
+  fn drop(&mut self) {
+    let _ = self.Child.kill();
+    let _ = self.Child.wait();
+  }
+}
+
+#[test]
+fn ScriptedSession() {
+  let mut engine = Engine::Start();
+  engine.Send("isready");
+  assert_eq!(engine.Reply(), "readyok");
+
+  // Red has three in the bottom row and wins at once in column 4
+  engine.Send("position startpos moves 112233");
+  engine.Send("go depth 4");
+  let lines = engine.Search();
+  assert_eq!(lines.last().unwrap(), "bestmove 4");
+  assert!(lines[0].starts_with("info depth 1 score win 1 "), "{:?}", lines);
+  assert!(lines[..lines.len() - 1].iter().all(|line| line.starts_with("info depth ")), "{:?}", lines);
+
+  // Moves written apart, without startpos, filling column 4
+  engine.Send("position moves 4 4 4 4 4 4");
+  engine.Send("go depth 2");
+  let lines = engine.Search();
+  let best: usize = lines.last().unwrap()["bestmove ".len()..].parse().unwrap();
+  assert!((1..=7).contains(&best) && best != 4, "{:?}", lines);
+
+  engine.Send("frobnicate");
+  assert_eq!(engine.Reply(), "error unknown command 'frobnicate'");
+  engine.Send("position startpos moves 19");
+  assert!(engine.Reply().starts_with("error "));
+  engine.Send("isready");
+  assert_eq!(engine.Reply(), "readyok");
+
+  // A bad position leaves the last good one in place
+  engine.Send("go depth 2");
+  assert_eq!(engine.Search().last().unwrap(), &format!("bestmove {}", best));
+
+  // An endless search goes on until stopped, then sends its best move so far
+  engine.Send("newgame");
+  engine.Send("go infinite");
+  assert!(engine.Reply().starts_with("info depth 1 "));
+  engine.Send("stop");
+  assert!(engine.Search().last().unwrap().starts_with("bestmove "));
+
+  engine.Send("quit");
+  assert!(engine.Out.next().is_none());
+  assert!(engine.Child.wait().unwrap().success());
+}
+
+#[test]
+fn EndOfInputEndsTheSession() {
+  let mut engine = Engine::Start();
+  engine.Send("isready");
+  assert_eq!(engine.Reply(), "readyok");
+  engine.Close();
+  assert!(engine.Out.next().is_none());
+  assert!(engine.Child.wait().unwrap().success());
+}
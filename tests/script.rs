@@ -0,0 +1,103 @@
+// Games played from a `--script` file and from piped stdin, start to finish, checking what's
+// printed and that the program leaves once its input runs out rather than waiting forever.
+
+#![allow(non_snake_case)]
+
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+// How long a game of a few moves may take before the program is taken to be stuck
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+// A folder of the test's own for config, saves and the script
+fn Home(test: &str) -> PathBuf {
+  let home = std::env::temp_dir().join(format!("ConnectFour-script-test-{}-{}", test, std::process::id()));
+  let _ = std::fs::remove_dir_all(&home);
+  std::fs::create_dir_all(&home).unwrap();
+  home
+}
+
+fn Start(home: &PathBuf, args: &[&str]) -> Child {
+  Command::new(env!("CARGO_BIN_EXE_ConnectFour"))
+      .args(["--no-animation", "--plain"])
+      .args(args)
+      .env("XDG_CONFIG_HOME", home)
+      .env("XDG_DATA_HOME", home)
+      .env("LC_ALL", "C")
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()
+      .unwrap()
+}
+
+// Waits for the program to leave, killing it if it doesn't in time, and returns what it printed
+fn Finish(mut child: Child) -> String {
+  let mut stdout = child.stdout.take().unwrap();
+  let reader = std::thread::spawn(move || {
+    let mut output = String::new();
+    stdout.read_to_string(&mut output).map(|_| output)
+  });
+  let started = Instant::now();
+  while child.try_wait().unwrap().is_none() {
+    if started.elapsed() > TIMEOUT {
+      let _ = child.kill();
+      panic!("still running after {:?}", TIMEOUT);
+    }
+    std::thread::sleep(Duration::from_millis(20));
+  }
+  let output = reader.join().unwrap().unwrap();
+  assert!(child.wait().unwrap().success(), "{}", output);
+  output
+}
+
+// Plays the lines from a script file, with stdin closed
+fn RunScript(test: &str, lines: &[&str]) -> String {
+  let home = Home(test);
+  let path = home.join("game.txt");
+  std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+  let mut child = Start(&home, &["--script", path.to_str().unwrap()]);
+  drop(child.stdin.take());
+  let output = Finish(child);
+  let _ = std::fs::remove_dir_all(&home);
+  output
+}
+
+#[test]
+fn ScriptedGamePlaysToTheEnd() {
+  let output = RunScript("win", &["# two players, then the moves", "2", "Ann", "Bob", "4", "5", "4", "5", "4", "5", "4", "n"]);
+  assert!(output.contains("Ann (Red)"), "{}", output);
+  assert!(output.contains("Bob (Yellow)"), "{}", output);
+  assert!(output.contains("Red wins"), "{}", output);
+  assert!(output.contains("Moves: 4545454"), "{}", output);
+  assert!(output.contains("Notation: 1. d e 2. d e 3. d e 4. d"), "{}", output);
+}
+
+#[test]
+fn ScriptedLinesAreEchoedAndCheckedLikeTypedOnes() {
+  let output = RunScript("invalid", &["2", "Ann", "Bob", "9", "4", "u", "3"]);
+  assert!(output.contains("\n9\n"), "{}", output);
+  assert!(output.contains("Error: "), "{}", output);
+  assert!(output.contains("Red dropped into column 3"), "{}", output);
+}
+
+#[test]
+fn ScriptEndingMidGameLeaves() {
+  let output = RunScript("short", &["2", "Ann", "Bob", "4", "4"]);
+  assert!(output.contains("Yellow dropped into column 4"), "{}", output);
+  assert!(output.trim_end().ends_with("bye"), "{}", output);
+}
+
+#[test]
+fn StdinClosingMidGameLeaves() {
+  let home = Home("stdin");
+  let mut child = Start(&home, &[]);
+  let mut input = child.stdin.take().unwrap();
+  input.write_all(b"2\nAnn\nBob\n4\n4\n5\n").unwrap();
+  drop(input);
+  let output = Finish(child);
+  let _ = std::fs::remove_dir_all(&home);
+  assert!(output.contains("Red dropped into column 5"), "{}", output);
+  assert!(output.trim_end().ends_with("bye"), "{}", output);
+}
@@ -0,0 +1,81 @@
+// Runs started with the same `--seed` play out the same: handicap pieces and the computer's
+// moves in a game, and every game of a `simulate` run.
+
+#![allow(non_snake_case)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+// A folder of the test's own for config and saves, emptied for each run so an autosave from
+// the one before can't be offered
+fn Home(test: &str) -> PathBuf {
+  let home = std::env::temp_dir().join(format!("ConnectFour-seed-test-{}-{}", test, std::process::id()));
+  let _ = std::fs::remove_dir_all(&home);
+  std::fs::create_dir_all(&home).unwrap();
+  home
+}
+
+fn Run(home: &Path, args: &[&str], input: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_ConnectFour"))
+      .args(args)
+      .env("XDG_CONFIG_HOME", home)
+      .env("XDG_DATA_HOME", home)
+      .env("LC_ALL", "C")
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()
+      .unwrap();
+  child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+  let output = child.wait_with_output().unwrap();
+  assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+  output
+}
+
+// What a game against the easy computer with a handicap prints, played from a fresh home
+fn HandicapGame(home: &Path, seed: &str) -> Vec<u8> {
+  let _ = std::fs::remove_dir_all(home);
+  std::fs::create_dir_all(home).unwrap();
+  let args = ["--no-animation", "--plain", "--handicap", "3", "--difficulty", "easy", "--seed", seed];
+  Run(home, &args, "1\nAnn\n4\n3\n5\n").stdout
+}
+
+// The games of a simulate run, as in its CSV file without the time each took
+fn SimulatedGames(home: &Path, seed: &str) -> Vec<String> {
+  let csv = home.join(format!("games-{}.csv", seed));
+  let args = ["simulate", "--p1", "ai:random", "--p2", "ai:random", "--games", "6", "--seed", seed];
+  Run(home, &[&args[..], &["--csv", csv.to_str().unwrap()]].concat(), "");
+  let written = std::fs::read_to_string(&csv).unwrap();
+  written
+      .lines()
+      .map(|row| {
+        let mut fields: Vec<&str> = row.split(',').collect();
+        fields.remove(6); // duration_ms
+        fields.join(",")
+      })
+      .collect()
+}
+
+#[test]
+fn SameSeedPlaysTheSameHandicapGame() {
+  let home = Home("handicap");
+  let first = HandicapGame(&home, "11");
+  let again = HandicapGame(&home, "11");
+  let other = HandicapGame(&home, "12");
+  let _ = std::fs::remove_dir_all(&home);
+  assert!(String::from_utf8_lossy(&first).contains("Easy bot chose column"));
+  assert!(first == again, "{}\n---\n{}", String::from_utf8_lossy(&first), String::from_utf8_lossy(&again));
+  assert!(first != other);
+}
+
+#[test]
+fn SameSeedSimulatesTheSameGames() {
+  let home = Home("simulate");
+  let first = SimulatedGames(&home, "7");
+  let again = SimulatedGames(&home, "7");
+  let other = SimulatedGames(&home, "8");
+  let _ = std::fs::remove_dir_all(&home);
+  assert_eq!(first.len(), 7);
+  assert_eq!(first, again);
+  assert_ne!(first, other);
+}
@@ -0,0 +1,163 @@
+// The WebSocket server, run as `serve --ws` and talked to over real sockets: the handshake,
+// a game between two clients, and a frame too long to take.
+
+#![allow(non_snake_case)]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+// The server, stopped when the test is done with it
+struct Server {
+  Child: Child,
+  Port: u16,
+}
+
+impl Drop for Server {
+  fn drop(&mut self) {
+    let _ = self.Child.kill();
+    let _ = self.Child.wait();
+  }
+}
+
+// Starts the server on a free port, with no config file of the user's in the way
+fn Serve() -> Server {
+  let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+  let home = std::env::temp_dir().join(format!("ConnectFour-server-test-{}", port));
+  let child = Command::new(env!("CARGO_BIN_EXE_ConnectFour"))
+      .args(["serve", "--ws", &format!("127.0.0.1:{}", port)])
+      .env("XDG_CONFIG_HOME", &home)
+      .env("XDG_DATA_HOME", &home)
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .spawn()
+      .unwrap();
+  Server { Child: child, Port: port }
+}
+
+// Connects to the server, waiting for it to be listening
+fn Connect(server: &Server) -> TcpStream {
+  let started = Instant::now();
+  loop {
+    match TcpStream::connect(("127.0.0.1", server.Port)) {
+      Ok(stream) => {
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        return stream;
+      }
+      Err(_) if started.elapsed() < Duration::from_secs(10) => std::thread::sleep(Duration::from_millis(50)),
+      Err(err) => panic!("the server isn't listening: {}", err),
+    }
+  }
+}
+
+// Opens a WebSocket connection with the key from the RFC's example, and returns the answer
+fn Handshake(stream: &mut TcpStream) -> String {
+  let request = "GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                 Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n";
+  stream.write_all(request.as_bytes()).unwrap();
+  let mut answer = Vec::new();
+  let mut byte = [0u8; 1];
+  while !answer.ends_with(b"\r\n\r\n") {
+    stream.read_exact(&mut byte).unwrap();
+    answer.push(byte[0]);
+  }
+  String::from_utf8(answer).unwrap()
+}
+
+// Sends a text message, masked as clients send them
+fn Send(stream: &mut TcpStream, text: &str) {
+  let mask = [0x12, 0x34, 0x56, 0x78];
+  let mut frame = vec![0x81, 0x80 | text.len() as u8];
+  frame.extend(mask);
+  frame.extend(text.bytes().enumerate().map(|(index, byte)| byte ^ mask[index % 4]));
+  stream.write_all(&frame).unwrap();
+}
+
+// Reads the next frame, returning its opcode and payload
+fn Receive(stream: &mut TcpStream) -> (u8, String) {
+  let mut header = [0u8; 2];
+  stream.read_exact(&mut header).unwrap();
+  let length = match header[1] & 0x7F {
+    126 => {
+      let mut bytes = [0u8; 2];
+      stream.read_exact(&mut bytes).unwrap();
+      u16::from_be_bytes(bytes) as usize
+    }
+    length => length as usize,
+  };
+  let mut payload = vec![0u8; length];
+  stream.read_exact(&mut payload).unwrap();
+  (header[0] & 0x0F, String::from_utf8(payload).unwrap())
+}
+
+// Reads messages until one for the event comes, and returns it
+fn Expect(stream: &mut TcpStream, event: &str) -> String {
+  let wanted = format!("\"event\":\"{}\"", event);
+  loop {
+    let (_, text) = Receive(stream);
+    if text.contains(&wanted) {
+      return text;
+    }
+  }
+}
+
+#[test]
+fn HandshakeAnswersTheKey() {
+  let server = Serve();
+  let mut stream = Connect(&server);
+  let answer = Handshake(&mut stream);
+  assert!(answer.starts_with("HTTP/1.1 101 "), "{}", answer);
+  assert!(answer.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"), "{}", answer);
+}
+
+#[test]
+fn TwoClientsPlayAGame() {
+  let server = Serve();
+  let mut ann = Connect(&server);
+  let mut bob = Connect(&server);
+  Handshake(&mut ann);
+  Handshake(&mut bob);
+  Send(&mut ann, r#"{"command":"join","room":"kitchen","name":"Ann"}"#);
+  assert!(Expect(&mut ann, "joined").contains("\"player\":1"));
+  Send(&mut bob, r#"{"command":"join","room":"kitchen","name":"Bob"}"#);
+  assert!(Expect(&mut bob, "joined").contains("\"player\":2"));
+  assert!(Expect(&mut ann, "state").contains(r#""players":["Ann","Bob"]"#));
+  Expect(&mut bob, "state");
+
+  Send(&mut bob, r#"{"command":"move","column":1}"#);
+  assert!(Expect(&mut bob, "error").contains("not_your_turn"));
+  for (turn, column) in [0, 1, 0, 1, 0, 1, 0].into_iter().enumerate() {
+    let mover = if turn % 2 == 0 { &mut ann } else { &mut bob };
+    Send(mover, &format!(r#"{{"command":"move","column":{}}}"#, column));
+    for stream in [&mut ann, &mut bob] {
+      let moved = Expect(stream, "move");
+      assert!(moved.contains(&format!("\"column\":{}", column)), "{}", moved);
+      assert!(moved.contains(&format!("\"move_number\":{}", turn + 1)), "{}", moved);
+    }
+  }
+  for stream in [&mut ann, &mut bob] {
+    let over = Expect(stream, "game_over");
+    assert!(over.contains("\"winner\":1"), "{}", over);
+    assert!(over.contains("\"line\":[[2,0],[3,0],[4,0],[5,0]]"), "{}", over);
+  }
+  Send(&mut bob, r#"{"command":"move","column":2}"#);
+  assert!(Expect(&mut bob, "error").contains("game_over"));
+}
+
+#[test]
+fn FrameTooLongClosesTheConnection() {
+  let server = Serve();
+  let mut stream = Connect(&server);
+  Handshake(&mut stream);
+  // The start of a message, then a masked continuation frame claiming the longest length there is
+  stream.write_all(&[0x01, 0x80 | 1, 0, 0, 0, 0, b'{']).unwrap();
+  let mut frame = vec![0x80, 0x80 | 127];
+  frame.extend(u64::MAX.to_be_bytes());
+  stream.write_all(&frame).unwrap();
+  assert_eq!(Receive(&mut stream).0, 0x8);
+
+  // The server is still up for everyone else
+  let mut next = Connect(&server);
+  assert!(Handshake(&mut next).starts_with("HTTP/1.1 101 "));
+}